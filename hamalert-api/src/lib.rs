@@ -0,0 +1,972 @@
+//! Client for the HamAlert.org web API: logging in, listing, creating,
+//! updating and deleting triggers, and the account's destination settings.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), hamalert_api::Error> {
+//! let client = hamalert_api::HamAlertClient::new()?;
+//! client.login("N0CALL", "password").await?;
+//! for trigger in client.triggers().await? {
+//!     println!("{}", trigger.comment);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Where HamAlert is reached unless overridden with `HamAlertClient::with_base_url`
+pub const DEFAULT_BASE_URL: &str = "https://hamalert.org";
+/// Default overall timeout for HamAlert requests
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Default timeout for connecting to HamAlert
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+/// How long a fetched trigger list is reused without asking HamAlert again
+pub const TRIGGER_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Errors returned by the HamAlert client
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The request could not be sent or the response could not be read
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    /// HamAlert rejected the login
+    #[error("{0}")]
+    Auth(String),
+    /// HamAlert answered with an error status
+    #[error("{message}: {status}{}", body_suffix(.body))]
+    Api {
+        message: String,
+        status: StatusCode,
+        body: String,
+    },
+    /// HamAlert refused to save a trigger
+    #[error("{message}: {reason}")]
+    Rejected { message: String, reason: Rejection },
+}
+
+/// Why HamAlert refused to save a trigger
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rejection {
+    /// The account already has as many triggers as HamAlert allows
+    LimitExceeded,
+    /// A condition was not accepted, with HamAlert's explanation
+    InvalidCondition(String),
+    /// The same trigger already exists
+    Duplicate,
+    /// Any other reason, as HamAlert worded it
+    Other(String),
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rejection::LimitExceeded => write!(
+                f,
+                "the account has reached HamAlert's trigger limit; delete or merge unused triggers first"
+            ),
+            Rejection::InvalidCondition(detail) => write!(
+                f,
+                "invalid condition ({}); check the callsign, band, mode and reference values",
+                detail
+            ),
+            Rejection::Duplicate => write!(f, "an identical trigger already exists"),
+            Rejection::Other(detail) => write!(f, "{}", detail),
+        }
+    }
+}
+
+impl Rejection {
+    /// Classify HamAlert's explanation of a refusal
+    fn from_reason(reason: &str) -> Self {
+        let lower = reason.to_lowercase();
+        let mentions = |words: &[&str]| words.iter().any(|w| lower.contains(w));
+        if mentions(&["limit", "maximum", "too many"]) {
+            Rejection::LimitExceeded
+        } else if mentions(&["duplicate", "already exists"]) {
+            Rejection::Duplicate
+        } else if mentions(&["invalid", "not valid", "condition"]) {
+            Rejection::InvalidCondition(reason.to_string())
+        } else {
+            Rejection::Other(reason.to_string())
+        }
+    }
+}
+
+fn body_suffix(body: &str) -> String {
+    let body = body.trim();
+    if body.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", body)
+    }
+}
+
+impl Error {
+    /// Whether the request failed because HamAlert did not answer in time
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Error::Http(e) if e.is_timeout())
+    }
+}
+
+/// Build an `Error::Api` from an unsuccessful response, keeping its body
+async fn api_error(response: reqwest::Response, message: String) -> Error {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    Error::Api {
+        message,
+        status,
+        body,
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A trigger as returned by HamAlert
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trigger {
+    #[serde(rename = "_id")]
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    pub conditions: serde_json::Value,
+    pub actions: Vec<String>,
+    pub comment: String,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "matchCount")]
+    pub match_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<serde_json::Value>,
+}
+
+/// A new callsign trigger
+#[derive(Serialize)]
+pub struct TriggerData {
+    pub conditions: Conditions,
+    pub comment: String,
+    pub actions: Vec<String>,
+    pub options: serde_json::Value,
+}
+
+#[derive(Serialize, Default)]
+pub struct Conditions {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub callsign: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// ADIF DXCC entity numbers
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dxcc: Vec<u16>,
+    /// SOTA summit references such as `W7W/LC-001`
+    #[serde(rename = "summitRef", skip_serializing_if = "Vec::is_empty")]
+    pub summit_ref: Vec<String>,
+    /// POTA park references such as `US-0001`
+    #[serde(rename = "potaRef", skip_serializing_if = "Vec::is_empty")]
+    pub pota_ref: Vec<String>,
+}
+
+/// Account-wide settings of HamAlert's Destinations page, which the trigger
+/// actions deliver to
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Destinations {
+    /// Endpoint called by the `url` action
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Threema ID that receives the `threema` action
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threema_id: Option<String>,
+    /// Whether the `telnet` action delivers to HamAlert's telnet server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub telnet_enabled: Option<bool>,
+    /// Settings this client does not know, sent back unchanged on update
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A spot submitted through HamAlert's spot simulation
+#[derive(Debug, Clone)]
+pub struct SimulatedSpot {
+    pub callsign: String,
+    pub frequency_khz: f64,
+    pub mode: Option<String>,
+    pub spotter: String,
+    pub comment: String,
+}
+
+/// Keys whose values are hidden when request payloads are logged
+const REDACTED_KEYS: &[&str] = &["password", "token", "webhook"];
+
+/// Whether a config or payload key holds a credential
+pub fn is_redacted_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    REDACTED_KEYS.iter().any(|k| key.contains(k))
+}
+
+/// Hide credentials in a JSON or form-encoded request payload before logging it
+pub fn redact_payload(payload: &str) -> String {
+    fn redact_json(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if is_redacted_key(key) {
+                        *value = json!("***");
+                    } else {
+                        redact_json(value);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+            _ => {}
+        }
+    }
+
+    if let Ok(mut value) = serde_json::from_str::<serde_json::Value>(payload) {
+        redact_json(&mut value);
+        return value.to_string();
+    }
+    payload
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if is_redacted_key(key) => format!("{}=***", key),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Attempts made for each HTTP request before giving up on transient errors
+const HTTP_MAX_ATTEMPTS: u32 = 4;
+/// Delay before the first retry; doubled on every further attempt
+const HTTP_RETRY_BASE_MS: u64 = 500;
+/// Upper bound on the delay between retries
+const HTTP_RETRY_MAX_MS: u64 = 30_000;
+
+/// Whether a response status is worth retrying (rate limiting and server errors)
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Delay before retry number `attempt` (1-based): exponential backoff plus
+/// jitter, or the server's Retry-After when it asks for longer
+fn retry_delay(attempt: u32, retry_after_secs: Option<u64>, jitter_ms: u64) -> Duration {
+    let backoff = HTTP_RETRY_BASE_MS
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .saturating_add(jitter_ms)
+        .min(HTTP_RETRY_MAX_MS);
+    let requested = retry_after_secs.unwrap_or(0).saturating_mul(1000);
+    Duration::from_millis(backoff.max(requested))
+}
+
+/// Cheap jitter source; good enough to spread out retries without a rand dependency
+fn retry_jitter_ms() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    u64::from(nanos) % HTTP_RETRY_BASE_MS
+}
+
+/// Which failures a request may be sent again after
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Retry {
+    /// Sending the request twice does no harm: timeouts, connect errors,
+    /// rate limiting and server errors are retried
+    Repeatable,
+    /// The request must not be repeated, e.g. creating a trigger, whose
+    /// retry after a timeout could create a duplicate. Only a failed
+    /// connection, which sent nothing, is retried.
+    Once,
+}
+
+impl Retry {
+    /// The policy for a method: GET, HEAD, PUT, DELETE and OPTIONS are
+    /// repeatable, POST is not
+    fn for_method(method: &reqwest::Method) -> Self {
+        if method.is_idempotent() {
+            Retry::Repeatable
+        } else {
+            Retry::Once
+        }
+    }
+}
+
+/// Sends HTTP requests, retrying transient failures with exponential backoff.
+/// Requests are traced at debug level, redacted payloads at trace level and
+/// retries as warnings.
+pub trait SendLogged {
+    /// Send with the retry policy of the request's method
+    fn send_logged(self) -> impl Future<Output = reqwest::Result<reqwest::Response>> + Send;
+
+    /// Send with an explicit retry policy, e.g. for a POST that is safe to repeat
+    fn send_logged_with(
+        self,
+        retry: Retry,
+    ) -> impl Future<Output = reqwest::Result<reqwest::Response>> + Send;
+}
+
+impl SendLogged for reqwest::RequestBuilder {
+    async fn send_logged(self) -> reqwest::Result<reqwest::Response> {
+        send_with_retries(self, None).await
+    }
+
+    async fn send_logged_with(self, retry: Retry) -> reqwest::Result<reqwest::Response> {
+        send_with_retries(self, Some(retry)).await
+    }
+}
+
+/// Send a request, retrying the failures `retry` allows (by default those the
+/// request's method allows)
+async fn send_with_retries(
+    builder: reqwest::RequestBuilder,
+    retry: Option<Retry>,
+) -> reqwest::Result<reqwest::Response> {
+    let (client, request) = builder.build_split();
+    let request = request?;
+    let method = request.method().clone();
+    let url = request.url().clone();
+    let retry = retry.unwrap_or_else(|| Retry::for_method(&method));
+
+    if tracing::enabled!(tracing::Level::TRACE)
+        && let Some(body) = request.body().and_then(|b| b.as_bytes())
+    {
+        let payload = redact_payload(&String::from_utf8_lossy(body));
+        tracing::trace!(%method, %url, %payload, "request payload");
+    }
+
+    let mut attempt = 1;
+    let mut request = request;
+    loop {
+        // Keep a copy for a retry; streaming bodies cannot be cloned and are sent once
+        let retry_copy = request.try_clone();
+
+        tracing::debug!(%method, %url, attempt, "sending request");
+        let started = std::time::Instant::now();
+        let result = client.execute(request).await;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        match &result {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                tracing::debug!(%method, %url, status, elapsed_ms, "response");
+            }
+            Err(error) => {
+                tracing::debug!(%method, %url, %error, elapsed_ms, "request failed");
+            }
+        }
+
+        let (reason, retry_after) = match &result {
+            Err(e) if e.is_connect() => (e.to_string(), None),
+            _ if retry == Retry::Once => return result,
+            Ok(response) if is_retryable_status(response.status()) => (
+                response.status().to_string(),
+                response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse::<u64>().ok()),
+            ),
+            Err(e) if e.is_timeout() => (e.to_string(), None),
+            _ => return result,
+        };
+        let Some(retry_copy) = retry_copy.filter(|_| attempt < HTTP_MAX_ATTEMPTS) else {
+            return result;
+        };
+
+        let delay = retry_delay(attempt, retry_after, retry_jitter_ms());
+        tracing::warn!(
+            %method,
+            %url,
+            %reason,
+            delay_ms = delay.as_millis() as u64,
+            attempt = attempt + 1,
+            max_attempts = HTTP_MAX_ATTEMPTS,
+            "request failed, retrying"
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+        request = retry_copy;
+    }
+}
+
+/// Whether a login response indicates success. HamAlert redirects away from
+/// /login on success and shows the login form again when credentials are wrong.
+fn login_succeeded(final_path: &str, body: &str) -> bool {
+    let shows_login_form = body.contains("type=\"password\"") || body.contains("name=\"password\"");
+    !(final_path.trim_end_matches('/').ends_with("/login") && shows_login_form)
+}
+
+/// Readable text of an HTML error page: tags, scripts and styles removed and
+/// whitespace collapsed
+fn html_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        text.push(' ');
+        let tag = &rest[start..];
+        let lower = tag.to_ascii_lowercase();
+        // Script and style elements are skipped along with their contents
+        let end = ["script", "style"]
+            .iter()
+            .find(|name| lower.starts_with(&format!("<{}", name)))
+            .and_then(|name| {
+                let close = format!("</{}>", name);
+                lower.find(&close).map(|i| i + close.len())
+            })
+            .or_else(|| tag.find('>').map(|i| i + 1));
+        rest = end.map_or("", |end| &tag[end..]);
+    }
+    text.push_str(rest);
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Interpret HamAlert's reply to saving a trigger: the trigger's id when the
+/// reply names it, or why the trigger was refused. `Err(None)` is a failure
+/// without any explanation.
+fn parse_save_reply(
+    status: StatusCode,
+    body: &str,
+) -> std::result::Result<Option<String>, Option<Rejection>> {
+    let body = body.trim();
+    if let Ok(serde_json::Value::Object(reply)) = serde_json::from_str(body) {
+        let text = |key: &str| match reply.get(key) {
+            Some(serde_json::Value::String(s)) if !s.trim().is_empty() => {
+                Some(s.trim().to_string())
+            }
+            Some(serde_json::Value::Array(items)) if !items.is_empty() => Some(
+                items
+                    .iter()
+                    .map(|item| {
+                        item.as_str()
+                            .map_or_else(|| item.to_string(), str::to_string)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            ),
+            _ => None,
+        };
+        let failed = !status.is_success()
+            || reply.get("success") == Some(&json!(false))
+            || reply.get("status") == Some(&json!("error"))
+            || text("error").is_some()
+            || text("errors").is_some();
+        if failed {
+            let reason = text("error")
+                .or_else(|| text("errors"))
+                .or_else(|| text("message"));
+            return Err(reason.map(|r| Rejection::from_reason(&r)));
+        }
+        return Ok(["_id", "id", "triggerId"].iter().find_map(|key| text(key)));
+    }
+
+    if status.is_success() {
+        return Ok(None);
+    }
+    let reason = if body.starts_with('<') {
+        html_text(body)
+    } else {
+        body.to_string()
+    };
+    if reason.is_empty() {
+        Err(None)
+    } else {
+        Err(Some(Rejection::from_reason(&reason)))
+    }
+}
+
+/// The last trigger list fetched, with the validators HamAlert sent for it
+#[derive(Debug)]
+struct TriggerCache {
+    triggers: Vec<Trigger>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// When the list was fetched or confirmed unchanged; None once a change
+    /// through this client made it stale
+    checked_at: Option<Instant>,
+}
+
+impl TriggerCache {
+    fn is_fresh(&self) -> bool {
+        self.checked_at
+            .is_some_and(|at| at.elapsed() < TRIGGER_CACHE_TTL)
+    }
+}
+
+/// A HamAlert session. Cloning is cheap and clones share the session cookies
+/// and the cached trigger list.
+#[derive(Clone, Debug)]
+pub struct HamAlertClient {
+    http: Client,
+    base_url: String,
+    trigger_cache: Arc<Mutex<Option<TriggerCache>>>,
+}
+
+impl HamAlertClient {
+    /// Client with the default timeouts
+    pub fn new() -> Result<Self> {
+        Self::with_timeouts(
+            Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS),
+        )
+    }
+
+    /// Client with a session cookie jar and the given overall and connect timeouts
+    pub fn with_timeouts(timeout: Duration, connect_timeout: Duration) -> Result<Self> {
+        let http = Client::builder()
+            .cookie_store(true)
+            .timeout(timeout)
+            .connect_timeout(connect_timeout.min(timeout))
+            .build()?;
+        Ok(Self::from_http(http))
+    }
+
+    /// Wrap an existing HTTP client, which needs a cookie store to keep the session
+    pub fn from_http(http: Client) -> Self {
+        Self {
+            http,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            trigger_cache: Arc::default(),
+        }
+    }
+
+    /// Talk to a different HamAlert instance (e.g. a test server)
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// The underlying HTTP client, for requests to other hosts
+    pub fn http(&self) -> &Client {
+        &self.http
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Make the next `triggers` call check with HamAlert, e.g. before an
+    /// explicit refresh or to see match counts change. The cached list is
+    /// kept so an unchanged one need not be downloaded again.
+    pub fn invalidate_triggers(&self) {
+        if let Some(cache) = self.trigger_cache.lock().unwrap().as_mut() {
+            cache.checked_at = None;
+        }
+    }
+
+    /// Send a request that changes triggers or their match counts, leaving
+    /// the cached trigger list stale whether or not it succeeds
+    async fn send_change(&self, request: RequestBuilder, retry: Retry) -> Result<Response> {
+        let response = request.send_logged_with(retry).await;
+        self.invalidate_triggers();
+        Ok(response?)
+    }
+
+    #[tracing::instrument(skip(self, password))]
+    pub async fn login(&self, username: &str, password: &str) -> Result<()> {
+        let params = [("username", username), ("password", password)];
+
+        let response = self
+            .http
+            .post(self.url("/login"))
+            .form(&params)
+            .send_logged_with(Retry::Repeatable)
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Error::Auth(format!("Login failed: {}", response.status())));
+        }
+
+        // A rejected login still returns 200, re-rendering the login form
+        let final_path = response.url().path().to_string();
+        let body = response.text().await?;
+        if !login_succeeded(&final_path, &body) {
+            return Err(Error::Auth(format!(
+                "Invalid HamAlert username or password for '{}'",
+                username
+            )));
+        }
+
+        // Another login may be another account
+        *self.trigger_cache.lock().unwrap() = None;
+        tracing::info!("logged in");
+        Ok(())
+    }
+
+    /// All triggers of the logged-in account. A list fetched less than
+    /// `TRIGGER_CACHE_TTL` ago is reused unless a change was made through
+    /// this client since; otherwise HamAlert is asked with a conditional
+    /// request, so an unchanged list is not downloaded again.
+    #[tracing::instrument(skip(self))]
+    pub async fn triggers(&self) -> Result<Vec<Trigger>> {
+        let mut request = self.http.get(self.url("/ajax/triggers"));
+        if let Some(cache) = self.trigger_cache.lock().unwrap().as_ref() {
+            if cache.is_fresh() {
+                tracing::debug!(count = cache.triggers.len(), "using cached triggers");
+                return Ok(cache.triggers.clone());
+            }
+            if let Some(etag) = &cache.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cache.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let response = request.send_logged().await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED
+            && let Some(cache) = self.trigger_cache.lock().unwrap().as_mut()
+        {
+            tracing::debug!(count = cache.triggers.len(), "triggers not modified");
+            cache.checked_at = Some(Instant::now());
+            return Ok(cache.triggers.clone());
+        }
+        if !response.status().is_success() {
+            return Err(api_error(response, "Failed to fetch triggers".to_string()).await);
+        }
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+        let triggers: Vec<Trigger> = response.json().await?;
+        tracing::debug!(count = triggers.len(), "fetched triggers");
+        *self.trigger_cache.lock().unwrap() = Some(TriggerCache {
+            triggers: triggers.clone(),
+            etag,
+            last_modified,
+            checked_at: Some(Instant::now()),
+        });
+        Ok(triggers)
+    }
+
+    /// Send a trigger to /ajax/trigger_update, returning the trigger's id
+    /// when HamAlert names it. Refusals HamAlert explains become
+    /// `Error::Rejected`; server and session failures stay `Error::Api`.
+    /// Updates (with an `_id`) are retried like any repeatable request, but
+    /// a create is not sent again once it may have reached HamAlert.
+    async fn save_trigger(
+        &self,
+        payload: &serde_json::Value,
+        message: String,
+    ) -> Result<Option<String>> {
+        let retry = if payload.get("_id").is_some() {
+            Retry::Repeatable
+        } else {
+            Retry::Once
+        };
+        let response = self
+            .send_change(
+                self.http
+                    .post(self.url("/ajax/trigger_update"))
+                    .json(payload),
+                retry,
+            )
+            .await?;
+
+        let status = response.status();
+        if status.is_server_error() || matches!(status.as_u16(), 401 | 403) {
+            return Err(api_error(response, message).await);
+        }
+        let body = response.text().await?;
+        match parse_save_reply(status, &body) {
+            Ok(id) => Ok(id),
+            Err(Some(reason)) => Err(Error::Rejected { message, reason }),
+            Err(None) => Err(Error::Api {
+                message,
+                status,
+                body,
+            }),
+        }
+    }
+
+    /// Create a callsign trigger, returning its id when HamAlert reports it
+    #[tracing::instrument(skip_all, fields(callsign = %trigger.conditions.callsign))]
+    pub async fn add_trigger(&self, trigger: &TriggerData) -> Result<Option<String>> {
+        let message = format!("Failed to add trigger for {}", trigger.conditions.callsign);
+        let id = self.save_trigger(&json!(trigger), message).await?;
+        tracing::info!(id = id.as_deref().unwrap_or("?"), "added trigger");
+        Ok(id)
+    }
+
+    /// Create a new trigger with the same conditions, actions and options as
+    /// `trigger`, returning its id when HamAlert reports it
+    #[tracing::instrument(skip_all, fields(comment = %trigger.comment))]
+    pub async fn create_trigger(&self, trigger: &Trigger) -> Result<Option<String>> {
+        // Build trigger data without _id so a new one is created
+        let trigger_data = json!({
+            "conditions": trigger.conditions,
+            "actions": trigger.actions,
+            "comment": trigger.comment,
+            "options": trigger.options.clone().unwrap_or(json!({})),
+        });
+
+        let message = format!("Failed to create trigger '{}'", trigger.comment);
+        let id = self.save_trigger(&trigger_data, message).await?;
+        tracing::info!(id = id.as_deref().unwrap_or("?"), "created trigger");
+        Ok(id)
+    }
+
+    /// Save changes to an existing trigger
+    #[tracing::instrument(skip_all, fields(id = %trigger.id, comment = %trigger.comment))]
+    pub async fn update_trigger(&self, trigger: &Trigger) -> Result<()> {
+        let trigger_data = json!({
+            "_id": trigger.id,
+            "conditions": trigger.conditions,
+            "actions": trigger.actions,
+            "comment": trigger.comment,
+            "options": trigger.options.clone().unwrap_or(json!({})),
+            "disabled": trigger.disabled.unwrap_or(false),
+        });
+
+        let message = format!("Failed to update trigger '{}'", trigger.comment);
+        self.save_trigger(&trigger_data, message).await?;
+        tracing::info!("updated trigger");
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_trigger(&self, id: &str) -> Result<()> {
+        let response = self
+            .send_change(
+                self.http
+                    .post(self.url("/ajax/trigger_delete"))
+                    .form(&[("id", id)]),
+                Retry::Once,
+            )
+            .await?;
+
+        if !response.status().is_success() {
+            let message = format!("Failed to delete trigger {}", id);
+            return Err(api_error(response, message).await);
+        }
+
+        tracing::info!("deleted trigger");
+        Ok(())
+    }
+
+    /// The account's destination settings
+    #[tracing::instrument(skip(self))]
+    pub async fn destinations(&self) -> Result<Destinations> {
+        let response = self
+            .http
+            .get(self.url("/ajax/destinations"))
+            .send_logged()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response, "Failed to fetch destinations".to_string()).await);
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Save the account's destination settings
+    #[tracing::instrument(skip_all)]
+    pub async fn update_destinations(&self, destinations: &Destinations) -> Result<()> {
+        let response = self
+            .http
+            .post(self.url("/ajax/destinations_update"))
+            .json(destinations)
+            .send_logged_with(Retry::Repeatable)
+            .await?;
+
+        if !response.status().is_success() {
+            let message = "Failed to update destinations".to_string();
+            return Err(api_error(response, message).await);
+        }
+
+        tracing::info!("updated destinations");
+        Ok(())
+    }
+
+    /// Submit a simulated spot, as on HamAlert's "Simulate" page, and return the reply
+    #[tracing::instrument(skip_all, fields(callsign = %spot.callsign))]
+    pub async fn simulate_spot(&self, spot: &SimulatedSpot) -> Result<String> {
+        let frequency = format!("{:.4}", spot.frequency_khz / 1000.0);
+        let mode = spot.mode.as_deref().unwrap_or("").to_lowercase();
+        let params = [
+            ("callsign", spot.callsign.as_str()),
+            ("frequency", frequency.as_str()),
+            ("mode", mode.as_str()),
+            ("spotter", spot.spotter.as_str()),
+            ("comment", spot.comment.as_str()),
+        ];
+        let response = self
+            .send_change(
+                self.http.post(self.url("/ajax/simulate")).form(&params),
+                Retry::Once,
+            )
+            .await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(Error::Api {
+                message: format!("Failed to simulate spot of {}", spot.callsign),
+                status,
+                body,
+            });
+        }
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_payload_form() {
+        assert_eq!(
+            redact_payload("username=W1AW&password=hunter2"),
+            "username=W1AW&password=***"
+        );
+    }
+
+    #[test]
+    fn test_redact_payload_json() {
+        let redacted =
+            redact_payload(r#"{"comment":"x","options":{"apiToken":"abc"},"password":"p"}"#);
+        let value: serde_json::Value = serde_json::from_str(&redacted).unwrap();
+        assert_eq!(value["comment"], "x");
+        assert_eq!(value["options"]["apiToken"], "***");
+        assert_eq!(value["password"], "***");
+    }
+
+    #[test]
+    fn test_login_succeeded() {
+        let form = r#"<form method="post"><input type="password" name="password"></form>"#;
+        assert!(!login_succeeded("/login", form));
+        assert!(login_succeeded("/home", "<h1>My triggers</h1>"));
+        assert!(login_succeeded("/login", "<p>Welcome back</p>"));
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay(1, None, 0).as_millis(), 500);
+        assert_eq!(retry_delay(2, None, 0).as_millis(), 1000);
+        assert_eq!(retry_delay(3, None, 100).as_millis(), 2100);
+        assert_eq!(
+            retry_delay(30, None, 0).as_millis(),
+            u128::from(HTTP_RETRY_MAX_MS)
+        );
+        // Retry-After wins when it asks for longer
+        assert_eq!(retry_delay(1, Some(5), 0).as_secs(), 5);
+    }
+
+    #[test]
+    fn test_is_retryable_status() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(!is_retryable_status(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable_status(StatusCode::OK));
+    }
+
+    #[tokio::test]
+    async fn test_send_logged_retries_server_errors() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ajax/triggers", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            for status in ["503 Service Unavailable", "200 OK"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf).unwrap();
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\n[]",
+                    status
+                )
+                .unwrap();
+            }
+        });
+
+        let response = Client::new().get(&url).send_logged().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_api_error_message() {
+        let error = Error::Api {
+            message: "Failed to add trigger for W1AW".to_string(),
+            status: StatusCode::BAD_REQUEST,
+            body: "Invalid callsign\n".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Failed to add trigger for W1AW: 400 Bad Request Invalid callsign"
+        );
+        let error = Error::Api {
+            message: "Failed to fetch triggers".to_string(),
+            status: StatusCode::FORBIDDEN,
+            body: String::new(),
+        };
+        assert_eq!(error.to_string(), "Failed to fetch triggers: 403 Forbidden");
+    }
+
+    #[test]
+    fn test_parse_save_reply() {
+        let ok = StatusCode::OK;
+        assert_eq!(parse_save_reply(ok, ""), Ok(None));
+        assert_eq!(
+            parse_save_reply(ok, r#"{"success":true,"_id":"65a1f0c2"}"#),
+            Ok(Some("65a1f0c2".to_string()))
+        );
+        assert_eq!(
+            parse_save_reply(
+                ok,
+                r#"{"success":false,"error":"Maximum number of triggers reached"}"#
+            ),
+            Err(Some(Rejection::LimitExceeded))
+        );
+        assert_eq!(
+            parse_save_reply(
+                StatusCode::BAD_REQUEST,
+                r#"{"errors":["Invalid band: 7m"]}"#
+            ),
+            Err(Some(Rejection::InvalidCondition(
+                "Invalid band: 7m".to_string()
+            )))
+        );
+        assert_eq!(
+            parse_save_reply(
+                StatusCode::CONFLICT,
+                "<html><style>p{}</style><p>Trigger already exists</p></html>"
+            ),
+            Err(Some(Rejection::Duplicate))
+        );
+        assert_eq!(
+            parse_save_reply(StatusCode::BAD_REQUEST, "Try again later"),
+            Err(Some(Rejection::Other("Try again later".to_string())))
+        );
+        assert_eq!(parse_save_reply(StatusCode::BAD_REQUEST, " "), Err(None));
+    }
+
+    #[test]
+    fn test_html_text() {
+        assert_eq!(
+            html_text(
+                "<div class=\"error\">\n  Callsign &amp; mode <b>required</b></div><script>x()</script>"
+            ),
+            "Callsign & mode required"
+        );
+    }
+}
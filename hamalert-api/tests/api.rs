@@ -0,0 +1,496 @@
+//! Tests of the HamAlert client against a local mock server replaying
+//! HamAlert-shaped responses from `tests/fixtures`.
+
+use hamalert_api::{Conditions, Error, HamAlertClient, Rejection, Trigger, TriggerData};
+use serde_json::json;
+use std::time::Duration;
+use wiremock::matchers::{body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const TRIGGERS: &str = include_str!("fixtures/triggers.json");
+const LOGIN_FORM: &str = include_str!("fixtures/login_form.html");
+const HOME: &str = include_str!("fixtures/home.html");
+
+fn client_for(server: &MockServer) -> HamAlertClient {
+    HamAlertClient::new().unwrap().with_base_url(&server.uri())
+}
+
+fn fixture_triggers() -> Vec<Trigger> {
+    serde_json::from_str(TRIGGERS).unwrap()
+}
+
+/// JSON bodies of all requests the server received for `request_path`
+async fn json_bodies(server: &MockServer, request_path: &str) -> Vec<serde_json::Value> {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|r| r.url.path() == request_path)
+        .map(|r| serde_json::from_slice(&r.body).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn login_follows_redirect_on_success() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/login"))
+        .and(body_string_contains("username=N0CALL"))
+        .respond_with(ResponseTemplate::new(302).insert_header("Location", "/home"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/home"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(HOME))
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server);
+    client.login("N0CALL", "secret").await.unwrap();
+}
+
+#[tokio::test]
+async fn login_rejected_when_form_is_shown_again() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/login"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(LOGIN_FORM))
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server);
+    let err = client.login("N0CALL", "wrong").await.unwrap_err();
+    assert!(matches!(err, Error::Auth(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn backup_reads_all_triggers() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/ajax/triggers"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(TRIGGERS, "application/json"))
+        .mount(&server)
+        .await;
+
+    let triggers = client_for(&server).triggers().await.unwrap();
+    assert_eq!(triggers.len(), 2);
+    assert_eq!(triggers[0].comment, "Club stations");
+    assert_eq!(triggers[0].conditions["mode"], "cw");
+    assert_eq!(triggers[0].match_count, Some(42));
+    assert_eq!(triggers[1].disabled, Some(true));
+    assert_eq!(triggers[1].options, None);
+}
+
+#[tokio::test]
+async fn fetching_triggers_reports_server_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/ajax/triggers"))
+        .respond_with(ResponseTemplate::new(403))
+        .mount(&server)
+        .await;
+
+    let err = client_for(&server).triggers().await.unwrap_err();
+    assert!(
+        matches!(err, Error::Api { status, .. } if status == 403),
+        "{:?}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn trigger_list_is_reused_until_a_change() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/ajax/triggers"))
+        .and(header("If-None-Match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/ajax/triggers"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("ETag", "\"v1\"")
+                .set_body_raw(TRIGGERS, "application/json"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_delete"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server);
+    assert_eq!(client.triggers().await.unwrap().len(), 2);
+    // Served from the cache, including by clones of the client
+    assert_eq!(client.clone().triggers().await.unwrap().len(), 2);
+
+    // A change makes the next call revalidate; HamAlert says nothing changed
+    client.delete_trigger("nothing").await.unwrap();
+    let triggers = client.triggers().await.unwrap();
+    assert_eq!(triggers.len(), 2);
+    assert_eq!(triggers[0].comment, "Club stations");
+}
+
+#[tokio::test]
+async fn invalidated_trigger_list_is_downloaded_when_changed() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/ajax/triggers"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Last-Modified", "Sat, 17 Oct 2026 12:00:00 GMT")
+                .set_body_raw(TRIGGERS, "application/json"),
+        )
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server);
+    client.triggers().await.unwrap();
+    client.invalidate_triggers();
+    assert_eq!(client.triggers().await.unwrap().len(), 2);
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(
+        requests[1].headers.get("If-Modified-Since").unwrap(),
+        "Sat, 17 Oct 2026 12:00:00 GMT"
+    );
+}
+
+#[tokio::test]
+async fn add_trigger_posts_callsign_conditions() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_update"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let trigger = TriggerData {
+        conditions: Conditions {
+            callsign: "W1AW,K3LR".to_string(),
+            mode: Some("cw".to_string()),
+            ..Default::default()
+        },
+        comment: "Club stations".to_string(),
+        actions: vec!["app".to_string()],
+        options: json!({}),
+    };
+    client_for(&server).add_trigger(&trigger).await.unwrap();
+
+    let bodies = json_bodies(&server, "/ajax/trigger_update").await;
+    assert_eq!(
+        bodies[0],
+        json!({
+            "conditions": { "callsign": "W1AW,K3LR", "mode": "cw" },
+            "comment": "Club stations",
+            "actions": ["app"],
+            "options": {}
+        })
+    );
+}
+
+#[tokio::test]
+async fn add_trigger_surfaces_rejection_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_update"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("Invalid callsign"))
+        .mount(&server)
+        .await;
+
+    let trigger = TriggerData {
+        conditions: Conditions {
+            callsign: "???".to_string(),
+            mode: None,
+            ..Default::default()
+        },
+        comment: String::new(),
+        actions: vec![],
+        options: json!({}),
+    };
+    let err = client_for(&server).add_trigger(&trigger).await.unwrap_err();
+    let Error::Rejected { reason, .. } = err else {
+        panic!("expected a rejection, got {:?}", err);
+    };
+    assert_eq!(
+        reason,
+        Rejection::InvalidCondition("Invalid callsign".to_string())
+    );
+}
+
+fn club_trigger() -> TriggerData {
+    TriggerData {
+        conditions: Conditions {
+            callsign: "W1AW".to_string(),
+            ..Default::default()
+        },
+        comment: "Club".to_string(),
+        actions: vec!["app".to_string()],
+        options: json!({}),
+    }
+}
+
+#[tokio::test]
+async fn add_trigger_returns_new_id() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_update"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"{"success":true,"_id":"65a1f0c2e4b0a1b2c3d4e5f8"}"#,
+            "application/json",
+        ))
+        .mount(&server)
+        .await;
+
+    let id = client_for(&server)
+        .add_trigger(&club_trigger())
+        .await
+        .unwrap();
+    assert_eq!(id.as_deref(), Some("65a1f0c2e4b0a1b2c3d4e5f8"));
+}
+
+#[tokio::test]
+async fn add_trigger_reports_limit_in_successful_reply() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_update"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"{"success":false,"error":"Trigger limit exceeded"}"#,
+            "application/json",
+        ))
+        .mount(&server)
+        .await;
+
+    let err = client_for(&server)
+        .add_trigger(&club_trigger())
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            Error::Rejected {
+                reason: Rejection::LimitExceeded,
+                ..
+            }
+        ),
+        "{:?}",
+        err
+    );
+    assert!(
+        err.to_string()
+            .starts_with("Failed to add trigger for W1AW: ")
+    );
+}
+
+#[tokio::test]
+async fn create_trigger_reports_duplicate_from_html() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_update"))
+        .respond_with(
+            ResponseTemplate::new(409).set_body_string(
+                "<html><body><p class=\"error\">Duplicate trigger</p></body></html>",
+            ),
+        )
+        .mount(&server)
+        .await;
+
+    let err = client_for(&server)
+        .create_trigger(&fixture_triggers()[0])
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::Rejected {
+                reason: Rejection::Duplicate,
+                ..
+            }
+        ),
+        "{:?}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn restore_deletes_existing_and_recreates_without_ids() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_delete"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_update"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server);
+    let backup = fixture_triggers();
+    for trigger in &backup {
+        client.delete_trigger(&trigger.id).await.unwrap();
+    }
+    for trigger in &backup {
+        client.create_trigger(trigger).await.unwrap();
+    }
+
+    let deleted: Vec<String> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|r| r.url.path() == "/ajax/trigger_delete")
+        .map(|r| String::from_utf8_lossy(&r.body).into_owned())
+        .collect();
+    assert_eq!(
+        deleted,
+        vec!["id=65a1f0c2e4b0a1b2c3d4e5f6", "id=65a1f0c2e4b0a1b2c3d4e5f7"]
+    );
+
+    let created = json_bodies(&server, "/ajax/trigger_update").await;
+    assert!(created.iter().all(|body| body.get("_id").is_none()));
+    assert_eq!(created[0]["comment"], "Club stations");
+    // Triggers without options are created with an empty options object
+    assert_eq!(created[1]["options"], json!({}));
+}
+
+#[tokio::test]
+async fn edit_updates_trigger_in_place() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_update"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mut trigger = fixture_triggers().remove(1);
+    trigger.comment = "DXpedition (edited)".to_string();
+    client_for(&server).update_trigger(&trigger).await.unwrap();
+
+    let bodies = json_bodies(&server, "/ajax/trigger_update").await;
+    assert_eq!(bodies[0]["_id"], "65a1f0c2e4b0a1b2c3d4e5f7");
+    assert_eq!(bodies[0]["comment"], "DXpedition (edited)");
+    assert_eq!(bodies[0]["disabled"], true);
+}
+
+#[tokio::test]
+async fn update_destinations_keeps_unknown_settings() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/ajax/destinations"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "url": "https://example.com/hook",
+            "threemaId": "ABCD1234",
+            "telnetEnabled": false,
+            "pushoverUser": "u123"
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/destinations_update"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server);
+    let mut destinations = client.destinations().await.unwrap();
+    assert_eq!(destinations.threema_id.as_deref(), Some("ABCD1234"));
+    destinations.telnet_enabled = Some(true);
+    destinations.url = None;
+    client.update_destinations(&destinations).await.unwrap();
+
+    let bodies = json_bodies(&server, "/ajax/destinations_update").await;
+    assert_eq!(
+        bodies[0],
+        json!({
+            "threemaId": "ABCD1234",
+            "telnetEnabled": true,
+            "pushoverUser": "u123"
+        })
+    );
+}
+
+#[tokio::test]
+async fn create_is_not_sent_again_after_timeout() {
+    let server = MockServer::start().await;
+    // HamAlert stores the trigger but answers too late
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_update"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(r#"{"success":true,"_id":"65a1f0c2"}"#, "application/json")
+                .set_delay(Duration::from_millis(500)),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = HamAlertClient::with_timeouts(Duration::from_millis(100), Duration::from_secs(1))
+        .unwrap()
+        .with_base_url(&server.uri());
+    let err = client
+        .create_trigger(&fixture_triggers()[0])
+        .await
+        .unwrap_err();
+    assert!(err.is_timeout(), "{:?}", err);
+}
+
+#[tokio::test]
+async fn update_is_retried_after_server_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_update"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_update"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    client_for(&server)
+        .update_trigger(&fixture_triggers()[0])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn delete_is_not_retried_after_server_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/ajax/trigger_delete"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let err = client_for(&server)
+        .delete_trigger("65a1f0c2e4b0a1b2c3d4e5f6")
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::Api { status, .. } if status == 503),
+        "{:?}",
+        err
+    );
+}
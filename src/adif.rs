@@ -0,0 +1,256 @@
+//! ADIF log parsing and export.
+
+use crate::model::{Spot, band_for_frequency, normalize_mode};
+use std::collections::HashMap;
+
+/// Parse ADIF records into field maps with lowercase field names.
+/// The header (anything before <EOH>) is skipped.
+pub(crate) fn parse_adif(content: &str) -> Vec<HashMap<String, String>> {
+    let lower = content.to_lowercase();
+    let mut pos = lower.find("<eoh>").map(|i| i + 5).unwrap_or(0);
+    let mut records = vec![];
+    let mut record = HashMap::new();
+
+    while let Some(start) = content[pos..].find('<').map(|i| pos + i) {
+        let Some(end) = content[start..].find('>').map(|i| start + i) else {
+            break;
+        };
+        let tag = &content[start + 1..end];
+        let mut parts = tag.split(':');
+        let name = parts.next().unwrap_or_default().trim().to_lowercase();
+        pos = end + 1;
+
+        if name == "eor" {
+            if !record.is_empty() {
+                records.push(std::mem::take(&mut record));
+            }
+            continue;
+        }
+        let Some(length) = parts.next().and_then(|l| l.trim().parse::<usize>().ok()) else {
+            continue;
+        };
+        let value_end = content[pos..]
+            .char_indices()
+            .nth(length)
+            .map(|(i, _)| pos + i)
+            .unwrap_or(content.len());
+        record.insert(name, content[pos..value_end].trim().to_string());
+        pos = value_end;
+    }
+    records
+}
+
+/// Format a single ADIF field
+pub(crate) fn adif_field(name: &str, value: &str) -> String {
+    format!("<{}:{}>{}", name, value.chars().count(), value)
+}
+
+/// Convert spots into an ADIF document of SWL records, suitable for
+/// importing into a logger as a "to work" list
+pub(crate) fn spots_to_adif(spots: &[Spot]) -> String {
+    let mut out = format!(
+        "Spots exported by hamalert-cli\n{} {} <EOH>\n",
+        adif_field("ADIF_VER", "3.1.4"),
+        adif_field("PROGRAMID", "hamalert-cli")
+    );
+    for spot in spots {
+        let mut fields = vec![
+            adif_field("CALL", &spot.callsign),
+            adif_field("FREQ", &format!("{:.4}", spot.frequency_khz / 1000.0)),
+        ];
+        if let Some(band) = spot.band() {
+            fields.push(adif_field("BAND", band));
+        }
+        if let Some(mode) = &spot.mode {
+            fields.push(adif_field("MODE", &normalize_mode(mode)));
+        }
+        if let Some(time) = spot_time(spot) {
+            fields.push(adif_field("TIME_ON", &time));
+        }
+        if !spot.comment.is_empty() {
+            fields.push(adif_field("COMMENT", &spot.comment));
+        }
+        fields.push(adif_field("SWL", "Y"));
+        out.push_str(&fields.join(" "));
+        out.push_str(" <EOR>\n");
+    }
+    out
+}
+
+/// The HHMM time cluster spots carry at the end of the comment, if any
+pub(crate) fn spot_time(spot: &Spot) -> Option<String> {
+    let last = spot.comment.split_whitespace().last()?;
+    let digits = last.strip_suffix('Z')?;
+    (digits.len() == 4 && digits.chars().all(|c| c.is_ascii_digit())).then(|| digits.to_string())
+}
+
+/// How a spot relates to stations already in the log
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum WorkedStatus {
+    /// Never worked this callsign
+    NewOne,
+    /// Worked, but never on this band
+    NewBand,
+    /// Worked on this band, but never in this mode
+    NewMode,
+    /// Worked on this band and mode, not confirmed
+    Worked,
+    /// Confirmed on this band and mode
+    Confirmed,
+}
+
+impl WorkedStatus {
+    pub(crate) fn marker(&self) -> &'static str {
+        match self {
+            WorkedStatus::NewOne => "NEW",
+            WorkedStatus::NewBand => "BND",
+            WorkedStatus::NewMode => "MOD",
+            WorkedStatus::Worked => "wkd",
+            WorkedStatus::Confirmed => "cfm",
+        }
+    }
+}
+
+/// Worked-before index built from an ADIF log
+#[derive(Default)]
+pub(crate) struct WorkedBefore {
+    pub(crate) calls: std::collections::HashSet<String>,
+    pub(crate) call_bands: std::collections::HashSet<(String, String)>,
+    pub(crate) call_band_modes: std::collections::HashSet<(String, String, String)>,
+    pub(crate) confirmed: std::collections::HashSet<(String, String, String)>,
+}
+
+impl WorkedBefore {
+    pub(crate) fn from_records(records: &[HashMap<String, String>]) -> Self {
+        let mut worked = WorkedBefore::default();
+        for record in records {
+            let Some(call) = record.get("call").map(|c| c.to_uppercase()) else {
+                continue;
+            };
+            let band = record.get("band").map(|b| b.to_lowercase()).or_else(|| {
+                record
+                    .get("freq")
+                    .and_then(|f| f.parse::<f64>().ok())
+                    .and_then(|mhz| band_for_frequency(mhz * 1000.0))
+                    .map(|b| b.to_string())
+            });
+            let mode = record
+                .get("submode")
+                .or_else(|| record.get("mode"))
+                .map(|m| normalize_mode(m));
+            let is_confirmed = ["qsl_rcvd", "lotw_qsl_rcvd", "eqsl_qsl_rcvd"]
+                .iter()
+                .any(|field| {
+                    record
+                        .get(*field)
+                        .is_some_and(|v| v.eq_ignore_ascii_case("y"))
+                });
+
+            worked.calls.insert(call.clone());
+            if let Some(band) = band {
+                worked.call_bands.insert((call.clone(), band.clone()));
+                if let Some(mode) = mode {
+                    let key = (call, band, mode);
+                    if is_confirmed {
+                        worked.confirmed.insert(key.clone());
+                    }
+                    worked.call_band_modes.insert(key);
+                }
+            }
+        }
+        worked
+    }
+
+    pub(crate) fn status(&self, spot: &Spot) -> WorkedStatus {
+        let call = spot.callsign.to_uppercase();
+        if !self.calls.contains(&call) {
+            return WorkedStatus::NewOne;
+        }
+        let Some(band) = spot.band() else {
+            return WorkedStatus::Worked;
+        };
+        if !self.call_bands.contains(&(call.clone(), band.to_string())) {
+            return WorkedStatus::NewBand;
+        }
+        let Some(mode) = spot.mode.as_deref().map(normalize_mode) else {
+            return WorkedStatus::Worked;
+        };
+        let key = (call, band.to_string(), mode);
+        if self.confirmed.contains(&key) {
+            WorkedStatus::Confirmed
+        } else if self.call_band_modes.contains(&key) {
+            WorkedStatus::Worked
+        } else {
+            WorkedStatus::NewMode
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::parse_spot_line;
+
+    #[test]
+    fn test_parse_adif() {
+        let content = "Exported log <ADIF_VER:5>3.1.4 <EOH>\n\
+            <CALL:4>W1AW <BAND:3>20m <MODE:2>CW <QSL_RCVD:1>Y <EOR>\n\
+            <call:5>K2DEF <freq:6>7.0740 <mode:4>MFSK <submode:3>FT4 <eor>\n";
+        let records = parse_adif(content);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["call"], "W1AW");
+        assert_eq!(records[0]["band"], "20m");
+        assert_eq!(records[1]["submode"], "FT4");
+        assert!(!records[0].contains_key("adif_ver"));
+    }
+
+    #[test]
+    fn test_worked_before_status() {
+        let records = parse_adif(
+            "<EOH><CALL:4>W1AW <BAND:3>20M <MODE:2>CW <LOTW_QSL_RCVD:1>Y <EOR>\
+             <CALL:4>W1AW <FREQ:5>7.030 <MODE:3>SSB <EOR>",
+        );
+        let worked = WorkedBefore::from_records(&records);
+        let spot = |line: &str| parse_spot_line(line).unwrap();
+
+        assert_eq!(
+            worked.status(&spot("DX de K1TTT: 14025.0 K2DEF CW")),
+            WorkedStatus::NewOne
+        );
+        assert_eq!(
+            worked.status(&spot("DX de K1TTT: 21025.0 W1AW CW")),
+            WorkedStatus::NewBand
+        );
+        assert_eq!(
+            worked.status(&spot("DX de K1TTT: 14074.0 W1AW FT8")),
+            WorkedStatus::NewMode
+        );
+        assert_eq!(
+            worked.status(&spot("DX de K1TTT: 14025.0 W1AW CW")),
+            WorkedStatus::Confirmed
+        );
+        assert_eq!(
+            worked.status(&spot("DX de K1TTT: 7185.0 W1AW LSB")),
+            WorkedStatus::Worked
+        );
+    }
+
+    #[test]
+    fn test_spots_to_adif_round_trip() {
+        let spots = vec![
+            parse_spot_line("DX de K1TTT:    14025.0  W1AW         CW 599 1234Z").unwrap(),
+            parse_spot_line("DX de N2XYZ:     7185.0  VP8LP        LSB").unwrap(),
+        ];
+        let document = spots_to_adif(&spots);
+        let records = parse_adif(&document);
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["call"], "W1AW");
+        assert_eq!(records[0]["freq"], "14.0250");
+        assert_eq!(records[0]["band"], "20m");
+        assert_eq!(records[0]["time_on"], "1234");
+        assert_eq!(records[0]["swl"], "Y");
+        assert_eq!(records[1]["mode"], "SSB");
+        assert!(!records[1].contains_key("time_on"));
+    }
+}
@@ -0,0 +1,266 @@
+//! Trigger access shared by the commands, honoring --offline.
+
+use crate::error::CliError;
+use crate::model::strip_callsign_count;
+use crate::storage::backups::{load_trigger_snapshot, save_trigger_snapshot};
+use crate::storage::history::record_match_counts;
+use crate::storage::state::{ManagedTrigger, find_new_trigger_id, load_state, save_state, slugify};
+use hamalert_api::{Conditions, HamAlertClient, Trigger, TriggerData};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by --offline: triggers come from the local snapshot instead of HamAlert
+pub(crate) static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+pub(crate) async fn fetch_triggers(client: &HamAlertClient) -> Result<Vec<Trigger>, CliError> {
+    if OFFLINE.load(Ordering::Relaxed) {
+        let snapshot = load_trigger_snapshot()?;
+        info!(
+            "Offline: using {} cached triggers from {}",
+            snapshot.triggers.len(),
+            snapshot.fetched_at
+        );
+        return Ok(snapshot.triggers);
+    }
+
+    let triggers = client.triggers().await?;
+    if let Err(e) = save_trigger_snapshot(&triggers) {
+        info!("Could not cache triggers for --offline: {}", e);
+    }
+    if let Err(e) = record_match_counts(&triggers) {
+        info!("Could not record matchCount history: {}", e);
+    }
+    Ok(triggers)
+}
+
+/// Requests kept in flight at once by bulk operations
+pub(crate) const API_CONCURRENCY: usize = 4;
+
+/// Run `op` for every item with at most `API_CONCURRENCY` requests in flight,
+/// stopping at the first error
+pub(crate) async fn for_each_concurrent<T, F, Fut>(
+    items: impl IntoIterator<Item = T>,
+    op: F,
+) -> Result<(), CliError>
+where
+    F: FnMut(T) -> Fut,
+    Fut: std::future::Future<Output = Result<(), CliError>>,
+{
+    use futures::stream::{self, StreamExt, TryStreamExt};
+
+    stream::iter(items)
+        .map(op)
+        .buffer_unordered(API_CONCURRENCY)
+        .try_collect::<Vec<()>>()
+        .await?;
+    Ok(())
+}
+
+/// Like `for_each_concurrent`, returning each item's result in input order
+pub(crate) async fn map_concurrent<T, R, F, Fut>(
+    items: impl IntoIterator<Item = T>,
+    op: F,
+) -> Result<Vec<R>, CliError>
+where
+    F: FnMut(T) -> Fut,
+    Fut: std::future::Future<Output = Result<R, CliError>>,
+{
+    use futures::stream::{self, StreamExt, TryStreamExt};
+
+    stream::iter(items)
+        .map(op)
+        .buffered(API_CONCURRENCY)
+        .try_collect()
+        .await
+}
+
+/// Create a callsign trigger, returning its id when HamAlert reports it
+pub(crate) async fn add_trigger(
+    client: &HamAlertClient,
+    conditions: Conditions,
+    comment: &str,
+    actions: Vec<String>,
+) -> Result<Option<String>, CliError> {
+    let trigger_data = TriggerData {
+        conditions,
+        comment: comment.to_string(),
+        actions,
+        options: json!({}),
+    };
+
+    let id = client.add_trigger(&trigger_data).await?;
+    match &id {
+        Some(id) => info!("Added trigger '{}' ({})", comment, id),
+        None => info!("Added trigger '{}'", comment),
+    }
+    Ok(id)
+}
+
+/// A trigger that was just created, to be tracked in the state file
+pub(crate) struct NewTrigger {
+    /// Id HamAlert reported for the trigger, if any
+    pub(crate) id: Option<String>,
+    /// Requested name; defaults to the comment as a slug
+    pub(crate) name: Option<String>,
+    pub(crate) comment: String,
+    pub(crate) metadata: ManagedTrigger,
+}
+
+/// A trigger a command created, as reported to the user
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct CreatedTrigger {
+    /// None when HamAlert did not report it and it could not be found
+    pub(crate) id: Option<String>,
+    /// Name in the state file, if it was recorded there
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) name: Option<String>,
+    pub(crate) comment: String,
+}
+
+/// Record triggers just created in the state file, looking up the ids
+/// HamAlert did not report, and return them with their ids and names. The
+/// triggers already exist, so failures are only reported.
+pub(crate) async fn remember_new_triggers(
+    client: &HamAlertClient,
+    created: Vec<NewTrigger>,
+) -> Vec<CreatedTrigger> {
+    let mut results: Vec<CreatedTrigger> = created
+        .iter()
+        .map(|new| CreatedTrigger {
+            id: new.id.clone(),
+            name: None,
+            comment: new.comment.clone(),
+        })
+        .collect();
+    let result: Result<(), CliError> = async {
+        let triggers = if created.iter().any(|new| new.id.is_none()) {
+            fetch_triggers(client).await?
+        } else {
+            Vec::new()
+        };
+        let mut state = load_state()?;
+        for (new, result) in created.into_iter().zip(results.iter_mut()) {
+            let id = match new.id {
+                Some(id) => id,
+                None => match find_new_trigger_id(&state, &triggers, &new.comment) {
+                    Some(id) => id.to_string(),
+                    None => {
+                        info!(
+                            "Could not find the new trigger '{}' to name it",
+                            new.comment
+                        );
+                        continue;
+                    }
+                },
+            };
+            let name = match new.name {
+                Some(name) => name,
+                None => state.unique_name(&slugify(strip_callsign_count(&new.comment))),
+            };
+            let metadata = ManagedTrigger {
+                id: id.clone(),
+                ..new.metadata
+            };
+            state.record(&name, metadata);
+            info!("Tracking trigger '{}' as {}", new.comment, name);
+            result.id = Some(id);
+            result.name = Some(name);
+        }
+        save_state(&state)
+    }
+    .await;
+    if let Err(e) = result {
+        info!("Could not record new triggers in the local state: {}", e);
+    }
+    results
+}
+
+/// Fill in the ids HamAlert did not report by matching comments against the
+/// triggers now on the account. Each gets the newest trigger with its comment
+/// that is not in `before` and not already taken; ids start with their
+/// creation time, so the largest is the newest.
+pub(crate) fn match_created_ids(
+    created: &mut [CreatedTrigger],
+    live: &[Trigger],
+    before: &[Trigger],
+) {
+    let mut taken: Vec<String> = before.iter().map(|t| t.id.clone()).collect();
+    taken.extend(created.iter().filter_map(|c| c.id.clone()));
+    for trigger in created.iter_mut().filter(|c| c.id.is_none()) {
+        trigger.id = live
+            .iter()
+            .filter(|t| t.comment == trigger.comment && !taken.contains(&t.id))
+            .map(|t| t.id.clone())
+            .max();
+        taken.extend(trigger.id.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_for_each_concurrent_bounds_in_flight() {
+        use std::sync::atomic::AtomicUsize;
+
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        for_each_concurrent(0..20, |_| async {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        })
+        .await
+        .unwrap();
+        assert_eq!(peak.load(Ordering::SeqCst), API_CONCURRENCY);
+
+        let failed = for_each_concurrent(0..5, |i| async move {
+            if i == 3 {
+                Err(CliError::Other("boom".into()))
+            } else {
+                Ok(())
+            }
+        })
+        .await;
+        assert!(failed.is_err());
+    }
+
+    #[test]
+    fn test_match_created_ids() {
+        let trigger = |id: &str, comment: &str| Trigger {
+            id: id.to_string(),
+            user_id: None,
+            conditions: json!({}),
+            actions: vec![],
+            comment: comment.to_string(),
+            match_count: None,
+            disabled: None,
+            options: None,
+        };
+        let created = |id: Option<&str>, comment: &str| CreatedTrigger {
+            id: id.map(str::to_string),
+            name: None,
+            comment: comment.to_string(),
+        };
+        let before = vec![trigger("1", "Club")];
+        let live = vec![
+            trigger("1", "Club"),
+            trigger("3", "Club"),
+            trigger("4", "Club"),
+            trigger("5", "DX"),
+        ];
+        let mut restored = vec![
+            created(None, "Club"),
+            created(Some("4"), "Club"),
+            created(None, "DX"),
+            created(None, "Gone"),
+        ];
+        match_created_ids(&mut restored, &live, &before);
+        let ids: Vec<Option<&str>> = restored.iter().map(|c| c.id.as_deref()).collect();
+        assert_eq!(ids, vec![Some("3"), Some("4"), Some("5"), None]);
+    }
+}
@@ -0,0 +1,372 @@
+//! Callsign syntax checks: a base call (prefix, number, suffix) with an
+//! optional portable prefix (`EA8/`) and designator (`/P`, `/MM`, `/4`), or
+//! a prefix wildcard such as `JA*`.
+
+use crate::error::CliError;
+use crate::output::{CYAN, YELLOW, paint};
+use std::fmt;
+
+/// Designators accepted after the base call besides a call area digit or a
+/// country prefix
+const DESIGNATORS: [&str; 8] = ["P", "M", "MM", "AM", "A", "QRP", "R", "B"];
+
+/// A syntactically valid callsign, uppercased
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Callsign {
+    /// Country prefix when operating abroad, e.g. `EA8` in `EA8/K1ABC`
+    pub(crate) prefix: Option<String>,
+    /// The station's own call, e.g. `K1ABC`
+    pub(crate) base: String,
+    /// Designator such as `P`, `MM`, a call area digit or a country prefix
+    pub(crate) suffix: Option<String>,
+}
+
+impl fmt::Display for Callsign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(prefix) = &self.prefix {
+            write!(f, "{}/", prefix)?;
+        }
+        write!(f, "{}", self.base)?;
+        if let Some(suffix) = &self.suffix {
+            write!(f, "/{}", suffix)?;
+        }
+        Ok(())
+    }
+}
+
+impl Callsign {
+    /// Prefix the station operates under instead of its base call's: the
+    /// portable prefix (`EA8/K1ABC`) or a country prefix given as the
+    /// designator (`W1AW/KH6`)
+    pub(crate) fn location_prefix(&self) -> Option<&str> {
+        self.prefix.as_deref().or(self
+            .suffix
+            .as_deref()
+            .filter(|s| !DESIGNATORS.contains(s) && !s.chars().all(|c| c.is_ascii_digit())))
+    }
+
+    /// Whether the station is maritime or aeronautical mobile, which counts
+    /// for no DXCC entity
+    pub(crate) fn is_maritime(&self) -> bool {
+        matches!(self.suffix.as_deref(), Some("MM" | "AM"))
+    }
+}
+
+/// Whether `part` is a base call: a prefix of one to three characters with
+/// at least one letter, one to four digits, then one to four letters
+fn is_base(part: &str) -> bool {
+    let letters = part
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_uppercase())
+        .count();
+    let rest = &part[..part.len() - letters];
+    let digits = rest
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    let prefix = &rest[..rest.len() - digits];
+    (1..=4).contains(&letters)
+        && (1..=4).contains(&digits)
+        && (1..=3).contains(&prefix.len())
+        && prefix.chars().all(|c| c.is_ascii_alphanumeric())
+        && prefix.chars().any(|c| c.is_ascii_uppercase())
+}
+
+/// Whether `part` looks like a country prefix such as `F`, `EA8`, `VP2E` or `3D2`
+fn is_prefix(part: &str) -> bool {
+    (1..=4).contains(&part.len())
+        && part.chars().all(|c| c.is_ascii_alphanumeric())
+        && part.chars().any(|c| c.is_ascii_uppercase())
+}
+
+fn is_suffix(part: &str) -> bool {
+    DESIGNATORS.contains(&part)
+        || (part.len() == 1 && part.chars().all(|c| c.is_ascii_digit()))
+        || is_prefix(part)
+}
+
+/// Parse `input` as a callsign, explaining what is wrong if it is not one
+pub(crate) fn parse_callsign(input: &str) -> Result<Callsign, String> {
+    let call = input.trim().to_ascii_uppercase();
+    if call.is_empty() {
+        return Err("callsign is empty".to_string());
+    }
+    if let Some(c) = call
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '/')
+    {
+        return Err(format!("'{}' is not allowed in a callsign", c));
+    }
+
+    let parts: Vec<&str> = call.split('/').collect();
+    let callsign = |prefix: Option<&str>, base: &str, suffix: Option<&str>| Callsign {
+        prefix: prefix.map(str::to_string),
+        base: base.to_string(),
+        suffix: suffix.map(str::to_string),
+    };
+    match parts.as_slice() {
+        [base] if is_base(base) => Ok(callsign(None, base, None)),
+        [base, suffix] if is_base(base) && is_suffix(suffix) => {
+            Ok(callsign(None, base, Some(suffix)))
+        }
+        [prefix, base] if is_prefix(prefix) && is_base(base) => {
+            Ok(callsign(Some(prefix), base, None))
+        }
+        [prefix, base, suffix] if is_prefix(prefix) && is_base(base) && is_suffix(suffix) => {
+            Ok(callsign(Some(prefix), base, Some(suffix)))
+        }
+        _ if parts.len() > 3 || parts.iter().any(|p| p.is_empty()) => {
+            Err("expected PREFIX/CALL/SUFFIX with no empty parts".to_string())
+        }
+        _ => Err("expected a prefix, a number and a suffix, like K1ABC or 2E0ABC".to_string()),
+    }
+}
+
+/// Why a pattern with other wildcards may not match what was meant
+const UNSUPPORTED_WILDCARD: &str =
+    "HamAlert only expands a single trailing '*', as in JA*; other wildcards match literally";
+
+/// One entry of a callsign condition
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum CallsignPattern {
+    /// A single station
+    Exact(Callsign),
+    /// Every callsign starting with this text, written `JA*`
+    Prefix(String),
+    /// Wildcards HamAlert does not expand, kept as typed
+    Unsupported(String),
+}
+
+impl fmt::Display for CallsignPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallsignPattern::Exact(call) => write!(f, "{}", call),
+            CallsignPattern::Prefix(prefix) => write!(f, "{}*", prefix),
+            CallsignPattern::Unsupported(pattern) => write!(f, "{}", pattern),
+        }
+    }
+}
+
+/// Parse `input` as a callsign or a prefix wildcard
+pub(crate) fn parse_pattern(input: &str) -> Result<CallsignPattern, String> {
+    let pattern = input.trim().to_ascii_uppercase();
+    if !pattern.contains(['*', '?']) {
+        return parse_callsign(&pattern).map(CallsignPattern::Exact);
+    }
+    if let Some(c) = pattern
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !"/*?".contains(*c))
+    {
+        return Err(format!("'{}' is not allowed in a callsign", c));
+    }
+    match pattern.strip_suffix('*') {
+        Some("") => Err("'*' alone would match every callsign".to_string()),
+        Some(prefix)
+            if !prefix.contains(['*', '?'])
+                && prefix.starts_with(|c: char| c.is_ascii_alphanumeric())
+                && prefix.chars().any(|c| c.is_ascii_uppercase()) =>
+        {
+            Ok(CallsignPattern::Prefix(prefix.to_string()))
+        }
+        Some(prefix) if !prefix.contains(['*', '?']) => {
+            Err("a wildcard prefix needs at least one letter, as in JA* or VP8*".to_string())
+        }
+        _ => Ok(CallsignPattern::Unsupported(pattern)),
+    }
+}
+
+/// Uppercased callsigns and patterns, failing on the first invalid one and
+/// warning about wildcards HamAlert will not expand
+pub(crate) fn validate_patterns(inputs: &[String]) -> Result<Vec<String>, CliError> {
+    inputs
+        .iter()
+        .map(|input| {
+            let pattern = parse_pattern(input).map_err(|reason| {
+                CliError::Validation(format!("Invalid callsign '{}': {}", input, reason))
+            })?;
+            if let CallsignPattern::Unsupported(pattern) = &pattern {
+                eprintln!(
+                    "{} '{}': {}",
+                    paint("Warning:", YELLOW),
+                    pattern,
+                    UNSUPPORTED_WILDCARD
+                );
+            }
+            Ok(pattern.to_string())
+        })
+        .collect()
+}
+
+/// Split `callsigns` into valid (uppercased) ones and invalid ones with the
+/// reason they were rejected. Wildcards HamAlert will not expand are invalid.
+pub(crate) fn partition_callsigns(callsigns: Vec<String>) -> (Vec<String>, Vec<(String, String)>) {
+    let mut valid = Vec::new();
+    let mut invalid = Vec::new();
+    for call in callsigns {
+        match parse_pattern(&call) {
+            Ok(CallsignPattern::Unsupported(_)) => {
+                invalid.push((call, UNSUPPORTED_WILDCARD.to_string()))
+            }
+            Ok(pattern) => valid.push(pattern.to_string()),
+            Err(reason) => invalid.push((call, reason)),
+        }
+    }
+    (valid, invalid)
+}
+
+/// A callsign condition with its wildcard patterns colored, keeping the
+/// separators as they are
+pub(crate) fn highlight_patterns(callsigns: &str) -> String {
+    let mut output = String::new();
+    let mut word = String::new();
+    let flush = |word: &mut String, output: &mut String| {
+        if word.contains(['*', '?']) {
+            output.push_str(&paint(word, CYAN));
+        } else {
+            output.push_str(word);
+        }
+        word.clear();
+    };
+    for c in callsigns.chars() {
+        if c == ',' || c.is_whitespace() {
+            flush(&mut word, &mut output);
+            output.push(c);
+        } else {
+            word.push(c);
+        }
+    }
+    flush(&mut word, &mut output);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_calls() {
+        for call in [
+            "W1AW", "K1ABC", "2E0ABC", "9A1A", "3DA0RU", "4X4XX", "HB9ABC", "VP8LP", "T88XX",
+            "W100AW", "E73ABC",
+        ] {
+            assert!(parse_callsign(call).is_ok(), "{} should be valid", call);
+        }
+        assert_eq!(parse_callsign(" k1abc ").unwrap().to_string(), "K1ABC");
+    }
+
+    #[test]
+    fn test_portable_calls() {
+        let call = parse_callsign("EA8/K1ABC/P").unwrap();
+        assert_eq!(call.prefix.as_deref(), Some("EA8"));
+        assert_eq!(call.base, "K1ABC");
+        assert_eq!(call.suffix.as_deref(), Some("P"));
+
+        assert_eq!(
+            parse_callsign("k1abc/mm").unwrap().suffix.as_deref(),
+            Some("MM")
+        );
+        assert_eq!(
+            parse_callsign("W1AW/4").unwrap().suffix.as_deref(),
+            Some("4")
+        );
+        assert_eq!(
+            parse_callsign("W1AW/KH6").unwrap().suffix.as_deref(),
+            Some("KH6")
+        );
+        assert_eq!(
+            parse_callsign("F/G4ABC").unwrap().prefix.as_deref(),
+            Some("F")
+        );
+    }
+
+    #[test]
+    fn test_invalid_calls() {
+        for call in [
+            "",
+            "ABC",
+            "12345",
+            "K1",
+            "1ABC",
+            "W1AW/",
+            "/W1AW",
+            "K1ABC/PORTABLE",
+            "K1-ABC",
+            "my",
+            "EA8/K1ABC/P/QRP",
+            "K1ABCDEF",
+        ] {
+            assert!(parse_callsign(call).is_err(), "{} should be invalid", call);
+        }
+        let err = validate_patterns(&["K1-ABC".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("'-' is not allowed"));
+    }
+
+    #[test]
+    fn test_partition_callsigns() {
+        let (valid, invalid) = partition_callsigns(vec![
+            "w1aw".to_string(),
+            "Notes:".to_string(),
+            "EA8/K1ABC".to_string(),
+        ]);
+        assert_eq!(valid, vec!["W1AW", "EA8/K1ABC"]);
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].0, "Notes:");
+    }
+
+    #[test]
+    fn test_parse_pattern() {
+        assert_eq!(
+            parse_pattern("ja*").unwrap(),
+            CallsignPattern::Prefix("JA".to_string())
+        );
+        assert_eq!(parse_pattern("VP8*").unwrap().to_string(), "VP8*");
+        assert_eq!(parse_pattern("EA8/*").unwrap().to_string(), "EA8/*");
+        assert!(matches!(
+            parse_pattern("W1AW").unwrap(),
+            CallsignPattern::Exact(_)
+        ));
+        for pattern in ["*ABC", "K*1", "JA?", "JA**"] {
+            assert!(
+                matches!(
+                    parse_pattern(pattern).unwrap(),
+                    CallsignPattern::Unsupported(_)
+                ),
+                "{} should be unsupported",
+                pattern
+            );
+        }
+        assert!(parse_pattern("*").is_err());
+        assert!(parse_pattern("12*").is_err());
+        assert!(parse_pattern("JA-*").is_err());
+
+        assert_eq!(
+            validate_patterns(&["ja*".to_string(), "k*1".to_string()]).unwrap(),
+            vec!["JA*", "K*1"]
+        );
+        let (valid, invalid) = partition_callsigns(vec!["JA*".to_string(), "*X".to_string()]);
+        assert_eq!(valid, vec!["JA*"]);
+        assert_eq!(invalid[0].0, "*X");
+    }
+
+    #[test]
+    fn test_highlight_patterns_keeps_separators() {
+        assert_eq!(highlight_patterns("W1AW, JA*\nVP8*"), "W1AW, JA*\nVP8*");
+    }
+
+    #[test]
+    fn test_location_prefix() {
+        let prefix = |call: &str| {
+            parse_callsign(call)
+                .unwrap()
+                .location_prefix()
+                .map(str::to_string)
+        };
+        assert_eq!(prefix("EA8/K1ABC/P").as_deref(), Some("EA8"));
+        assert_eq!(prefix("W1AW/KH6").as_deref(), Some("KH6"));
+        assert_eq!(prefix("W1AW/4"), None);
+        assert_eq!(prefix("K1ABC/QRP"), None);
+        assert!(parse_callsign("G4ABC/MM").unwrap().is_maritime());
+    }
+}
@@ -0,0 +1,1439 @@
+//! Command-line arguments and subcommand definitions.
+
+use crate::commands::export::CallsignListFormat;
+use crate::commands::import::OverLimit;
+use crate::commands::list::{ListColumn, ListGroup, ListOutput};
+use crate::commands::spots::QueryOutput;
+use crate::config::TriggerDefaults;
+use crate::error::CliError;
+use crate::output::ColorChoice;
+use crate::pipeline::{Highlight, parse_highlight};
+use crate::storage::state::{ManagedTrigger, retain_named, retain_tagged};
+use clap::{Parser, Subcommand, ValueEnum};
+use hamalert_api::Trigger;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "hamalert-cli")]
+#[command(about = "CLI for HamAlert API", long_about = None)]
+pub(crate) struct Cli {
+    #[arg(long)]
+    pub(crate) config_file: Option<PathBuf>,
+
+    /// Print machine-readable JSON on stdout for read commands
+    #[arg(long, global = true)]
+    pub(crate) json: bool,
+
+    /// Print only the values at this path of the JSON output, one per line
+    /// (e.g. '[].comment'); implies --json
+    #[arg(long, global = true, value_name = "PATH")]
+    pub(crate) get: Option<String>,
+
+    /// Suppress informational status messages
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub(crate) quiet: bool,
+
+    /// Log API requests (-v) and their redacted payloads (-vv) to stderr
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub(crate) verbose: u8,
+
+    /// Append logs to this file instead of stderr
+    #[arg(long, global = true, value_name = "PATH")]
+    pub(crate) log_file: Option<PathBuf>,
+
+    /// Ask for the HamAlert password instead of reading it from the config
+    #[arg(long, global = true)]
+    pub(crate) prompt_password: bool,
+
+    /// Use the cached trigger snapshot instead of contacting HamAlert
+    #[arg(long, global = true)]
+    pub(crate) offline: bool,
+
+    /// Timeout for each HamAlert request, in seconds (default 30)
+    #[arg(long, global = true)]
+    pub(crate) timeout: Option<u64>,
+
+    /// Answer yes to confirmation prompts
+    #[arg(short, long, global = true)]
+    pub(crate) yes: bool,
+
+    /// When to colorize output
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub(crate) color: ColorChoice,
+
+    /// Keep backups, profiles and state in this directory instead of the
+    /// platform data directory
+    #[arg(long, global = true, value_name = "DIR")]
+    pub(crate) data_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub(crate) command: Option<Commands>,
+}
+
+impl Cli {
+    /// Long names of the options before the command that take a value, taken
+    /// from the parser itself so alias expansion can skip over their values
+    pub(crate) fn value_options() -> Vec<String> {
+        <Cli as clap::CommandFactory>::command()
+            .get_arguments()
+            .filter(|arg| arg.get_action().takes_values())
+            .filter_map(|arg| arg.get_long().map(|long| format!("--{}", long)))
+            .collect()
+    }
+}
+
+impl Commands {
+    /// Whether the command talks to HamAlert and therefore needs to log in.
+    /// Purely local commands run without credentials or network access.
+    /// `serve http` only logs in when a route selects triggers by tag.
+    pub(crate) fn needs_api(&self) -> bool {
+        match self {
+            Commands::Spots(_)
+            | Commands::Serve(ServeCommands::Http { .. })
+            | Commands::Config(_)
+            | Commands::CopyAccount { .. }
+            | Commands::Lookup { .. }
+            | Commands::Backup {
+                command: Some(_), ..
+            }
+            | Commands::Restore {
+                to_profile: Some(_),
+                ..
+            }
+            | Commands::Lint { file: Some(_), .. } => false,
+            Commands::Profile(profile_cmd) => match profile_cmd {
+                ProfileCommands::Show { .. }
+                | ProfileCommands::ShowPermanent
+                | ProfileCommands::Delete { .. } => false,
+                ProfileCommands::Save { from_backup, .. }
+                | ProfileCommands::SetPermanent { from_backup } => from_backup.is_none(),
+                ProfileCommands::List
+                | ProfileCommands::Status
+                | ProfileCommands::Switch { .. }
+                | ProfileCommands::Triage => true,
+            },
+            _ => true,
+        }
+    }
+
+    /// Commands accepted by `works_offline`, as listed in the --offline error.
+    /// Keep the two in sync.
+    pub(crate) const OFFLINE_COMMANDS: &[&str] = &[
+        "list",
+        "backup",
+        "share",
+        "plan",
+        "lint",
+        "stats",
+        "check",
+        "report",
+        "export",
+        "names",
+        "spots",
+        "lookup",
+        "restore --to-profile",
+        "config",
+        "profile list/show/status/show-permanent/delete",
+    ];
+
+    /// Whether the command can run from the cached trigger snapshot
+    pub(crate) fn works_offline(&self) -> bool {
+        matches!(
+            self,
+            Commands::List { .. }
+                | Commands::Backup { .. }
+                | Commands::Share { .. }
+                | Commands::Plan { .. }
+                | Commands::Lint { .. }
+                | Commands::Stats { .. }
+                | Commands::Check(_)
+                | Commands::Report(_)
+                | Commands::Export(_)
+                | Commands::Names {
+                    command: None,
+                    prune: false,
+                }
+                | Commands::Spots(_)
+                | Commands::Lookup { update: false, .. }
+                | Commands::Restore {
+                    to_profile: Some(_),
+                    ..
+                }
+                | Commands::Config(_)
+                | Commands::Profile(
+                    ProfileCommands::List
+                        | ProfileCommands::Show { .. }
+                        | ProfileCommands::Status
+                        | ProfileCommands::ShowPermanent
+                        | ProfileCommands::Delete { .. }
+                )
+        )
+    }
+
+    /// Whether the command only shows what it would change
+    pub(crate) fn is_dry_run(&self) -> bool {
+        match self {
+            Commands::Duplicate { dry_run, .. }
+            | Commands::ImportUrl { dry_run, .. }
+            | Commands::BulkDelete { dry_run, .. }
+            | Commands::PruneExpired { dry_run, .. } => *dry_run,
+            Commands::ImportPoloNotes { options, .. } | Commands::ImportFile { options, .. } => {
+                options.dry_run
+            }
+            Commands::Generate(GenerateCommands::Greyline { options }) => options.dry_run,
+            Commands::Restore { no_dry_run, .. }
+            | Commands::CopyAccount { no_dry_run, .. }
+            | Commands::Apply { no_dry_run, .. }
+            | Commands::Contest(
+                ContestCommands::Start { no_dry_run, .. } | ContestCommands::End { no_dry_run, .. },
+            )
+            | Commands::Profile(ProfileCommands::Switch { no_dry_run, .. }) => !*no_dry_run,
+            _ => false,
+        }
+    }
+
+    /// Name of the command if it needs an interactive terminal
+    pub(crate) fn interactive_name(&self) -> Option<&'static str> {
+        match self {
+            Commands::Edit { .. } => Some("edit"),
+            Commands::BulkDelete { .. } => Some("bulk-delete"),
+            Commands::Manage => Some("manage"),
+            Commands::Shell => Some("shell"),
+            Commands::Tui { .. } => Some("tui"),
+            Commands::Profile(ProfileCommands::SetPermanent { .. }) => {
+                Some("profile set-permanent")
+            }
+            Commands::Profile(ProfileCommands::Triage) => Some("profile triage"),
+            Commands::Config(ConfigCommands::Init) => Some("config init"),
+            _ => None,
+        }
+    }
+}
+
+/// Fail fast when an interactive command runs without a terminal (cron, CI, pipes)
+/// instead of hanging on a prompt that can never be answered
+pub(crate) fn require_terminal(command: &str) -> Result<(), CliError> {
+    use std::io::IsTerminal;
+    if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+        return Ok(());
+    }
+    Err(CliError::Validation(format!(
+        "'{}' is interactive and needs a terminal on stdin and stdout. \
+         Use non-interactive commands such as add-trigger, restore or profile switch in scripts.",
+        command
+    )))
+}
+
+/// Shared options for trigger creation
+#[derive(Parser, Clone)]
+pub(crate) struct TriggerOptions {
+    #[arg(long)]
+    pub(crate) comment: String,
+
+    /// Notification actions (e.g., --actions app telnet)
+    #[arg(long, value_enum, num_args = 1..)]
+    pub(crate) actions: Vec<Action>,
+
+    /// Filter by mode (e.g., --mode cw ft8)
+    #[arg(long, value_enum, num_args = 1..)]
+    pub(crate) mode: Vec<Mode>,
+
+    /// Only spots from these DXCC entities, by prefix, name or number
+    /// (e.g., --dxcc VK,ZL)
+    #[arg(long, value_delimiter = ',')]
+    pub(crate) dxcc: Vec<String>,
+
+    /// Only spots of activations on these SOTA summits
+    /// (e.g., --summit-ref W7W/LC-001,W7W/KG-001)
+    #[arg(long, value_delimiter = ',')]
+    pub(crate) summit_ref: Vec<String>,
+
+    /// Confirm summit refs exist via the SOTA API and add their names to the comment
+    #[arg(long)]
+    pub(crate) check_summits: bool,
+
+    /// Only spots of activations in these POTA parks (e.g., --pota-ref US-0001,US-0002).
+    /// Each park is confirmed via the POTA API and its name added to the comment.
+    #[arg(long, value_delimiter = ',')]
+    pub(crate) pota_ref: Vec<String>,
+
+    /// Use compact format (comma-only, no spaces) for callsigns
+    #[arg(long, conflicts_with = "one_per_line")]
+    pub(crate) compact: bool,
+
+    /// Send callsigns one per line instead of comma-separated
+    #[arg(long, conflicts_with = "compact")]
+    pub(crate) one_per_line: bool,
+
+    /// Add a "(N calls)" suffix to the comment when there are several callsigns
+    #[arg(long)]
+    pub(crate) count_suffix: bool,
+
+    /// Name to refer to the new trigger by (default: derived from the comment)
+    #[arg(long)]
+    pub(crate) name: Option<String>,
+
+    /// Tag recorded with the trigger in the local state (repeatable)
+    #[arg(long)]
+    pub(crate) tag: Vec<String>,
+
+    /// Date (YYYY-MM-DD) after which the trigger is no longer wanted
+    #[arg(long)]
+    pub(crate) expires: Option<String>,
+}
+
+impl TriggerOptions {
+    /// Fill in actions, mode and callsign format the user did not pass on the command line
+    pub(crate) fn with_defaults(mut self, defaults: &TriggerDefaults) -> Self {
+        if self.actions.is_empty() {
+            self.actions = defaults.actions.clone();
+        }
+        if self.mode.is_empty() {
+            self.mode = defaults.mode.clone();
+        }
+        self.count_suffix |= defaults.count_suffix;
+        if !self.compact && !self.one_per_line {
+            match defaults.callsign_format {
+                Some(CallsignFormat::Compact) => self.compact = true,
+                Some(CallsignFormat::OnePerLine) => self.one_per_line = true,
+                Some(CallsignFormat::Default) | None => {}
+            }
+        }
+        self
+    }
+
+    /// State metadata for a trigger created with these options by `source`
+    pub(crate) fn managed(&self, source: String) -> Result<ManagedTrigger, CliError> {
+        if let Some(date) = &self.expires {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+                CliError::Validation(format!("--expires must be YYYY-MM-DD, got '{}'", date))
+            })?;
+        }
+        Ok(ManagedTrigger::new(
+            source,
+            self.expires.clone(),
+            self.tag.clone(),
+        ))
+    }
+}
+
+/// Options for `temp-trigger`
+#[derive(Parser, Clone)]
+pub(crate) struct TempTriggerOptions {
+    /// Hours until the trigger expires
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(1..=168))]
+    pub(crate) hours: u32,
+
+    /// Trigger comment (default: "Temporary: <callsigns>")
+    #[arg(long)]
+    pub(crate) comment: Option<String>,
+
+    /// Notification actions (default: from [defaults])
+    #[arg(long, value_enum, num_args = 1..)]
+    pub(crate) actions: Vec<Action>,
+
+    /// Filter by mode (default: from [defaults])
+    #[arg(long, value_enum, num_args = 1..)]
+    pub(crate) mode: Vec<Mode>,
+
+    /// Keep running and delete the trigger when the window ends,
+    /// instead of leaving it to prune-expired
+    #[arg(long)]
+    pub(crate) wait: bool,
+}
+
+/// Changes `duplicate` makes to the copy
+#[derive(Parser, Clone)]
+pub(crate) struct DuplicateOverrides {
+    /// Replace the mode filter (no value: any mode)
+    #[arg(long, value_enum, num_args = 0..)]
+    pub(crate) set_mode: Option<Vec<Mode>>,
+
+    /// Comment of the copy (default: the original's with " (copy)")
+    #[arg(long)]
+    pub(crate) set_comment: Option<String>,
+
+    /// Replace the notification actions
+    #[arg(long, value_enum, num_args = 1..)]
+    pub(crate) set_actions: Vec<Action>,
+}
+
+/// Options for `generate greyline`
+#[derive(Parser, Clone)]
+pub(crate) struct GreylineOptions {
+    /// Your Maidenhead grid locator (e.g., FN31 or FN31pr)
+    #[arg(long)]
+    pub(crate) grid: String,
+
+    /// DXCC entities to watch, by prefix, name or number (e.g., --dxcc VK,ZL)
+    #[arg(long, required = true, value_delimiter = ',')]
+    pub(crate) dxcc: Vec<String>,
+
+    /// Minutes either side of sunrise and sunset to alert for
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u32).range(5..=180))]
+    pub(crate) window: u32,
+
+    /// Notification actions (default: from [defaults])
+    #[arg(long, value_enum, num_args = 1..)]
+    pub(crate) actions: Vec<Action>,
+
+    /// Show the windows without creating or updating triggers
+    #[arg(long)]
+    pub(crate) dry_run: bool,
+}
+
+/// Grouping options for `list`
+#[derive(Parser, Clone)]
+pub(crate) struct ListGroupOptions {
+    /// Show triggers in groups with a count for each
+    #[arg(long, value_enum)]
+    pub(crate) group_by: Option<ListGroup>,
+
+    /// Only show the group headings and counts
+    #[arg(long, requires = "group_by")]
+    pub(crate) collapse: bool,
+}
+
+/// Which triggers a command works on; all of them by default
+#[derive(Parser, Clone, Default)]
+pub(crate) struct TriggerSelection {
+    /// Only include triggers recorded with this tag (repeatable; all must match)
+    #[arg(long)]
+    pub(crate) tag: Vec<String>,
+
+    /// Only include the managed trigger with this name (repeatable; see `names`)
+    #[arg(long)]
+    pub(crate) name: Vec<String>,
+}
+
+impl TriggerSelection {
+    /// Keep only the selected triggers
+    pub(crate) fn retain(&self, triggers: &mut Vec<Trigger>) -> Result<(), CliError> {
+        retain_tagged(triggers, &self.tag)?;
+        retain_named(triggers, &self.name)
+    }
+}
+
+/// Shared options for import commands
+#[derive(Parser, Clone)]
+pub(crate) struct ImportOptions {
+    #[command(flatten)]
+    pub(crate) trigger: TriggerOptions,
+
+    /// What to do when the callsigns do not fit in one trigger
+    #[arg(long, value_enum, default_value_t)]
+    pub(crate) over_limit: OverLimit,
+
+    /// Show what would be added without actually adding triggers
+    #[arg(long)]
+    pub(crate) dry_run: bool,
+}
+
+/// Shortest `apply --interval` accepted, to go easy on HamAlert
+const MIN_INTERVAL_SECS: u64 = 30;
+
+/// A duration such as `90s`, `15m`, `2h` or `1d`; a bare number is minutes
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not a duration like 30s, 15m or 1h", s))?;
+    let multiplier = match unit {
+        "s" => 1,
+        "" | "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(format!("unknown unit '{}'; use s, m, h or d", unit)),
+    };
+    let seconds = number
+        .checked_mul(multiplier)
+        .ok_or_else(|| "duration too large".to_string())?;
+    if seconds == 0 {
+        return Err("must be longer than zero".to_string());
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+/// A `--udp-forward` address: host and port, e.g. 127.0.0.1:2333
+fn parse_udp_address(s: &str) -> Result<String, String> {
+    match s.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => Ok(s.to_string()),
+        _ => Err(format!("'{}' is not a host:port address", s)),
+    }
+}
+
+/// An `apply --interval`: a duration of at least `MIN_INTERVAL_SECS`
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    let interval = parse_duration(s)?;
+    if interval.as_secs() < MIN_INTERVAL_SECS {
+        return Err(format!("must be at least {}s", MIN_INTERVAL_SECS));
+    }
+    Ok(interval)
+}
+
+#[derive(Subcommand)]
+pub(crate) enum Commands {
+    /// Add a trigger for one or more callsigns
+    AddTrigger {
+        #[arg(long)]
+        callsign: Vec<String>,
+
+        #[command(flatten)]
+        options: TriggerOptions,
+    },
+    /// Add a trigger that is deleted again after a number of hours
+    TempTrigger {
+        /// Callsign to watch (repeatable)
+        #[arg(long, required = true)]
+        callsign: Vec<String>,
+
+        #[command(flatten)]
+        options: TempTriggerOptions,
+    },
+    /// Copy an existing trigger with some changes as a new trigger
+    Duplicate {
+        /// Id of the trigger to copy
+        #[arg(long, required_unless_present = "name", conflicts_with = "name")]
+        id: Option<String>,
+
+        /// Name of a managed trigger to copy (see `names`)
+        #[arg(long)]
+        name: Option<String>,
+
+        #[command(flatten)]
+        overrides: DuplicateOverrides,
+
+        /// Show the copy without creating it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Add triggers for all callsigns in a Ham2K PoLo callsign notes file (fetched from URL)
+    ImportPoloNotes {
+        /// URL to a Ham2K PoLo callsign notes file (repeatable)
+        #[arg(long, required_unless_present = "url_file")]
+        url: Vec<String>,
+
+        /// File listing notes URLs, one per line (# and // lines are comments)
+        #[arg(long)]
+        url_file: Option<PathBuf>,
+
+        #[command(flatten)]
+        options: ImportOptions,
+    },
+    /// Import callsigns from a local file (one callsign per line)
+    ImportFile {
+        /// Path to the callsign file
+        #[arg(long)]
+        file: PathBuf,
+
+        #[command(flatten)]
+        options: ImportOptions,
+    },
+    /// Add the triggers of a set published with `share`, skipping ones the
+    /// account already has
+    ImportUrl {
+        /// Gist link, or URL serving the shared set
+        url: String,
+
+        /// Tag to record on the imported triggers (repeatable)
+        #[arg(long)]
+        tag: Vec<String>,
+
+        /// Show what would be added without actually adding triggers
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Publish triggers without ids or account details for others to import;
+    /// prints the set when no destination is given
+    Share {
+        /// Publish as a GitHub gist (needs GITHUB_TOKEN or [share] github_token)
+        #[arg(long, conflicts_with = "paste")]
+        gist: bool,
+
+        /// List the gist publicly instead of making it secret
+        #[arg(long, requires = "gist")]
+        public: bool,
+
+        /// POST the set to this paste endpoint, which answers with its URL
+        #[arg(long, value_name = "URL")]
+        paste: Option<String>,
+
+        /// Description shown with the set
+        #[arg(long)]
+        description: Option<String>,
+
+        #[command(flatten)]
+        selection: TriggerSelection,
+    },
+    /// Backup all triggers to a JSON file
+    #[command(args_conflicts_with_subcommands = true)]
+    Backup {
+        #[command(subcommand)]
+        command: Option<BackupCommands>,
+
+        /// Output file path (default: hamalert-backup-YYYY-MM-DD.json)
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        #[command(flatten)]
+        selection: TriggerSelection,
+
+        /// Skip the backup unless the newest one is older than this (e.g. 24h, 7d)
+        #[arg(long, value_name = "AGE", value_parser = parse_duration)]
+        if_stale: Option<Duration>,
+    },
+    /// Restore triggers from a JSON backup file
+    Restore {
+        /// Input backup file path
+        #[arg(long)]
+        input: PathBuf,
+
+        /// Actually perform the restore (default is dry-run)
+        #[arg(long)]
+        no_dry_run: bool,
+
+        /// Save the backup as this profile instead, without touching HamAlert
+        #[arg(long, conflicts_with = "no_dry_run")]
+        to_profile: Option<String>,
+
+        /// Also replace the permanent triggers (see `profile set-permanent`)
+        #[arg(long)]
+        include_permanent: bool,
+    },
+    /// List your triggers
+    List {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ListOutput::Table)]
+        output: ListOutput,
+
+        /// Columns to show in table output
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            default_value = "comment,mode,callsigns,matchCount"
+        )]
+        columns: Vec<ListColumn>,
+
+        /// Print each trigger with a template, e.g. '{comment}\t{id}'
+        #[arg(long, conflicts_with_all = ["output", "columns", "group_by"])]
+        format_string: Option<String>,
+
+        #[command(flatten)]
+        selection: TriggerSelection,
+
+        #[command(flatten)]
+        grouping: ListGroupOptions,
+    },
+    /// Interactively edit an existing trigger
+    Edit {
+        /// Name of a managed trigger to edit (see `names`) instead of picking one
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Change the trigger field by field with prompts instead of in $EDITOR
+        #[arg(long)]
+        guided: bool,
+    },
+    /// Interactively delete multiple triggers with TUI selection
+    BulkDelete {
+        /// Show what would be deleted without actually deleting
+        #[arg(long)]
+        dry_run: bool,
+
+        #[command(flatten)]
+        selection: TriggerSelection,
+
+        /// Also offer the permanent triggers (see `profile set-permanent`)
+        #[arg(long)]
+        include_permanent: bool,
+    },
+    /// Manage trigger profiles for different locations/activities
+    #[command(subcommand)]
+    Profile(ProfileCommands),
+    /// Re-serve HamAlert spots to local software
+    #[command(subcommand)]
+    Serve(ServeCommands),
+    /// Interactively search, toggle, duplicate, delete and rename triggers
+    Manage,
+    /// Full-screen dashboard with live spots and your triggers
+    Tui {
+        /// Address of a hamlib rigctld instance for tuning to spots (e.g. localhost:4532)
+        #[arg(long)]
+        rigctld: Option<String>,
+
+        /// ADIF log used to mark spots of stations you have already worked
+        #[arg(long)]
+        adif: Option<PathBuf>,
+    },
+    /// Show the logged-in account, trigger usage and limits
+    Whoami,
+    /// Run newline-delimited JSON commands (add, update, delete) from stdin
+    /// over one session
+    Batch,
+    /// Run commands interactively over one login, with history and completion
+    Shell,
+    /// Show the DXCC entity, continent and zones of callsigns or prefixes
+    Lookup {
+        /// Callsigns or prefixes to look up
+        #[arg(required_unless_present = "update")]
+        callsigns: Vec<String>,
+
+        /// Download the full prefix table from country-files.com first
+        #[arg(long)]
+        update: bool,
+    },
+    /// Submit simulated spots to check that triggers and their actions fire
+    Simulate {
+        /// Spotted callsign
+        #[arg(long, required_unless_present = "file")]
+        callsign: Option<String>,
+
+        /// Frequency in kHz
+        #[arg(long, required_unless_present = "file")]
+        freq: Option<f64>,
+
+        /// Mode (e.g. cw, ssb, ft8)
+        #[arg(long)]
+        mode: Option<String>,
+
+        /// Spotter callsign
+        #[arg(long, default_value = "HAMALERT-CLI")]
+        spotter: String,
+
+        /// Spot comment
+        #[arg(long, default_value = "")]
+        comment: String,
+
+        /// Read spots from a file, one per line ("CALL FREQ [MODE]" or a DX cluster line)
+        #[arg(long, conflicts_with_all = ["callsign", "freq"])]
+        file: Option<PathBuf>,
+    },
+    /// Copy triggers from one configured account to another
+    CopyAccount {
+        /// Account to copy from (a name from the [accounts] table)
+        #[arg(long)]
+        from: String,
+
+        /// Account to copy to (a name from the [accounts] table)
+        #[arg(long)]
+        to: String,
+
+        /// Only copy triggers whose comment or callsigns contain this text
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Actually create the triggers (default is dry run)
+        #[arg(long)]
+        no_dry_run: bool,
+    },
+    /// Make the account's triggers match a declarative trigger file
+    Apply {
+        /// TOML file listing the desired triggers as [[trigger]] entries
+        #[arg(long)]
+        file: PathBuf,
+
+        /// Also delete triggers whose comment is not in the file
+        #[arg(long)]
+        delete_unmanaged: bool,
+
+        /// Actually make the changes (default is dry run)
+        #[arg(long)]
+        no_dry_run: bool,
+
+        /// Keep running, reconciling without asking whenever the file changes
+        /// and every --interval
+        #[arg(long)]
+        daemon: bool,
+
+        /// Time between reconciliations with --daemon, such as 30s, 15m or 1h
+        #[arg(long, default_value = "15m", value_parser = parse_interval, requires = "daemon")]
+        interval: Duration,
+    },
+    /// Show what apply would change, exiting with status 7 if anything would
+    Plan {
+        /// TOML file listing the desired triggers as [[trigger]] entries
+        #[arg(long)]
+        file: PathBuf,
+
+        /// Also count triggers whose comment is not in the file as drift
+        #[arg(long)]
+        delete_unmanaged: bool,
+    },
+    /// Show matchCount per trigger
+    Stats {
+        /// Also show alerts in the last day, week and month from the recorded history
+        #[arg(long)]
+        trend: bool,
+    },
+    /// Flag triggers that are probably mistakes
+    Lint {
+        /// Lint the entries of a trigger file instead of the account
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Also fail when there are only warnings
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Delete triggers whose --expires date has passed
+    PruneExpired {
+        /// Show what would be deleted without actually deleting
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Disable a trigger for a while; it is enabled again by the first command after that
+    Snooze {
+        /// Id of the trigger to snooze
+        #[arg(long, required_unless_present = "name", conflicts_with = "name")]
+        id: Option<String>,
+
+        /// Name of a managed trigger to snooze (see `names`)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// How long to snooze, such as 45m, 2h or 1d
+        #[arg(long = "for", value_parser = parse_duration)]
+        duration: Duration,
+    },
+    /// Keep a callsign out of every trigger that could alert on it for a while,
+    /// by adding it to their notCallsign conditions
+    MuteCallsign {
+        /// Callsign to mute
+        callsign: String,
+
+        /// How long to mute, such as 45m, 24h or 2d
+        #[arg(long = "for", value_parser = parse_duration)]
+        duration: Duration,
+    },
+    /// Lift the mute of a callsign now
+    UnmuteCallsign {
+        /// Muted callsign
+        callsign: String,
+    },
+    /// Enable a snoozed trigger again now
+    Unsnooze {
+        /// Id of the trigger to enable
+        #[arg(long, required_unless_present = "name", conflicts_with = "name")]
+        id: Option<String>,
+
+        /// Name of a managed trigger to enable (see `names`)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// List the names, tags and expiry dates recorded for managed triggers
+    #[command(alias = "name", args_conflicts_with_subcommands = true)]
+    Names {
+        #[command(subcommand)]
+        command: Option<NamesCommands>,
+
+        /// Forget names whose trigger no longer exists on HamAlert
+        #[arg(long)]
+        prune: bool,
+    },
+    /// Create, inspect and check the config file
+    #[command(subcommand)]
+    Config(ConfigCommands),
+    /// Work with spots collected by a file destination
+    #[command(subcommand)]
+    Spots(SpotsCommands),
+    /// Analyze the trigger set as a whole
+    #[command(subcommand)]
+    Check(CheckCommands),
+    /// Summarize what the trigger set covers
+    #[command(subcommand)]
+    Report(ReportCommands),
+    /// Write trigger data in formats other tools read
+    #[command(subcommand)]
+    Export(ExportCommands),
+    /// Create triggers computed from your location or the date
+    #[command(subcommand)]
+    Generate(GenerateCommands),
+    /// Show or change the account's notification destinations on HamAlert
+    #[command(subcommand)]
+    Destinations(DestinationsCommands),
+    /// Swap in a minimal trigger set for a contest and restore it afterwards
+    #[command(subcommand)]
+    Contest(ContestCommands),
+}
+
+#[derive(Subcommand)]
+pub(crate) enum ConfigCommands {
+    /// Interactively create the config file
+    Init,
+    /// Print the config with secrets redacted
+    Show,
+    /// Check the config file and try logging in
+    Validate,
+    /// Print the resolved config file location
+    Path,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum NamesCommands {
+    /// Give an existing trigger a name, replacing any name it had
+    Set {
+        /// HamAlert trigger id (see `list --columns comment,id`)
+        id: String,
+
+        /// Name to refer to the trigger by with --name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum BackupCommands {
+    /// Check a backup file for truncation or corruption without restoring it
+    Verify {
+        /// Backup file to check
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum CheckCommands {
+    /// Find triggers that duplicate, contain or double-alert with each other
+    Overlaps,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum ExportCommands {
+    /// Sorted list of every callsign in your callsign triggers, one per line
+    Callsigns {
+        #[command(flatten)]
+        selection: TriggerSelection,
+
+        /// Leave out prefix patterns such as W1*
+        #[arg(long)]
+        exact: bool,
+
+        /// File layout: a plain list, or a logger's highlight file
+        #[arg(long, value_enum, default_value_t = CallsignListFormat::Plain)]
+        format: CallsignListFormat,
+
+        /// Output file path (default: stdout)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum ReportCommands {
+    /// Band by mode grid of how many enabled triggers cover each combination
+    Matrix,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum GenerateCommands {
+    /// Triggers for DX entities around your sunrise and sunset; re-run to
+    /// move the windows as the seasons change
+    Greyline {
+        #[command(flatten)]
+        options: GreylineOptions,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum ContestCommands {
+    /// Save the current triggers and switch to the profile "contest-<NAME>"
+    Start {
+        /// Contest name, e.g. cqww-cw
+        name: String,
+
+        /// Generate the contest profile from a file of needed DXCC multipliers
+        /// (prefixes, names or numbers, one per line or comma-separated)
+        #[arg(long)]
+        multipliers: Option<PathBuf>,
+
+        /// Modes for the generated multiplier trigger
+        #[arg(long, value_enum, num_args = 1.., requires = "multipliers")]
+        mode: Vec<Mode>,
+
+        /// Notification actions for the generated trigger (default: from [defaults])
+        #[arg(long, value_enum, num_args = 1.., requires = "multipliers")]
+        actions: Vec<Action>,
+
+        /// Actually make the changes (default is dry run)
+        #[arg(long)]
+        no_dry_run: bool,
+    },
+    /// Restore the triggers saved when the contest started
+    End {
+        /// Actually make the changes (default is dry run)
+        #[arg(long)]
+        no_dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum DestinationsCommands {
+    /// Print the URL, Threema and telnet settings
+    Show,
+    /// Change destination settings; an empty value clears it
+    Set {
+        /// Endpoint called by the url action
+        #[arg(long)]
+        url: Option<String>,
+
+        /// Threema ID that receives the threema action
+        #[arg(long)]
+        threema_id: Option<String>,
+
+        /// Deliver the telnet action to HamAlert's telnet server
+        #[arg(long, action = clap::ArgAction::Set)]
+        telnet: Option<bool>,
+    },
+    /// Fire a simulated spot through a short-lived trigger to check that
+    /// notifications arrive
+    Test {
+        /// Action to test
+        #[arg(long, value_enum)]
+        action: Action,
+
+        /// Callsign of the test trigger and spot
+        #[arg(long, default_value = "N0CALL")]
+        callsign: String,
+
+        /// Seconds to wait for HamAlert to report the match before cleaning up
+        #[arg(long, default_value_t = 30)]
+        wait: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum SpotsCommands {
+    /// Search a spot log, e.g. for when a station was last spotted on a band
+    Query {
+        /// Spot log written by a `file` destination
+        #[arg(long)]
+        input: PathBuf,
+
+        /// Only spots of this callsign
+        #[arg(long)]
+        call: Option<String>,
+
+        /// Only spots on this band (e.g., 15m)
+        #[arg(long)]
+        band: Option<String>,
+
+        /// Only spots in this mode (e.g., CW)
+        #[arg(long)]
+        mode: Option<String>,
+
+        /// Only spots logged within this long (e.g., 24h or 7d)
+        #[arg(long, value_name = "AGE", value_parser = parse_duration)]
+        since: Option<Duration>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = QueryOutput::Table)]
+        output: QueryOutput,
+    },
+    /// Convert a spot log into another format
+    Export {
+        /// Spot log written by a `file` destination
+        #[arg(long)]
+        input: PathBuf,
+
+        /// Write ADIF SWL records
+        #[arg(long)]
+        adif: bool,
+
+        /// Output file path (default: stdout)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Summarize a spot log: activity by band and hour, top callsigns and sources
+    Stats {
+        /// Spot log written by a `file` destination
+        #[arg(long)]
+        input: PathBuf,
+
+        /// Only spots logged within this long (e.g., 24h or 7d)
+        #[arg(long, value_name = "AGE", value_parser = parse_duration)]
+        since: Option<Duration>,
+    },
+    /// Show at which hours (UTC) spots from an entity or band turn up in a spot log
+    Openings {
+        /// Spot log written by a `file` destination
+        #[arg(long)]
+        input: PathBuf,
+
+        /// Only spots of these DXCC entities, by prefix, name or number
+        /// (e.g., --dxcc JA,VK)
+        #[arg(long, value_delimiter = ',')]
+        dxcc: Vec<String>,
+
+        /// Only spots on this band (e.g., 15m)
+        #[arg(long)]
+        band: Option<String>,
+    },
+}
+
+/// How the serve commands handle received spots, on top of the config file
+#[derive(Parser, Clone, Default)]
+pub(crate) struct PipelineOptions {
+    /// Run a command for every spot, with spot fields in HAMALERT_* env vars
+    #[arg(long)]
+    pub(crate) exec: Vec<String>,
+
+    /// Template for line output, e.g. '{time} {call} {freq} {mode} via {spotter}'
+    #[arg(long)]
+    pub(crate) format: Option<String>,
+
+    /// Also send every spot line as a UDP datagram to this host:port, e.g.
+    /// for a logger's spot input
+    #[arg(long, value_name = "ADDRESS", value_parser = parse_udp_address)]
+    pub(crate) udp_forward: Vec<String>,
+
+    /// Also post every spot to this Discord channel webhook URL
+    #[arg(long, value_name = "URL")]
+    pub(crate) discord_webhook: Vec<String>,
+
+    /// Also publish every spot to this ntfy.sh topic
+    #[arg(long, value_name = "TOPIC")]
+    pub(crate) ntfy: Vec<String>,
+
+    /// Forward every spot, ignoring `dedupe_minutes` in the [watch] config
+    #[arg(long)]
+    pub(crate) no_dedupe: bool,
+
+    /// Mark spots matching this expression in terminal output, e.g.
+    /// 'call=VP8* band=15m'; repeat for alternatives
+    #[arg(long, value_parser = parse_highlight)]
+    pub(crate) highlight: Vec<Highlight>,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum ServeCommands {
+    /// Re-serve the HamAlert spot feed as a local DX-cluster telnet server
+    Telnet {
+        /// Local port to listen on
+        #[arg(long, default_value_t = 7301)]
+        port: u16,
+
+        /// Local address to bind to
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: String,
+
+        /// Expose Prometheus metrics at /metrics on this port
+        #[arg(long)]
+        metrics_port: Option<u16>,
+
+        #[command(flatten)]
+        pipeline: PipelineOptions,
+    },
+    /// Receive HamAlert "url" action notifications over HTTP
+    Http {
+        /// Local port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Local address to bind to
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: String,
+
+        #[command(flatten)]
+        pipeline: PipelineOptions,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum ProfileCommands {
+    /// List all available profiles
+    List,
+    /// Show triggers in a profile
+    Show {
+        /// Profile name
+        name: String,
+    },
+    /// Show current profile status and match analysis
+    Status,
+    /// Save current triggers as a profile
+    Save {
+        /// Profile name
+        name: String,
+        /// Create from backup file instead of current triggers
+        #[arg(long)]
+        from_backup: Option<PathBuf>,
+    },
+    /// Switch to a different profile
+    Switch {
+        /// Profile name to switch to
+        name: String,
+        /// Actually perform the switch (default is dry-run)
+        #[arg(long)]
+        no_dry_run: bool,
+    },
+    /// Delete a profile
+    Delete {
+        /// Profile name
+        name: String,
+    },
+    /// Interactively select permanent triggers
+    SetPermanent {
+        /// Set from backup file instead of current triggers
+        #[arg(long)]
+        from_backup: Option<PathBuf>,
+    },
+    /// Show current permanent triggers
+    ShowPermanent,
+    /// Sort triggers that are in no profile and not permanent: add each to
+    /// a profile, mark it permanent or delete it
+    Triage,
+}
+
+#[derive(Clone, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Action {
+    Url,
+    App,
+    Threema,
+    Telnet,
+}
+
+#[derive(Clone, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(clippy::upper_case_acronyms)]
+pub(crate) enum Mode {
+    CW,
+    FT8,
+    SSB,
+}
+
+impl Action {
+    pub(crate) fn as_str(&self) -> &str {
+        match self {
+            Action::Url => "url",
+            Action::App => "app",
+            Action::Threema => "threema",
+            Action::Telnet => "telnet",
+        }
+    }
+}
+
+impl Mode {
+    pub(crate) fn as_str(&self) -> &str {
+        match self {
+            Mode::CW => "cw",
+            Mode::FT8 => "ft8",
+            Mode::SSB => "ssb",
+        }
+    }
+}
+
+/// Determines how multiple callsigns are formatted when sent to HamAlert
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum CallsignFormat {
+    /// Comma-space separated: "N0CALL, K0TEST, W0XYZ"
+    #[default]
+    Default,
+    /// Comma-only (compact): "N0CALL,K0TEST,W0XYZ"
+    Compact,
+    /// One per line: "N0CALL\nK0TEST\nW0XYZ"
+    OnePerLine,
+}
+
+impl CallsignFormat {
+    pub(crate) fn separator(&self) -> &'static str {
+        match self {
+            CallsignFormat::Default => ", ",
+            CallsignFormat::Compact => ",",
+            CallsignFormat::OnePerLine => "\n",
+        }
+    }
+
+    pub(crate) fn from_flags(compact: bool, one_per_line: bool) -> Self {
+        match (compact, one_per_line) {
+            (true, _) => CallsignFormat::Compact,
+            (_, true) => CallsignFormat::OnePerLine,
+            _ => CallsignFormat::Default,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_value_options() {
+        let options = Cli::value_options();
+        for option in [
+            "--config-file",
+            "--get",
+            "--log-file",
+            "--timeout",
+            "--color",
+        ] {
+            assert!(options.iter().any(|o| o == option), "{} is missing", option);
+        }
+        assert!(!options.iter().any(|o| o == "--json" || o == "--verbose"));
+    }
+
+    #[test]
+    fn test_trigger_options_with_defaults() {
+        let config: Config = toml::from_str(
+            r#"
+            username = "W1AW"
+            password = "x"
+
+            [defaults]
+            actions = ["app", "telnet"]
+            mode = ["cw"]
+            callsign_format = "one-per-line"
+            "#,
+        )
+        .unwrap();
+        let options = TriggerOptions::try_parse_from(["add", "--comment", "x"])
+            .unwrap()
+            .with_defaults(&config.defaults);
+        assert_eq!(
+            options
+                .actions
+                .iter()
+                .map(|a| a.as_str())
+                .collect::<Vec<_>>(),
+            vec!["app", "telnet"]
+        );
+        assert_eq!(options.mode.len(), 1);
+        assert!(options.one_per_line);
+
+        let options = TriggerOptions::try_parse_from([
+            "add",
+            "--comment",
+            "x",
+            "--actions",
+            "url",
+            "--compact",
+        ])
+        .unwrap()
+        .with_defaults(&config.defaults);
+        assert_eq!(
+            options
+                .actions
+                .iter()
+                .map(|a| a.as_str())
+                .collect::<Vec<_>>(),
+            vec!["url"]
+        );
+        assert!(options.compact && !options.one_per_line);
+    }
+
+    #[test]
+    fn test_local_commands_skip_login() {
+        let needs_api = |args: &[&str]| {
+            let cli =
+                Cli::try_parse_from(std::iter::once("hamalert-cli").chain(args.iter().copied()))
+                    .unwrap();
+            cli.command.unwrap().needs_api()
+        };
+        assert!(!needs_api(&["profile", "show", "home"]));
+        assert!(!needs_api(&[
+            "profile",
+            "save",
+            "home",
+            "--from-backup",
+            "b.json"
+        ]));
+        assert!(!needs_api(&[
+            "spots", "export", "--input", "s.log", "--adif"
+        ]));
+        assert!(!needs_api(&[
+            "restore",
+            "--input",
+            "b.json",
+            "--to-profile",
+            "old"
+        ]));
+        assert!(!needs_api(&["backup", "verify", "b.json"]));
+        assert!(!needs_api(&["serve", "http"]));
+        assert!(needs_api(&["serve", "telnet"]));
+        assert!(needs_api(&["backup", "--output", "b.json"]));
+        assert!(needs_api(&["restore", "--input", "b.json"]));
+        assert!(needs_api(&["profile", "save", "home"]));
+        assert!(needs_api(&["list"]));
+    }
+
+    #[test]
+    fn test_is_dry_run() {
+        let is_dry_run = |args: &[&str]| {
+            let cli =
+                Cli::try_parse_from(std::iter::once("hamalert-cli").chain(args.iter().copied()))
+                    .unwrap();
+            cli.command.unwrap().is_dry_run()
+        };
+        assert!(is_dry_run(&["prune-expired", "--dry-run"]));
+        assert!(is_dry_run(&["apply", "--file", "t.toml"]));
+        assert!(!is_dry_run(&["apply", "--file", "t.toml", "--no-dry-run"]));
+        assert!(is_dry_run(&["profile", "switch", "home"]));
+        assert!(!is_dry_run(&["list"]));
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("15m").unwrap(), Duration::from_secs(900));
+        assert_eq!(parse_interval("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_interval("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_interval("5").unwrap(), Duration::from_secs(300));
+        assert!(parse_interval("10s").is_err());
+        assert!(parse_interval("15x").is_err());
+        assert!(parse_interval("m").is_err());
+        assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86400));
+        assert!(parse_duration("0h").is_err());
+        assert_eq!(
+            parse_duration("999999999999999999d").unwrap_err(),
+            "duration too large"
+        );
+    }
+
+    #[test]
+    fn test_works_offline_matches_offline_commands() {
+        let parse = |args: &str| {
+            Cli::try_parse_from(std::iter::once("hamalert-cli").chain(args.split_whitespace()))
+                .unwrap()
+                .command
+                .unwrap()
+        };
+        for args in [
+            "list",
+            "backup",
+            "share",
+            "plan --file x.toml",
+            "lint",
+            "stats",
+            "check overlaps",
+            "report matrix",
+            "export callsigns",
+            "names",
+            "spots export --input spots.log",
+            "lookup W1AW",
+            "restore --input x.json --to-profile contest",
+            "config show",
+            "profile list",
+        ] {
+            let name = Commands::OFFLINE_COMMANDS
+                .iter()
+                .any(|name| args.starts_with(name.split(' ').next().unwrap()));
+            assert!(name, "{} is missing from OFFLINE_COMMANDS", args);
+            assert!(parse(args).works_offline(), "{} should work offline", args);
+        }
+        assert!(!parse("restore --input x.json").works_offline());
+        assert!(!parse("names --prune").works_offline());
+    }
+
+    #[test]
+    fn test_edit_takes_name_flag() {
+        let cli = Cli::try_parse_from(["hamalert-cli", "edit", "--name", "vp8pj"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Edit { name: Some(ref name), guided: false }) if name == "vp8pj"
+        ));
+        assert!(Cli::try_parse_from(["hamalert-cli", "edit", "vp8pj"]).is_err());
+    }
+}
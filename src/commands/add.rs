@@ -0,0 +1,87 @@
+//! `add-trigger`.
+
+use crate::api::{NewTrigger, add_trigger, remember_new_triggers};
+use crate::callsign::validate_patterns;
+use crate::cli::{CallsignFormat, TriggerOptions};
+use crate::config::Config;
+use crate::dxcc::resolve_dxcc;
+use crate::error::CliError;
+use crate::model::with_callsign_count;
+use crate::output::print_created;
+use crate::references::{comment_with_names, resolve_parks, resolve_summits};
+use crate::storage::state::ensure_name_available;
+use hamalert_api::{Conditions, HamAlertClient};
+
+/// Add one trigger for the given callsigns
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    config: &Config,
+    callsign: Vec<String>,
+    options: TriggerOptions,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let options = options.with_defaults(&config.defaults);
+    let action_strings: Vec<String> = options
+        .actions
+        .iter()
+        .map(|a| a.as_str().to_string())
+        .collect();
+
+    let mode_string = if options.mode.is_empty() {
+        None
+    } else {
+        Some(
+            options
+                .mode
+                .iter()
+                .map(|m| m.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+    };
+
+    if callsign.is_empty()
+        && options.dxcc.is_empty()
+        && options.summit_ref.is_empty()
+        && options.pota_ref.is_empty()
+    {
+        return Err(CliError::Validation(
+            "At least one --callsign, --dxcc, --summit-ref or --pota-ref must be provided"
+                .to_string(),
+        ));
+    }
+    let callsign = validate_patterns(&callsign)?;
+    let dxcc = resolve_dxcc(&options.dxcc)?;
+    let (summit_ref, summit_names) =
+        resolve_summits(client, &options.summit_ref, options.check_summits).await?;
+    let (pota_ref, park_names) = resolve_parks(client, &options.pota_ref).await?;
+    let mut comment = comment_with_names(&options.comment, &[summit_names, park_names].concat());
+    if options.count_suffix {
+        comment = with_callsign_count(&comment, callsign.len());
+    }
+    let metadata = options.managed("add-trigger".to_string())?;
+    if let Some(name) = &options.name {
+        ensure_name_available(name)?;
+    }
+    // Join callsigns with the specified format
+    let format = CallsignFormat::from_flags(options.compact, options.one_per_line);
+    let conditions = Conditions {
+        callsign: callsign.join(format.separator()),
+        mode: mode_string,
+        dxcc,
+        summit_ref,
+        pota_ref,
+    };
+    let id = add_trigger(client, conditions, &comment, action_strings).await?;
+    let created = remember_new_triggers(
+        client,
+        vec![NewTrigger {
+            id,
+            name: options.name,
+            comment,
+            metadata,
+        }],
+    )
+    .await;
+    print_created(&created, json_output)
+}
@@ -0,0 +1,236 @@
+//! `apply`: reconcile the account with a declarative trigger file.
+
+use crate::api::{
+    NewTrigger, fetch_triggers, for_each_concurrent, map_concurrent, remember_new_triggers,
+};
+use crate::config::Config;
+use crate::declarative::{Plan, desired_triggers, load_trigger_file, plan, print_plan};
+use crate::error::CliError;
+use crate::output::confirm;
+use crate::storage::backups::{backup_dir, write_backup};
+use crate::storage::profiles::load_permanent_triggers;
+use crate::storage::state::{ManagedTrigger, State, load_state, save_state};
+use chrono::Local;
+use hamalert_api::HamAlertClient;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often --daemon checks the trigger file for changes
+const WATCH_POLL: Duration = Duration::from_secs(5);
+
+/// Track every matched trigger under its entry's name and forget deleted ones
+fn record_matches(state: &mut State, plan: &Plan, source: &str) {
+    for (live, entry) in plan.update.iter().chain(&plan.unchanged) {
+        let metadata = ManagedTrigger {
+            id: live.id.clone(),
+            ..ManagedTrigger::new(
+                source.to_string(),
+                entry.expires.clone(),
+                entry.tags.clone(),
+            )
+        };
+        state.record(&entry.state_name(), metadata);
+    }
+    for trigger in &plan.delete {
+        if let Some(name) = state.name_for_id(&trigger.id).map(str::to_string) {
+            state.triggers.remove(&name);
+        }
+    }
+}
+
+/// Create, update and (with `delete_unmanaged`) delete triggers until the
+/// account matches `file`
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    config: &Config,
+    file: &Path,
+    delete_unmanaged: bool,
+    no_dry_run: bool,
+    assume_yes: bool,
+) -> Result<(), CliError> {
+    let desired = desired_triggers(file, &config.defaults)?;
+    let current_triggers = fetch_triggers(client).await?;
+    let mut state = load_state()?;
+    let mut plan = plan(&desired, &current_triggers, delete_unmanaged, &state);
+    let spared = plan.spare_permanent(&load_permanent_triggers()?);
+    if spared > 0 {
+        info!("Not deleting {} permanent triggers", spared);
+    }
+    let source = format!("apply {}", file.display());
+
+    if !plan.unmanaged.is_empty() {
+        info!(
+            "Leaving {} triggers not in {} alone (use --delete-unmanaged to remove them)",
+            plan.unmanaged.len(),
+            file.display()
+        );
+    }
+    if !plan.has_changes() {
+        println!("No changes: HamAlert already matches {}.", file.display());
+        if no_dry_run {
+            record_matches(&mut state, &plan, &source);
+            save_state(&state)?;
+        }
+        return Ok(());
+    }
+
+    if !no_dry_run {
+        println!("DRY RUN - No changes will be made\n");
+    }
+    println!(
+        "{} to create, {} to update, {} to delete:",
+        plan.create.len(),
+        plan.update.len(),
+        plan.delete.len()
+    );
+    print_plan(&plan);
+    if !no_dry_run {
+        println!("\nRun with --no-dry-run to execute.");
+        return Ok(());
+    }
+
+    let question = format!(
+        "Apply {} changes from {}?",
+        plan.create.len() + plan.update.len() + plan.delete.len(),
+        file.display()
+    );
+    if !confirm(&question, assume_yes)? {
+        return Err(CliError::Cancelled("Apply cancelled.".to_string()));
+    }
+
+    // Updates and deletes change existing triggers, so keep a copy first
+    if !plan.update.is_empty() || !plan.delete.is_empty() {
+        let backup_path = backup_dir()?.join(format!(
+            "hamalert-backup-before-apply-{}.json",
+            Local::now().format("%Y-%m-%d-%H%M%S")
+        ));
+        write_backup(&backup_path, &current_triggers)?;
+        println!(
+            "Backed up {} existing triggers to {}",
+            current_triggers.len(),
+            backup_path.display()
+        );
+    }
+
+    // Delete first so the account stays under its trigger limit
+    for_each_concurrent(&plan.delete, |trigger| async move {
+        client.delete_trigger(&trigger.id).await?;
+        println!("Deleted trigger: {}", trigger.comment);
+        Ok(())
+    })
+    .await?;
+    for_each_concurrent(&plan.update, |(live, entry)| async move {
+        client.update_trigger(&entry.to_trigger(Some(live))).await?;
+        println!("Updated trigger: {}", entry.comment);
+        Ok(())
+    })
+    .await?;
+    let ids = map_concurrent(&plan.create, |entry| async move {
+        let id = client.create_trigger(&entry.to_trigger(None)).await?;
+        println!("Created trigger: {}", entry.comment);
+        Ok(id)
+    })
+    .await?;
+
+    record_matches(&mut state, &plan, &source);
+    save_state(&state)?;
+    let created = plan
+        .create
+        .iter()
+        .zip(ids)
+        .map(|(entry, id)| NewTrigger {
+            id,
+            name: Some(entry.state_name()),
+            comment: entry.comment.clone(),
+            metadata: ManagedTrigger::new(
+                source.clone(),
+                entry.expires.clone(),
+                entry.tags.clone(),
+            ),
+        })
+        .collect();
+    remember_new_triggers(client, created).await;
+
+    println!(
+        "\nApplied {}: {} created, {} updated, {} deleted",
+        file.display(),
+        plan.create.len(),
+        plan.update.len(),
+        plan.delete.len()
+    );
+    Ok(())
+}
+
+fn modified_time(file: &Path) -> Option<SystemTime> {
+    fs::metadata(file).and_then(|m| m.modified()).ok()
+}
+
+/// Sleep until `interval` has passed or the modification time of `file` is
+/// no longer `modified`
+async fn wait_for_change(file: &Path, modified: Option<SystemTime>, interval: Duration) {
+    let deadline = Instant::now() + interval;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return;
+        }
+        tokio::time::sleep(remaining.min(WATCH_POLL)).await;
+        if modified_time(file) != modified {
+            tracing::info!("{} changed", file.display());
+            return;
+        }
+    }
+}
+
+/// Reconcile with `file` without asking, again whenever the file changes and
+/// at least every `interval`, until interrupted. A failed reconciliation is
+/// logged and retried after logging in again, in case the session expired.
+pub(crate) async fn daemon(
+    client: &HamAlertClient,
+    config: &Config,
+    file: &Path,
+    delete_unmanaged: bool,
+    no_dry_run: bool,
+    interval: Duration,
+) -> Result<(), CliError> {
+    // A broken file at startup is a mistake worth stopping for
+    load_trigger_file(file)?;
+    tracing::info!(
+        "Reconciling with {} every {}s and whenever it changes",
+        file.display(),
+        interval.as_secs()
+    );
+    loop {
+        let modified = modified_time(file);
+        println!(
+            "\n[{}] Reconciling with {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            file.display()
+        );
+        client.invalidate_triggers();
+        match super::snooze::wake_due(client, false).await {
+            Ok(woken) => {
+                for name in woken {
+                    println!("Enabled snoozed trigger '{}' again", name);
+                }
+            }
+            Err(e) => tracing::error!(error = %e, "waking snoozed triggers failed"),
+        }
+        match super::mute::unmute_due(client, false).await {
+            Ok(unmuted) => {
+                for call in unmuted {
+                    println!("Unmuted {}", call);
+                }
+            }
+            Err(e) => tracing::error!(error = %e, "lifting mutes failed"),
+        }
+        if let Err(e) = run(client, config, file, delete_unmanaged, no_dry_run, true).await {
+            tracing::error!(error = %e, "reconciliation failed");
+            if let Err(e) = client.login(&config.username, &config.password).await {
+                tracing::error!(error = %e, "login failed");
+            }
+        }
+        wait_for_change(file, modified, interval).await;
+    }
+}
@@ -0,0 +1,344 @@
+//! `backup` and `restore`.
+
+use crate::api::{
+    CreatedTrigger, fetch_triggers, for_each_concurrent, map_concurrent, match_created_ids,
+};
+use crate::cli::TriggerSelection;
+use crate::error::CliError;
+use crate::model::{StoredTrigger, triggers_match};
+use crate::output::{confirm, format_trigger_for_display, print_created};
+use crate::storage::backups::{
+    backup_dir, check_backup, newest_backup, read_backup, unchanged_since, write_backup,
+};
+use crate::storage::profiles::{
+    filter_out_permanent, list_profiles, load_permanent_triggers, load_profile,
+    partition_permanent, save_profile,
+};
+use chrono::Local;
+use hamalert_api::HamAlertClient;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Save all triggers, or the `selection`, to a JSON backup file
+pub(crate) async fn backup(
+    client: &HamAlertClient,
+    output: Option<PathBuf>,
+    selection: &TriggerSelection,
+    if_stale: Option<Duration>,
+) -> Result<(), CliError> {
+    if let Some(max_age) = if_stale
+        && let Some((path, age)) = fresh_backup(output.as_deref(), max_age)?
+    {
+        report_fresh(&path, age);
+        return Ok(());
+    }
+
+    let mut triggers = fetch_triggers(client).await?;
+    selection.retain(&mut triggers)?;
+
+    let output_path = match output {
+        Some(path) => path,
+        None => {
+            // Keep the backup directory a history of changes
+            let dir = backup_dir()?;
+            if let Some((newest, _)) = newest_backup(&dir)?
+                && let Some(created_at) = unchanged_since(&newest, &triggers)
+            {
+                println!(
+                    "No changes since {} ({}); not backing up again",
+                    newest.display(),
+                    created_at
+                );
+                return Ok(());
+            }
+            let date = Local::now().format("%Y-%m-%d");
+            dir.join(format!("hamalert-backup-{}.json", date))
+        }
+    };
+
+    write_backup(&output_path, &triggers)?;
+
+    println!(
+        "Backed up {} triggers to {}",
+        triggers.len(),
+        output_path.display()
+    );
+    Ok(())
+}
+
+/// The backup that makes a new one unnecessary: `output` if given, else the
+/// newest in the backup directory, when it is younger than `max_age`.
+/// Returns its path and age.
+pub(crate) fn fresh_backup(
+    output: Option<&Path>,
+    max_age: Duration,
+) -> Result<Option<(PathBuf, Duration)>, CliError> {
+    let newest = match output {
+        Some(path) => match fs::metadata(path) {
+            Ok(metadata) => Some((path.to_path_buf(), metadata.modified()?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        },
+        None => newest_backup(&backup_dir()?)?,
+    };
+    Ok(newest.and_then(|(path, modified)| {
+        // A clock set back makes the file look new; treat it as current
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        (age < max_age).then_some((path, age))
+    }))
+}
+
+/// Age rounded to the largest whole unit, e.g. `3h`
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..172800 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+/// Say why `backup --if-stale` did nothing
+pub(crate) fn report_fresh(path: &Path, age: Duration) {
+    println!(
+        "Backup {} is only {} old; not backing up again",
+        path.display(),
+        format_age(age)
+    );
+}
+
+/// Check that a backup file is complete and unmodified since it was written
+pub(crate) fn verify(file: &Path, json_output: bool) -> Result<(), CliError> {
+    let content = fs::read_to_string(file)
+        .map_err(|e| CliError::io(format!("Failed to read backup file {}", file.display()), e))?;
+    let result = check_backup(&content);
+
+    if json_output {
+        let report = match &result {
+            Ok(check) => serde_json::json!({
+                "file": file,
+                "ok": true,
+                "triggers": check.triggers.len(),
+                "created_at": check.created_at,
+                "checksum": check.created_at.is_some(),
+            }),
+            Err(problem) => serde_json::json!({
+                "file": file,
+                "ok": false,
+                "problem": problem,
+            }),
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+
+    match result {
+        Ok(check) => {
+            if !json_output {
+                match check.created_at {
+                    Some(created_at) => println!(
+                        "OK: {} triggers, written {}, checksum matches",
+                        check.triggers.len(),
+                        created_at
+                    ),
+                    None => println!(
+                        "OK: {} triggers (older backup without a checksum; only the JSON was checked)",
+                        check.triggers.len()
+                    ),
+                }
+            }
+            Ok(())
+        }
+        Err(problem) => Err(CliError::Validation(format!(
+            "Backup {} is damaged: {}",
+            file.display(),
+            problem
+        ))),
+    }
+}
+
+/// Save the triggers of a backup file as profile `name`, leaving out the
+/// permanent ones as `profile save` does. HamAlert is not contacted.
+pub(crate) fn restore_to_profile(
+    input: &Path,
+    name: &str,
+    assume_yes: bool,
+) -> Result<(), CliError> {
+    let stored: Vec<StoredTrigger> = read_backup(input)?
+        .iter()
+        .map(StoredTrigger::from_trigger)
+        .collect();
+    let profile = filter_out_permanent(&stored, &load_permanent_triggers()?);
+
+    if list_profiles()?.iter().any(|p| p == name) {
+        let existing = load_profile(name)?;
+        if existing == profile {
+            println!("Profile '{}' already has these triggers.", name);
+            return Ok(());
+        }
+        let question = format!(
+            "Overwrite profile '{}' ({} triggers) with {} from {}?",
+            name,
+            existing.len(),
+            profile.len(),
+            input.display()
+        );
+        if !confirm(&question, assume_yes)? {
+            return Err(CliError::Cancelled("Cancelled.".to_string()));
+        }
+    }
+
+    save_profile(name, &profile)?;
+    println!(
+        "Saved {} triggers from {} to profile '{}' (excluded {} permanent)",
+        profile.len(),
+        input.display(),
+        name,
+        stored.len() - profile.len()
+    );
+    Ok(())
+}
+
+/// Replace all triggers with the contents of a backup file. Permanent
+/// triggers stay unless `include_permanent`, and backed up copies of them
+/// are not restored twice.
+pub(crate) async fn restore(
+    client: &HamAlertClient,
+    input: &Path,
+    no_dry_run: bool,
+    include_permanent: bool,
+    json_output: bool,
+    assume_yes: bool,
+) -> Result<(), CliError> {
+    let mut backup_triggers = read_backup(input)?;
+
+    // Fetch current triggers
+    let current_triggers = fetch_triggers(client).await?;
+    let (kept, to_delete) = if include_permanent {
+        (Vec::new(), current_triggers.clone())
+    } else {
+        partition_permanent(current_triggers.clone(), &load_permanent_triggers()?)
+    };
+    let kept_stored: Vec<StoredTrigger> = kept.iter().map(StoredTrigger::from_trigger).collect();
+    backup_triggers.retain(|t| {
+        let stored = StoredTrigger::from_trigger(t);
+        !kept_stored.iter().any(|k| triggers_match(&stored, k))
+    });
+    let kept_note = format!(
+        "Keeping {} permanent triggers (use --include-permanent to replace them too).",
+        kept.len()
+    );
+
+    if !no_dry_run {
+        println!("DRY RUN - No changes will be made\n");
+        println!(
+            "This will DELETE {} existing triggers and restore {} triggers from backup.\n",
+            to_delete.len(),
+            backup_triggers.len()
+        );
+        if !kept.is_empty() {
+            println!("{}\n", kept_note);
+        }
+        println!("Triggers to be restored:");
+        for trigger in &backup_triggers {
+            println!("  {}", format_trigger_for_display(trigger));
+        }
+        println!("\nRun with --no-dry-run to execute.");
+        return Ok(());
+    }
+
+    let question = format!(
+        "Delete {} existing triggers and restore {} from backup?",
+        to_delete.len(),
+        backup_triggers.len()
+    );
+    if !confirm(&question, assume_yes)? {
+        return Err(CliError::Cancelled("Restore cancelled.".to_string()));
+    }
+
+    // Create auto-backup before destructive operation
+    let backup_path = backup_dir()?.join(format!(
+        "hamalert-backup-before-restore-{}.json",
+        Local::now().format("%Y-%m-%d-%H%M%S")
+    ));
+    write_backup(&backup_path, &current_triggers)?;
+    if !json_output {
+        println!(
+            "Backed up {} existing triggers to {}",
+            current_triggers.len(),
+            backup_path.display()
+        );
+    }
+
+    // Delete the existing triggers
+    for_each_concurrent(&to_delete, |trigger| async move {
+        Ok(client.delete_trigger(&trigger.id).await?)
+    })
+    .await?;
+    if !json_output {
+        println!("Deleted {} existing triggers", to_delete.len());
+        if !kept.is_empty() {
+            println!("{}", kept_note);
+        }
+    }
+
+    // Restore from backup, matching up the ids HamAlert did not report
+    let ids = map_concurrent(&backup_triggers, |trigger| async move {
+        Ok(client.create_trigger(trigger).await?)
+    })
+    .await?;
+    let mut created: Vec<CreatedTrigger> = backup_triggers
+        .iter()
+        .zip(ids)
+        .map(|(trigger, id)| CreatedTrigger {
+            id,
+            name: None,
+            comment: trigger.comment.clone(),
+        })
+        .collect();
+    if created.iter().any(|c| c.id.is_none()) {
+        match fetch_triggers(client).await {
+            Ok(live) => match_created_ids(&mut created, &live, &kept),
+            Err(e) => info!("Could not look up the restored trigger ids: {}", e),
+        }
+    }
+    print_created(&created, json_output)?;
+    if !json_output {
+        println!(
+            "\nRestored {} triggers from {}",
+            backup_triggers.len(),
+            input.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fresh_backup() {
+        let path = std::env::temp_dir().join(format!("hamalert-fresh-{}.json", std::process::id()));
+        let day = Duration::from_secs(86400);
+        assert!(fresh_backup(Some(&path), day).unwrap().is_none());
+
+        fs::write(&path, "[]").unwrap();
+        let (found, age) = fresh_backup(Some(&path), day).unwrap().unwrap();
+        assert_eq!(found, path);
+        assert!(age < day);
+        assert!(fresh_backup(Some(&path), Duration::ZERO).unwrap().is_none());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::from_secs(42)), "42s");
+        assert_eq!(format_age(Duration::from_secs(600)), "10m");
+        assert_eq!(format_age(Duration::from_secs(30 * 3600)), "30h");
+        assert_eq!(format_age(Duration::from_secs(3 * 86400)), "3d");
+    }
+}
@@ -0,0 +1,341 @@
+//! `batch`: run newline-delimited JSON commands from stdin over one session.
+
+use crate::api::{NewTrigger, add_trigger, fetch_triggers, remember_new_triggers};
+use crate::callsign::validate_patterns;
+use crate::cli::{Action, Mode};
+use crate::config::Config;
+use crate::error::CliError;
+use crate::model::{refresh_callsign_count, with_callsign_count};
+use crate::output::print_json;
+use crate::storage::profiles::{is_permanent, load_permanent_triggers};
+use crate::storage::state::{ManagedTrigger, ensure_name_available, load_state, save_state};
+use hamalert_api::{Conditions, HamAlertClient, Trigger};
+use serde::Deserialize;
+use serde_json::json;
+use std::io::BufRead;
+
+/// One line of batch input, selected by its "op" field
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase", deny_unknown_fields)]
+enum BatchCommand {
+    Add {
+        callsign: Vec<String>,
+        comment: String,
+        #[serde(default)]
+        actions: Vec<Action>,
+        #[serde(default)]
+        mode: Vec<Mode>,
+        name: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+        expires: Option<String>,
+    },
+    Update {
+        id: Option<String>,
+        name: Option<String>,
+        comment: Option<String>,
+        callsign: Option<Vec<String>>,
+        mode: Option<Vec<Mode>>,
+        actions: Option<Vec<Action>>,
+        disabled: Option<bool>,
+    },
+    Delete {
+        id: Option<String>,
+        name: Option<String>,
+        #[serde(default)]
+        include_permanent: bool,
+    },
+}
+
+/// Counts reported in the summary
+#[derive(Debug, Default, PartialEq)]
+struct Summary {
+    added: usize,
+    updated: usize,
+    deleted: usize,
+    failed: usize,
+}
+
+/// Parse batch input, skipping blank lines. Each command keeps its 1-based
+/// line number; lines that fail to parse become errors.
+fn parse_batch(input: &str) -> Vec<(usize, Result<BatchCommand, String>)> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| (i + 1, serde_json::from_str(line).map_err(|e| e.to_string())))
+        .collect()
+}
+
+/// Trigger id from an "id" or a managed trigger "name"
+pub(crate) fn resolve_target(id: Option<String>, name: Option<String>) -> Result<String, CliError> {
+    match (id, name) {
+        (Some(id), None) => Ok(id),
+        (None, Some(name)) => load_state()?
+            .triggers
+            .get(&name)
+            .map(|managed| managed.id.clone())
+            .ok_or_else(|| CliError::Validation(format!("No managed trigger named '{}'", name))),
+        _ => Err(CliError::Validation(
+            "give exactly one of \"id\" or \"name\"".to_string(),
+        )),
+    }
+}
+
+fn mode_string(mode: &[Mode]) -> String {
+    mode.iter()
+        .map(|m| m.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Run one command, returning a description of what it did
+async fn execute(
+    client: &HamAlertClient,
+    config: &Config,
+    triggers: &mut Vec<Trigger>,
+    created: &mut Vec<NewTrigger>,
+    summary: &mut Summary,
+    command: BatchCommand,
+) -> Result<String, CliError> {
+    match command {
+        BatchCommand::Add {
+            callsign,
+            comment,
+            mut actions,
+            mut mode,
+            name,
+            tags,
+            expires,
+        } => {
+            if callsign.is_empty() {
+                return Err(CliError::Validation("\"callsign\" is empty".to_string()));
+            }
+            let callsign = validate_patterns(&callsign)?;
+            if let Some(name) = &name {
+                ensure_name_available(name)?;
+            }
+            if actions.is_empty() {
+                actions = config.defaults.actions.clone();
+            }
+            if mode.is_empty() {
+                mode = config.defaults.mode.clone();
+            }
+            let mode = (!mode.is_empty()).then(|| mode_string(&mode));
+            let comment = if config.defaults.count_suffix {
+                with_callsign_count(&comment, callsign.len())
+            } else {
+                comment
+            };
+            let conditions = Conditions {
+                callsign: callsign.join(","),
+                mode,
+                ..Default::default()
+            };
+            let id = add_trigger(
+                client,
+                conditions,
+                &comment,
+                actions.iter().map(|a| a.as_str().to_string()).collect(),
+            )
+            .await?;
+            summary.added += 1;
+            created.push(NewTrigger {
+                id,
+                name,
+                comment: comment.clone(),
+                metadata: ManagedTrigger::new("batch".to_string(), expires, tags),
+            });
+            Ok(format!("added trigger '{}'", comment))
+        }
+        BatchCommand::Update {
+            id,
+            name,
+            comment,
+            callsign,
+            mode,
+            actions,
+            disabled,
+        } => {
+            let id = resolve_target(id, name)?;
+            let trigger = triggers
+                .iter_mut()
+                .find(|t| t.id == id)
+                .ok_or_else(|| CliError::Validation(format!("No trigger with id {}", id)))?;
+            if let Some(comment) = comment {
+                trigger.comment = comment;
+            }
+            if let Some(conditions) = trigger.conditions.as_object_mut() {
+                if let Some(callsign) = callsign {
+                    let callsign = validate_patterns(&callsign)?;
+                    conditions.insert("callsign".to_string(), json!(callsign.join(",")));
+                }
+                match mode {
+                    Some(mode) if mode.is_empty() => {
+                        conditions.remove("mode");
+                    }
+                    Some(mode) => {
+                        conditions.insert("mode".to_string(), json!(mode_string(&mode)));
+                    }
+                    None => {}
+                }
+            }
+            if let Some(actions) = actions {
+                trigger.actions = actions.iter().map(|a| a.as_str().to_string()).collect();
+            }
+            if disabled.is_some() {
+                trigger.disabled = disabled;
+            }
+            refresh_callsign_count(trigger);
+            client.update_trigger(trigger).await?;
+            summary.updated += 1;
+            Ok(format!("updated trigger '{}'", trigger.comment))
+        }
+        BatchCommand::Delete {
+            id,
+            name,
+            include_permanent,
+        } => {
+            let id = resolve_target(id, name)?;
+            if !include_permanent
+                && let Some(trigger) = triggers.iter().find(|t| t.id == id)
+                && is_permanent(trigger, &load_permanent_triggers()?)
+            {
+                return Err(CliError::Validation(format!(
+                    "Trigger '{}' is permanent; set \"include_permanent\": true to delete it",
+                    trigger.comment
+                )));
+            }
+            client.delete_trigger(&id).await?;
+            let comment = triggers
+                .iter()
+                .position(|t| t.id == id)
+                .map(|i| triggers.remove(i).comment)
+                .unwrap_or_else(|| id.clone());
+            let mut state = load_state()?;
+            if state.forget(&id) {
+                save_state(&state)?;
+            }
+            summary.deleted += 1;
+            Ok(format!("deleted trigger '{}'", comment))
+        }
+    }
+}
+
+/// Read commands from stdin and run them in order, continuing past failures
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    config: &Config,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let mut input = String::new();
+    for line in std::io::stdin().lock().lines() {
+        input.push_str(&line?);
+        input.push('\n');
+    }
+
+    let mut triggers = fetch_triggers(client).await?;
+    let mut created = Vec::new();
+    let mut summary = Summary::default();
+    let mut results = Vec::new();
+    for (line, command) in parse_batch(&input) {
+        let result = match command {
+            Ok(command) => {
+                execute(
+                    client,
+                    config,
+                    &mut triggers,
+                    &mut created,
+                    &mut summary,
+                    command,
+                )
+                .await
+            }
+            Err(e) => Err(CliError::Validation(format!("invalid command: {}", e))),
+        };
+        match &result {
+            Ok(message) if !json_output => println!("line {}: {}", line, message),
+            Err(e) if !json_output => eprintln!("line {}: error: {}", line, e),
+            _ => {}
+        }
+        if result.is_err() {
+            summary.failed += 1;
+        }
+        results.push(match result {
+            Ok(message) => json!({"line": line, "ok": true, "message": message}),
+            Err(e) => json!({"line": line, "ok": false, "error": e.to_string()}),
+        });
+    }
+    if !created.is_empty() {
+        remember_new_triggers(client, created).await;
+    }
+
+    if json_output {
+        print_json(&json!({
+            "results": results,
+            "added": summary.added,
+            "updated": summary.updated,
+            "deleted": summary.deleted,
+            "failed": summary.failed,
+        }))?;
+    } else {
+        println!(
+            "\nBatch: {} added, {} updated, {} deleted, {} failed",
+            summary.added, summary.updated, summary.deleted, summary.failed
+        );
+    }
+    if summary.failed > 0 {
+        return Err(CliError::Other(format!(
+            "{} of {} batch commands failed",
+            summary.failed,
+            results.len()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_batch() {
+        let input = r#"{"op": "add", "callsign": ["W1AW", "K3LR"], "comment": "Club", "mode": ["cw"]}
+
+{"op": "delete", "name": "club"}
+{"op": "update", "id": "65a1", "disabled": true, "actions": ["app"]}
+{"op": "rename", "id": "65a1"}
+not json
+"#;
+        let commands = parse_batch(input);
+        let lines: Vec<usize> = commands.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![1, 3, 4, 5, 6]);
+        assert!(matches!(
+            &commands[0].1,
+            Ok(BatchCommand::Add { callsign, mode, .. }) if callsign.len() == 2 && mode.len() == 1
+        ));
+        assert!(matches!(
+            &commands[1].1,
+            Ok(BatchCommand::Delete { id: None, name: Some(name), include_permanent: false })
+                if name == "club"
+        ));
+        assert!(matches!(
+            &commands[2].1,
+            Ok(BatchCommand::Update {
+                disabled: Some(true),
+                actions: Some(_),
+                comment: None,
+                ..
+            })
+        ));
+        assert!(commands[3].1.is_err());
+        assert!(commands[4].1.is_err());
+    }
+
+    #[test]
+    fn test_resolve_target_needs_exactly_one_key() {
+        assert_eq!(resolve_target(Some("abc".into()), None).unwrap(), "abc");
+        assert!(resolve_target(None, None).is_err());
+        assert!(resolve_target(Some("abc".into()), Some("club".into())).is_err());
+    }
+}
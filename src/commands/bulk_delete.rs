@@ -0,0 +1,129 @@
+//! `bulk-delete`.
+
+use crate::api::{fetch_triggers, for_each_concurrent};
+use crate::cli::TriggerSelection;
+use crate::error::CliError;
+use crate::output::{confirm, format_trigger_for_display};
+use crate::storage::backups::{backup_dir, write_backup};
+use crate::storage::profiles::{load_permanent_triggers, partition_permanent};
+use chrono::Local;
+use hamalert_api::{HamAlertClient, Trigger};
+use inquire::{InquireError, MultiSelect};
+
+/// Pick triggers interactively, among the `selection`, and delete them
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    dry_run: bool,
+    selection: &TriggerSelection,
+    include_permanent: bool,
+    assume_yes: bool,
+) -> Result<(), CliError> {
+    let all_triggers = fetch_triggers(client).await?;
+    let mut triggers = all_triggers.clone();
+    selection.retain(&mut triggers)?;
+    let mut permanent = Vec::new();
+    if !include_permanent {
+        (permanent, triggers) = partition_permanent(triggers, &load_permanent_triggers()?);
+    }
+    if !permanent.is_empty() {
+        println!(
+            "Not offering {} permanent triggers (use --include-permanent to delete them too).",
+            permanent.len()
+        );
+    }
+
+    if triggers.is_empty() {
+        println!("No triggers found.");
+        return Ok(());
+    }
+
+    println!("Fetched {} triggers.\n", triggers.len());
+    println!("Instructions:");
+    println!("  j/k or arrows: Navigate up/down");
+    println!("  Space: Toggle selection (unchecked = will be DELETED)");
+    println!("  Enter: Confirm");
+    println!("  Esc: Cancel\n");
+
+    // Build display items
+    let display_items: Vec<String> = triggers.iter().map(format_trigger_for_display).collect();
+
+    // All items start selected (checked = keep)
+    let default_selections: Vec<usize> = (0..triggers.len()).collect();
+
+    // Run the interactive multi-select
+    let kept_result = MultiSelect::new(
+        "Select triggers to KEEP (unchecked will be deleted):",
+        display_items.clone(),
+    )
+    .with_default(&default_selections)
+    .with_vim_mode(true)
+    .with_page_size(15)
+    .with_help_message("Space=toggle, j/k=navigate, Enter=confirm, Esc=cancel")
+    .prompt();
+
+    let kept_displays: Vec<String> = match kept_result {
+        Ok(selected) => selected,
+        Err(InquireError::OperationCanceled) | Err(InquireError::OperationInterrupted) => {
+            return Err(CliError::Cancelled("Operation cancelled.".to_string()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // Find triggers to delete (those NOT in kept list)
+    let kept_set: std::collections::HashSet<&str> =
+        kept_displays.iter().map(|s| s.as_str()).collect();
+    let to_delete: Vec<&Trigger> = triggers
+        .iter()
+        .filter(|t| !kept_set.contains(format_trigger_for_display(t).as_str()))
+        .collect();
+
+    if to_delete.is_empty() {
+        println!("No triggers selected for deletion.");
+        return Ok(());
+    }
+
+    // Show summary
+    println!("\nTriggers to DELETE ({}):", to_delete.len());
+    for trigger in &to_delete {
+        println!("  - {}", format_trigger_for_display(trigger));
+    }
+
+    // Dry run mode
+    if dry_run {
+        println!("\n[DRY RUN] No triggers were deleted.");
+        return Ok(());
+    }
+
+    // Confirmation prompt
+    println!();
+    if !confirm("Proceed with deletion?", assume_yes)? {
+        return Err(CliError::Cancelled("Deletion cancelled.".to_string()));
+    }
+
+    // Auto-backup before deletion
+    let backup_path = backup_dir()?.join(format!(
+        "hamalert-backup-before-bulk-delete-{}.json",
+        Local::now().format("%Y-%m-%d-%H%M%S")
+    ));
+    write_backup(&backup_path, &all_triggers)?;
+    println!(
+        "Backed up {} triggers to {}",
+        all_triggers.len(),
+        backup_path.display()
+    );
+
+    // Delete the selected triggers
+    for_each_concurrent(&to_delete, |trigger| async move {
+        client.delete_trigger(&trigger.id).await?;
+        println!("Deleted: {}", format_trigger_for_display(trigger));
+        Ok(())
+    })
+    .await?;
+
+    println!(
+        "\nDeleted {} trigger(s). Kept {} trigger(s).",
+        to_delete.len(),
+        triggers.len() - to_delete.len() + permanent.len()
+    );
+    Ok(())
+}
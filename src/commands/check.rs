@@ -0,0 +1,290 @@
+//! `check`: analyses of the trigger set as a whole.
+
+use crate::api::fetch_triggers;
+use crate::cli::CheckCommands;
+use crate::declarative::normalize_conditions;
+use crate::error::CliError;
+use crate::output::{print_json, render_table, terminal_width};
+use hamalert_api::{HamAlertClient, Trigger};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum OverlapKind {
+    /// Both triggers have the same conditions
+    Duplicate,
+    /// Every spot for `trigger` also matches `other`
+    Subsumed,
+    /// Some spots match both triggers
+    Shared,
+}
+
+impl OverlapKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OverlapKind::Duplicate => "duplicate",
+            OverlapKind::Subsumed => "subsumed",
+            OverlapKind::Shared => "shared",
+        }
+    }
+}
+
+/// Two triggers that alert for the same spots
+#[derive(Debug, Serialize)]
+pub(crate) struct Overlap {
+    pub(crate) kind: OverlapKind,
+    pub(crate) trigger: String,
+    pub(crate) other: String,
+    pub(crate) suggestion: String,
+}
+
+/// Whether callsign pattern `general` matches every callsign `specific` does
+fn covers_callsign(general: &str, specific: &str) -> bool {
+    general == specific
+        || general
+            .strip_suffix('*')
+            .is_some_and(|prefix| specific.starts_with(prefix))
+}
+
+fn item_covers(key: &str, general: &Value, specific: &Value) -> bool {
+    match (key, general.as_str(), specific.as_str()) {
+        ("callsign", Some(general), Some(specific)) => covers_callsign(general, specific),
+        _ => general == specific,
+    }
+}
+
+/// Whether condition `general` accepts every value `specific` does. Lists
+/// are compared as sets, anything else must be equal.
+fn value_covers(key: &str, general: &Value, specific: &Value) -> bool {
+    match (general, specific) {
+        (Value::Array(general), Value::Array(specific)) => specific
+            .iter()
+            .all(|s| general.iter().any(|g| item_covers(key, g, s))),
+        _ => general == specific,
+    }
+}
+
+/// Whether every spot matching `specific` also matches `general`: each
+/// condition of `general` is present in `specific` and at most as wide
+fn subsumes(general: &Map<String, Value>, specific: &Map<String, Value>) -> bool {
+    general.iter().all(|(key, value)| {
+        specific
+            .get(key)
+            .is_some_and(|other| value_covers(key, value, other))
+    })
+}
+
+/// Items of list condition `key` that some spot could match in both `a` and `b`
+fn shared_items(key: &str, a: &Value, b: &Value) -> Vec<String> {
+    let (Value::Array(a), Value::Array(b)) = (a, b) else {
+        return if a == b {
+            vec![a.to_string()]
+        } else {
+            Vec::new()
+        };
+    };
+    let mut shared: Vec<String> = a
+        .iter()
+        .filter(|x| b.iter().any(|y| item_covers(key, y, x)))
+        .chain(
+            b.iter()
+                .filter(|y| a.iter().any(|x| item_covers(key, x, y))),
+        )
+        .map(|v| {
+            v.as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| v.to_string())
+        })
+        .collect();
+    shared.sort();
+    shared.dedup();
+    shared
+}
+
+/// Callsigns both triggers alert for, if all their common conditions can
+/// match the same spot
+fn shared_callsigns(a: &Map<String, Value>, b: &Map<String, Value>) -> Vec<String> {
+    let compatible = a.iter().all(|(key, value)| {
+        b.get(key)
+            .is_none_or(|other| !shared_items(key, value, other).is_empty())
+    });
+    match (a.get("callsign"), b.get("callsign")) {
+        (Some(x), Some(y)) if compatible => shared_items("callsign", x, y),
+        _ => Vec::new(),
+    }
+}
+
+/// Overlapping pairs among the enabled `triggers`
+pub(crate) fn find_overlaps(triggers: &[Trigger]) -> Vec<Overlap> {
+    let enabled: Vec<(&Trigger, Map<String, Value>)> = triggers
+        .iter()
+        .filter(|t| !t.disabled.unwrap_or(false))
+        .map(|t| match normalize_conditions(&t.conditions) {
+            Value::Object(map) => (t, map),
+            _ => (t, Map::new()),
+        })
+        .collect();
+
+    let mut overlaps = Vec::new();
+    for (i, (a, a_conditions)) in enabled.iter().enumerate() {
+        for (b, b_conditions) in &enabled[i + 1..] {
+            let a_covers_b = subsumes(a_conditions, b_conditions);
+            let b_covers_a = subsumes(b_conditions, a_conditions);
+            let (kind, trigger, other, suggestion) = if a_covers_b && b_covers_a {
+                (
+                    OverlapKind::Duplicate,
+                    b,
+                    a,
+                    "same conditions; merge the actions into one trigger".to_string(),
+                )
+            } else if a_covers_b || b_covers_a {
+                let (narrow, wide) = if a_covers_b { (b, a) } else { (a, b) };
+                (
+                    OverlapKind::Subsumed,
+                    narrow,
+                    wide,
+                    format!(
+                        "every spot also matches '{}'; delete this one or narrow the other",
+                        wide.comment
+                    ),
+                )
+            } else {
+                let shared = shared_callsigns(a_conditions, b_conditions);
+                if shared.is_empty() {
+                    continue;
+                }
+                (
+                    OverlapKind::Shared,
+                    b,
+                    a,
+                    format!(
+                        "both alert for {}; move them into one trigger",
+                        shared.join(", ")
+                    ),
+                )
+            };
+            overlaps.push(Overlap {
+                kind,
+                trigger: trigger.comment.clone(),
+                other: other.comment.clone(),
+                suggestion,
+            });
+        }
+    }
+    overlaps
+}
+
+/// Run a `check` subcommand and print its report
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    command: CheckCommands,
+    json_output: bool,
+) -> Result<(), CliError> {
+    match command {
+        CheckCommands::Overlaps => {
+            let triggers = fetch_triggers(client).await?;
+            let overlaps = find_overlaps(&triggers);
+            if json_output {
+                print_json(&serde_json::to_value(&overlaps)?)?;
+            } else if overlaps.is_empty() {
+                println!("No overlapping triggers among {}.", triggers.len());
+            } else {
+                let rows: Vec<Vec<String>> = overlaps
+                    .iter()
+                    .map(|o| {
+                        vec![
+                            o.kind.as_str().to_string(),
+                            o.trigger.clone(),
+                            o.other.clone(),
+                            o.suggestion.clone(),
+                        ]
+                    })
+                    .collect();
+                println!(
+                    "{}",
+                    render_table(
+                        &["KIND", "TRIGGER", "OVERLAPS", "SUGGESTION"],
+                        &rows,
+                        terminal_width()
+                    )
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::test_trigger;
+    use serde_json::json;
+
+    fn trigger(comment: &str, conditions: Value) -> Trigger {
+        test_trigger(comment, comment, conditions)
+    }
+
+    fn kinds(triggers: &[Trigger]) -> Vec<(OverlapKind, String, String)> {
+        find_overlaps(triggers)
+            .into_iter()
+            .map(|o| (o.kind, o.trigger, o.other))
+            .collect()
+    }
+
+    #[test]
+    fn test_duplicate_and_subsumed() {
+        let triggers = vec![
+            trigger("Japan", json!({"callsign": "JA*"})),
+            trigger("JA1", json!({"callsign": "JA1ABC, ja1xyz", "mode": "cw"})),
+            trigger("Japan again", json!({"callsign": ["ja*"]})),
+        ];
+        assert_eq!(
+            kinds(&triggers),
+            vec![
+                (
+                    OverlapKind::Subsumed,
+                    "JA1".to_string(),
+                    "Japan".to_string()
+                ),
+                (
+                    OverlapKind::Duplicate,
+                    "Japan again".to_string(),
+                    "Japan".to_string()
+                ),
+                (
+                    OverlapKind::Subsumed,
+                    "JA1".to_string(),
+                    "Japan again".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shared_callsigns() {
+        let triggers = vec![
+            trigger("Friends", json!({"callsign": "W1AW,K3LR", "mode": "cw"})),
+            trigger(
+                "Contesters",
+                json!({"callsign": "K3LR,N2IC", "mode": "cw,ssb"}),
+            ),
+            trigger("Phone", json!({"callsign": "K3LR", "mode": "ssb"})),
+        ];
+        let overlaps = find_overlaps(&triggers);
+        assert_eq!(overlaps.len(), 2);
+        assert_eq!(overlaps[0].kind, OverlapKind::Shared);
+        assert!(overlaps[0].suggestion.contains("K3LR"));
+        // Friends (cw) and Phone (ssb) never match the same spot
+        assert_eq!(overlaps[1].kind, OverlapKind::Subsumed);
+        assert_eq!(overlaps[1].trigger, "Phone");
+    }
+
+    #[test]
+    fn test_disabled_triggers_ignored() {
+        let mut disabled = trigger("Old", json!({"callsign": "W1AW"}));
+        disabled.disabled = Some(true);
+        let triggers = vec![trigger("New", json!({"callsign": "W1AW"})), disabled];
+        assert!(find_overlaps(&triggers).is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+//! `config`: create, show and validate the config file.
+
+use crate::api::fetch_triggers;
+use crate::cli::ConfigCommands;
+use crate::config::{Config, load_config, resolve_config_path};
+use crate::error::CliError;
+use crate::output::confirm;
+use hamalert_api::is_redacted_key;
+use std::fs;
+use std::path::PathBuf;
+
+/// Replace secret values in a parsed config before showing it
+pub(crate) fn redact_config(value: &mut toml::Value) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                if is_redacted_key(key) && value.is_str() {
+                    *value = toml::Value::String("***".to_string());
+                } else {
+                    redact_config(value);
+                }
+            }
+        }
+        toml::Value::Array(items) => items.iter_mut().for_each(redact_config),
+        _ => {}
+    }
+}
+
+/// Render the contents of a new config file
+pub(crate) fn render_new_config(
+    username: &str,
+    password: &str,
+    telnet_password: Option<&str>,
+) -> String {
+    let mut table = toml::map::Map::new();
+    table.insert(
+        "username".to_string(),
+        toml::Value::String(username.to_string()),
+    );
+    table.insert(
+        "password".to_string(),
+        toml::Value::String(password.to_string()),
+    );
+    if let Some(telnet_password) = telnet_password {
+        let mut telnet = toml::map::Map::new();
+        telnet.insert(
+            "password".to_string(),
+            toml::Value::String(telnet_password.to_string()),
+        );
+        table.insert("telnet".to_string(), toml::Value::Table(telnet));
+    }
+    toml::to_string(&toml::Value::Table(table)).unwrap_or_default()
+}
+
+pub(crate) async fn run(
+    command: ConfigCommands,
+    config_file: Option<PathBuf>,
+    assume_yes: bool,
+    prompt_password: bool,
+    timeout: Option<u64>,
+) -> Result<(), CliError> {
+    let path = resolve_config_path(config_file.clone())?;
+    match command {
+        ConfigCommands::Path => println!("{}", path.display()),
+        ConfigCommands::Show => {
+            let content = fs::read_to_string(&path).map_err(|e| {
+                CliError::Config(format!("Failed to read {}: {}", path.display(), e))
+            })?;
+            let mut value: toml::Value = toml::from_str(&content)
+                .map_err(|e| CliError::Config(format!("Failed to parse config file: {}", e)))?;
+            redact_config(&mut value);
+            println!("# {}", path.display());
+            print!(
+                "{}",
+                toml::to_string_pretty(&value).map_err(|e| CliError::Config(e.to_string()))?
+            );
+        }
+        ConfigCommands::Validate => {
+            let config = load_config(config_file, prompt_password)?;
+            println!("Config file {} is valid.", path.display());
+            let client = config.api_client(timeout)?;
+            client.login(&config.username, &config.password).await?;
+            fetch_triggers(&client).await?;
+            println!("Logged in to HamAlert as {}.", config.username);
+        }
+        ConfigCommands::Init => {
+            if path.exists()
+                && !confirm(
+                    &format!("{} already exists. Overwrite?", path.display()),
+                    assume_yes,
+                )?
+            {
+                return Err(CliError::Cancelled("Cancelled.".to_string()));
+            }
+
+            let username = inquire::Text::new("HamAlert username:").prompt()?;
+            let password = inquire::Password::new("HamAlert password:")
+                .without_confirmation()
+                .prompt()?;
+            let telnet_password = inquire::Password::new(
+                "Telnet password (leave empty if the same as the web password):",
+            )
+            .without_confirmation()
+            .prompt()?;
+            let telnet_password = Some(telnet_password.trim()).filter(|p| !p.is_empty());
+
+            let client = Config::default().api_client(timeout)?;
+            if let Err(e) = client.login(username.trim(), &password).await {
+                eprintln!("Warning: could not log in with these credentials: {}", e);
+            }
+
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(
+                &path,
+                render_new_config(username.trim(), &password, telnet_password),
+            )?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+            }
+            println!("Wrote {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_render_new_config_round_trips() {
+        let content = render_new_config("W1AW", "web \"pw\"", Some("telnet"));
+        let config: Config = toml::from_str(&content).unwrap();
+        assert_eq!(config.username, "W1AW");
+        assert_eq!(config.password, "web \"pw\"");
+        assert_eq!(config.telnet_login().password, "telnet");
+    }
+
+    #[test]
+    fn test_redact_config() {
+        let mut value: toml::Value = toml::from_str(
+            r#"
+            username = "W1AW"
+            password = "secret"
+
+            [[destinations]]
+            type = "discord"
+            webhook_url = "https://discord.com/api/webhooks/1"
+            "#,
+        )
+        .unwrap();
+        redact_config(&mut value);
+        assert_eq!(value["username"].as_str(), Some("W1AW"));
+        assert_eq!(value["password"].as_str(), Some("***"));
+        assert_eq!(
+            value["destinations"][0]["webhook_url"].as_str(),
+            Some("***")
+        );
+    }
+}
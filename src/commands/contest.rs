@@ -0,0 +1,239 @@
+//! `contest`: swap in a minimal trigger set for a contest and back again.
+
+use super::profile::switch;
+use crate::api::fetch_triggers;
+use crate::cli::{Action, ContestCommands, Mode};
+use crate::config::Config;
+use crate::dxcc::resolve_dxcc;
+use crate::error::CliError;
+use crate::model::StoredTrigger;
+use crate::output::format_stored_trigger_for_display;
+use crate::storage::profiles::{
+    ContestSession, clear_contest, delete_profile, filter_out_permanent, load_contest,
+    load_current_profile_name, load_permanent_triggers, load_profile, save_contest,
+    save_current_profile_name, save_profile,
+};
+use crate::storage::state::slugify;
+use chrono::Local;
+use hamalert_api::HamAlertClient;
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+/// Profile holding the triggers for contest `name`
+fn contest_profile(name: &str) -> String {
+    format!("contest-{}", slugify(name))
+}
+
+/// Profile the triggers from before contest `name` are saved to
+fn restore_profile(name: &str) -> String {
+    format!("before-contest-{}", slugify(name))
+}
+
+/// Needed multipliers listed one per line or separated by commas. Empty
+/// lines and lines starting with # or // are skipped.
+fn parse_multipliers(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("//"))
+        .flat_map(|line| line.split(','))
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The one trigger of a generated contest profile: spots of the needed
+/// multipliers, optionally in `modes`
+fn multiplier_trigger(
+    contest: &str,
+    dxcc: &[u16],
+    modes: &[Mode],
+    actions: &[Action],
+) -> StoredTrigger {
+    let mut conditions = json!({ "dxcc": dxcc });
+    if !modes.is_empty() {
+        let modes: Vec<&str> = modes.iter().map(|m| m.as_str()).collect();
+        conditions["mode"] = json!(modes.join(","));
+    }
+    StoredTrigger {
+        conditions,
+        actions: actions.iter().map(|a| a.as_str().to_string()).collect(),
+        comment: format!("Contest {}: needed multipliers", contest),
+        options: None,
+    }
+}
+
+/// The contest profile's triggers, generated from `multipliers` or saved
+/// beforehand with `profile save`
+fn contest_triggers(
+    name: &str,
+    multipliers: Option<&Path>,
+    modes: &[Mode],
+    actions: &[Action],
+) -> Result<Vec<StoredTrigger>, CliError> {
+    let Some(path) = multipliers else {
+        let profile = contest_profile(name);
+        return load_profile(&profile).map_err(|_| {
+            CliError::Validation(format!(
+                "No profile '{}'; save one with `profile save {}` or pass --multipliers",
+                profile, profile
+            ))
+        });
+    };
+    let content = fs::read_to_string(path)
+        .map_err(|e| CliError::io(format!("Failed to read {}", path.display()), e))?;
+    let needed = parse_multipliers(&content);
+    if needed.is_empty() {
+        return Err(CliError::Validation(format!(
+            "No multipliers listed in {}",
+            path.display()
+        )));
+    }
+    let dxcc = resolve_dxcc(&needed)?;
+    Ok(vec![multiplier_trigger(name, &dxcc, modes, actions)])
+}
+
+async fn start(
+    client: &HamAlertClient,
+    name: &str,
+    contest: Vec<StoredTrigger>,
+    no_dry_run: bool,
+) -> Result<(), CliError> {
+    if let Some(session) = load_contest()? {
+        return Err(CliError::Validation(format!(
+            "Contest '{}' is already running; run `contest end` first",
+            session.name
+        )));
+    }
+    let permanent = load_permanent_triggers()?;
+    let current: Vec<StoredTrigger> = fetch_triggers(client)
+        .await?
+        .iter()
+        .map(StoredTrigger::from_trigger)
+        .collect();
+    let saved = filter_out_permanent(&current, &permanent);
+    let (profile, restore) = (contest_profile(name), restore_profile(name));
+
+    println!(
+        "Saving {} triggers to profile '{}' ({} permanent stay active)",
+        saved.len(),
+        restore,
+        current.len() - saved.len()
+    );
+    println!(
+        "Contest profile '{}' has {} triggers:",
+        profile,
+        contest.len()
+    );
+    for trigger in &contest {
+        println!("  + {}", format_stored_trigger_for_display(trigger));
+    }
+    if !no_dry_run {
+        println!("\nDRY RUN - No changes made. Run with --no-dry-run to start the contest.");
+        return Ok(());
+    }
+
+    save_profile(&profile, &contest)?;
+    save_profile(&restore, &saved)?;
+    save_contest(&ContestSession {
+        name: name.to_string(),
+        started_at: Local::now().to_rfc3339(),
+        restore_profile: restore,
+        previous_profile: load_current_profile_name()?,
+    })?;
+    switch(client, &profile, true).await?;
+    println!(
+        "\nContest '{}' started. Run `contest end --no-dry-run` to restore your triggers.",
+        name
+    );
+    Ok(())
+}
+
+async fn end(client: &HamAlertClient, no_dry_run: bool) -> Result<(), CliError> {
+    let session =
+        load_contest()?.ok_or_else(|| CliError::Validation("No contest is running".to_string()))?;
+    let restore = load_profile(&session.restore_profile)?;
+    println!(
+        "Ending contest '{}' (started {}): restoring {} triggers from '{}'",
+        session.name,
+        session.started_at.get(..16).unwrap_or(&session.started_at),
+        restore.len(),
+        session.restore_profile
+    );
+    if !no_dry_run {
+        println!("\nDRY RUN - No changes made. Run with --no-dry-run to end the contest.");
+        return Ok(());
+    }
+
+    switch(client, &session.restore_profile, true).await?;
+    save_current_profile_name(session.previous_profile.as_deref().unwrap_or_default())?;
+    delete_profile(&session.restore_profile)?;
+    clear_contest()?;
+    println!(
+        "\nContest '{}' ended; your triggers are back.",
+        session.name
+    );
+    Ok(())
+}
+
+/// Run a `contest` subcommand
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    config: &Config,
+    command: ContestCommands,
+) -> Result<(), CliError> {
+    match command {
+        ContestCommands::Start {
+            name,
+            multipliers,
+            mode,
+            actions,
+            no_dry_run,
+        } => {
+            let actions = if actions.is_empty() {
+                config.defaults.actions.clone()
+            } else {
+                actions
+            };
+            let contest = contest_triggers(&name, multipliers.as_deref(), &mode, &actions)?;
+            start(client, &name, contest, no_dry_run).await
+        }
+        ContestCommands::End { no_dry_run } => end(client, no_dry_run).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_names() {
+        assert_eq!(contest_profile("CQWW CW"), "contest-cqww-cw");
+        assert_eq!(restore_profile("CQWW CW"), "before-contest-cqww-cw");
+    }
+
+    #[test]
+    fn test_parse_multipliers() {
+        let content = "# Still needed\nVK, ZL\n\n// done: JA\nNew Zealand\n 291 \n";
+        assert_eq!(
+            parse_multipliers(content),
+            vec!["VK", "ZL", "New Zealand", "291"]
+        );
+    }
+
+    #[test]
+    fn test_multiplier_trigger() {
+        let trigger = multiplier_trigger("cqww-cw", &[150, 170], &[Mode::CW], &[Action::App]);
+        assert_eq!(
+            trigger.conditions,
+            json!({"dxcc": [150, 170], "mode": "cw"})
+        );
+        assert_eq!(trigger.actions, vec!["app"]);
+        assert_eq!(trigger.comment, "Contest cqww-cw: needed multipliers");
+
+        let trigger = multiplier_trigger("cqww-cw", &[150], &[], &[]);
+        assert_eq!(trigger.conditions, json!({"dxcc": [150]}));
+    }
+}
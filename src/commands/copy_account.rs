@@ -0,0 +1,168 @@
+//! `copy-account`: replicate triggers between configured accounts.
+
+use crate::api::{fetch_triggers, for_each_concurrent};
+use crate::config::{Config, resolve_config_path};
+use crate::error::CliError;
+use crate::model::{StoredTrigger, triggers_match};
+use crate::output::{confirm, format_trigger_for_display};
+use hamalert_api::Trigger;
+use std::fs;
+use std::path::PathBuf;
+
+/// Triggers from `source` to copy into an account that already has `existing`:
+/// those matching `filter` (case-insensitive, against comment and callsigns)
+/// and not already present there
+pub(crate) fn select_triggers_to_copy<'a>(
+    source: &'a [Trigger],
+    existing: &[Trigger],
+    filter: Option<&str>,
+) -> Vec<&'a Trigger> {
+    let existing: Vec<StoredTrigger> = existing.iter().map(StoredTrigger::from_trigger).collect();
+    let filter = filter.map(|f| f.to_lowercase());
+    source
+        .iter()
+        .filter(|trigger| match &filter {
+            Some(filter) => {
+                let callsigns = trigger
+                    .conditions
+                    .get("callsign")
+                    .map(|v| v.to_string())
+                    .unwrap_or_default();
+                trigger.comment.to_lowercase().contains(filter)
+                    || callsigns.to_lowercase().contains(filter)
+            }
+            None => true,
+        })
+        .filter(|trigger| {
+            let stored = StoredTrigger::from_trigger(trigger);
+            !existing.iter().any(|e| triggers_match(e, &stored))
+        })
+        .collect()
+}
+
+/// Log into two named accounts and copy the selected triggers across
+pub(crate) async fn run(
+    config_file: Option<PathBuf>,
+    timeout: Option<u64>,
+    from: &str,
+    to: &str,
+    filter: Option<&str>,
+    no_dry_run: bool,
+    assume_yes: bool,
+) -> Result<(), CliError> {
+    if from == to {
+        return Err(CliError::Validation(
+            "--from and --to must be different accounts".to_string(),
+        ));
+    }
+    let config_path = resolve_config_path(config_file)?;
+    let content = fs::read_to_string(&config_path).map_err(|e| {
+        CliError::Config(format!(
+            "Failed to read config file at {}: {}",
+            config_path.display(),
+            e
+        ))
+    })?;
+    let config: Config = toml::from_str(&content)
+        .map_err(|e| CliError::Config(format!("Failed to parse config file: {}", e)))?;
+
+    let (from_user, from_password) = config.account_login(from)?;
+    let (to_user, to_password) = config.account_login(to)?;
+
+    let source_client = config.api_client(timeout)?;
+    source_client.login(&from_user, &from_password).await?;
+    let dest_client = config.api_client(timeout)?;
+    dest_client.login(&to_user, &to_password).await?;
+
+    let source_triggers = fetch_triggers(&source_client).await?;
+    let dest_triggers = fetch_triggers(&dest_client).await?;
+    let to_copy = select_triggers_to_copy(&source_triggers, &dest_triggers, filter);
+
+    if to_copy.is_empty() {
+        println!(
+            "Nothing to copy: {} already has every selected trigger from {}.",
+            to, from
+        );
+        return Ok(());
+    }
+
+    if !no_dry_run {
+        println!("DRY RUN - No changes will be made\n");
+        println!(
+            "This will copy {} of {} triggers from {} ({}) to {} ({}):",
+            to_copy.len(),
+            source_triggers.len(),
+            from,
+            from_user,
+            to,
+            to_user
+        );
+        for trigger in &to_copy {
+            println!("  {}", format_trigger_for_display(trigger));
+        }
+        println!("\nRun with --no-dry-run to execute.");
+        return Ok(());
+    }
+
+    let question = format!("Copy {} triggers from {} to {}?", to_copy.len(), from, to);
+    if !confirm(&question, assume_yes)? {
+        return Err(CliError::Cancelled("Copy cancelled.".to_string()));
+    }
+
+    let dest_client = &dest_client;
+    for_each_concurrent(&to_copy, |trigger| async move {
+        dest_client.create_trigger(trigger).await?;
+        println!("Copied trigger: {}", trigger.comment);
+        Ok(())
+    })
+    .await?;
+    println!(
+        "\nCopied {} triggers from {} to {}",
+        to_copy.len(),
+        from,
+        to
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_select_triggers_to_copy() {
+        let trigger = |id: &str, callsign: &str, comment: &str| -> Trigger {
+            serde_json::from_value(json!({
+                "_id": id,
+                "conditions": { "callsign": callsign },
+                "actions": ["app"],
+                "comment": comment
+            }))
+            .unwrap()
+        };
+        let source = vec![
+            trigger("1", "W1AW", "Club DXpedition"),
+            trigger("2", "K3LR", "Contest"),
+            trigger("3", "VP8PJ", "Club DXpedition"),
+        ];
+        let existing = vec![trigger("9", "W1AW", "Club DXpedition")];
+
+        let ids = |selected: Vec<&Trigger>| -> Vec<String> {
+            selected.iter().map(|t| t.id.clone()).collect()
+        };
+        assert_eq!(
+            ids(select_triggers_to_copy(&source, &existing, None)),
+            vec!["2", "3"]
+        );
+        assert_eq!(
+            ids(select_triggers_to_copy(&source, &[], Some("club"))),
+            vec!["1", "3"]
+        );
+        assert_eq!(
+            ids(select_triggers_to_copy(&source, &existing, Some("k3lr"))),
+            vec!["2"]
+        );
+    }
+}
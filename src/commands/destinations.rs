@@ -0,0 +1,356 @@
+//! `destinations`: the account's notification settings on HamAlert.
+
+use crate::api::add_trigger;
+use crate::cli::{Action, DestinationsCommands};
+use crate::error::CliError;
+use crate::output::{YELLOW, paint, print_json, render_table, terminal_width};
+use chrono::Local;
+use hamalert_api::{Conditions, Destinations, HamAlertClient, SimulatedSpot};
+use std::time::Duration;
+
+/// Length of a Threema ID
+const THREEMA_ID_LEN: usize = 8;
+
+/// Frequency of the test spot in kHz
+const TEST_FREQUENCY_KHZ: f64 = 14060.0;
+
+/// Seconds between checks for the test trigger's match
+const POLL_SECONDS: u64 = 5;
+
+/// Requested changes; `Some("")` clears a text setting
+#[derive(Debug, Default)]
+pub(crate) struct DestinationChanges {
+    pub(crate) url: Option<String>,
+    pub(crate) threema_id: Option<String>,
+    pub(crate) telnet: Option<bool>,
+}
+
+fn validate_url(url: &str) -> Result<(), CliError> {
+    if url.is_empty() || url.starts_with("https://") || url.starts_with("http://") {
+        Ok(())
+    } else {
+        Err(CliError::Validation(format!(
+            "--url must start with http:// or https://, got '{}'",
+            url
+        )))
+    }
+}
+
+/// Uppercased Threema ID, or "" to clear it
+fn validate_threema_id(id: &str) -> Result<String, CliError> {
+    let id = id.trim().to_ascii_uppercase();
+    if id.is_empty()
+        || (id.len() == THREEMA_ID_LEN && id.chars().all(|c| c.is_ascii_alphanumeric()))
+    {
+        Ok(id)
+    } else {
+        Err(CliError::Validation(format!(
+            "A Threema ID has {} letters and digits, got '{}'",
+            THREEMA_ID_LEN, id
+        )))
+    }
+}
+
+/// Apply `changes` to `destinations`, returning descriptions of what changed
+fn apply_changes(
+    destinations: &mut Destinations,
+    changes: DestinationChanges,
+) -> Result<Vec<String>, CliError> {
+    let mut changed = Vec::new();
+    if let Some(url) = changes.url {
+        validate_url(&url)?;
+        if destinations.url.as_deref().unwrap_or_default() != url {
+            changed.push(format!("url: {}", display_text(Some(&url))));
+            destinations.url = Some(url);
+        }
+    }
+    if let Some(id) = changes.threema_id {
+        let id = validate_threema_id(&id)?;
+        if destinations.threema_id.as_deref().unwrap_or_default() != id {
+            changed.push(format!("threema id: {}", display_text(Some(&id))));
+            destinations.threema_id = Some(id);
+        }
+    }
+    if let Some(telnet) = changes.telnet
+        && destinations.telnet_enabled != Some(telnet)
+    {
+        changed.push(format!("telnet: {}", display_flag(Some(telnet))));
+        destinations.telnet_enabled = Some(telnet);
+    }
+    Ok(changed)
+}
+
+fn display_text(value: Option<&str>) -> String {
+    match value {
+        Some(value) if !value.is_empty() => value.to_string(),
+        _ => "(not set)".to_string(),
+    }
+}
+
+fn display_flag(value: Option<bool>) -> String {
+    match value {
+        Some(true) => "enabled".to_string(),
+        Some(false) => "disabled".to_string(),
+        None => "(not set)".to_string(),
+    }
+}
+
+fn print_destinations(destinations: &Destinations) {
+    let rows = vec![
+        vec!["url".to_string(), display_text(destinations.url.as_deref())],
+        vec![
+            "threema".to_string(),
+            display_text(destinations.threema_id.as_deref()),
+        ],
+        vec![
+            "telnet".to_string(),
+            display_flag(destinations.telnet_enabled),
+        ],
+    ];
+    println!(
+        "{}",
+        render_table(&["ACTION", "DESTINATION"], &rows, terminal_width())
+    );
+}
+
+/// Why `action` cannot deliver anywhere with these settings
+fn missing_destination(action: &Action, destinations: &Destinations) -> Option<String> {
+    let (unset, flag) = match action {
+        Action::Url => (
+            destinations.url.as_deref().unwrap_or_default().is_empty(),
+            "--url",
+        ),
+        Action::Threema => (
+            destinations
+                .threema_id
+                .as_deref()
+                .unwrap_or_default()
+                .is_empty(),
+            "--threema-id",
+        ),
+        Action::Telnet => (destinations.telnet_enabled == Some(false), "--telnet true"),
+        Action::App => (false, ""),
+    };
+    unset.then(|| {
+        format!(
+            "The {} action has no destination; run `destinations set {}` first",
+            action.as_str(),
+            flag
+        )
+    })
+}
+
+/// Comment of the test trigger, unique enough to find it again
+fn test_comment(action: &Action) -> String {
+    format!(
+        "hamalert-cli destinations test ({}) {}",
+        action.as_str(),
+        Local::now().format("%Y-%m-%d %H:%M:%S")
+    )
+}
+
+/// Simulate a spot for `callsign` and wait up to `wait` seconds for trigger
+/// `id` to count it. Returns whether the match was seen.
+async fn fire_and_wait(
+    client: &HamAlertClient,
+    id: &str,
+    callsign: &str,
+    wait: u64,
+) -> Result<bool, CliError> {
+    let reply = client
+        .simulate_spot(&SimulatedSpot {
+            callsign: callsign.to_string(),
+            frequency_khz: TEST_FREQUENCY_KHZ,
+            mode: Some("CW".to_string()),
+            spotter: "HAMALERT-CLI".to_string(),
+            comment: "destinations test".to_string(),
+        })
+        .await?;
+    if !reply.trim().is_empty() {
+        info!("  {}", reply.trim());
+    }
+    let mut waited = 0;
+    while waited < wait {
+        tokio::time::sleep(Duration::from_secs(POLL_SECONDS)).await;
+        waited += POLL_SECONDS;
+        client.invalidate_triggers();
+        let matched = client
+            .triggers()
+            .await?
+            .iter()
+            .any(|t| t.id == id && t.match_count.unwrap_or(0) > 0);
+        if matched {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Create a trigger for `callsign` with only `action`, fire a simulated spot
+/// at it and delete it again, even if the spot fails
+async fn test_action(
+    client: &HamAlertClient,
+    action: Action,
+    callsign: &str,
+    wait: u64,
+) -> Result<(), CliError> {
+    let callsign = callsign.trim().to_uppercase();
+    if let Some(problem) = missing_destination(&action, &client.destinations().await?) {
+        return Err(CliError::Validation(problem));
+    }
+
+    let comment = test_comment(&action);
+    let conditions = Conditions {
+        callsign: callsign.clone(),
+        ..Default::default()
+    };
+    add_trigger(
+        client,
+        conditions,
+        &comment,
+        vec![action.as_str().to_string()],
+    )
+    .await?;
+    let id = client
+        .triggers()
+        .await?
+        .into_iter()
+        .find(|t| t.comment == comment)
+        .map(|t| t.id)
+        .ok_or_else(|| {
+            CliError::Remote("Could not find the test trigger after creating it".to_string())
+        })?;
+
+    let result = fire_and_wait(client, &id, &callsign, wait).await;
+    client.delete_trigger(&id).await?;
+    info!("Deleted the test trigger");
+
+    if result? {
+        println!(
+            "HamAlert matched the test spot for {} and sent it to the {} action; check that it arrived.",
+            callsign,
+            action.as_str()
+        );
+    } else {
+        println!(
+            "{}",
+            paint(
+                &format!(
+                    "HamAlert did not report a match within {} s; the notification may still arrive.",
+                    wait
+                ),
+                YELLOW
+            )
+        );
+    }
+    Ok(())
+}
+
+/// Run a `destinations` subcommand
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    command: DestinationsCommands,
+    json_output: bool,
+) -> Result<(), CliError> {
+    match command {
+        DestinationsCommands::Show => {
+            let destinations = client.destinations().await?;
+            if json_output {
+                print_json(&serde_json::to_value(&destinations)?)
+            } else {
+                print_destinations(&destinations);
+                Ok(())
+            }
+        }
+        DestinationsCommands::Set {
+            url,
+            threema_id,
+            telnet,
+        } => {
+            if url.is_none() && threema_id.is_none() && telnet.is_none() {
+                return Err(CliError::Validation(
+                    "Give at least one of --url, --threema-id or --telnet".to_string(),
+                ));
+            }
+            let mut destinations = client.destinations().await?;
+            let changed = apply_changes(
+                &mut destinations,
+                DestinationChanges {
+                    url,
+                    threema_id,
+                    telnet,
+                },
+            )?;
+            if changed.is_empty() {
+                println!("Destinations already up to date.");
+                return Ok(());
+            }
+            client.update_destinations(&destinations).await?;
+            for change in &changed {
+                println!("Set {}", change);
+            }
+            Ok(())
+        }
+        DestinationsCommands::Test {
+            action,
+            callsign,
+            wait,
+        } => test_action(client, action, &callsign, wait).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_changes() {
+        let mut destinations = Destinations {
+            url: Some("https://example.com/old".to_string()),
+            telnet_enabled: Some(true),
+            ..Default::default()
+        };
+        let changed = apply_changes(
+            &mut destinations,
+            DestinationChanges {
+                url: Some(String::new()),
+                threema_id: Some("abcd1234".to_string()),
+                telnet: Some(true),
+            },
+        )
+        .unwrap();
+        assert_eq!(changed, vec!["url: (not set)", "threema id: ABCD1234"]);
+        assert_eq!(destinations.url.as_deref(), Some(""));
+        assert_eq!(destinations.threema_id.as_deref(), Some("ABCD1234"));
+    }
+
+    #[test]
+    fn test_missing_destination() {
+        let destinations = Destinations {
+            url: Some(String::new()),
+            telnet_enabled: Some(false),
+            ..Default::default()
+        };
+        assert!(
+            missing_destination(&Action::Url, &destinations)
+                .unwrap()
+                .contains("--url")
+        );
+        assert!(missing_destination(&Action::Threema, &destinations).is_some());
+        assert!(missing_destination(&Action::Telnet, &destinations).is_some());
+        assert_eq!(missing_destination(&Action::App, &destinations), None);
+        assert_eq!(
+            missing_destination(&Action::Telnet, &Destinations::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(validate_url("ftp://example.com").is_err());
+        assert!(validate_url("").is_ok());
+        assert!(validate_threema_id("ABC").is_err());
+        assert!(validate_threema_id("ABCD-123").is_err());
+        assert_eq!(validate_threema_id(" abcd1234 ").unwrap(), "ABCD1234");
+    }
+}
@@ -0,0 +1,129 @@
+//! `duplicate`: copy a trigger with a few changes.
+
+use super::batch::resolve_target;
+use crate::api::{NewTrigger, fetch_triggers, remember_new_triggers};
+use crate::cli::DuplicateOverrides;
+use crate::error::CliError;
+use crate::output::format_trigger_for_display;
+use crate::storage::state::ManagedTrigger;
+use hamalert_api::{HamAlertClient, Trigger};
+use serde_json::Value;
+
+/// The copy of `trigger` that `overrides` describe, without an id or match count
+fn apply_overrides(trigger: &Trigger, overrides: &DuplicateOverrides) -> Trigger {
+    let mut copy = Trigger {
+        id: String::new(),
+        user_id: None,
+        match_count: None,
+        ..trigger.clone()
+    };
+    copy.comment = overrides
+        .set_comment
+        .clone()
+        .unwrap_or_else(|| format!("{} (copy)", trigger.comment));
+    if let Some(modes) = &overrides.set_mode
+        && let Value::Object(conditions) = &mut copy.conditions
+    {
+        if modes.is_empty() {
+            conditions.remove("mode");
+        } else {
+            let modes: Vec<&str> = modes.iter().map(|m| m.as_str()).collect();
+            conditions.insert("mode".to_string(), Value::from(modes.join(",")));
+        }
+    }
+    if !overrides.set_actions.is_empty() {
+        copy.actions = overrides
+            .set_actions
+            .iter()
+            .map(|a| a.as_str().to_string())
+            .collect();
+    }
+    copy
+}
+
+/// Create a copy of the trigger with `id`, or the managed trigger `name`,
+/// with `overrides` applied
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    id: Option<String>,
+    name: Option<String>,
+    overrides: DuplicateOverrides,
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let id = resolve_target(id, name)?;
+    let triggers = fetch_triggers(client).await?;
+    let original = triggers
+        .iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| CliError::Validation(format!("No trigger with id '{}'", id)))?;
+    let copy = apply_overrides(original, &overrides);
+
+    if dry_run {
+        println!("Would add: {}", format_trigger_for_display(&copy));
+        return Ok(());
+    }
+    let new_id = client.create_trigger(&copy).await?;
+    remember_new_triggers(
+        client,
+        vec![NewTrigger {
+            id: new_id,
+            name: None,
+            comment: copy.comment.clone(),
+            metadata: ManagedTrigger::new(format!("duplicate of {}", id), None, Vec::new()),
+        }],
+    )
+    .await;
+    println!("Added: {}", format_trigger_for_display(&copy));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Action, Mode};
+    use serde_json::json;
+
+    fn original() -> Trigger {
+        serde_json::from_value(json!({
+            "_id": "abc",
+            "conditions": {"callsign": "W1AW", "mode": "cw", "band": ["20m"]},
+            "actions": ["app"],
+            "comment": "Friends",
+            "matchCount": 12,
+            "options": {"quiet": true},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let overrides = DuplicateOverrides {
+            set_mode: Some(vec![Mode::FT8]),
+            set_comment: Some("Friends on FT8".to_string()),
+            set_actions: vec![Action::App, Action::Telnet],
+        };
+        let copy = apply_overrides(&original(), &overrides);
+        assert_eq!(copy.id, "");
+        assert_eq!(copy.match_count, None);
+        assert_eq!(copy.comment, "Friends on FT8");
+        assert_eq!(
+            copy.conditions,
+            json!({"callsign": "W1AW", "mode": "ft8", "band": ["20m"]})
+        );
+        assert_eq!(copy.actions, vec!["app", "telnet"]);
+        assert_eq!(copy.options, Some(json!({"quiet": true})));
+    }
+
+    #[test]
+    fn test_apply_overrides_defaults() {
+        let overrides = DuplicateOverrides {
+            set_mode: Some(Vec::new()),
+            set_comment: None,
+            set_actions: Vec::new(),
+        };
+        let copy = apply_overrides(&original(), &overrides);
+        assert_eq!(copy.comment, "Friends (copy)");
+        assert_eq!(copy.conditions.get("mode"), None);
+        assert_eq!(copy.actions, vec!["app"]);
+    }
+}
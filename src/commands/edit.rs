@@ -0,0 +1,356 @@
+//! `edit`: edit a trigger in $EDITOR.
+
+use super::wizard::guided_edit;
+use crate::api::fetch_triggers;
+use crate::error::CliError;
+use crate::model::{EditableTrigger, refresh_callsign_count};
+use crate::output::{YELLOW, format_trigger_for_display, paint, render_table, terminal_width};
+use crate::storage::state::load_state;
+use hamalert_api::{HamAlertClient, Trigger};
+use std::collections::BTreeSet;
+use std::fs;
+
+/// Editor used when neither $VISUAL nor $EDITOR is set
+const DEFAULT_EDITOR: &str = if cfg!(windows) { "notepad" } else { "vi" };
+
+/// The editor command from $VISUAL or $EDITOR, which may carry arguments
+/// such as `code --wait`
+fn pick_editor(visual: Option<String>, editor: Option<String>) -> String {
+    [visual, editor]
+        .into_iter()
+        .flatten()
+        .find(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+/// The fields of a trigger as (name, compact JSON) pairs, one per condition
+fn editable_fields(trigger: &EditableTrigger) -> Vec<(String, String)> {
+    let mut fields = vec![
+        ("comment".to_string(), trigger.comment.clone()),
+        ("actions".to_string(), trigger.actions.join(",")),
+    ];
+    if let Some(options) = &trigger.options {
+        fields.push(("options".to_string(), options.to_string()));
+    }
+    if let Some(conditions) = trigger.conditions.as_object() {
+        for (key, value) in conditions {
+            let value = match value {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            fields.push((format!("conditions.{}", key), value));
+        }
+    }
+    fields
+}
+
+/// Fields that differ between the version an edit started from, the one now
+/// on HamAlert and the edited one, as (field, base, theirs, mine) rows
+fn three_way_diff(
+    base: &EditableTrigger,
+    theirs: &EditableTrigger,
+    mine: &EditableTrigger,
+) -> Vec<[String; 4]> {
+    let (base, theirs, mine) = (
+        editable_fields(base),
+        editable_fields(theirs),
+        editable_fields(mine),
+    );
+    let names: BTreeSet<&String> = base
+        .iter()
+        .chain(&theirs)
+        .chain(&mine)
+        .map(|(name, _)| name)
+        .collect();
+    let value = |fields: &[(String, String)], name: &str| {
+        fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.clone())
+            .unwrap_or_else(|| "(none)".to_string())
+    };
+    names
+        .into_iter()
+        .map(|name| {
+            [
+                name.clone(),
+                value(&base, name),
+                value(&theirs, name),
+                value(&mine, name),
+            ]
+        })
+        .filter(|[_, base, theirs, mine]| base != theirs || base != mine)
+        .collect()
+}
+
+/// Save an edit of `original`, first checking that nobody changed the
+/// trigger on HamAlert (e.g. on the website) since the edit started. Returns
+/// false when the edit was not saved.
+async fn save_edit(
+    client: &HamAlertClient,
+    original: &Trigger,
+    edited: EditableTrigger,
+) -> Result<bool, CliError> {
+    client.invalidate_triggers();
+    let current = fetch_triggers(client)
+        .await?
+        .into_iter()
+        .find(|t| t.id == original.id)
+        .ok_or_else(|| {
+            CliError::Validation(format!(
+                "Trigger '{}' was deleted on HamAlert while you were editing it",
+                original.comment
+            ))
+        })?;
+
+    let base = EditableTrigger::from_trigger(original);
+    let theirs = EditableTrigger::from_trigger(&current);
+    if serde_json::to_value(&theirs)? != serde_json::to_value(&base)? {
+        println!(
+            "{}",
+            paint(
+                &format!(
+                    "Warning: trigger '{}' was changed on HamAlert since you started editing it",
+                    current.comment
+                ),
+                YELLOW
+            )
+        );
+        loop {
+            print!("Show a three-way diff (d), overwrite their changes (o) or cancel (c)? [c]: ");
+            std::io::Write::flush(&mut std::io::stdout())?;
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer)?;
+            match answer.trim().to_lowercase().as_str() {
+                "d" => {
+                    let rows: Vec<Vec<String>> = three_way_diff(&base, &theirs, &edited)
+                        .into_iter()
+                        .map(Vec::from)
+                        .collect();
+                    println!(
+                        "{}",
+                        render_table(
+                            &["FIELD", "BEFORE EDIT", "ON HAMALERT", "YOURS"],
+                            &rows,
+                            terminal_width()
+                        )
+                    );
+                }
+                "o" => break,
+                _ => return Ok(false),
+            }
+        }
+    }
+
+    // Fields outside the editable ones (e.g. disabled) stay as they are now
+    let mut trigger = current;
+    edited.apply_to_trigger(&mut trigger);
+    refresh_callsign_count(&mut trigger);
+    client.update_trigger(&trigger).await?;
+    println!("Updated trigger: {}", trigger.comment);
+    Ok(true)
+}
+
+/// Open a trigger in $EDITOR as JSON and save the changes back to HamAlert
+pub(crate) async fn edit_trigger_in_editor(
+    client: &HamAlertClient,
+    trigger: &Trigger,
+) -> Result<(), CliError> {
+    let original_editable = EditableTrigger::from_trigger(trigger);
+
+    // Create temp file with editable JSON
+    let temp_dir = std::env::temp_dir();
+    let temp_path = temp_dir.join(format!("hamalert-edit-{}.json", trigger.id));
+    let json = serde_json::to_string_pretty(&original_editable)?;
+    fs::write(&temp_path, &json)?;
+
+    // Open in editor
+    let editor = pick_editor(std::env::var("VISUAL").ok(), std::env::var("EDITOR").ok());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(DEFAULT_EDITOR);
+    let args: Vec<&str> = words.collect();
+
+    loop {
+        let status = std::process::Command::new(program)
+            .args(&args)
+            .arg(&temp_path)
+            .status()
+            .map_err(|e| CliError::io(format!("Failed to open editor '{}'", editor), e))?;
+
+        if !status.success() {
+            fs::remove_file(&temp_path).ok();
+            return Err(std::io::Error::other("Editor exited with error").into());
+        }
+
+        // Read and parse edited content
+        let edited_content = fs::read_to_string(&temp_path)?;
+
+        match serde_json::from_str::<EditableTrigger>(&edited_content) {
+            Ok(edited) => {
+                // Check if anything changed
+                let edited_json = serde_json::to_string(&edited)?;
+                let original_json = serde_json::to_string(&original_editable)?;
+
+                if edited_json == original_json {
+                    println!("No changes made.");
+                } else if !save_edit(client, trigger, edited).await? {
+                    // Keep the edited file so the work is not lost
+                    println!("Not saved; your version is in {}", temp_path.display());
+                    break;
+                }
+
+                fs::remove_file(&temp_path).ok();
+                break;
+            }
+            Err(e) => {
+                println!("Invalid JSON: {}", e);
+                println!("Press Enter to re-edit, or 'q' to quit without saving: ");
+
+                let mut retry_input = String::new();
+                std::io::stdin().read_line(&mut retry_input)?;
+
+                if retry_input.trim().eq_ignore_ascii_case("q") {
+                    fs::remove_file(&temp_path).ok();
+                    println!("Cancelled without saving.");
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Change a trigger field by field with prompts and save the changes back to HamAlert
+pub(crate) async fn edit_trigger_guided(
+    client: &HamAlertClient,
+    trigger: &Trigger,
+) -> Result<(), CliError> {
+    let original = EditableTrigger::from_trigger(trigger);
+    let Some(edited) = guided_edit(trigger)? else {
+        println!("Discarded changes.");
+        return Ok(());
+    };
+    if serde_json::to_value(&edited)? == serde_json::to_value(&original)? {
+        println!("No changes made.");
+        return Ok(());
+    }
+    if !save_edit(client, trigger, edited).await? {
+        println!("Not saved.");
+    }
+    Ok(())
+}
+
+async fn edit_one(
+    client: &HamAlertClient,
+    trigger: &Trigger,
+    guided: bool,
+) -> Result<(), CliError> {
+    if guided {
+        edit_trigger_guided(client, trigger).await
+    } else {
+        edit_trigger_in_editor(client, trigger).await
+    }
+}
+
+/// Pick a trigger, or take the managed trigger called `name`, and edit it in
+/// $EDITOR or, with `guided`, with prompts for each field
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    name: Option<&str>,
+    guided: bool,
+) -> Result<(), CliError> {
+    let triggers = fetch_triggers(client).await?;
+
+    if let Some(name) = name {
+        let state = load_state()?;
+        let managed = state.triggers.get(name).ok_or_else(|| {
+            CliError::Validation(format!(
+                "No managed trigger named '{}'; see 'hamalert-cli names'",
+                name
+            ))
+        })?;
+        let trigger = triggers
+            .iter()
+            .find(|t| t.id == managed.id)
+            .ok_or_else(|| {
+                CliError::Validation(format!(
+                    "Trigger '{}' no longer exists on HamAlert; run 'hamalert-cli names --prune'",
+                    name
+                ))
+            })?;
+        return edit_one(client, trigger, guided).await;
+    }
+
+    if triggers.is_empty() {
+        println!("No triggers found.");
+        return Ok(());
+    }
+
+    // Display numbered list
+    println!("Select a trigger to edit:\n");
+    for (i, trigger) in triggers.iter().enumerate() {
+        println!("  {}. {}", i + 1, format_trigger_for_display(trigger));
+    }
+    println!("\nEnter number (1-{}), or q to quit: ", triggers.len());
+
+    // Read user selection
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let input = input.trim();
+
+    if input.eq_ignore_ascii_case("q") {
+        return Err(CliError::Cancelled("Cancelled.".to_string()));
+    }
+
+    let selection: usize = input
+        .parse()
+        .map_err(|_| CliError::Validation("Invalid selection".to_string()))?;
+
+    if selection < 1 || selection > triggers.len() {
+        return Err(CliError::Validation(format!(
+            "Selection must be between 1 and {}",
+            triggers.len()
+        )));
+    }
+
+    edit_one(client, &triggers[selection - 1], guided).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_pick_editor() {
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(pick_editor(some("code --wait"), some("vim")), "code --wait");
+        assert_eq!(pick_editor(some(""), some("nano")), "nano");
+        assert_eq!(pick_editor(None, None), DEFAULT_EDITOR);
+    }
+
+    #[test]
+    fn test_three_way_diff() {
+        let editable = |conditions: serde_json::Value, comment: &str| EditableTrigger {
+            conditions,
+            actions: vec!["app".to_string()],
+            comment: comment.to_string(),
+            options: None,
+        };
+        let base = editable(json!({"callsign": "K1ABC", "band": "20m"}), "DX");
+        let theirs = editable(json!({"callsign": "K1ABC", "band": "40m"}), "DX");
+        let mine = editable(json!({"callsign": "K1ABC", "mode": "cw"}), "DX (1)");
+        let rows = three_way_diff(&base, &theirs, &mine);
+        assert_eq!(
+            rows,
+            vec![
+                ["comment", "DX", "DX", "DX (1)"].map(String::from),
+                ["conditions.band", "20m", "40m", "(none)"].map(String::from),
+                ["conditions.mode", "(none)", "(none)", "cw"].map(String::from),
+            ]
+        );
+        assert!(three_way_diff(&base, &base, &base).is_empty());
+    }
+}
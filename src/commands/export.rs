@@ -0,0 +1,128 @@
+//! `export`: hand trigger data to other tools.
+
+use crate::api::fetch_triggers;
+use crate::cli::ExportCommands;
+use crate::error::CliError;
+use crate::model::trigger_callsigns;
+use crate::output::render_csv;
+use clap::ValueEnum;
+use hamalert_api::{HamAlertClient, Trigger};
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Layout of an exported callsign list
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum CallsignListFormat {
+    /// One callsign per line
+    Plain,
+    /// N1MM Logger+ call history file; the trigger comment is the UserText
+    N1mm,
+    /// `call,comment` CSV with a header, for loggers that import spreadsheets
+    Csv,
+}
+
+/// Every callsign in `triggers`, sorted, with the comment of the first
+/// trigger listing it; `exact` leaves out prefix patterns such as `W1*`
+fn callsign_union(triggers: &[Trigger], exact: bool) -> BTreeMap<String, String> {
+    let mut calls = BTreeMap::new();
+    for trigger in triggers {
+        for call in trigger_callsigns(trigger) {
+            if !(exact && call.contains('*')) {
+                calls.entry(call).or_insert_with(|| trigger.comment.clone());
+            }
+        }
+    }
+    calls
+}
+
+/// `calls` laid out as `format`
+fn render_callsigns(calls: &BTreeMap<String, String>, format: CallsignListFormat) -> String {
+    match format {
+        CallsignListFormat::Plain => calls.keys().map(|call| format!("{}\n", call)).collect(),
+        CallsignListFormat::N1mm => {
+            // Call history files have no quoting, so commas cannot appear in fields
+            let mut document = "!!Order!!,Call,UserText\n".to_string();
+            for (call, comment) in calls {
+                document.push_str(&format!("{},{}\n", call, comment.replace(',', ";")));
+            }
+            document
+        }
+        CallsignListFormat::Csv => {
+            let rows: Vec<Vec<String>> = calls
+                .iter()
+                .map(|(call, comment)| vec![call.clone(), comment.clone()])
+                .collect();
+            render_csv(&["call", "comment"], &rows) + "\n"
+        }
+    }
+}
+
+/// Run an export subcommand
+pub(crate) async fn run(client: &HamAlertClient, command: ExportCommands) -> Result<(), CliError> {
+    match command {
+        ExportCommands::Callsigns {
+            selection,
+            exact,
+            format,
+            output,
+        } => {
+            let mut triggers = fetch_triggers(client).await?;
+            selection.retain(&mut triggers)?;
+            let calls = callsign_union(&triggers, exact);
+            let document = render_callsigns(&calls, format);
+
+            match output {
+                Some(path) => {
+                    fs::write(&path, document)?;
+                    println!("Exported {} callsigns to {}", calls.len(), path.display());
+                }
+                None => print!("{}", document),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_callsign_union() {
+        let triggers: Vec<Trigger> = serde_json::from_value(json!([
+            {"_id": "1", "conditions": {"callsign": "w1aw, K3LR"}, "actions": ["app"], "comment": "Club"},
+            {"_id": "2", "conditions": {"callsign": "K3LR,VP8*"}, "actions": ["app"], "comment": "DX"},
+            {"_id": "3", "conditions": {"band": "20m"}, "actions": ["app"], "comment": "20m"},
+        ]))
+        .unwrap();
+        let calls = callsign_union(&triggers, false);
+        assert_eq!(
+            calls.keys().collect::<Vec<_>>(),
+            vec!["K3LR", "VP8*", "W1AW"]
+        );
+        assert_eq!(calls["K3LR"], "Club");
+        let exact = callsign_union(&triggers, true);
+        assert_eq!(exact.keys().collect::<Vec<_>>(), vec!["K3LR", "W1AW"]);
+    }
+
+    #[test]
+    fn test_render_callsigns() {
+        let calls = BTreeMap::from([
+            ("K3LR".to_string(), "Club, big guns".to_string()),
+            ("W1AW".to_string(), "Club".to_string()),
+        ]);
+        assert_eq!(
+            render_callsigns(&calls, CallsignListFormat::Plain),
+            "K3LR\nW1AW\n"
+        );
+        assert_eq!(
+            render_callsigns(&calls, CallsignListFormat::N1mm),
+            "!!Order!!,Call,UserText\nK3LR,Club; big guns\nW1AW,Club\n"
+        );
+        assert_eq!(
+            render_callsigns(&calls, CallsignListFormat::Csv),
+            "call,comment\nK3LR,\"Club, big guns\"\nW1AW,Club\n"
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! `generate`: triggers computed from the operator's location and the date.
+
+use crate::api::{NewTrigger, fetch_triggers, remember_new_triggers};
+use crate::cli::{GenerateCommands, GreylineOptions};
+use crate::config::Config;
+use crate::dxcc::resolve_dxcc;
+use crate::error::CliError;
+use crate::solar::{grid_to_lat_lon, hhmm, sun_times, window};
+use crate::storage::state::{ManagedTrigger, load_state, slugify};
+use chrono::{NaiveDate, Utc};
+use hamalert_api::{HamAlertClient, Trigger};
+use serde_json::{Value, json};
+
+/// Tag recorded with every grey-line trigger
+const GREYLINE_TAG: &str = "greyline";
+
+/// One grey-line trigger to create or refresh
+#[derive(Debug, PartialEq)]
+struct GreylineWindow {
+    /// Managed trigger name, stable across runs
+    name: String,
+    comment: String,
+    /// Window start and end in minutes after midnight UTC
+    from: u32,
+    to: u32,
+}
+
+/// Sunrise and sunset windows for `dxcc` at `lat`/`lon` on `date`
+fn greyline_windows(
+    dxcc: &[String],
+    lat: f64,
+    lon: f64,
+    date: NaiveDate,
+    half_width: u32,
+) -> Option<Vec<GreylineWindow>> {
+    let (sunrise, sunset) = sun_times(date, lat, lon)?;
+    let label = dxcc
+        .iter()
+        .map(|d| d.trim().to_uppercase())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let windows = [("sunrise", sunrise), ("sunset", sunset)]
+        .into_iter()
+        .map(|(event, center)| {
+            let (from, to) = window(center, half_width);
+            GreylineWindow {
+                name: format!("greyline-{}-{}", slugify(&label), event),
+                comment: format!(
+                    "Greyline: {} at {} ({}-{} UTC)",
+                    label,
+                    event,
+                    hhmm(from),
+                    hhmm(to)
+                ),
+                from,
+                to,
+            }
+        })
+        .collect();
+    Some(windows)
+}
+
+/// Conditions for `window`: the DXCC entities, restricted to the window's
+/// time of day (HamAlert's `timeFrom`/`timeTo`, HHMM in UTC; a window
+/// that crosses midnight has `timeFrom` after `timeTo`)
+fn window_conditions(base: &Value, dxcc: &[u16], window: &GreylineWindow) -> Value {
+    let mut conditions = match base {
+        Value::Object(map) => map.clone(),
+        _ => Default::default(),
+    };
+    conditions.insert("dxcc".to_string(), json!(dxcc));
+    conditions.insert("timeFrom".to_string(), json!(hhmm(window.from)));
+    conditions.insert("timeTo".to_string(), json!(hhmm(window.to)));
+    Value::Object(conditions)
+}
+
+async fn greyline(
+    client: &HamAlertClient,
+    config: &Config,
+    options: GreylineOptions,
+) -> Result<(), CliError> {
+    let (lat, lon) = grid_to_lat_lon(&options.grid).map_err(CliError::Validation)?;
+    let dxcc = resolve_dxcc(&options.dxcc)?;
+    let today = Utc::now().date_naive();
+    let windows =
+        greyline_windows(&options.dxcc, lat, lon, today, options.window).ok_or_else(|| {
+            CliError::Validation(format!(
+                "The sun does not rise or set at {} on {}",
+                options.grid, today
+            ))
+        })?;
+    let actions: Vec<String> = if options.actions.is_empty() {
+        &config.defaults.actions
+    } else {
+        &options.actions
+    }
+    .iter()
+    .map(|a| a.as_str().to_string())
+    .collect();
+
+    let state = load_state()?;
+    let live = fetch_triggers(client).await?;
+    let mut created = Vec::new();
+    for window in &windows {
+        let existing = state
+            .triggers
+            .get(&window.name)
+            .and_then(|managed| live.iter().find(|t| t.id == managed.id));
+        if options.dry_run {
+            let verb = if existing.is_some() { "update" } else { "add" };
+            println!("Would {}: {}", verb, window.comment);
+            continue;
+        }
+        match existing {
+            Some(trigger) => {
+                let mut trigger = trigger.clone();
+                trigger.conditions = window_conditions(&trigger.conditions, &dxcc, window);
+                trigger.comment = window.comment.clone();
+                client.update_trigger(&trigger).await?;
+                println!("Updated: {}", window.comment);
+            }
+            None => {
+                let trigger = Trigger {
+                    id: String::new(),
+                    user_id: None,
+                    conditions: window_conditions(&Value::Null, &dxcc, window),
+                    actions: actions.clone(),
+                    comment: window.comment.clone(),
+                    match_count: None,
+                    disabled: None,
+                    options: None,
+                };
+                let id = client.create_trigger(&trigger).await?;
+                println!("Added: {}", window.comment);
+                created.push(NewTrigger {
+                    id,
+                    name: Some(window.name.clone()),
+                    comment: window.comment.clone(),
+                    metadata: ManagedTrigger::new(
+                        "generate greyline".to_string(),
+                        None,
+                        vec![GREYLINE_TAG.to_string()],
+                    ),
+                });
+            }
+        }
+    }
+    if !created.is_empty() {
+        remember_new_triggers(client, created).await;
+    }
+    Ok(())
+}
+
+/// Run a `generate` subcommand
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    config: &Config,
+    command: GenerateCommands,
+) -> Result<(), CliError> {
+    match command {
+        GenerateCommands::Greyline { options } => greyline(client, config, options).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_greyline_windows() {
+        let date = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let dxcc = vec!["vk".to_string(), "ZL".to_string()];
+        let windows = greyline_windows(&dxcc, 41.5, -73.0, date, 30).unwrap();
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].name, "greyline-vk-zl-sunrise");
+        assert_eq!(windows[1].name, "greyline-vk-zl-sunset");
+        assert!(
+            windows[0]
+                .comment
+                .starts_with("Greyline: VK, ZL at sunrise (")
+        );
+        assert_eq!((windows[0].to + 1440 - windows[0].from) % 1440, 60);
+        // Sunset in UTC is just after midnight, so its window wraps
+        assert!(windows[1].from > windows[1].to);
+
+        assert!(greyline_windows(&dxcc, 78.2, 15.6, date, 30).is_none());
+    }
+
+    #[test]
+    fn test_window_conditions() {
+        let window = GreylineWindow {
+            name: "greyline-vk-sunset".to_string(),
+            comment: String::new(),
+            from: 1420,
+            to: 40,
+        };
+        let base = json!({"dxcc": [150], "mode": "cw", "timeFrom": "0100"});
+        assert_eq!(
+            window_conditions(&base, &[150, 170], &window),
+            json!({"dxcc": [150, 170], "mode": "cw", "timeFrom": "2340", "timeTo": "0040"})
+        );
+    }
+}
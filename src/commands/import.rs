@@ -0,0 +1,562 @@
+//! `import-polo-notes` and `import-file`.
+
+use crate::api::{API_CONCURRENCY, NewTrigger, add_trigger, fetch_triggers, remember_new_triggers};
+use crate::callsign::partition_callsigns;
+use crate::cli::{CallsignFormat, ImportOptions};
+use crate::config::{AccountLimits, Config};
+use crate::dxcc::resolve_dxcc;
+use crate::error::CliError;
+use crate::model::with_callsign_count;
+use crate::output::{YELLOW, paint, print_created};
+use crate::references::{comment_with_names, resolve_parks, resolve_summits};
+use crate::storage::state::ensure_name_available;
+use clap::ValueEnum;
+use hamalert_api::{Conditions, HamAlertClient, SendLogged};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Parse Ham2K PoLo callsign notes content and extract callsigns.
+/// Each line's first word is treated as a callsign.
+/// Empty lines and comment lines (starting with # or //) are skipped.
+pub(crate) fn parse_polo_notes_content(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let trimmed = line.trim();
+            // Skip empty lines
+            if trimmed.is_empty() {
+                return None;
+            }
+            // Skip comment lines
+            if trimmed.starts_with('#') || trimmed.starts_with("//") {
+                return None;
+            }
+            // Extract the first word (callsign)
+            trimmed.split_whitespace().next().map(|s| s.to_string())
+        })
+        .collect()
+}
+
+/// Fetch and parse Ham2K PoLo callsign notes from a URL.
+pub(crate) async fn fetch_polo_notes(
+    client: &HamAlertClient,
+    url: &str,
+) -> Result<Vec<String>, CliError> {
+    let response = client.http().get(url).send_logged().await?;
+
+    if !response.status().is_success() {
+        return Err(CliError::Remote(format!(
+            "Failed to fetch PoLo notes from {}: {}",
+            url,
+            response.status()
+        )));
+    }
+
+    let content = response.text().await?;
+    Ok(parse_polo_notes_content(&content))
+}
+
+/// URLs listed in a manifest file, one per line. Empty lines and comment
+/// lines are skipped as in a notes file.
+pub(crate) fn parse_url_manifest(content: &str) -> Vec<String> {
+    parse_polo_notes_content(content)
+}
+
+/// Callsigns from several notes files in order, dropping repeats
+/// (compared case-insensitively) after the first
+fn merge_callsigns(lists: Vec<Vec<String>>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    lists
+        .into_iter()
+        .flatten()
+        .filter(|call| seen.insert(call.to_uppercase()))
+        .collect()
+}
+
+/// What an import does when its callsigns do not fit in one trigger
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub(crate) enum OverLimit {
+    /// Stop without creating anything
+    #[default]
+    Refuse,
+    /// Spread the callsigns over as many triggers as needed
+    Split,
+    /// Import only as many callsigns as fit in one trigger
+    Trim,
+}
+
+/// How the callsigns of an import are spread over new triggers
+#[derive(Debug, PartialEq)]
+struct ImportPlan {
+    chunks: Vec<Vec<String>>,
+    /// Callsigns left out to stay within the limits
+    dropped: Vec<String>,
+}
+
+/// Spread `callsigns` over new triggers within `limits`, given the
+/// `existing` triggers on the account. Errors explain what does not fit.
+fn plan_import(
+    mut callsigns: Vec<String>,
+    existing: usize,
+    limits: &AccountLimits,
+    over_limit: OverLimit,
+) -> Result<ImportPlan, String> {
+    let per_trigger = limits.max_callsigns_per_trigger.max(1);
+    let free = limits.max_triggers.saturating_sub(existing);
+    if free == 0 {
+        return Err(format!(
+            "The account already has {} triggers, the limit is {}",
+            existing, limits.max_triggers
+        ));
+    }
+    let needed = callsigns.len().div_ceil(per_trigger);
+    let mut dropped = Vec::new();
+    if needed > 1 {
+        match over_limit {
+            OverLimit::Refuse => {
+                return Err(format!(
+                    "{} callsigns exceed the limit of {} per trigger; use --over-limit split \
+                     to spread them over {} triggers or --over-limit trim to import the first {}",
+                    callsigns.len(),
+                    per_trigger,
+                    needed,
+                    per_trigger
+                ));
+            }
+            OverLimit::Split if needed > free => {
+                return Err(format!(
+                    "{} callsigns need {} triggers of {}, but only {} of the account's {} \
+                     triggers are free",
+                    callsigns.len(),
+                    needed,
+                    per_trigger,
+                    free,
+                    limits.max_triggers
+                ));
+            }
+            OverLimit::Split => {}
+            OverLimit::Trim => dropped = callsigns.split_off(per_trigger),
+        }
+    }
+    Ok(ImportPlan {
+        chunks: callsigns
+            .chunks(per_trigger)
+            .map(<[String]>::to_vec)
+            .collect(),
+        dropped,
+    })
+}
+
+/// Comment and name of trigger `index` of `count` created by one import
+fn chunk_label(
+    comment: &str,
+    name: Option<&str>,
+    index: usize,
+    count: usize,
+) -> (String, Option<String>) {
+    if count == 1 {
+        return (comment.to_string(), name.map(str::to_string));
+    }
+    (
+        format!("{} ({}/{})", comment, index + 1, count),
+        name.map(|name| format!("{}-{}", name, index + 1)),
+    )
+}
+
+/// Shared logic for importing callsigns from any source, recorded in the
+/// local state as coming from `source`. Words that are not valid callsigns
+/// are skipped with a warning, and the import is checked against the
+/// account limits before anything is created.
+pub(crate) async fn import_callsigns(
+    client: &HamAlertClient,
+    callsigns: Vec<String>,
+    options: &ImportOptions,
+    limits: &AccountLimits,
+    source: String,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let action_strings: Vec<String> = options
+        .trigger
+        .actions
+        .iter()
+        .map(|a| a.as_str().to_string())
+        .collect();
+
+    let mode_string = if options.trigger.mode.is_empty() {
+        None
+    } else {
+        Some(
+            options
+                .trigger
+                .mode
+                .iter()
+                .map(|m| m.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+    };
+    let format = CallsignFormat::from_flags(options.trigger.compact, options.trigger.one_per_line);
+
+    let (callsigns, invalid) = partition_callsigns(callsigns);
+    for (call, reason) in &invalid {
+        eprintln!(
+            "{} Skipping '{}': {}",
+            paint("Warning:", YELLOW),
+            call,
+            reason
+        );
+    }
+    if callsigns.is_empty() {
+        return Err(CliError::Validation(
+            "None of the imported lines start with a valid callsign".to_string(),
+        ));
+    }
+
+    let existing = fetch_triggers(client).await?.len();
+    let plan = plan_import(callsigns, existing, limits, options.over_limit)
+        .map_err(CliError::Validation)?;
+    let count = plan.chunks.len();
+    if !json_output {
+        println!(
+            "Import plan: {} callsigns in {} trigger{}; the account will have {} of {} triggers",
+            plan.chunks.iter().map(Vec::len).sum::<usize>(),
+            count,
+            if count == 1 { "" } else { "s" },
+            existing + count,
+            limits.max_triggers
+        );
+    }
+    if !plan.dropped.is_empty() {
+        eprintln!(
+            "{} Trimmed {} callsigns over the limit of {} per trigger: {}",
+            paint("Warning:", YELLOW),
+            plan.dropped.len(),
+            limits.max_callsigns_per_trigger,
+            plan.dropped.join(", ")
+        );
+    }
+
+    if options.dry_run {
+        println!("\nDry run - would add triggers for:");
+        for (i, chunk) in plan.chunks.iter().enumerate() {
+            let (mut comment, _) = chunk_label(&options.trigger.comment, None, i, count);
+            if options.trigger.count_suffix {
+                comment = with_callsign_count(&comment, chunk.len());
+            }
+            for cs in chunk {
+                println!(
+                    "  {} (comment: {:?}, actions: {:?}, mode: {:?})",
+                    cs, comment, action_strings, mode_string
+                );
+            }
+        }
+    } else {
+        let metadata = options.trigger.managed(source)?;
+        let dxcc = resolve_dxcc(&options.trigger.dxcc)?;
+        let (summit_ref, summit_names) = resolve_summits(
+            client,
+            &options.trigger.summit_ref,
+            options.trigger.check_summits,
+        )
+        .await?;
+        let (pota_ref, park_names) = resolve_parks(client, &options.trigger.pota_ref).await?;
+        let comment = comment_with_names(
+            &options.trigger.comment,
+            &[summit_names, park_names].concat(),
+        );
+        let labels: Vec<(String, Option<String>)> = (0..count)
+            .map(|i| chunk_label(&comment, options.trigger.name.as_deref(), i, count))
+            .collect();
+        for name in labels.iter().filter_map(|(_, name)| name.as_deref()) {
+            ensure_name_available(name)?;
+        }
+        let mut created = Vec::new();
+        for (chunk, (mut comment, name)) in plan.chunks.iter().zip(labels) {
+            if options.trigger.count_suffix {
+                comment = with_callsign_count(&comment, chunk.len());
+            }
+            let conditions = Conditions {
+                callsign: chunk.join(format.separator()),
+                mode: mode_string.clone(),
+                dxcc: dxcc.clone(),
+                summit_ref: summit_ref.clone(),
+                pota_ref: pota_ref.clone(),
+            };
+            let id = add_trigger(client, conditions, &comment, action_strings.clone()).await?;
+            created.push(NewTrigger {
+                id,
+                name,
+                comment,
+                metadata: metadata.clone(),
+            });
+        }
+        let created = remember_new_triggers(client, created).await;
+        print_created(&created, json_output)?;
+    }
+
+    Ok(())
+}
+
+/// Import the callsigns listed on one or more Polo notes pages, given with
+/// `--url` or listed in `url_file`, into a single trigger
+pub(crate) async fn polo_notes(
+    client: &HamAlertClient,
+    config: &Config,
+    mut urls: Vec<String>,
+    url_file: Option<&Path>,
+    mut options: ImportOptions,
+    json_output: bool,
+) -> Result<(), CliError> {
+    use futures::stream::{self, StreamExt, TryStreamExt};
+
+    options.trigger = options.trigger.with_defaults(&config.defaults);
+    if let Some(path) = url_file {
+        let content = fs::read_to_string(path)
+            .map_err(|e| CliError::io(format!("Failed to read URL file {}", path.display()), e))?;
+        urls.extend(parse_url_manifest(&content));
+    }
+    let mut seen = HashSet::new();
+    urls.retain(|url| seen.insert(url.clone()));
+    if urls.is_empty() {
+        return Err(CliError::Validation("No notes URLs to import".to_string()));
+    }
+
+    let lists: Vec<Vec<String>> = stream::iter(&urls)
+        .map(|url| fetch_polo_notes(client, url))
+        .buffered(API_CONCURRENCY)
+        .try_collect()
+        .await?;
+    if !json_output {
+        for (url, callsigns) in urls.iter().zip(&lists) {
+            println!("Found {} callsigns at {}", callsigns.len(), url);
+        }
+    }
+    let callsigns = merge_callsigns(lists);
+
+    if callsigns.is_empty() {
+        println!("No callsigns found");
+        return Ok(());
+    }
+    if urls.len() > 1 && !json_output {
+        println!("{} distinct callsigns in total", callsigns.len());
+    }
+
+    let source = format!("import-polo-notes {}", urls.join(" "));
+    import_callsigns(
+        client,
+        callsigns,
+        &options,
+        &config.limits,
+        source,
+        json_output,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Import the callsigns listed in a local file
+pub(crate) async fn file(
+    client: &HamAlertClient,
+    config: &Config,
+    file: &Path,
+    mut options: ImportOptions,
+    json_output: bool,
+) -> Result<(), CliError> {
+    options.trigger = options.trigger.with_defaults(&config.defaults);
+    let content = fs::read_to_string(file)
+        .map_err(|e| CliError::io(format!("Failed to read file {}", file.display()), e))?;
+    let callsigns = parse_polo_notes_content(&content);
+
+    if callsigns.is_empty() {
+        println!("No callsigns found in {}", file.display());
+        return Ok(());
+    }
+
+    if !json_output {
+        println!("Found {} callsigns in {}", callsigns.len(), file.display());
+    }
+
+    let source = format!("import-file {}", file.display());
+    import_callsigns(
+        client,
+        callsigns,
+        &options,
+        &config.limits,
+        source,
+        json_output,
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_polo_notes_simple_callsigns() {
+        let content = "W1ABC\nK2DEF\nN3GHI";
+        let result = parse_polo_notes_content(content);
+        assert_eq!(result, vec!["W1ABC", "K2DEF", "N3GHI"]);
+    }
+
+    #[test]
+    fn test_parse_polo_notes_callsigns_with_notes() {
+        let content = "W1ABC friend from club\nK2DEF met at field day\nN3GHI";
+        let result = parse_polo_notes_content(content);
+        assert_eq!(result, vec!["W1ABC", "K2DEF", "N3GHI"]);
+    }
+
+    #[test]
+    fn test_parse_polo_notes_empty_content() {
+        let content = "";
+        let result = parse_polo_notes_content(content);
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_parse_polo_notes_only_empty_lines() {
+        let content = "\n\n\n";
+        let result = parse_polo_notes_content(content);
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_parse_polo_notes_hash_comments() {
+        let content = "# This is a comment\nW1ABC\n# Another comment\nK2DEF";
+        let result = parse_polo_notes_content(content);
+        assert_eq!(result, vec!["W1ABC", "K2DEF"]);
+    }
+
+    #[test]
+    fn test_parse_polo_notes_slash_comments() {
+        let content = "// This is a comment\nW1ABC\n// Another comment\nK2DEF";
+        let result = parse_polo_notes_content(content);
+        assert_eq!(result, vec!["W1ABC", "K2DEF"]);
+    }
+
+    #[test]
+    fn test_parse_polo_notes_mixed_comments() {
+        let content = "# Hash comment\n// Slash comment\nW1ABC";
+        let result = parse_polo_notes_content(content);
+        assert_eq!(result, vec!["W1ABC"]);
+    }
+
+    #[test]
+    fn test_parse_polo_notes_whitespace_handling() {
+        let content = "  W1ABC  \n\tK2DEF\t\n   N3GHI   notes here";
+        let result = parse_polo_notes_content(content);
+        assert_eq!(result, vec!["W1ABC", "K2DEF", "N3GHI"]);
+    }
+
+    #[test]
+    fn test_parse_polo_notes_mixed_content() {
+        let content = "# Header comment\n\nW1ABC friend\n\n// Another comment\nK2DEF\n\n";
+        let result = parse_polo_notes_content(content);
+        assert_eq!(result, vec!["W1ABC", "K2DEF"]);
+    }
+
+    #[test]
+    fn test_parse_polo_notes_only_comments() {
+        let content = "# Comment 1\n// Comment 2\n# Comment 3";
+        let result = parse_polo_notes_content(content);
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_parse_polo_notes_indented_comments() {
+        let content = "  # Indented hash comment\n  // Indented slash comment\nW1ABC";
+        let result = parse_polo_notes_content(content);
+        assert_eq!(result, vec!["W1ABC"]);
+    }
+
+    #[test]
+    fn test_parse_polo_notes_single_callsign() {
+        let content = "W1ABC";
+        let result = parse_polo_notes_content(content);
+        assert_eq!(result, vec!["W1ABC"]);
+    }
+
+    #[test]
+    fn test_parse_polo_notes_callsign_with_hash_in_note() {
+        // A hash in the middle of a note (not at start) should not be treated as comment
+        let content = "W1ABC note with #hashtag";
+        let result = parse_polo_notes_content(content);
+        assert_eq!(result, vec!["W1ABC"]);
+    }
+
+    #[test]
+    fn test_parse_url_manifest() {
+        let content =
+            "# Shared lists\nhttps://example.com/a.txt\n\n// old\n  https://example.com/b.txt  \n";
+        assert_eq!(
+            parse_url_manifest(content),
+            vec!["https://example.com/a.txt", "https://example.com/b.txt"]
+        );
+    }
+
+    #[test]
+    fn test_merge_callsigns() {
+        let merged = merge_callsigns(vec![
+            vec!["W1ABC".to_string(), "K2DEF".to_string()],
+            vec![
+                "k2def".to_string(),
+                "N3GHI".to_string(),
+                "W1ABC".to_string(),
+            ],
+        ]);
+        assert_eq!(merged, vec!["W1ABC", "K2DEF", "N3GHI"]);
+    }
+
+    fn limits(max_triggers: usize, max_callsigns_per_trigger: usize) -> AccountLimits {
+        AccountLimits {
+            max_triggers,
+            max_callsigns_per_trigger,
+        }
+    }
+
+    fn calls(n: usize) -> Vec<String> {
+        (1..=n).map(|i| format!("K{}A", i)).collect()
+    }
+
+    #[test]
+    fn test_plan_import_within_limits() {
+        let plan = plan_import(calls(3), 10, &limits(500, 3), OverLimit::Refuse).unwrap();
+        assert_eq!(plan.chunks, vec![calls(3)]);
+        assert!(plan.dropped.is_empty());
+    }
+
+    #[test]
+    fn test_plan_import_over_limit() {
+        let err = plan_import(calls(7), 10, &limits(500, 3), OverLimit::Refuse).unwrap_err();
+        assert!(err.contains("spread them over 3 triggers"), "{}", err);
+
+        let plan = plan_import(calls(7), 10, &limits(500, 3), OverLimit::Split).unwrap();
+        assert_eq!(plan.chunks.len(), 3);
+        assert_eq!(plan.chunks[2], vec!["K7A"]);
+
+        let plan = plan_import(calls(7), 10, &limits(500, 3), OverLimit::Trim).unwrap();
+        assert_eq!(plan.chunks, vec![calls(3)]);
+        assert_eq!(plan.dropped, vec!["K4A", "K5A", "K6A", "K7A"]);
+    }
+
+    #[test]
+    fn test_plan_import_trigger_limit() {
+        let err = plan_import(calls(7), 498, &limits(500, 3), OverLimit::Split).unwrap_err();
+        assert!(err.contains("only 2 of"), "{}", err);
+        assert!(plan_import(calls(1), 500, &limits(500, 3), OverLimit::Trim).is_err());
+    }
+
+    #[test]
+    fn test_chunk_label() {
+        assert_eq!(
+            chunk_label("Friends", Some("friends"), 0, 1),
+            ("Friends".to_string(), Some("friends".to_string()))
+        );
+        assert_eq!(
+            chunk_label("Friends", Some("friends"), 1, 3),
+            ("Friends (2/3)".to_string(), Some("friends-2".to_string()))
+        );
+    }
+}
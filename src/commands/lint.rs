@@ -0,0 +1,331 @@
+//! `lint`: flag triggers that are probably mistakes.
+
+use crate::api::fetch_triggers;
+use crate::callsign::parse_pattern;
+use crate::declarative::load_trigger_file;
+use crate::error::CliError;
+use crate::model::{band_for_frequency, trigger_callsigns};
+use crate::output::{RED, YELLOW, paint, print_json, render_table, terminal_width};
+use hamalert_api::{HamAlertClient, Trigger};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// FT8 dial frequencies in kHz; FT8 signals sit in the 3 kHz above them
+const FT8_DIAL_KHZ: [f64; 12] = [
+    1840.0, 3573.0, 5357.0, 7074.0, 10136.0, 14074.0, 18100.0, 21074.0, 24915.0, 28074.0, 50313.0,
+    144174.0,
+];
+
+/// Comments closer than this many edits count as near-duplicates
+const MAX_COMMENT_DISTANCE: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+}
+
+/// One problem with one trigger
+#[derive(Debug, Serialize)]
+pub(crate) struct Finding {
+    /// Comment of the trigger, or its id if the comment is empty
+    pub(crate) trigger: String,
+    pub(crate) severity: Severity,
+    pub(crate) rule: &'static str,
+    pub(crate) message: String,
+}
+
+fn label(trigger: &Trigger) -> String {
+    if trigger.comment.trim().is_empty() {
+        format!("(id {})", trigger.id)
+    } else {
+        trigger.comment.clone()
+    }
+}
+
+/// Lower-cased items of a list condition, given as a string or an array
+fn condition_list(conditions: &Value, key: &str) -> Vec<String> {
+    match conditions.get(key) {
+        Some(Value::String(s)) => s
+            .split([',', '\n'])
+            .map(|item| item.trim().to_lowercase())
+            .filter(|item| !item.is_empty())
+            .collect(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(str::to_lowercase)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A frequency condition in kHz, given as a number or a numeric string
+fn condition_khz(conditions: &Value, key: &str) -> Option<f64> {
+    match conditions.get(key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Why the frequency range (`freqMin`/`freqMax`, in kHz) can never match
+/// together with the other conditions
+fn frequency_problem(conditions: &Value) -> Option<String> {
+    let low = condition_khz(conditions, "freqMin")?;
+    let high = condition_khz(conditions, "freqMax")?;
+    if low > high {
+        return Some(format!("freqMin {} kHz is above freqMax {} kHz", low, high));
+    }
+    let modes = condition_list(conditions, "mode");
+    let ft8_only = FT8_DIAL_KHZ
+        .iter()
+        .any(|dial| low >= *dial && high <= dial + 3.0);
+    if ft8_only && !modes.is_empty() && !modes.iter().any(|m| m == "ft8") {
+        return Some(format!(
+            "{}-{} kHz only covers the FT8 segment, but mode is {}",
+            low,
+            high,
+            modes.join(",")
+        ));
+    }
+    let bands = condition_list(conditions, "band");
+    if let Some(band) = band_for_frequency(low)
+        && band_for_frequency(high) == Some(band)
+        && !bands.is_empty()
+        && !bands.iter().any(|b| b == band)
+    {
+        return Some(format!(
+            "{}-{} kHz is on {}, but band is {}",
+            low,
+            high,
+            band,
+            bands.join(",")
+        ));
+    }
+    None
+}
+
+/// Comment reduced to lower-case letters and digits
+fn normalize_comment(comment: &str) -> String {
+    comment
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Whether two comments are equal or nearly so, ignoring case and punctuation
+fn near_duplicate(a: &str, b: &str) -> bool {
+    let (a, b) = (normalize_comment(a), normalize_comment(b));
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    a == b
+        || (a.len().min(b.len()) > 3 * MAX_COMMENT_DISTANCE
+            && edit_distance(&a, &b) <= MAX_COMMENT_DISTANCE)
+}
+
+/// Every finding for `triggers`, in trigger order
+pub(crate) fn lint(triggers: &[Trigger]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (i, trigger) in triggers.iter().enumerate() {
+        let mut add = |severity, rule, message: String| {
+            findings.push(Finding {
+                trigger: label(trigger),
+                severity,
+                rule,
+                message,
+            })
+        };
+        if trigger.actions.is_empty() {
+            add(
+                Severity::Warning,
+                "no-actions",
+                "has no actions, so it never notifies".to_string(),
+            );
+        }
+        if trigger.comment.trim().is_empty() {
+            add(
+                Severity::Warning,
+                "empty-comment",
+                "has no comment to tell it apart".to_string(),
+            );
+        }
+        for callsign in trigger_callsigns(trigger) {
+            if let Err(e) = parse_pattern(&callsign) {
+                add(
+                    Severity::Error,
+                    "malformed-callsign",
+                    format!("'{}': {}", callsign, e),
+                );
+            }
+        }
+        if let Some(problem) = frequency_problem(&trigger.conditions) {
+            add(Severity::Error, "impossible-conditions", problem);
+        }
+        if let Some(earlier) = triggers[..i]
+            .iter()
+            .find(|t| near_duplicate(&t.comment, &trigger.comment))
+        {
+            add(
+                Severity::Warning,
+                "near-duplicate-comment",
+                format!("comment is close to '{}'", earlier.comment),
+            );
+        }
+    }
+    findings
+}
+
+/// Lint the live triggers, or the entries of a trigger file. Fails when
+/// there are errors, or with `strict` when there are any findings.
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    file: Option<&Path>,
+    strict: bool,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let triggers = match file {
+        Some(path) => load_trigger_file(path)?
+            .iter()
+            .map(|entry| entry.to_trigger(None))
+            .collect(),
+        None => fetch_triggers(client).await?,
+    };
+    let findings = lint(&triggers);
+    let errors = findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .count();
+    let warnings = findings.len() - errors;
+
+    if json_output {
+        print_json(&serde_json::to_value(&findings)?)?;
+    } else if findings.is_empty() {
+        println!("No problems found in {} triggers.", triggers.len());
+    } else {
+        let rows: Vec<Vec<String>> = findings
+            .iter()
+            .map(|f| {
+                let severity = match f.severity {
+                    Severity::Error => paint("error", RED),
+                    Severity::Warning => paint("warning", YELLOW),
+                };
+                vec![
+                    severity,
+                    f.trigger.clone(),
+                    f.rule.to_string(),
+                    f.message.clone(),
+                ]
+            })
+            .collect();
+        println!(
+            "{}",
+            render_table(
+                &["SEVERITY", "TRIGGER", "RULE", "MESSAGE"],
+                &rows,
+                terminal_width()
+            )
+        );
+    }
+
+    if errors > 0 || (strict && warnings > 0) {
+        return Err(CliError::Validation(format!(
+            "Lint found {} error(s) and {} warning(s)",
+            errors, warnings
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::test_trigger;
+    use serde_json::json;
+
+    fn trigger(comment: &str, conditions: Value, actions: &[&str]) -> Trigger {
+        Trigger {
+            actions: actions.iter().map(|a| a.to_string()).collect(),
+            ..test_trigger("abc", comment, conditions)
+        }
+    }
+
+    fn rules(triggers: &[Trigger]) -> Vec<&'static str> {
+        lint(triggers).into_iter().map(|f| f.rule).collect()
+    }
+
+    #[test]
+    fn test_lint_clean() {
+        let triggers = vec![
+            trigger("Friends", json!({"callsign": "W1AW,K3LR"}), &["app"]),
+            trigger("Japan", json!({"callsign": "JA*"}), &["app"]),
+        ];
+        assert!(lint(&triggers).is_empty());
+    }
+
+    #[test]
+    fn test_lint_rules() {
+        assert_eq!(
+            rules(&[trigger("Silent", json!({"callsign": "W1AW"}), &[])]),
+            vec!["no-actions"]
+        );
+        assert_eq!(
+            rules(&[trigger(" ", json!({"callsign": "W1AW"}), &["app"])]),
+            vec!["empty-comment"]
+        );
+        assert_eq!(
+            rules(&[trigger("Typo", json!({"callsign": "W1AW,W1-AW"}), &["app"])]),
+            vec!["malformed-callsign"]
+        );
+        assert_eq!(
+            rules(&[
+                trigger("DXpedition VP8", json!({"callsign": "VP8PJ"}), &["app"]),
+                trigger("Dxpedition VP8!", json!({"callsign": "VP8A"}), &["app"]),
+                trigger("DXpeditoin VP8", json!({"callsign": "VP8B"}), &["app"]),
+                trigger("POTA", json!({"callsign": "K1A"}), &["app"]),
+                trigger("SOTA", json!({"callsign": "K1B"}), &["app"]),
+            ]),
+            vec!["near-duplicate-comment", "near-duplicate-comment"]
+        );
+    }
+
+    #[test]
+    fn test_frequency_problem() {
+        let cw_on_ft8 = json!({"mode": "cw", "freqMin": 14074, "freqMax": "14076"});
+        assert!(frequency_problem(&cw_on_ft8).unwrap().contains("FT8"));
+        let ft8 = json!({"mode": "ft8,cw", "freqMin": 14074, "freqMax": 14076});
+        assert_eq!(frequency_problem(&ft8), None);
+        let cw_segment = json!({"mode": "cw", "freqMin": 14000, "freqMax": 14070});
+        assert_eq!(frequency_problem(&cw_segment), None);
+        let wrong_band = json!({"band": ["40m"], "freqMin": 14000, "freqMax": 14070});
+        assert!(frequency_problem(&wrong_band).unwrap().contains("20m"));
+        let reversed = json!({"freqMin": 7100, "freqMax": 7000});
+        assert!(frequency_problem(&reversed).is_some());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+}
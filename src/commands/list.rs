@@ -0,0 +1,435 @@
+//! `list`: print triggers in several formats.
+
+use crate::api::fetch_triggers;
+use crate::callsign::highlight_patterns;
+use crate::cli::{ListGroupOptions, TriggerSelection};
+use crate::error::CliError;
+use crate::output::{
+    CYAN, format_trigger_for_display, paint, print_json, render_csv, render_table, terminal_width,
+};
+use crate::references::{annotate_parks, load_park_names};
+use crate::storage::state::{State, load_state};
+use clap::ValueEnum;
+use hamalert_api::{HamAlertClient, Trigger};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum ListOutput {
+    /// Aligned columns sized to the terminal
+    Table,
+    /// One summary line per trigger
+    Plain,
+    /// Comma-separated values with a header row, for spreadsheets
+    Csv,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum ListGroup {
+    /// Tags recorded in the state file; a trigger shows under each of its tags
+    Tag,
+    /// Comment up to the first ':', or its first word
+    CommentPrefix,
+    Mode,
+}
+
+/// Group name for triggers without a value for the grouping
+const UNGROUPED: &str = "(none)";
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum ListColumn {
+    Id,
+    Comment,
+    Mode,
+    Callsigns,
+    Actions,
+    #[value(name = "matchCount")]
+    MatchCount,
+    Disabled,
+    /// POTA park references, with names once they have been looked up
+    Parks,
+}
+
+impl ListColumn {
+    pub(crate) fn header(&self) -> &'static str {
+        match self {
+            ListColumn::Id => "ID",
+            ListColumn::Comment => "COMMENT",
+            ListColumn::Mode => "MODE",
+            ListColumn::Callsigns => "CALLSIGNS",
+            ListColumn::Actions => "ACTIONS",
+            ListColumn::MatchCount => "MATCHES",
+            ListColumn::Disabled => "DISABLED",
+            ListColumn::Parks => "PARKS",
+        }
+    }
+
+    /// Header used in CSV output, matching the --columns name
+    pub(crate) fn csv_header(&self) -> &'static str {
+        match self {
+            ListColumn::Id => "id",
+            ListColumn::Comment => "comment",
+            ListColumn::Mode => "mode",
+            ListColumn::Callsigns => "callsigns",
+            ListColumn::Actions => "actions",
+            ListColumn::MatchCount => "matchCount",
+            ListColumn::Disabled => "disabled",
+            ListColumn::Parks => "parks",
+        }
+    }
+
+    pub(crate) fn value(&self, trigger: &Trigger) -> String {
+        let condition = |key: &str| match trigger.conditions.get(key) {
+            Some(serde_json::Value::String(s)) => s.clone(),
+            Some(serde_json::Value::Array(items)) => items
+                .iter()
+                .map(|v| {
+                    v.as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| v.to_string())
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+            Some(other) => other.to_string(),
+            None => String::new(),
+        };
+        match self {
+            ListColumn::Id => trigger.id.clone(),
+            ListColumn::Comment => trigger.comment.clone(),
+            ListColumn::Mode => {
+                let mode = condition("mode");
+                if mode.is_empty() {
+                    "any".to_string()
+                } else {
+                    mode
+                }
+            }
+            ListColumn::Callsigns => condition("callsign"),
+            ListColumn::Actions => trigger.actions.join(","),
+            ListColumn::MatchCount => trigger
+                .match_count
+                .map(|c| c.to_string())
+                .unwrap_or_default(),
+            ListColumn::Disabled => {
+                if trigger.disabled.unwrap_or(false) {
+                    "yes".to_string()
+                } else {
+                    String::new()
+                }
+            }
+            ListColumn::Parks => condition("potaRef"),
+        }
+    }
+}
+
+/// Render a trigger with a --format-string template. Placeholders are column
+/// names such as {comment} or {matchCount}; \t and \n escapes are expanded.
+pub(crate) fn format_trigger_template(template: &str, trigger: &Trigger) -> Result<String, String> {
+    let template = template
+        .replace("\\\\", "\u{0}")
+        .replace("\\t", "\t")
+        .replace("\\n", "\n")
+        .replace('\u{0}', "\\");
+    let mut output = String::new();
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed '{{' in format string: {}", template))?;
+        let name = &rest[start + 1..start + end];
+        let column = ListColumn::from_str(name, false)
+            .map_err(|_| format!("Unknown format field {{{}}}", name))?;
+        output.push_str(&column.value(trigger));
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Leading part of a comment that related triggers tend to share
+fn comment_prefix(comment: &str) -> String {
+    let prefix = match comment.split_once(':') {
+        Some((prefix, _)) => prefix,
+        None => comment.split_whitespace().next().unwrap_or_default(),
+    };
+    prefix.trim().to_string()
+}
+
+/// Names of the groups `trigger` belongs to
+fn group_names(trigger: &Trigger, group_by: ListGroup, state: &State) -> Vec<String> {
+    let names = match group_by {
+        ListGroup::Tag => state
+            .triggers
+            .values()
+            .find(|t| t.id == trigger.id)
+            .map(|t| t.tags.clone())
+            .unwrap_or_default(),
+        ListGroup::CommentPrefix => vec![comment_prefix(&trigger.comment)],
+        ListGroup::Mode => vec![ListColumn::Mode.value(trigger)],
+    };
+    let names: Vec<String> = names.into_iter().filter(|n| !n.is_empty()).collect();
+    if names.is_empty() {
+        vec![UNGROUPED.to_string()]
+    } else {
+        names
+    }
+}
+
+/// Triggers by group, sorted by group name with ungrouped triggers last.
+/// Comment prefixes are grouped case-insensitively under their first spelling.
+pub(crate) fn group_triggers(
+    triggers: &[Trigger],
+    group_by: ListGroup,
+    state: &State,
+) -> Vec<(String, Vec<Trigger>)> {
+    let mut groups: BTreeMap<String, (String, Vec<Trigger>)> = BTreeMap::new();
+    for trigger in triggers {
+        for name in group_names(trigger, group_by, state) {
+            groups
+                .entry(name.to_lowercase())
+                .or_insert_with(|| (name, Vec::new()))
+                .1
+                .push(trigger.clone());
+        }
+    }
+    let mut groups: Vec<(String, Vec<Trigger>)> = groups.into_values().collect();
+    groups.sort_by_key(|(name, _)| name == UNGROUPED);
+    groups
+}
+
+/// Table or CSV cells of `triggers`, with park references annotated
+fn trigger_rows(columns: &[ListColumn], triggers: &[Trigger]) -> Vec<Vec<String>> {
+    let mut rows: Vec<Vec<String>> = triggers
+        .iter()
+        .map(|t| columns.iter().map(|c| c.value(t)).collect())
+        .collect();
+    if let Some(index) = columns.iter().position(|c| *c == ListColumn::Parks) {
+        let park_names = load_park_names();
+        for row in &mut rows {
+            row[index] = annotate_parks(&row[index], &park_names);
+        }
+    }
+    rows
+}
+
+fn print_plain(triggers: &[Trigger]) {
+    let park_names = load_park_names();
+    for trigger in triggers {
+        let parks = ListColumn::Parks.value(trigger);
+        if parks.is_empty() {
+            println!("{}", format_trigger_for_display(trigger));
+        } else {
+            println!(
+                "{} parks: {}",
+                format_trigger_for_display(trigger),
+                annotate_parks(&parks, &park_names)
+            );
+        }
+    }
+}
+
+fn print_table(columns: &[ListColumn], mut rows: Vec<Vec<String>>) {
+    // Color wildcard patterns so they stand out from exact callsigns
+    if let Some(index) = columns.iter().position(|c| *c == ListColumn::Callsigns) {
+        for row in &mut rows {
+            row[index] = highlight_patterns(&row[index]);
+        }
+    }
+    let headers: Vec<&str> = columns.iter().map(|c| c.header()).collect();
+    println!("{}", render_table(&headers, &rows, terminal_width()));
+}
+
+/// Print `groups` under a heading each; collapsed, only the headings
+fn print_groups(
+    groups: &[(String, Vec<Trigger>)],
+    output: ListOutput,
+    columns: &[ListColumn],
+    collapse: bool,
+) {
+    if output == ListOutput::Csv {
+        let mut headers = vec!["group"];
+        headers.extend(columns.iter().map(|c| c.csv_header()));
+        let rows: Vec<Vec<String>> = groups
+            .iter()
+            .flat_map(|(name, triggers)| {
+                trigger_rows(columns, triggers).into_iter().map(|row| {
+                    let mut cells = vec![name.clone()];
+                    cells.extend(row);
+                    cells
+                })
+            })
+            .collect();
+        println!("{}", render_csv(&headers, &rows));
+        return;
+    }
+    for (i, (name, triggers)) in groups.iter().enumerate() {
+        let marker = if collapse { '▸' } else { '▾' };
+        let heading = format!("{} {} ({})", marker, name, triggers.len());
+        if collapse {
+            println!("{}", paint(&heading, CYAN));
+            continue;
+        }
+        if i > 0 {
+            println!();
+        }
+        println!("{}", paint(&heading, CYAN));
+        if output == ListOutput::Plain {
+            print_plain(triggers);
+        } else {
+            print_table(columns, trigger_rows(columns, triggers));
+        }
+    }
+}
+
+/// Print all triggers, or the `selection`, as a table, CSV, JSON or
+/// a custom template, optionally in groups
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    output: ListOutput,
+    columns: Vec<ListColumn>,
+    format_string: Option<String>,
+    selection: &TriggerSelection,
+    grouping: ListGroupOptions,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let mut triggers = fetch_triggers(client).await?;
+    selection.retain(&mut triggers)?;
+    if let Some(group_by) = grouping.group_by {
+        let state = if group_by == ListGroup::Tag {
+            load_state()?
+        } else {
+            State::default()
+        };
+        let groups = group_triggers(&triggers, group_by, &state);
+        if json_output {
+            let groups: serde_json::Map<String, serde_json::Value> = groups
+                .into_iter()
+                .map(|(name, triggers)| Ok((name, serde_json::to_value(triggers)?)))
+                .collect::<Result<_, serde_json::Error>>()?;
+            print_json(&serde_json::Value::Object(groups))?;
+        } else {
+            print_groups(&groups, output, &columns, grouping.collapse);
+        }
+    } else if json_output {
+        print_json(&serde_json::to_value(&triggers)?)?;
+    } else if let Some(template) = format_string {
+        for trigger in &triggers {
+            println!(
+                "{}",
+                format_trigger_template(&template, trigger).map_err(CliError::Validation)?
+            );
+        }
+    } else if output == ListOutput::Plain {
+        print_plain(&triggers);
+    } else {
+        let rows = trigger_rows(&columns, &triggers);
+        if output == ListOutput::Csv {
+            let headers: Vec<&str> = columns.iter().map(|c| c.csv_header()).collect();
+            println!("{}", render_csv(&headers, &rows));
+        } else {
+            print_table(&columns, rows);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::state::ManagedTrigger;
+    use serde_json::json;
+
+    #[test]
+    fn test_list_column_values() {
+        let trigger: Trigger = serde_json::from_value(json!({
+            "_id": "abc",
+            "conditions": { "callsign": ["W1AW", "K3LR"] },
+            "actions": ["app", "telnet"],
+            "comment": "Friends",
+            "matchCount": 12
+        }))
+        .unwrap();
+        assert_eq!(ListColumn::Mode.value(&trigger), "any");
+        assert_eq!(ListColumn::Callsigns.value(&trigger), "W1AW,K3LR");
+        assert_eq!(ListColumn::Actions.value(&trigger), "app,telnet");
+        assert_eq!(ListColumn::MatchCount.value(&trigger), "12");
+        assert_eq!(ListColumn::Parks.value(&trigger), "");
+        assert_eq!(ListColumn::Disabled.value(&trigger), "");
+    }
+
+    #[test]
+    fn test_format_trigger_template() {
+        let trigger: Trigger = serde_json::from_value(json!({
+            "_id": "abc",
+            "conditions": { "callsign": "W1AW", "mode": "cw" },
+            "actions": ["app"],
+            "comment": "Friends"
+        }))
+        .unwrap();
+        assert_eq!(
+            format_trigger_template("{comment}\\t{id}", &trigger).unwrap(),
+            "Friends\tabc"
+        );
+        assert_eq!(
+            format_trigger_template("{callsigns} ({mode})", &trigger).unwrap(),
+            "W1AW (cw)"
+        );
+        assert!(format_trigger_template("{nope}", &trigger).is_err());
+    }
+
+    #[test]
+    fn test_group_triggers() {
+        let trigger = |id: &str, comment: &str, mode: Option<&str>| -> Trigger {
+            let mut conditions = json!({ "callsign": "W1AW" });
+            if let Some(mode) = mode {
+                conditions["mode"] = json!(mode);
+            }
+            serde_json::from_value(json!({
+                "_id": id,
+                "conditions": conditions,
+                "actions": ["app"],
+                "comment": comment,
+            }))
+            .unwrap()
+        };
+        let triggers = vec![
+            trigger("a", "POTA: K-0001", Some("cw")),
+            trigger("b", "pota: K-0002", None),
+            trigger("c", "Friends on CW", Some("cw")),
+            trigger("d", "", None),
+        ];
+        let names = |groups: Vec<(String, Vec<Trigger>)>| -> Vec<(String, usize)> {
+            groups
+                .into_iter()
+                .map(|(name, triggers)| (name, triggers.len()))
+                .collect()
+        };
+        let state = State::default();
+        assert_eq!(
+            names(group_triggers(&triggers, ListGroup::CommentPrefix, &state)),
+            vec![
+                ("Friends".to_string(), 1),
+                ("POTA".to_string(), 2),
+                (UNGROUPED.to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            names(group_triggers(&triggers, ListGroup::Mode, &state)),
+            vec![("any".to_string(), 2), ("cw".to_string(), 2)]
+        );
+
+        let mut state = State::default();
+        let mut managed =
+            ManagedTrigger::new("add".to_string(), None, vec!["pota".into(), "cw".into()]);
+        managed.id = "a".to_string();
+        state.record("pota-k-0001", managed);
+        assert_eq!(
+            names(group_triggers(&triggers, ListGroup::Tag, &state)),
+            vec![
+                ("cw".to_string(), 1),
+                ("pota".to_string(), 1),
+                (UNGROUPED.to_string(), 3)
+            ]
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! `lookup`: DXCC entity, continent and zones for callsigns.
+
+use crate::dxcc::{cty_path, load_table, update_table};
+use crate::error::CliError;
+use crate::output::{print_json, render_table, terminal_width};
+use hamalert_api::HamAlertClient;
+use serde_json::json;
+
+/// Resolve each callsign (or prefix) with the prefix table, after
+/// downloading the full table when `update` is set
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    callsigns: Vec<String>,
+    update: bool,
+    json_output: bool,
+) -> Result<(), CliError> {
+    if update {
+        let count = update_table(client).await?;
+        info!("Saved {} DXCC entities to {}", count, cty_path()?.display());
+    }
+    if callsigns.is_empty() {
+        return Ok(());
+    }
+
+    let table = load_table()?;
+    let locations: Vec<_> = callsigns
+        .iter()
+        .map(|call| (call.to_uppercase(), table.lookup(call)))
+        .collect();
+    let unknown: Vec<&str> = locations
+        .iter()
+        .filter(|(_, location)| location.is_none())
+        .map(|(call, _)| call.as_str())
+        .collect();
+
+    if json_output {
+        let entries: Vec<serde_json::Value> = locations
+            .iter()
+            .map(|(call, location)| json!({"callsign": call, "location": location}))
+            .collect();
+        print_json(&json!(entries))?;
+    } else {
+        let rows: Vec<Vec<String>> = locations
+            .iter()
+            .map(|(call, location)| match location {
+                Some(location) => vec![
+                    call.clone(),
+                    location.entity.name.clone(),
+                    location.entity.prefix.clone(),
+                    location.entity.dxcc.to_string(),
+                    location.continent.clone(),
+                    location.cq_zone.to_string(),
+                    location.itu_zone.to_string(),
+                ],
+                None => vec![call.clone(), "?".to_string()],
+            })
+            .collect();
+        println!(
+            "{}",
+            render_table(
+                &["CALLSIGN", "ENTITY", "PREFIX", "DXCC", "CONT", "CQ", "ITU"],
+                &rows,
+                terminal_width()
+            )
+        );
+    }
+
+    if !unknown.is_empty() {
+        return Err(CliError::Validation(format!(
+            "No DXCC entity found for {}",
+            unknown.join(", ")
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,380 @@
+//! `manage`: the interactive trigger manager.
+
+use crate::api::fetch_triggers;
+use crate::error::CliError;
+use crate::model::StoredTrigger;
+use crate::output::format_trigger_for_display;
+use crate::storage::profiles::{is_permanent, load_permanent_triggers};
+use crate::storage::state::{load_state, save_state};
+use hamalert_api::{HamAlertClient, Trigger};
+
+/// Score how well `pattern` fuzzy-matches `text`: every pattern character must
+/// appear in order (case-insensitive). Consecutive and word-start matches score higher.
+/// Returns None when the pattern does not match.
+pub(crate) fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous_match: Option<usize> = None;
+    for p in pattern
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+    {
+        let found = (pos..text.len()).find(|&i| text[i] == p)?;
+        score += 1;
+        if previous_match.is_some_and(|prev| prev + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous_match = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+/// Input mode of the trigger manager
+pub(crate) enum ManageMode {
+    Normal,
+    Search,
+    EditComment(String),
+    ConfirmDelete,
+}
+
+/// State of the interactive trigger manager
+pub(crate) struct TriggerManager {
+    pub(crate) triggers: Vec<Trigger>,
+    /// Permanent triggers, which cannot be deleted from the manager
+    pub(crate) permanent: Vec<StoredTrigger>,
+    pub(crate) query: String,
+    pub(crate) mode: ManageMode,
+    pub(crate) state: ratatui::widgets::ListState,
+    pub(crate) status: String,
+}
+
+impl TriggerManager {
+    /// Indices of triggers matching the search query, best match first
+    pub(crate) fn visible(&self) -> Vec<usize> {
+        if self.query.is_empty() {
+            return (0..self.triggers.len()).collect();
+        }
+        let mut scored: Vec<(i64, usize)> = self
+            .triggers
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| {
+                fuzzy_score(&self.query, &format_trigger_for_display(t)).map(|s| (s, i))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        scored.into_iter().map(|(_, i)| i).collect()
+    }
+
+    pub(crate) fn selected_index(&self) -> Option<usize> {
+        self.visible().get(self.state.selected()?).copied()
+    }
+
+    pub(crate) fn move_selection(&mut self, delta: isize) {
+        let len = self.visible().len();
+        if len == 0 {
+            self.state.select(None);
+            return;
+        }
+        let current = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((current + delta).clamp(0, len as isize - 1) as usize));
+    }
+}
+
+pub(crate) fn draw_manager(frame: &mut ratatui::Frame, manager: &mut TriggerManager) {
+    use ratatui::layout::{Constraint, Layout};
+    use ratatui::style::{Color, Modifier, Style};
+    use ratatui::widgets::{Block, List, ListItem, Paragraph};
+
+    let [search, list, status] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let search_text = match manager.mode {
+        ManageMode::Search => format!("{}_", manager.query),
+        _ => manager.query.clone(),
+    };
+    frame.render_widget(
+        Paragraph::new(search_text).block(Block::bordered().title(" Search ")),
+        search,
+    );
+
+    let visible = manager.visible();
+    let items: Vec<ListItem> = visible
+        .iter()
+        .map(|&i| {
+            let trigger = &manager.triggers[i];
+            let disabled = trigger.disabled.unwrap_or(false);
+            let text = format!(
+                "{} {}{}",
+                if disabled { "[off]" } else { "[on] " },
+                format_trigger_for_display(trigger),
+                trigger
+                    .match_count
+                    .map(|c| format!(" ({} matches)", c))
+                    .unwrap_or_default()
+            );
+            let style = if disabled {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            ListItem::new(text).style(style)
+        })
+        .collect();
+    let list_widget = List::new(items)
+        .block(Block::bordered().title(format!(
+            " Triggers ({}/{}) ",
+            visible.len(),
+            manager.triggers.len()
+        )))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list_widget, list, &mut manager.state);
+
+    let line = match &manager.mode {
+        ManageMode::Normal => format!(
+            "{} | q=quit /=search space=enable/disable c=duplicate d=delete e=edit comment r=refresh",
+            manager.status
+        ),
+        ManageMode::Search => "type to search, Enter=done, Esc=clear".to_string(),
+        ManageMode::EditComment(comment) => {
+            format!("New comment: {}_  (Enter=save, Esc=cancel)", comment)
+        }
+        ManageMode::ConfirmDelete => "Delete selected trigger? [y/N]".to_string(),
+    };
+    frame.render_widget(Paragraph::new(line), status);
+}
+
+/// Run the trigger manager UI loop on a blocking thread, driving API calls
+/// through the runtime handle
+pub(crate) fn run_manager(
+    client: HamAlertClient,
+    triggers: Vec<Trigger>,
+    permanent: Vec<StoredTrigger>,
+    runtime: tokio::runtime::Handle,
+) -> std::io::Result<()> {
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+
+    let mut manager = TriggerManager {
+        triggers,
+        permanent,
+        query: String::new(),
+        mode: ManageMode::Normal,
+        state: ratatui::widgets::ListState::default(),
+        status: String::new(),
+    };
+    manager.move_selection(0);
+    manager.status = format!("Loaded {} triggers", manager.triggers.len());
+
+    let mut terminal = ratatui::try_init()?;
+    let result = loop {
+        if let Err(e) = terminal.draw(|frame| draw_manager(frame, &mut manager)) {
+            break Err(e);
+        }
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+            Ok(_) => continue,
+            Err(e) => break Err(e),
+        };
+
+        match &mut manager.mode {
+            ManageMode::Search => {
+                match key.code {
+                    KeyCode::Char(c) => manager.query.push(c),
+                    KeyCode::Backspace => {
+                        manager.query.pop();
+                    }
+                    KeyCode::Enter => manager.mode = ManageMode::Normal,
+                    KeyCode::Esc => {
+                        manager.query.clear();
+                        manager.mode = ManageMode::Normal;
+                    }
+                    _ => {}
+                }
+                manager.state.select(Some(0));
+                manager.move_selection(0);
+                continue;
+            }
+            ManageMode::EditComment(comment) => {
+                match key.code {
+                    KeyCode::Char(c) => comment.push(c),
+                    KeyCode::Backspace => {
+                        comment.pop();
+                    }
+                    KeyCode::Enter => {
+                        let comment = comment.clone();
+                        manager.mode = ManageMode::Normal;
+                        if let Some(i) = manager.selected_index() {
+                            let mut trigger = manager.triggers[i].clone();
+                            trigger.comment = comment;
+                            manager.status = match runtime.block_on(client.update_trigger(&trigger))
+                            {
+                                Ok(()) => {
+                                    let status = format!("Renamed to \"{}\"", trigger.comment);
+                                    manager.triggers[i] = trigger;
+                                    status
+                                }
+                                Err(e) => format!("Update failed: {}", e),
+                            };
+                        }
+                    }
+                    KeyCode::Esc => manager.mode = ManageMode::Normal,
+                    _ => {}
+                }
+                continue;
+            }
+            ManageMode::ConfirmDelete => {
+                manager.mode = ManageMode::Normal;
+                if key.code != KeyCode::Char('y') {
+                    manager.status = "Delete cancelled".to_string();
+                    continue;
+                }
+                if let Some(i) = manager.selected_index() {
+                    let trigger = manager.triggers[i].clone();
+                    manager.status = match runtime.block_on(client.delete_trigger(&trigger.id)) {
+                        Ok(()) => {
+                            manager.triggers.remove(i);
+                            manager.move_selection(0);
+                            match forget(&trigger.id) {
+                                Ok(()) => {
+                                    format!("Deleted {}", format_trigger_for_display(&trigger))
+                                }
+                                Err(e) => format!("Deleted, but updating the state failed: {}", e),
+                            }
+                        }
+                        Err(e) => format!("Delete failed: {}", e),
+                    };
+                }
+                continue;
+            }
+            ManageMode::Normal => {}
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => break Ok(()),
+            KeyCode::Char('j') | KeyCode::Down => manager.move_selection(1),
+            KeyCode::Char('k') | KeyCode::Up => manager.move_selection(-1),
+            KeyCode::Char('/') => manager.mode = ManageMode::Search,
+            KeyCode::Char('d') => {
+                if let Some(i) = manager.selected_index() {
+                    if is_permanent(&manager.triggers[i], &manager.permanent) {
+                        manager.status = "Permanent triggers cannot be deleted here".to_string();
+                    } else {
+                        manager.mode = ManageMode::ConfirmDelete;
+                    }
+                }
+            }
+            KeyCode::Char('e') => {
+                if let Some(i) = manager.selected_index() {
+                    manager.mode = ManageMode::EditComment(manager.triggers[i].comment.clone());
+                }
+            }
+            KeyCode::Char(' ') => {
+                let Some(i) = manager.selected_index() else {
+                    continue;
+                };
+                let mut trigger = manager.triggers[i].clone();
+                trigger.disabled = Some(!trigger.disabled.unwrap_or(false));
+                manager.status = match runtime.block_on(client.update_trigger(&trigger)) {
+                    Ok(()) => {
+                        let status = format!(
+                            "{} \"{}\"",
+                            if trigger.disabled == Some(true) {
+                                "Disabled"
+                            } else {
+                                "Enabled"
+                            },
+                            trigger.comment
+                        );
+                        manager.triggers[i] = trigger;
+                        status
+                    }
+                    Err(e) => format!("Update failed: {}", e),
+                };
+            }
+            KeyCode::Char('c') => {
+                let Some(i) = manager.selected_index() else {
+                    continue;
+                };
+                let mut copy = manager.triggers[i].clone();
+                copy.comment = format!("{} (copy)", copy.comment);
+                let created = runtime.block_on(async {
+                    client.create_trigger(&copy).await?;
+                    fetch_triggers(&client).await
+                });
+                manager.status = match created {
+                    Ok(triggers) => {
+                        manager.triggers = triggers;
+                        format!("Created \"{}\"", copy.comment)
+                    }
+                    Err(e) => format!("Duplicate failed: {}", e),
+                };
+            }
+            KeyCode::Char('r') => {
+                client.invalidate_triggers();
+                manager.status = match runtime.block_on(fetch_triggers(&client)) {
+                    Ok(triggers) => {
+                        manager.triggers = triggers;
+                        manager.move_selection(0);
+                        "Refreshed triggers".to_string()
+                    }
+                    Err(e) => format!("Refresh failed: {}", e),
+                };
+            }
+            _ => {}
+        }
+    };
+
+    ratatui::restore();
+    result
+}
+
+/// Drop the name, tags and snooze of a deleted trigger from the local state
+fn forget(id: &str) -> Result<(), CliError> {
+    let mut state = load_state()?;
+    if state.forget(id) {
+        save_state(&state)?;
+    }
+    Ok(())
+}
+
+/// Run the interactive trigger manager
+pub(crate) async fn run(client: &HamAlertClient) -> Result<(), CliError> {
+    let triggers = fetch_triggers(client).await?;
+    let permanent = load_permanent_triggers()?;
+    let client = client.clone();
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || run_manager(client, triggers, permanent, runtime))
+        .await??;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score_matches_subsequence() {
+        assert!(fuzzy_score("wa", "[cw] W1AW - \"Friends\"").is_some());
+        assert!(fuzzy_score("frnd", "[cw] W1AW - \"Friends\"").is_some());
+        assert!(fuzzy_score("xyz", "[cw] W1AW - \"Friends\"").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn test_fuzzy_score_prefers_contiguous_matches() {
+        let contiguous = fuzzy_score("sota", "SOTA activators").unwrap();
+        let scattered = fuzzy_score("sota", "Some other trigger alerts").unwrap();
+        assert!(contiguous > scattered);
+    }
+}
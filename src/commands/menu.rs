@@ -0,0 +1,84 @@
+//! The interactive menu shown when no subcommand is given.
+
+use crate::cli::Action;
+use crate::error::CliError;
+use crate::storage::profiles::list_profiles;
+use clap::ValueEnum;
+use inquire::{InquireError, MultiSelect};
+
+/// Menu shown when the CLI is run on a terminal without a command. Returns the
+/// arguments of the chosen command, or None to quit.
+pub(crate) fn prompt_main_menu() -> Result<Option<Vec<String>>, CliError> {
+    const LIST: &str = "List triggers";
+    const ADD: &str = "Add a trigger";
+    const EDIT: &str = "Edit a trigger";
+    const MANAGE: &str = "Manage triggers (search, toggle, delete)";
+    const SWITCH: &str = "Switch profile";
+    const TUI: &str = "Watch live spots";
+    const BACKUP: &str = "Back up triggers";
+    const QUIT: &str = "Quit";
+
+    let words = |w: &[&str]| w.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    let choice = match inquire::Select::new(
+        "What would you like to do?",
+        vec![LIST, ADD, EDIT, MANAGE, SWITCH, TUI, BACKUP, QUIT],
+    )
+    .prompt()
+    {
+        Ok(choice) => choice,
+        Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => {
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let args = match choice {
+        LIST => words(&["list"]),
+        EDIT => words(&["edit"]),
+        MANAGE => words(&["manage"]),
+        TUI => words(&["tui"]),
+        BACKUP => words(&["backup"]),
+        ADD => {
+            let callsigns =
+                inquire::Text::new("Callsigns (separated by spaces or commas):").prompt()?;
+            let comment = inquire::Text::new("Comment:").prompt()?;
+            let actions: Vec<String> = Action::value_variants()
+                .iter()
+                .filter_map(|a| a.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect();
+            let actions = MultiSelect::new("Actions:", actions).prompt()?;
+
+            let mut args = words(&["add-trigger", "--comment"]);
+            args.push(comment);
+            for callsign in callsigns.split([',', ' ']).filter(|c| !c.is_empty()) {
+                args.push("--callsign".to_string());
+                args.push(callsign.to_string());
+            }
+            if !actions.is_empty() {
+                args.push("--actions".to_string());
+                args.extend(actions);
+            }
+            args
+        }
+        SWITCH => {
+            let profiles = list_profiles()?;
+            if profiles.is_empty() {
+                println!("No profiles saved.");
+                return Ok(None);
+            }
+            let name = inquire::Select::new("Profile:", profiles).prompt()?;
+            let apply = inquire::Confirm::new("Apply the switch now? (No shows a preview)")
+                .with_default(false)
+                .prompt()?;
+            let mut args = words(&["profile", "switch"]);
+            args.push(name);
+            if apply {
+                args.push("--no-dry-run".to_string());
+            }
+            args
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(args))
+}
@@ -0,0 +1,287 @@
+//! One module per subcommand.
+
+use crate::cli::{BackupCommands, Commands, NamesCommands};
+use crate::config::Config;
+use crate::error::CliError;
+use hamalert_api::HamAlertClient;
+
+pub(crate) mod add;
+pub(crate) mod apply;
+pub(crate) mod backup;
+pub(crate) mod batch;
+pub(crate) mod bulk_delete;
+pub(crate) mod check;
+pub(crate) mod config;
+pub(crate) mod contest;
+pub(crate) mod copy_account;
+pub(crate) mod destinations;
+pub(crate) mod duplicate;
+pub(crate) mod edit;
+pub(crate) mod export;
+pub(crate) mod generate;
+pub(crate) mod import;
+pub(crate) mod lint;
+pub(crate) mod list;
+pub(crate) mod lookup;
+pub(crate) mod manage;
+pub(crate) mod menu;
+pub(crate) mod mute;
+pub(crate) mod names;
+pub(crate) mod plan;
+pub(crate) mod profile;
+pub(crate) mod prune;
+pub(crate) mod report;
+pub(crate) mod serve;
+pub(crate) mod share;
+pub(crate) mod shell;
+pub(crate) mod simulate;
+pub(crate) mod snooze;
+pub(crate) mod spots;
+pub(crate) mod stats;
+pub(crate) mod temp;
+pub(crate) mod tui;
+pub(crate) mod whoami;
+pub(crate) mod wizard;
+
+/// Run a command that uses an existing session; `config` and `copy-account`
+/// are handled before logging in
+pub(crate) async fn dispatch(
+    command: Commands,
+    client: &HamAlertClient,
+    config: &Config,
+    json_output: bool,
+    assume_yes: bool,
+) -> Result<(), CliError> {
+    match command {
+        Commands::AddTrigger { callsign, options } => {
+            add::run(client, config, callsign, options, json_output).await
+        }
+        Commands::Duplicate {
+            id,
+            name,
+            overrides,
+            dry_run,
+        } => duplicate::run(client, id, name, overrides, dry_run).await,
+        Commands::TempTrigger { callsign, options } => {
+            temp::run(client, config, callsign, options).await
+        }
+        Commands::ImportPoloNotes {
+            url,
+            url_file,
+            options,
+        } => {
+            import::polo_notes(
+                client,
+                config,
+                url,
+                url_file.as_deref(),
+                options,
+                json_output,
+            )
+            .await
+        }
+        Commands::ImportFile { file, options } => {
+            import::file(client, config, &file, options, json_output).await
+        }
+        Commands::ImportUrl { url, tag, dry_run } => {
+            share::import_url(client, config, &url, tag, dry_run, json_output).await
+        }
+        Commands::Share {
+            gist,
+            public,
+            paste,
+            description,
+            selection,
+        } => {
+            let target = match (gist, paste) {
+                (true, _) => share::ShareTarget::Gist { public },
+                (false, Some(endpoint)) => share::ShareTarget::Paste(endpoint),
+                (false, None) => share::ShareTarget::Stdout,
+            };
+            share::share(client, config, &selection, description, target, assume_yes).await
+        }
+        Commands::Backup {
+            command: Some(BackupCommands::Verify { file }),
+            ..
+        } => backup::verify(&file, json_output),
+        Commands::Backup {
+            command: None,
+            output,
+            selection,
+            if_stale,
+        } => backup::backup(client, output, &selection, if_stale).await,
+        Commands::Restore {
+            input,
+            to_profile: Some(name),
+            ..
+        } => backup::restore_to_profile(&input, &name, assume_yes),
+        Commands::Restore {
+            input,
+            no_dry_run,
+            include_permanent,
+            ..
+        } => {
+            backup::restore(
+                client,
+                &input,
+                no_dry_run,
+                include_permanent,
+                json_output,
+                assume_yes,
+            )
+            .await
+        }
+        Commands::Edit { name, guided } => edit::run(client, name.as_deref(), guided).await,
+        Commands::BulkDelete {
+            dry_run,
+            selection,
+            include_permanent,
+        } => bulk_delete::run(client, dry_run, &selection, include_permanent, assume_yes).await,
+        Commands::Profile(profile_cmd) => {
+            profile::run(client, profile_cmd, json_output, assume_yes).await
+        }
+        Commands::List {
+            output,
+            columns,
+            format_string,
+            selection,
+            grouping,
+        } => {
+            list::run(
+                client,
+                output,
+                columns,
+                format_string,
+                &selection,
+                grouping,
+                json_output,
+            )
+            .await
+        }
+        Commands::Whoami => whoami::run(client, config, json_output).await,
+        Commands::Simulate {
+            callsign,
+            freq,
+            mode,
+            spotter,
+            comment,
+            file,
+        } => simulate::run(client, callsign, freq, mode, spotter, comment, file).await,
+        Commands::Manage => manage::run(client).await,
+        Commands::Tui { rigctld, adif } => tui::run(config, client, rigctld, adif).await,
+        Commands::Apply {
+            file,
+            delete_unmanaged,
+            no_dry_run,
+            daemon: true,
+            interval,
+        } => {
+            apply::daemon(
+                client,
+                config,
+                &file,
+                delete_unmanaged,
+                no_dry_run,
+                interval,
+            )
+            .await
+        }
+        Commands::Apply {
+            file,
+            delete_unmanaged,
+            no_dry_run,
+            ..
+        } => {
+            apply::run(
+                client,
+                config,
+                &file,
+                delete_unmanaged,
+                no_dry_run,
+                assume_yes,
+            )
+            .await
+        }
+        Commands::Plan {
+            file,
+            delete_unmanaged,
+        } => plan::run(client, config, &file, delete_unmanaged, json_output).await,
+        Commands::Names {
+            command: Some(NamesCommands::Set { id, name }),
+            ..
+        } => names::set(client, &id, &name).await,
+        Commands::Names {
+            command: None,
+            prune,
+        } => names::run(client, prune, json_output).await,
+        Commands::Stats { trend } => stats::run(client, trend, json_output).await,
+        Commands::Lint { file, strict } => {
+            lint::run(client, file.as_deref(), strict, json_output).await
+        }
+        Commands::PruneExpired { dry_run } => prune::run(client, dry_run, json_output).await,
+        Commands::Snooze { id, name, duration } => snooze::snooze(client, id, name, duration).await,
+        Commands::Unsnooze { id, name } => snooze::unsnooze(client, id, name).await,
+        Commands::MuteCallsign { callsign, duration } => {
+            mute::mute(client, &callsign, duration).await
+        }
+        Commands::UnmuteCallsign { callsign } => mute::unmute(client, &callsign).await,
+        Commands::Batch => batch::run(client, config, json_output).await,
+        Commands::Config(_) => unreachable!("config commands run before login"),
+        Commands::CopyAccount { .. } => unreachable!("copy-account logs in on its own"),
+        Commands::Shell => unreachable!("the shell runs before dispatch"),
+        Commands::Lookup { callsigns, update } => {
+            lookup::run(client, callsigns, update, json_output).await
+        }
+        Commands::Spots(spots_cmd) => spots::run(spots_cmd, json_output).await,
+        Commands::Check(check_cmd) => check::run(client, check_cmd, json_output).await,
+        Commands::Report(report_cmd) => report::run(client, report_cmd, json_output).await,
+        Commands::Export(export_cmd) => export::run(client, export_cmd).await,
+        Commands::Generate(generate_cmd) => generate::run(client, config, generate_cmd).await,
+        Commands::Contest(contest_cmd) => contest::run(client, config, contest_cmd).await,
+        Commands::Destinations(destinations_cmd) => {
+            destinations::run(client, destinations_cmd, json_output).await
+        }
+        Commands::Serve(serve_cmd) => serve::run(client, config, serve_cmd).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::Parser;
+
+    #[tokio::test]
+    async fn test_dispatch_backup_if_stale_skips_fresh_backup() {
+        let path = std::env::temp_dir().join(format!(
+            "hamalert-dispatch-fresh-{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, "[]").unwrap();
+        let cli = Cli::try_parse_from([
+            "hamalert-cli",
+            "backup",
+            "--if-stale",
+            "1d",
+            "--output",
+            path.to_str().unwrap(),
+        ])
+        .unwrap();
+
+        // Nothing listens here, so reaching HamAlert would fail the command
+        let client = HamAlertClient::new()
+            .unwrap()
+            .with_base_url("http://127.0.0.1:9");
+        let result = dispatch(
+            cli.command.unwrap(),
+            &client,
+            &Config::default(),
+            false,
+            false,
+        )
+        .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[]");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
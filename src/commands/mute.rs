@@ -0,0 +1,280 @@
+//! `mute-callsign` and `unmute-callsign`: keep one station out of alerts for a while.
+
+use super::snooze::wake_time;
+use crate::api::fetch_triggers;
+use crate::callsign::parse_callsign;
+use crate::error::CliError;
+use crate::storage::state::{Mute, State, load_state, save_state};
+use chrono::{DateTime, Local};
+use hamalert_api::{HamAlertClient, Trigger};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+/// Condition excluding callsigns from a trigger
+const NOT_CALLSIGN: &str = "notCallsign";
+
+/// Uppercased entries of a comma- or newline-separated condition
+fn condition_calls(trigger: &Trigger, key: &str) -> Vec<String> {
+    match trigger.conditions.get(key) {
+        Some(Value::String(s)) => s
+            .split([',', '\n'])
+            .map(|c| c.trim().to_uppercase())
+            .filter(|c| !c.is_empty())
+            .collect(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(str::to_uppercase)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether `trigger` lists `call` itself in its callsign condition
+fn lists_call(trigger: &Trigger, call: &str) -> bool {
+    condition_calls(trigger, "callsign")
+        .iter()
+        .any(|c| c == call)
+}
+
+/// Whether `trigger` could alert on `call` without naming it: it has no
+/// callsign condition, or a prefix wildcard `call` starts with
+fn could_match(trigger: &Trigger, call: &str) -> bool {
+    let calls = condition_calls(trigger, "callsign");
+    calls.is_empty()
+        || calls.iter().any(|c| {
+            c.strip_suffix('*')
+                .is_some_and(|prefix| call.starts_with(prefix))
+        })
+}
+
+/// `trigger` with `call` added to or removed from its `notCallsign` condition
+fn with_not_callsign(trigger: &Trigger, call: &str, muted: bool) -> Trigger {
+    let mut calls = condition_calls(trigger, NOT_CALLSIGN);
+    calls.retain(|c| c != call);
+    if muted {
+        calls.push(call.to_string());
+    }
+    let mut trigger = trigger.clone();
+    if let Some(conditions) = trigger.conditions.as_object_mut() {
+        if calls.is_empty() {
+            conditions.remove(NOT_CALLSIGN);
+        } else {
+            conditions.insert(NOT_CALLSIGN.to_string(), json!(calls.join(",")));
+        }
+    }
+    trigger
+}
+
+/// Muted callsigns whose mute has run out at `now`
+fn find_due(state: &State, now: &DateTime<Local>) -> Vec<String> {
+    state
+        .mutes
+        .iter()
+        .filter(|(_, mute)| mute.is_over(now))
+        .map(|(call, _)| call.clone())
+        .collect()
+}
+
+/// The triggers of `mutes` with every muted call taken out of their
+/// `notCallsign` conditions, so a trigger shared by several mutes is updated
+/// once. Triggers deleted in the meantime are skipped.
+fn lifted(triggers: &[Trigger], mutes: &[(String, Mute)]) -> Vec<Trigger> {
+    triggers
+        .iter()
+        .filter_map(|trigger| {
+            let calls: Vec<&str> = mutes
+                .iter()
+                .filter(|(_, mute)| mute.triggers.contains(&trigger.id))
+                .map(|(call, _)| call.as_str())
+                .collect();
+            (!calls.is_empty()).then(|| {
+                calls.iter().fold(trigger.clone(), |trigger, call| {
+                    with_not_callsign(&trigger, call, false)
+                })
+            })
+        })
+        .collect()
+}
+
+/// Take the calls of `mutes` out of the `notCallsign` conditions of their triggers
+async fn lift(
+    client: &HamAlertClient,
+    triggers: &[Trigger],
+    mutes: &[(String, Mute)],
+) -> Result<(), CliError> {
+    for trigger in lifted(triggers, mutes) {
+        client.update_trigger(&trigger).await?;
+    }
+    Ok(())
+}
+
+/// Lift the mutes that have run out and forget them; with `dry_run` nothing
+/// is changed. Returns the callsigns.
+pub(crate) async fn unmute_due(
+    client: &HamAlertClient,
+    dry_run: bool,
+) -> Result<Vec<String>, CliError> {
+    let mut state = load_state()?;
+    let due = find_due(&state, &Local::now());
+    if dry_run || due.is_empty() {
+        return Ok(due);
+    }
+
+    let triggers = fetch_triggers(client).await?;
+    let mutes: Vec<(String, Mute)> = due
+        .iter()
+        .map(|call| {
+            let mute = state
+                .mutes
+                .remove(call)
+                .expect("due calls come from the state");
+            (call.clone(), mute)
+        })
+        .collect();
+    lift(client, &triggers, &mutes).await?;
+    save_state(&state)?;
+    tracing::info!("Unmuted {} callsigns", due.len());
+    Ok(due)
+}
+
+/// Exclude `callsign` from every trigger that could alert on it until
+/// `duration` from now. Muting a muted callsign moves the end of the mute.
+pub(crate) async fn mute(
+    client: &HamAlertClient,
+    callsign: &str,
+    duration: Duration,
+) -> Result<(), CliError> {
+    let call = parse_callsign(callsign)
+        .map_err(|reason| {
+            CliError::Validation(format!("Invalid callsign '{}': {}", callsign, reason))
+        })?
+        .to_string();
+    let until = wake_time(Local::now(), duration)?;
+    let triggers = fetch_triggers(client).await?;
+    let mut state = load_state()?;
+    let mut mute = state.mutes.get(&call).cloned().unwrap_or(Mute {
+        until: until.clone(),
+        triggers: Vec::new(),
+    });
+    mute.until = until.clone();
+
+    let listing = triggers.iter().filter(|t| lists_call(t, &call)).count();
+    let targets: Vec<&Trigger> = triggers
+        .iter()
+        .filter(|t| could_match(t, &call) && !lists_call(t, &call))
+        .filter(|t| !condition_calls(t, NOT_CALLSIGN).contains(&call))
+        .collect();
+    if targets.is_empty() && mute.triggers.is_empty() {
+        return Err(CliError::Validation(format!(
+            "No trigger could alert on {} without naming it",
+            call
+        )));
+    }
+    for trigger in &targets {
+        client
+            .update_trigger(&with_not_callsign(trigger, &call, true))
+            .await?;
+        mute.triggers.push(trigger.id.clone());
+    }
+    let count = mute.triggers.len();
+    state.mutes.insert(call.clone(), mute);
+    save_state(&state)?;
+
+    println!("Muted {} in {} triggers until {}", call, count, until);
+    if listing > 0 {
+        println!(
+            "{} triggers list {} by name and were left alone; snooze them to silence them too.",
+            listing, call
+        );
+    }
+    Ok(())
+}
+
+/// Lift the mute of `callsign` now
+pub(crate) async fn unmute(client: &HamAlertClient, callsign: &str) -> Result<(), CliError> {
+    let call = callsign.trim().to_uppercase();
+    let mut state = load_state()?;
+    let mute = state
+        .mutes
+        .remove(&call)
+        .ok_or_else(|| CliError::Validation(format!("{} is not muted", call)))?;
+    let count = mute.triggers.len();
+    let mutes = [(call.clone(), mute)];
+    lift(client, &fetch_triggers(client).await?, &mutes).await?;
+    save_state(&state)?;
+    println!("Unmuted {} in {} triggers", call, count);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::test_trigger;
+
+    fn trigger(conditions: Value) -> Trigger {
+        test_trigger("1", "Test", conditions)
+    }
+
+    #[test]
+    fn test_could_match() {
+        let band = trigger(json!({"band": "20m"}));
+        assert!(could_match(&band, "W1XYZ"));
+        let prefix = trigger(json!({"callsign": "W1*,K1*"}));
+        assert!(could_match(&prefix, "W1XYZ"));
+        assert!(!could_match(&prefix, "N1XYZ"));
+        let named = trigger(json!({"callsign": "W1XYZ,K3LR"}));
+        assert!(!could_match(&named, "W1XYZ"));
+        assert!(lists_call(&named, "W1XYZ"));
+    }
+
+    #[test]
+    fn test_with_not_callsign() {
+        let muted = with_not_callsign(
+            &trigger(json!({"band": "20m", "notCallsign": "K3LR"})),
+            "W1XYZ",
+            true,
+        );
+        assert_eq!(muted.conditions["notCallsign"], "K3LR,W1XYZ");
+        let lifted = with_not_callsign(&muted, "W1XYZ", false);
+        assert_eq!(lifted.conditions["notCallsign"], "K3LR");
+        let lifted = with_not_callsign(&lifted, "K3LR", false);
+        assert_eq!(lifted.conditions, json!({"band": "20m"}));
+    }
+
+    #[test]
+    fn test_find_due() {
+        let now = Local::now();
+        let mut state = State::default();
+        for (call, until) in [
+            ("W1XYZ", now - chrono::Duration::minutes(1)),
+            ("K3LR", now + chrono::Duration::hours(1)),
+        ] {
+            state.mutes.insert(
+                call.to_string(),
+                Mute {
+                    until: until.to_rfc3339(),
+                    triggers: vec!["1".to_string()],
+                },
+            );
+        }
+        assert_eq!(find_due(&state, &now), vec!["W1XYZ"]);
+    }
+
+    #[test]
+    fn test_lifted_mutes_sharing_a_trigger() {
+        let shared = trigger(json!({"band": "20m", "notCallsign": "K1ABC,W2XYZ,K3LR"}));
+        let mut other = trigger(json!({"notCallsign": "K1ABC"}));
+        other.id = "2".to_string();
+        let mute = || Mute {
+            until: Local::now().to_rfc3339(),
+            triggers: vec!["1".to_string()],
+        };
+        let mutes = [("K1ABC".to_string(), mute()), ("W2XYZ".to_string(), mute())];
+
+        let updates = lifted(&[shared, other], &mutes);
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].id, "1");
+        assert_eq!(updates[0].conditions["notCallsign"], "K3LR");
+    }
+}
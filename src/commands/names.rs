@@ -0,0 +1,167 @@
+//! `names`: the friendly names recorded for managed triggers.
+
+use crate::api::fetch_triggers;
+use crate::error::CliError;
+use crate::output::{RED, YELLOW, paint, print_json, render_table, terminal_width};
+use crate::storage::state::{ManagedTrigger, State, load_state, save_state};
+use chrono::Local;
+use hamalert_api::HamAlertClient;
+use serde_json::json;
+
+/// List managed triggers with their metadata, marking expired ones and those
+/// deleted on HamAlert; `prune` forgets the deleted ones
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    prune: bool,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let mut state = load_state()?;
+    let triggers = fetch_triggers(client).await?;
+    let missing = state.missing(&triggers);
+
+    if prune && !missing.is_empty() {
+        for name in &missing {
+            state.triggers.remove(name);
+        }
+        save_state(&state)?;
+        info!("Forgot {} names of deleted triggers", missing.len());
+    }
+
+    if json_output {
+        let entries: Vec<serde_json::Value> = state
+            .triggers
+            .iter()
+            .map(|(name, managed)| {
+                json!({
+                    "name": name,
+                    "id": managed.id,
+                    "created_at": managed.created_at,
+                    "source": managed.source,
+                    "expires": managed.expires,
+                    "tags": managed.tags,
+                    "snoozed_until": managed.snoozed_until,
+                    "exists": !missing.contains(name),
+                })
+            })
+            .collect();
+        return print_json(&json!(entries));
+    }
+
+    if state.triggers.is_empty() {
+        println!("No managed triggers. Use --name with add-trigger, or apply a trigger file.");
+        return Ok(());
+    }
+
+    let now = Local::now();
+    let rows: Vec<Vec<String>> = state
+        .triggers
+        .iter()
+        .map(|(name, managed)| {
+            let comment = triggers
+                .iter()
+                .find(|t| t.id == managed.id)
+                .map(|t| t.comment.clone())
+                .unwrap_or_else(|| paint("(deleted)", RED));
+            let expires = match &managed.expires {
+                Some(date) if managed.is_expired(&now) => paint(date, YELLOW),
+                Some(date) => date.clone(),
+                None => String::new(),
+            };
+            vec![
+                name.clone(),
+                comment,
+                managed.source.clone(),
+                managed.created_at.get(..10).unwrap_or_default().to_string(),
+                expires,
+                managed.tags.join(","),
+            ]
+        })
+        .collect();
+    println!(
+        "{}",
+        render_table(
+            &["NAME", "COMMENT", "SOURCE", "CREATED", "EXPIRES", "TAGS"],
+            &rows,
+            terminal_width()
+        )
+    );
+    if !prune && !missing.is_empty() {
+        info!(
+            "\n{} triggers were deleted on HamAlert; run with --prune to forget them.",
+            missing.len()
+        );
+    }
+    Ok(())
+}
+
+/// Record `name` for the trigger `id`, moving its entry if it already has a
+/// name. Returns the old name.
+fn assign_name(state: &mut State, id: &str, name: &str) -> Result<Option<String>, CliError> {
+    if name.trim().is_empty() {
+        return Err(CliError::Validation("Names cannot be empty".to_string()));
+    }
+    if let Some(other) = state.triggers.get(name)
+        && other.id != id
+    {
+        return Err(CliError::Validation(format!(
+            "'{}' already names trigger {}",
+            name, other.id
+        )));
+    }
+    let old_name = state.name_for_id(id).map(str::to_string);
+    let managed = match &old_name {
+        Some(old_name) => state
+            .triggers
+            .remove(old_name)
+            .expect("the old name comes from the state"),
+        None => ManagedTrigger {
+            id: id.to_string(),
+            ..ManagedTrigger::new("names set".to_string(), None, Vec::new())
+        },
+    };
+    state.triggers.insert(name.to_string(), managed);
+    Ok(old_name)
+}
+
+/// Name the existing trigger `id` so other commands accept `--name`
+pub(crate) async fn set(client: &HamAlertClient, id: &str, name: &str) -> Result<(), CliError> {
+    let triggers = fetch_triggers(client).await?;
+    let trigger = triggers
+        .iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| CliError::Validation(format!("No trigger with id {}", id)))?;
+    let mut state = load_state()?;
+    let old_name = assign_name(&mut state, id, name)?;
+    save_state(&state)?;
+    match old_name {
+        Some(old_name) if old_name != name => {
+            println!("Renamed '{}' to '{}' ({})", old_name, name, trigger.comment)
+        }
+        _ => println!("Named '{}' {}", trigger.comment, name),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assign_name() {
+        let mut state = State::default();
+        assert_eq!(assign_name(&mut state, "abc", "friends").unwrap(), None);
+        assert_eq!(state.triggers["friends"].id, "abc");
+        assert_eq!(state.triggers["friends"].source, "names set");
+
+        state.triggers.get_mut("friends").unwrap().tags = vec!["club".to_string()];
+        assert_eq!(
+            assign_name(&mut state, "abc", "pals").unwrap().as_deref(),
+            Some("friends")
+        );
+        assert!(!state.triggers.contains_key("friends"));
+        assert_eq!(state.triggers["pals"].tags, vec!["club"]);
+
+        assert!(assign_name(&mut state, "def", "pals").is_err());
+        assert!(assign_name(&mut state, "def", " ").is_err());
+    }
+}
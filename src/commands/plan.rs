@@ -0,0 +1,95 @@
+//! `plan`: preview what `apply` would change, for CI drift checks.
+
+use crate::api::fetch_triggers;
+use crate::config::Config;
+use crate::declarative::{changed_fields, desired_triggers, plan, print_plan};
+use crate::error::CliError;
+use crate::output::print_json;
+use crate::storage::profiles::load_permanent_triggers;
+use crate::storage::state::load_state;
+use hamalert_api::HamAlertClient;
+use serde_json::json;
+use std::path::Path;
+
+/// Print the changes needed to make the account match `file`, failing with
+/// `CliError::Drift` when there are any
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    config: &Config,
+    file: &Path,
+    delete_unmanaged: bool,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let desired = desired_triggers(file, &config.defaults)?;
+    let current_triggers = fetch_triggers(client).await?;
+    let mut plan = plan(
+        &desired,
+        &current_triggers,
+        delete_unmanaged,
+        &load_state()?,
+    );
+    plan.spare_permanent(&load_permanent_triggers()?);
+    let changes = plan.create.len() + plan.update.len() + plan.delete.len();
+
+    if json_output {
+        print_json(&json!({
+            "create": plan.create.iter().map(|e| &e.comment).collect::<Vec<_>>(),
+            "update": plan
+                .update
+                .iter()
+                .map(|(live, entry)| json!({
+                    "id": live.id,
+                    "comment": entry.comment,
+                    "changes": changed_fields(live, entry)
+                        .into_iter()
+                        .map(|(field, current, desired)| json!({
+                            "field": field,
+                            "current": current,
+                            "desired": desired,
+                        }))
+                        .collect::<Vec<_>>(),
+                }))
+                .collect::<Vec<_>>(),
+            "delete": plan
+                .delete
+                .iter()
+                .map(|t| json!({"id": t.id, "comment": t.comment}))
+                .collect::<Vec<_>>(),
+            "unmanaged": plan.unmanaged.len(),
+        }))?;
+    } else if changes == 0 {
+        println!("No changes: HamAlert matches {}.", file.display());
+    } else {
+        println!(
+            "Plan: {} to create, {} to update, {} to delete\n",
+            plan.create.len(),
+            plan.update.len(),
+            plan.delete.len()
+        );
+        print_plan(&plan);
+        println!(
+            "\nRun 'hamalert-cli apply --file {}{} --no-dry-run' to make these changes.",
+            file.display(),
+            if delete_unmanaged {
+                " --delete-unmanaged"
+            } else {
+                ""
+            }
+        );
+    }
+    if !plan.unmanaged.is_empty() && !json_output {
+        info!(
+            "{} triggers not in {} are unmanaged and not counted as drift",
+            plan.unmanaged.len(),
+            file.display()
+        );
+    }
+
+    if changes > 0 {
+        return Err(CliError::Drift(format!(
+            "{} trigger change(s) pending",
+            changes
+        )));
+    }
+    Ok(())
+}
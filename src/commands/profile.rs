@@ -0,0 +1,876 @@
+//! `profile`: save and switch between sets of triggers.
+
+use crate::api::{fetch_triggers, for_each_concurrent};
+use crate::cli::ProfileCommands;
+use crate::error::CliError;
+use crate::model::{StoredTrigger, triggers_match};
+use crate::output::{
+    GREEN, RED, YELLOW, confirm, format_stored_trigger_for_display, format_trigger_for_display,
+    paint, print_json,
+};
+use crate::storage::backups::{backup_dir, read_backup, write_backup};
+use crate::storage::profiles::{
+    calculate_profile_match, current_profile_path, delete_profile, filter_out_permanent,
+    find_unexpected_triggers, list_profiles, load_current_profile_name, load_permanent_triggers,
+    load_profile, profiles_dir, save_current_profile_name, save_permanent_triggers, save_profile,
+};
+use chrono::Local;
+use hamalert_api::{HamAlertClient, Trigger};
+use inquire::{InquireError, MultiSelect};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+
+/// Run a profile subcommand
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    command: ProfileCommands,
+    json_output: bool,
+    assume_yes: bool,
+) -> Result<(), CliError> {
+    match command {
+        ProfileCommands::List => list(client, json_output).await,
+        ProfileCommands::Show { name } => show(&name, json_output).await,
+        ProfileCommands::Status => status(client, json_output).await,
+        ProfileCommands::Save { name, from_backup } => {
+            save(client, &name, from_backup, assume_yes).await
+        }
+        ProfileCommands::Switch { name, no_dry_run } => switch(client, &name, no_dry_run).await,
+        ProfileCommands::Delete { name } => delete(&name, assume_yes).await,
+        ProfileCommands::SetPermanent { from_backup } => set_permanent(client, from_backup).await,
+        ProfileCommands::ShowPermanent => show_permanent(json_output).await,
+        ProfileCommands::Triage => triage(client, assume_yes).await,
+    }
+}
+
+/// List saved profiles and how closely each matches the current triggers
+async fn list(client: &HamAlertClient, json_output: bool) -> Result<(), CliError> {
+    let profiles = list_profiles()?;
+    let current_profile = load_current_profile_name()?;
+    let permanent = load_permanent_triggers()?;
+
+    if profiles.is_empty() {
+        if json_output {
+            print_json(&json!({
+                "current": current_profile,
+                "profiles": [],
+                "permanent": permanent.len(),
+            }))?;
+            return Ok(());
+        }
+        println!("No profiles saved.");
+        println!("\nUse 'hamalert-cli profile save <name>' to create one.");
+        return Ok(());
+    }
+
+    // Fetch current triggers to calculate match percentages
+    let current_triggers = fetch_triggers(client).await?;
+    let current_stored: Vec<StoredTrigger> = current_triggers
+        .iter()
+        .map(StoredTrigger::from_trigger)
+        .collect();
+
+    // Filter out permanent triggers for matching
+    let current_non_permanent = filter_out_permanent(&current_stored, &permanent);
+
+    if json_output {
+        let rows: Vec<serde_json::Value> = profiles
+            .iter()
+            .map(|name| {
+                let profile = load_profile(name).unwrap_or_default();
+                let (matched, total) = calculate_profile_match(&current_non_permanent, &profile);
+                json!({
+                    "name": name,
+                    "matched": matched,
+                    "total": total,
+                    "percentage": (matched * 100).checked_div(total).unwrap_or(100),
+                    "current": current_profile.as_ref() == Some(name),
+                })
+            })
+            .collect();
+        print_json(&json!({
+            "current": current_profile,
+            "profiles": rows,
+            "permanent": permanent.len(),
+        }))?;
+        return Ok(());
+    }
+
+    println!("Profiles:");
+    let mut best_match: Option<(&str, usize, usize)> = None;
+
+    for profile_name in &profiles {
+        let profile = load_profile(profile_name).unwrap_or_default();
+        let (matched, total) = calculate_profile_match(&current_non_permanent, &profile);
+        let percentage = (matched * 100).checked_div(total).unwrap_or(100);
+
+        let is_current = current_profile.as_ref() == Some(profile_name);
+        let marker = if is_current { "*" } else { " " };
+
+        println!(
+            "  {} {:<15} ({}/{}  {}% match){}",
+            marker,
+            profile_name,
+            matched,
+            total,
+            percentage,
+            if is_current { " <- current" } else { "" }
+        );
+
+        // Track best match
+        if best_match.is_none() || matched > best_match.unwrap().1 {
+            best_match = Some((profile_name, matched, total));
+        }
+    }
+
+    // Warn if recorded profile doesn't match best
+    if let Some(current) = &current_profile
+        && let Some((best_name, best_matched, best_total)) = best_match
+        && best_name != current
+        && best_matched == best_total
+        && best_total > 0
+    {
+        let current_profile_data = load_profile(current).unwrap_or_default();
+        let (current_matched, current_total) =
+            calculate_profile_match(&current_non_permanent, &current_profile_data);
+        if current_matched < current_total {
+            println!(
+                "\n{} Current triggers match '{}' better than recorded '{}'",
+                paint("⚠", YELLOW),
+                best_name,
+                current
+            );
+            println!("Run 'profile status' for details.");
+        }
+    }
+
+    println!("\nPermanent triggers: {}", permanent.len());
+    Ok(())
+}
+
+/// Print the triggers stored in a profile
+async fn show(name: &str, json_output: bool) -> Result<(), CliError> {
+    let profile = load_profile(name)?;
+    if json_output {
+        print_json(&json!({ "name": name, "triggers": profile }))?;
+    } else if profile.is_empty() {
+        println!("Profile '{}' is empty.", name);
+    } else {
+        println!("Profile '{}' ({} triggers):", name, profile.len());
+        for trigger in &profile {
+            println!("  - {}", format_stored_trigger_for_display(trigger));
+        }
+    }
+    Ok(())
+}
+
+/// Compare the current triggers with the active profile
+async fn status(client: &HamAlertClient, json_output: bool) -> Result<(), CliError> {
+    let current_triggers = fetch_triggers(client).await?;
+    let permanent = load_permanent_triggers()?;
+    let current_profile_name = load_current_profile_name()?;
+    let profiles = list_profiles()?;
+
+    let current_stored: Vec<StoredTrigger> = current_triggers
+        .iter()
+        .map(StoredTrigger::from_trigger)
+        .collect();
+
+    // Count permanent matches
+    let permanent_matched = current_stored
+        .iter()
+        .filter(|t| permanent.iter().any(|p| triggers_match(t, p)))
+        .count();
+
+    let current_non_permanent = filter_out_permanent(&current_stored, &permanent);
+
+    if json_output {
+        let rows: Vec<serde_json::Value> = profiles
+            .iter()
+            .map(|name| {
+                let profile = load_profile(name).unwrap_or_default();
+                let (matched, total) = calculate_profile_match(&current_non_permanent, &profile);
+                json!({
+                    "name": name,
+                    "matched": matched,
+                    "total": total,
+                    "percentage": (matched * 100).checked_div(total).unwrap_or(100),
+                })
+            })
+            .collect();
+        let current_profile_data = current_profile_name
+            .as_ref()
+            .and_then(|n| load_profile(n).ok());
+        let unexpected =
+            find_unexpected_triggers(&current_stored, &permanent, current_profile_data.as_deref());
+        print_json(&json!({
+            "triggers": current_triggers.len(),
+            "permanent_matched": permanent_matched,
+            "permanent_total": permanent.len(),
+            "current_profile": current_profile_name,
+            "profiles": rows,
+            "unmatched": unexpected,
+        }))?;
+        return Ok(());
+    }
+
+    println!("Current triggers on HamAlert: {}", current_triggers.len());
+    println!(
+        "Permanent triggers matched: {}/{}",
+        permanent_matched,
+        permanent.len()
+    );
+
+    if profiles.is_empty() {
+        println!("\nNo profiles saved.");
+        return Ok(());
+    }
+
+    println!("\nProfile match analysis:");
+    let mut best_match: Option<(String, usize, usize)> = None;
+
+    for profile_name in &profiles {
+        let profile = load_profile(profile_name).unwrap_or_default();
+        let (matched, total) = calculate_profile_match(&current_non_permanent, &profile);
+        let percentage = (matched * 100).checked_div(total).unwrap_or(100);
+
+        let marker = if matched == total && total > 0 {
+            " <- best match"
+        } else {
+            ""
+        };
+        println!(
+            "  {:<15} {}/{} ({}% match){}",
+            profile_name, matched, total, percentage, marker
+        );
+
+        if best_match.is_none() || matched > best_match.as_ref().unwrap().1 {
+            best_match = Some((profile_name.clone(), matched, total));
+        }
+    }
+
+    // Current profile status
+    println!(
+        "\nRecorded current profile: {}",
+        current_profile_name.as_deref().unwrap_or("(none)")
+    );
+
+    // Check for mismatch
+    if let Some((best_name, best_matched, best_total)) = &best_match {
+        let is_in_sync =
+            current_profile_name.as_ref() == Some(best_name) && *best_matched == *best_total;
+
+        if is_in_sync {
+            println!("Status: {}", paint("✓ In sync", GREEN));
+        } else if current_profile_name.is_some() && best_matched == best_total && *best_total > 0 {
+            println!(
+                "Status: {}",
+                paint(
+                    &format!("⚠ Mismatch - HamAlert matches '{}' better", best_name),
+                    YELLOW
+                )
+            );
+            println!("\nActions:");
+            println!(
+                "  [U]pdate record to '{}' (no changes to HamAlert)",
+                best_name
+            );
+            println!("  [S]ave current triggers as new profile");
+            println!("  [I]gnore");
+
+            print!("\nChoice: ");
+            std::io::Write::flush(&mut std::io::stdout())?;
+            let mut choice = String::new();
+            std::io::stdin().read_line(&mut choice)?;
+
+            match choice.trim().to_lowercase().as_str() {
+                "u" => {
+                    save_current_profile_name(best_name)?;
+                    println!("Updated current profile record to '{}'.", best_name);
+                }
+                "s" => {
+                    print!("Enter profile name: ");
+                    std::io::Write::flush(&mut std::io::stdout())?;
+                    let mut new_name = String::new();
+                    std::io::stdin().read_line(&mut new_name)?;
+                    let new_name = new_name.trim();
+                    if !new_name.is_empty() {
+                        let profile_triggers = filter_out_permanent(&current_stored, &permanent);
+                        save_profile(new_name, &profile_triggers)?;
+                        save_current_profile_name(new_name)?;
+                        println!("Saved and set '{}' as current profile.", new_name);
+                    }
+                }
+                _ => {
+                    println!("No changes made.");
+                }
+            }
+        } else {
+            println!("Status: No exact profile match");
+        }
+    }
+
+    // Show unexpected triggers
+    let current_profile_data = current_profile_name
+        .as_ref()
+        .and_then(|n| load_profile(n).ok());
+    let unexpected =
+        find_unexpected_triggers(&current_stored, &permanent, current_profile_data.as_deref());
+
+    if !unexpected.is_empty() {
+        println!("\nUnmatched triggers ({}):", unexpected.len());
+        for t in &unexpected {
+            println!("  - {}", format_stored_trigger_for_display(t));
+        }
+    }
+    Ok(())
+}
+
+/// Save the current (or backed-up) triggers as a profile
+async fn save(
+    client: &HamAlertClient,
+    name: &str,
+    from_backup: Option<PathBuf>,
+    assume_yes: bool,
+) -> Result<(), CliError> {
+    let permanent = load_permanent_triggers()?;
+
+    let triggers: Vec<StoredTrigger> = match &from_backup {
+        Some(path) => read_backup(path)?
+            .iter()
+            .map(StoredTrigger::from_trigger)
+            .collect(),
+        None => {
+            let fetched = fetch_triggers(client).await?;
+            fetched.iter().map(StoredTrigger::from_trigger).collect()
+        }
+    };
+
+    // Filter out permanent triggers
+    let profile_triggers = filter_out_permanent(&triggers, &permanent);
+
+    // Check if profile already exists
+    let profile_path = profiles_dir()?.join(format!("{}.json", name));
+    if profile_path.exists() {
+        let existing = load_profile(name)?;
+        if existing == profile_triggers {
+            // Identical content - no need to re-save
+            println!(
+                "Profile '{}' already has identical content. No changes needed.",
+                name
+            );
+            // Still set as current profile if saving from live state
+            if from_backup.is_none() {
+                save_current_profile_name(name)?;
+                println!("Set '{}' as current profile.", name);
+            }
+            return Ok(());
+        }
+        // Different content - prompt for confirmation
+        println!("Profile '{}' already exists with different content.", name);
+        println!(
+            "Existing: {} triggers, New: {} triggers",
+            existing.len(),
+            profile_triggers.len()
+        );
+        if !confirm("Overwrite?", assume_yes)? {
+            return Err(CliError::Cancelled("Cancelled.".to_string()));
+        }
+    }
+
+    let _path = save_profile(name, &profile_triggers)?;
+    println!(
+        "Saved {} triggers to profile '{}' (excluded {} permanent)",
+        profile_triggers.len(),
+        name,
+        triggers.len() - profile_triggers.len()
+    );
+
+    // Set as current profile if saving from live state
+    if from_backup.is_none() {
+        save_current_profile_name(name)?;
+        println!("Set '{}' as current profile.", name);
+    }
+    Ok(())
+}
+
+/// Replace the non-permanent triggers with those of a profile
+pub(crate) async fn switch(
+    client: &HamAlertClient,
+    name: &str,
+    no_dry_run: bool,
+) -> Result<(), CliError> {
+    // Track whether we modified the profile during dry-run
+    let mut profile_modified = false;
+
+    // Load all data
+    let target_profile = load_profile(name)?;
+    let permanent = load_permanent_triggers()?;
+    let current_profile_name = load_current_profile_name()?;
+    let current_triggers = fetch_triggers(client).await?;
+
+    let current_stored: Vec<StoredTrigger> = current_triggers
+        .iter()
+        .map(StoredTrigger::from_trigger)
+        .collect();
+
+    // Categorize current triggers
+    let permanent_triggers: Vec<&StoredTrigger> = current_stored
+        .iter()
+        .filter(|t| permanent.iter().any(|p| triggers_match(t, p)))
+        .collect();
+
+    let current_profile_data = current_profile_name
+        .as_ref()
+        .and_then(|n| load_profile(n).ok());
+
+    let unexpected =
+        find_unexpected_triggers(&current_stored, &permanent, current_profile_data.as_deref());
+
+    // Triggers to delete (non-permanent current triggers)
+    let to_delete: Vec<&Trigger> = current_triggers
+        .iter()
+        .filter(|t| {
+            let stored = StoredTrigger::from_trigger(t);
+            !permanent.iter().any(|p| triggers_match(&stored, p))
+        })
+        .collect();
+
+    // Display plan
+    println!(
+        "Current profile: {}",
+        current_profile_name.as_deref().unwrap_or("(none)")
+    );
+    println!("Switching to: {}\n", name);
+
+    println!(
+        "Permanent triggers (unchanged): {}",
+        permanent_triggers.len()
+    );
+    if !permanent_triggers.is_empty() {
+        for t in &permanent_triggers {
+            println!("  - {}", format_stored_trigger_for_display(t));
+        }
+    }
+
+    println!("\nWill DELETE {} triggers:", to_delete.len());
+    for t in &to_delete {
+        println!("  {} {}", paint("-", RED), format_trigger_for_display(t));
+    }
+
+    println!(
+        "\nWill CREATE {} triggers from '{}':",
+        target_profile.len(),
+        name
+    );
+    for t in &target_profile {
+        println!(
+            "  {} {}",
+            paint("+", GREEN),
+            format_stored_trigger_for_display(t)
+        );
+    }
+
+    // Handle unexpected triggers
+    if !unexpected.is_empty() {
+        println!(
+            "\n{} Found {} unexpected triggers (not permanent, not in current profile):",
+            paint("⚠", YELLOW),
+            unexpected.len()
+        );
+        for t in &unexpected {
+            println!("  - {}", format_stored_trigger_for_display(t));
+        }
+
+        if !no_dry_run {
+            println!("\n  [D]elete them");
+            if let Some(ref current_name) = current_profile_name {
+                println!("  [S]ave to '{}' profile first", current_name);
+            }
+            println!("  [C]ancel");
+
+            print!("\nChoice: ");
+            std::io::Write::flush(&mut std::io::stdout())?;
+            let mut choice = String::new();
+            std::io::stdin().read_line(&mut choice)?;
+
+            match choice.trim().to_lowercase().as_str() {
+                "d" => {
+                    // Continue with deletion
+                }
+                "s" => {
+                    if let Some(ref current_name) = current_profile_name {
+                        // Update current profile to include unexpected triggers
+                        let mut updated_profile = current_profile_data.unwrap_or_default();
+                        for t in &unexpected {
+                            if !updated_profile.iter().any(|p| triggers_match(p, t)) {
+                                updated_profile.push(t.clone());
+                            }
+                        }
+                        save_profile(current_name, &updated_profile)?;
+                        println!(
+                            "Updated '{}' profile with {} additional triggers.",
+                            current_name,
+                            unexpected.len()
+                        );
+                        profile_modified = true;
+                    }
+                }
+                _ => {
+                    return Err(CliError::Cancelled("Cancelled.".to_string()));
+                }
+            }
+        }
+    }
+
+    if !no_dry_run {
+        if profile_modified {
+            println!("\nNote: Profile was updated with unexpected triggers.");
+        }
+        println!("DRY RUN - No trigger changes made on HamAlert.");
+        println!("Run with --no-dry-run to execute the switch.");
+        return Ok(());
+    }
+
+    // Execute the switch
+    // 1. Create backup
+    let backup_path = backup_dir()?.join(format!(
+        "hamalert-backup-before-switch-{}.json",
+        Local::now().format("%Y-%m-%d-%H%M%S")
+    ));
+    write_backup(&backup_path, &current_triggers)?;
+    println!(
+        "\nBacked up {} triggers to {}",
+        current_triggers.len(),
+        backup_path.display()
+    );
+
+    // 2. Delete non-permanent triggers
+    for_each_concurrent(&to_delete, |trigger| async move {
+        Ok(client.delete_trigger(&trigger.id).await?)
+    })
+    .await?;
+    println!("Deleted {} triggers.", to_delete.len());
+
+    // 3. Create triggers from target profile
+    for_each_concurrent(&target_profile, |stored| async move {
+        // Convert StoredTrigger to Trigger for API
+        let trigger = Trigger {
+            id: String::new(),
+            user_id: None,
+            conditions: stored.conditions.clone(),
+            actions: stored.actions.clone(),
+            comment: stored.comment.clone(),
+            match_count: None,
+            disabled: None,
+            options: stored.options.clone(),
+        };
+        client.create_trigger(&trigger).await?;
+        Ok(())
+    })
+    .await?;
+    println!("Created {} triggers from '{}'.", target_profile.len(), name);
+
+    // 4. Update current profile
+    save_current_profile_name(name)?;
+    println!("\nSwitched to profile '{}'.", name);
+    Ok(())
+}
+
+/// Delete a saved profile
+async fn delete(name: &str, assume_yes: bool) -> Result<(), CliError> {
+    // Check if it's the current profile
+    let current = load_current_profile_name()?;
+    if current.as_deref() == Some(name) {
+        println!(
+            "{} '{}' is the current profile.",
+            paint("Warning:", YELLOW),
+            name
+        );
+        if !confirm("Delete anyway?", assume_yes)? {
+            return Err(CliError::Cancelled("Cancelled.".to_string()));
+        }
+        // Clear current profile
+        let path = current_profile_path()?;
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+    }
+
+    delete_profile(name)?;
+    println!("Deleted profile '{}'.", name);
+    Ok(())
+}
+
+/// Choose the triggers kept across profile switches
+async fn set_permanent(
+    client: &HamAlertClient,
+    from_backup: Option<PathBuf>,
+) -> Result<(), CliError> {
+    // Load triggers from backup file or fetch from HamAlert
+    let triggers: Vec<Trigger> = match from_backup {
+        Some(path) => read_backup(&path)?,
+        None => fetch_triggers(client).await?,
+    };
+
+    if triggers.is_empty() {
+        println!("No triggers found.");
+        return Ok(());
+    }
+
+    // Load existing permanent triggers
+    let existing_permanent = load_permanent_triggers()?;
+
+    // Convert to StoredTrigger for comparison
+    let stored_triggers: Vec<StoredTrigger> =
+        triggers.iter().map(StoredTrigger::from_trigger).collect();
+
+    // Build display items
+    let display_items: Vec<String> = triggers.iter().map(format_trigger_for_display).collect();
+
+    // Pre-select triggers that are already permanent
+    let default_selections: Vec<usize> = stored_triggers
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| existing_permanent.iter().any(|p| triggers_match(t, p)))
+        .map(|(i, _)| i)
+        .collect();
+
+    println!("Select triggers to mark as PERMANENT (always active across all profiles):\n");
+
+    let selected_result = MultiSelect::new(
+        "Permanent triggers (checked = permanent):",
+        display_items.clone(),
+    )
+    .with_default(&default_selections)
+    .with_vim_mode(true)
+    .with_page_size(15)
+    .with_help_message("Space=toggle, j/k=navigate, Enter=confirm, Esc=cancel")
+    .prompt();
+
+    let selected_displays: Vec<String> = match selected_result {
+        Ok(selected) => selected,
+        Err(InquireError::OperationCanceled) | Err(InquireError::OperationInterrupted) => {
+            return Err(CliError::Cancelled("Operation cancelled.".to_string()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // Find which triggers were selected
+    let selected_set: std::collections::HashSet<&str> =
+        selected_displays.iter().map(|s| s.as_str()).collect();
+    let new_permanent: Vec<StoredTrigger> = triggers
+        .iter()
+        .filter(|t| selected_set.contains(format_trigger_for_display(t).as_str()))
+        .map(StoredTrigger::from_trigger)
+        .collect();
+
+    save_permanent_triggers(&new_permanent)?;
+    println!("\nSaved {} permanent triggers.", new_permanent.len());
+    Ok(())
+}
+
+/// What `profile triage` does with a trigger
+#[derive(Debug, PartialEq)]
+enum Triage {
+    Profile(String),
+    Permanent,
+    Delete,
+    Skip,
+}
+
+/// Live triggers that are in none of the `profiles` and not permanent
+fn orphaned_triggers(
+    triggers: &[Trigger],
+    permanent: &[StoredTrigger],
+    profiles: &[StoredTrigger],
+) -> Vec<Trigger> {
+    let stored: Vec<StoredTrigger> = triggers.iter().map(StoredTrigger::from_trigger).collect();
+    let unexpected = find_unexpected_triggers(&stored, permanent, Some(profiles));
+    triggers
+        .iter()
+        .zip(&stored)
+        .filter(|(_, s)| unexpected.contains(s))
+        .map(|(t, _)| t.clone())
+        .collect()
+}
+
+/// Ask what to do with one trigger; `None` stops the triage
+fn ask_triage(profiles: &[String]) -> Result<Option<Triage>, CliError> {
+    const NEW_PROFILE: &str = "Add to a new profile...";
+    const PERMANENT: &str = "Mark permanent";
+    const DELETE: &str = "Delete from HamAlert";
+    const SKIP: &str = "Skip";
+    const STOP: &str = "Stop here (keep the choices so far)";
+
+    let mut options: Vec<String> = profiles
+        .iter()
+        .map(|p| format!("Add to profile '{}'", p))
+        .collect();
+    options.extend([NEW_PROFILE, PERMANENT, DELETE, SKIP, STOP].map(str::to_string));
+    let choice = match inquire::Select::new("What should happen to it?", options).raw_prompt() {
+        Ok(choice) => choice,
+        Err(InquireError::OperationCanceled) | Err(InquireError::OperationInterrupted) => {
+            return Err(CliError::Cancelled("Triage cancelled.".to_string()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(profile) = profiles.get(choice.index) {
+        return Ok(Some(Triage::Profile(profile.clone())));
+    }
+    Ok(match choice.value.as_str() {
+        NEW_PROFILE => {
+            let name = inquire::Text::new("Profile name:").prompt()?;
+            match name.trim() {
+                "" => Some(Triage::Skip),
+                name => Some(Triage::Profile(name.to_string())),
+            }
+        }
+        PERMANENT => Some(Triage::Permanent),
+        DELETE => Some(Triage::Delete),
+        SKIP => Some(Triage::Skip),
+        _ => None,
+    })
+}
+
+/// Walk through the triggers that are in no profile and not permanent,
+/// assigning each to a profile, marking it permanent or deleting it
+async fn triage(client: &HamAlertClient, assume_yes: bool) -> Result<(), CliError> {
+    let triggers = fetch_triggers(client).await?;
+    let mut permanent = load_permanent_triggers()?;
+    let mut profiles = BTreeMap::new();
+    for name in list_profiles()? {
+        let profile = load_profile(&name)?;
+        profiles.insert(name, profile);
+    }
+    let in_profiles: Vec<StoredTrigger> = profiles.values().flatten().cloned().collect();
+    let orphans = orphaned_triggers(&triggers, &permanent, &in_profiles);
+    if orphans.is_empty() {
+        println!("Every trigger is in a profile or permanent.");
+        return Ok(());
+    }
+
+    println!(
+        "{} triggers are in no profile and not permanent.\n",
+        orphans.len()
+    );
+    let mut decisions = Vec::new();
+    for (i, trigger) in orphans.iter().enumerate() {
+        println!(
+            "[{}/{}] {}",
+            i + 1,
+            orphans.len(),
+            format_trigger_for_display(trigger)
+        );
+        let names: Vec<String> = profiles.keys().cloned().collect();
+        match ask_triage(&names)? {
+            Some(choice) => {
+                if let Triage::Profile(name) = &choice {
+                    profiles.entry(name.clone()).or_default();
+                }
+                decisions.push((trigger, choice));
+            }
+            None => break,
+        }
+    }
+
+    let mut changed_profiles = BTreeSet::new();
+    let mut marked = 0;
+    let mut to_delete = Vec::new();
+    for (trigger, choice) in decisions {
+        match choice {
+            Triage::Profile(name) => {
+                profiles
+                    .entry(name.clone())
+                    .or_default()
+                    .push(StoredTrigger::from_trigger(trigger));
+                changed_profiles.insert(name);
+            }
+            Triage::Permanent => {
+                permanent.push(StoredTrigger::from_trigger(trigger));
+                marked += 1;
+            }
+            Triage::Delete => to_delete.push(trigger),
+            Triage::Skip => {}
+        }
+    }
+
+    for name in &changed_profiles {
+        save_profile(name, &profiles[name])?;
+    }
+    if marked > 0 {
+        save_permanent_triggers(&permanent)?;
+    }
+    println!(
+        "\nUpdated {} profiles and marked {} triggers permanent.",
+        changed_profiles.len(),
+        marked
+    );
+
+    if to_delete.is_empty() {
+        return Ok(());
+    }
+    let question = format!("Delete {} triggers from HamAlert?", to_delete.len());
+    if !confirm(&question, assume_yes)? {
+        return Err(CliError::Cancelled("Deletion cancelled.".to_string()));
+    }
+    let backup_path = backup_dir()?.join(format!(
+        "hamalert-backup-before-triage-{}.json",
+        Local::now().format("%Y-%m-%d-%H%M%S")
+    ));
+    write_backup(&backup_path, &triggers)?;
+    println!(
+        "Backed up {} triggers to {}",
+        triggers.len(),
+        backup_path.display()
+    );
+    for_each_concurrent(&to_delete, |trigger| async move {
+        Ok(client.delete_trigger(&trigger.id).await?)
+    })
+    .await?;
+    println!("Deleted {} triggers.", to_delete.len());
+    Ok(())
+}
+
+/// Print the permanent triggers
+async fn show_permanent(json_output: bool) -> Result<(), CliError> {
+    let permanent = load_permanent_triggers()?;
+    if json_output {
+        print_json(&json!({ "triggers": permanent }))?;
+    } else if permanent.is_empty() {
+        println!("No permanent triggers set.");
+        println!("\nUse 'hamalert-cli profile set-permanent' to select permanent triggers.");
+    } else {
+        println!("Permanent triggers ({}):", permanent.len());
+        for trigger in &permanent {
+            println!("  - {}", format_stored_trigger_for_display(trigger));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::test_trigger;
+
+    fn trigger(id: &str, comment: &str) -> Trigger {
+        test_trigger(id, comment, json!({"band": "20m"}))
+    }
+
+    #[test]
+    fn test_orphaned_triggers() {
+        let live = vec![
+            trigger("1", "Club"),
+            trigger("2", "POTA"),
+            trigger("3", "Test"),
+        ];
+        let permanent = vec![StoredTrigger::from_trigger(&live[0])];
+        let profiles = vec![StoredTrigger::from_trigger(&live[1])];
+        let orphans = orphaned_triggers(&live, &permanent, &profiles);
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].id, "3");
+        assert!(orphaned_triggers(&live[..2], &permanent, &profiles).is_empty());
+    }
+}
@@ -0,0 +1,181 @@
+//! `prune-expired`: delete triggers whose expiry date has passed.
+
+use crate::api::{fetch_triggers, for_each_concurrent};
+use crate::error::CliError;
+use crate::output::{print_json, render_table, terminal_width};
+use crate::storage::backups::{backup_dir, write_backup};
+use crate::storage::state::{State, load_state, save_state};
+use chrono::{DateTime, Local};
+use hamalert_api::{HamAlertClient, Trigger};
+use serde_json::json;
+
+/// A managed trigger past its expiry date
+pub(crate) struct ExpiredTrigger {
+    pub(crate) name: String,
+    pub(crate) expires: String,
+    pub(crate) trigger: Trigger,
+}
+
+/// Managed triggers that have expired at `now` and still exist
+fn find_expired(state: &State, live: &[Trigger], now: &DateTime<Local>) -> Vec<ExpiredTrigger> {
+    state
+        .triggers
+        .iter()
+        .filter(|(_, managed)| managed.is_expired(now))
+        .filter_map(|(name, managed)| {
+            let trigger = live.iter().find(|t| t.id == managed.id)?;
+            Some(ExpiredTrigger {
+                name: name.clone(),
+                expires: managed.expires.clone().unwrap_or_default(),
+                trigger: trigger.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Delete expired triggers, backing them up first, and forget their names.
+/// With `dry_run` nothing is changed. Returns the expired triggers.
+pub(crate) async fn prune_expired(
+    client: &HamAlertClient,
+    dry_run: bool,
+) -> Result<Vec<ExpiredTrigger>, CliError> {
+    let mut state = load_state()?;
+    let triggers = fetch_triggers(client).await?;
+    let expired = find_expired(&state, &triggers, &Local::now());
+    if dry_run || expired.is_empty() {
+        return Ok(expired);
+    }
+
+    let backup_path = backup_dir()?.join(format!(
+        "hamalert-backup-before-prune-expired-{}.json",
+        Local::now().format("%Y-%m-%d-%H%M%S")
+    ));
+    let deleted: Vec<&Trigger> = expired.iter().map(|e| &e.trigger).collect();
+    write_backup(&backup_path, &deleted)?;
+    info!(
+        "Backed up {} expired triggers to {}",
+        deleted.len(),
+        backup_path.display()
+    );
+
+    for_each_concurrent(&deleted, |trigger| async move {
+        client.delete_trigger(&trigger.id).await?;
+        Ok(())
+    })
+    .await?;
+    for entry in &expired {
+        state.triggers.remove(&entry.name);
+    }
+    save_state(&state)?;
+    tracing::info!("Pruned {} expired triggers", expired.len());
+    Ok(expired)
+}
+
+/// Prune expired triggers and report what was (or would be) deleted
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    dry_run: bool,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let expired = prune_expired(client, dry_run).await?;
+    let woken = super::snooze::wake_due(client, dry_run).await?;
+    let unmuted = super::mute::unmute_due(client, dry_run).await?;
+
+    if json_output {
+        let entries: Vec<serde_json::Value> = expired
+            .iter()
+            .map(|e| {
+                json!({
+                    "name": e.name,
+                    "id": e.trigger.id,
+                    "comment": e.trigger.comment,
+                    "expires": e.expires,
+                    "deleted": !dry_run,
+                })
+            })
+            .collect();
+        return print_json(&json!(entries));
+    }
+
+    for name in &woken {
+        if dry_run {
+            println!("Would enable snoozed trigger '{}' again", name);
+        } else {
+            println!("Enabled snoozed trigger '{}' again", name);
+        }
+    }
+    for call in &unmuted {
+        if dry_run {
+            println!("Would unmute {}", call);
+        } else {
+            println!("Unmuted {}", call);
+        }
+    }
+    if expired.is_empty() {
+        println!("No expired triggers.");
+        return Ok(());
+    }
+    let rows: Vec<Vec<String>> = expired
+        .iter()
+        .map(|e| vec![e.name.clone(), e.trigger.comment.clone(), e.expires.clone()])
+        .collect();
+    println!(
+        "{}",
+        render_table(&["NAME", "COMMENT", "EXPIRED"], &rows, terminal_width())
+    );
+    if dry_run {
+        println!(
+            "\n[DRY RUN] {} expired trigger(s) would be deleted.",
+            expired.len()
+        );
+    } else {
+        println!("\nDeleted {} expired trigger(s).", expired.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::state::ManagedTrigger;
+
+    #[test]
+    fn test_find_expired() {
+        let live: Vec<Trigger> = ["1", "2", "3"]
+            .iter()
+            .map(|id| {
+                serde_json::from_value(json!({
+                    "_id": id,
+                    "conditions": {"callsign": "W1AW"},
+                    "actions": ["app"],
+                    "comment": format!("Trigger {}", id),
+                }))
+                .unwrap()
+            })
+            .collect();
+        let mut state = State::default();
+        for (name, id, expires) in [
+            ("old", "1", Some("2026-01-31")),
+            ("today", "2", Some("2026-02-01")),
+            ("forever", "3", None),
+            ("gone", "9", Some("2025-12-31")),
+        ] {
+            let mut managed = ManagedTrigger::new(
+                "add-trigger".to_string(),
+                expires.map(str::to_string),
+                vec![],
+            );
+            managed.id = id.to_string();
+            state.record(name, managed);
+        }
+
+        use chrono::TimeZone;
+
+        let now = Local.with_ymd_and_hms(2026, 2, 1, 12, 0, 0).unwrap();
+        let expired = find_expired(&state, &live, &now);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].name, "old");
+        assert_eq!(expired[0].trigger.comment, "Trigger 1");
+        assert_eq!(expired[0].expires, "2026-01-31");
+    }
+}
@@ -0,0 +1,191 @@
+//! `report`: what the trigger set covers, to find gaps.
+
+use crate::api::fetch_triggers;
+use crate::cli::{Mode, ReportCommands};
+use crate::error::CliError;
+use crate::model::BANDS;
+use crate::output::{RED, paint, print_json, render_table, terminal_width};
+use clap::ValueEnum;
+use hamalert_api::{HamAlertClient, Trigger};
+use serde_json::json;
+
+/// Lowercased entries of a comma-separated or list condition
+fn condition_items(trigger: &Trigger, key: &str) -> Vec<String> {
+    let items: Vec<String> = match trigger.conditions.get(key) {
+        Some(serde_json::Value::String(s)) => s.split(',').map(str::to_string).collect(),
+        Some(serde_json::Value::Array(values)) => values
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    };
+    items
+        .iter()
+        .map(|item| item.trim().to_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// How many enabled triggers cover each band and mode. A trigger without a
+/// band or mode condition covers every band or mode.
+#[derive(Debug, PartialEq)]
+struct Matrix {
+    bands: Vec<String>,
+    modes: Vec<String>,
+    /// `counts[band][mode]`, indexed like `bands` and `modes`
+    counts: Vec<Vec<usize>>,
+}
+
+impl Matrix {
+    /// Bands no trigger covers in any mode
+    fn uncovered_bands(&self) -> Vec<&str> {
+        self.bands
+            .iter()
+            .zip(&self.counts)
+            .filter(|(_, row)| row.iter().all(|&count| count == 0))
+            .map(|(band, _)| band.as_str())
+            .collect()
+    }
+}
+
+fn coverage_matrix(triggers: &[Trigger]) -> Matrix {
+    let enabled: Vec<&Trigger> = triggers
+        .iter()
+        .filter(|t| !t.disabled.unwrap_or(false))
+        .collect();
+
+    // Every band and the modes add-trigger offers, plus any others in use
+    let mut bands: Vec<String> = BANDS.iter().map(|(_, _, b)| b.to_string()).collect();
+    let mut modes: Vec<String> = Mode::value_variants()
+        .iter()
+        .map(|m| m.as_str().to_string())
+        .collect();
+    for trigger in &enabled {
+        for band in condition_items(trigger, "band") {
+            if !bands.contains(&band) {
+                bands.push(band);
+            }
+        }
+        for mode in condition_items(trigger, "mode") {
+            if !modes.contains(&mode) {
+                modes.push(mode);
+            }
+        }
+    }
+
+    let mut counts = vec![vec![0; modes.len()]; bands.len()];
+    for trigger in &enabled {
+        let trigger_bands = condition_items(trigger, "band");
+        let trigger_modes = condition_items(trigger, "mode");
+        for (b, band) in bands.iter().enumerate() {
+            if !trigger_bands.is_empty() && !trigger_bands.contains(band) {
+                continue;
+            }
+            for (m, mode) in modes.iter().enumerate() {
+                if trigger_modes.is_empty() || trigger_modes.contains(mode) {
+                    counts[b][m] += 1;
+                }
+            }
+        }
+    }
+    Matrix {
+        bands,
+        modes,
+        counts,
+    }
+}
+
+/// Run a report subcommand
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    command: ReportCommands,
+    json_output: bool,
+) -> Result<(), CliError> {
+    match command {
+        ReportCommands::Matrix => {
+            let matrix = coverage_matrix(&fetch_triggers(client).await?);
+            let uncovered = matrix.uncovered_bands();
+            if json_output {
+                let bands: Vec<serde_json::Value> = matrix
+                    .bands
+                    .iter()
+                    .zip(&matrix.counts)
+                    .map(|(band, row)| {
+                        let counts: serde_json::Map<String, serde_json::Value> = matrix
+                            .modes
+                            .iter()
+                            .zip(row)
+                            .map(|(mode, count)| (mode.clone(), json!(count)))
+                            .collect();
+                        json!({"band": band, "counts": counts})
+                    })
+                    .collect();
+                return print_json(&json!({
+                    "modes": matrix.modes,
+                    "bands": bands,
+                    "uncovered": uncovered,
+                }));
+            }
+
+            let mut headers = vec!["BAND".to_string()];
+            headers.extend(matrix.modes.iter().map(|m| m.to_uppercase()));
+            let headers: Vec<&str> = headers.iter().map(String::as_str).collect();
+            let rows: Vec<Vec<String>> = matrix
+                .bands
+                .iter()
+                .zip(&matrix.counts)
+                .map(|(band, row)| {
+                    let mut cells = vec![band.clone()];
+                    cells.extend(row.iter().map(|&count| match count {
+                        0 => paint("0", RED),
+                        n => n.to_string(),
+                    }));
+                    cells
+                })
+                .collect();
+            println!("{}", render_table(&headers, &rows, terminal_width()));
+            if !uncovered.is_empty() {
+                println!("\nNo coverage at all on: {}", uncovered.join(", "));
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::test_trigger;
+
+    fn trigger(conditions: serde_json::Value, disabled: bool) -> Trigger {
+        Trigger {
+            disabled: Some(disabled),
+            ..test_trigger("1", "Test", conditions)
+        }
+    }
+
+    #[test]
+    fn test_coverage_matrix() {
+        let matrix = coverage_matrix(&[
+            trigger(json!({"band": "20m,40m", "mode": "cw"}), false),
+            trigger(json!({"mode": ["CW", "rtty"]}), false),
+            trigger(json!({"band": "17m"}), true),
+        ]);
+        assert_eq!(matrix.modes, vec!["cw", "ft8", "ssb", "rtty"]);
+        let count = |band: &str, mode: &str| {
+            let b = matrix.bands.iter().position(|x| x == band).unwrap();
+            let m = matrix.modes.iter().position(|x| x == mode).unwrap();
+            matrix.counts[b][m]
+        };
+        assert_eq!(count("20m", "cw"), 2);
+        assert_eq!(count("17m", "cw"), 1);
+        assert_eq!(count("20m", "ssb"), 0);
+        assert_eq!(count("17m", "ssb"), 0);
+        assert!(matrix.uncovered_bands().is_empty());
+
+        let matrix = coverage_matrix(&[trigger(json!({"band": "20m"}), false)]);
+        assert!(matrix.uncovered_bands().contains(&"17m"));
+        assert!(!matrix.uncovered_bands().contains(&"20m"));
+    }
+}
@@ -0,0 +1,389 @@
+//! `serve telnet` and `serve http`: relay spots to local clients and destinations.
+
+use crate::api::fetch_triggers;
+use crate::cli::{PipelineOptions, ServeCommands};
+use crate::config::Config;
+use crate::error::CliError;
+use crate::feed::{
+    TELNET_RECONNECT_DELAY_SECS, connect_upstream, decode_telnet_line, relay_upstream_spots,
+};
+use crate::model::{parse_spot_line, spot_from_webhook_params};
+use crate::pipeline::{SharedMetrics, SpotPipeline, option_destinations};
+use crate::storage::state::load_state;
+use hamalert_api::HamAlertClient;
+use std::collections::HashMap;
+
+/// Minimal HTTP request as needed by the metrics and webhook servers
+pub(crate) struct HttpRequest {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) query: String,
+    pub(crate) body: String,
+}
+
+/// Read a single HTTP/1.1 request (headers plus Content-Length body) from a socket
+pub(crate) async fn read_http_request(
+    socket: &mut tokio::net::TcpStream,
+) -> std::io::Result<HttpRequest> {
+    use tokio::io::AsyncReadExt;
+
+    const MAX_REQUEST_SIZE: usize = 64 * 1024;
+
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    let header_end = loop {
+        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        let n = socket.read(&mut buf).await?;
+        if n == 0 || data.len() > MAX_REQUEST_SIZE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "incomplete HTTP request",
+            ));
+        }
+        data.extend_from_slice(&buf[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let content_length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0)
+        .min(MAX_REQUEST_SIZE);
+
+    while data.len() < header_end + content_length {
+        let n = socket.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+    let body_end = data.len().min(header_end + content_length);
+
+    Ok(HttpRequest {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        body: String::from_utf8_lossy(&data[header_end..body_end]).to_string(),
+    })
+}
+
+/// Write a plain-text HTTP response and close the connection
+pub(crate) async fn write_http_response(
+    socket: &mut tokio::net::TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    socket.write_all(response.as_bytes()).await
+}
+
+/// Serve the Prometheus /metrics endpoint
+pub(crate) async fn serve_metrics(listener: tokio::net::TcpListener, metrics: SharedMetrics) {
+    loop {
+        let Ok((mut socket, _)) = listener.accept().await else {
+            continue;
+        };
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            let Ok(request) = read_http_request(&mut socket).await else {
+                return;
+            };
+            let _ = if request.method == "GET" && request.path == "/metrics" {
+                let body = metrics.lock().unwrap().render();
+                write_http_response(&mut socket, "200 OK", "text/plain; version=0.0.4", &body).await
+            } else {
+                write_http_response(&mut socket, "404 Not Found", "text/plain", "").await
+            };
+        });
+    }
+}
+
+/// Serve a single DX-cluster client: ask for a callsign, then stream spots
+pub(crate) async fn handle_cluster_client(
+    socket: tokio::net::TcpStream,
+    mut spots: tokio::sync::broadcast::Receiver<String>,
+) -> std::io::Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::sync::broadcast::error::RecvError;
+
+    let (reader, mut writer) = socket.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut raw = Vec::new();
+
+    writer.write_all(b"Please enter your call: ").await?;
+    if reader.read_until(b'\n', &mut raw).await? == 0 {
+        return Ok(());
+    }
+    let call = decode_telnet_line(&raw).to_uppercase();
+    let call = if call.is_empty() {
+        "NOCALL".to_string()
+    } else {
+        call
+    };
+    writer
+        .write_all(
+            format!(
+                "Hello {}, this is hamalert-cli relaying your HamAlert spots.\r\n{} de HAMALERT >\r\n",
+                call, call
+            )
+            .as_bytes(),
+        )
+        .await?;
+
+    loop {
+        raw.clear();
+        tokio::select! {
+            spot = spots.recv() => match spot {
+                Ok(line) => writer.write_all(format!("{}\r\n", line).as_bytes()).await?,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            read = reader.read_until(b'\n', &mut raw) => {
+                if read? == 0 {
+                    return Ok(());
+                }
+                // Cluster commands other than disconnecting are not supported
+                let command = decode_telnet_line(&raw).to_lowercase();
+                if matches!(command.as_str(), "bye" | "quit" | "exit") {
+                    writer.write_all(b"73!\r\n").await?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// The spot pipeline for `config`, with the triggers of tag routes looked up
+async fn build_pipeline(
+    client: &HamAlertClient,
+    config: &Config,
+    options: PipelineOptions,
+) -> Result<SpotPipeline, CliError> {
+    let mut pipeline =
+        SpotPipeline::from_config(config, option_destinations(&options), options.format)
+            .map_err(CliError::Validation)?;
+    if options.no_dedupe {
+        pipeline.dedupe_window = None;
+    }
+    pipeline.highlights = options.highlight;
+    if pipeline.routes_by_tag() {
+        pipeline.tag_routes(&fetch_triggers(client).await?, &load_state()?);
+    }
+    Ok(pipeline)
+}
+
+/// Run a local DX-cluster telnet server fed by the HamAlert telnet feed
+pub(crate) async fn serve_telnet(
+    client: &HamAlertClient,
+    config: &Config,
+    bind: &str,
+    port: u16,
+    metrics_port: Option<u16>,
+    options: PipelineOptions,
+) -> Result<(), CliError> {
+    let listener = tokio::net::TcpListener::bind((bind, port))
+        .await
+        .map_err(|e| CliError::io(format!("Failed to listen on {}:{}", bind, port), e))?;
+    tracing::info!("Serving HamAlert spots on telnet://{}:{}", bind, port);
+
+    let pipeline = std::sync::Arc::new(build_pipeline(client, config, options).await?);
+    let metrics = pipeline.metrics.clone();
+    if let Some(metrics_port) = metrics_port {
+        let metrics_listener = tokio::net::TcpListener::bind((bind, metrics_port))
+            .await
+            .map_err(|e| {
+                CliError::io(format!("Failed to listen on {}:{}", bind, metrics_port), e)
+            })?;
+        tracing::info!(
+            "Serving metrics on http://{}:{}/metrics",
+            bind,
+            metrics_port
+        );
+        tokio::spawn(serve_metrics(metrics_listener, metrics.clone()));
+    }
+
+    let (spots, mut dispatched) = tokio::sync::broadcast::channel::<String>(256);
+
+    tokio::spawn(async move {
+        use tokio::sync::broadcast::error::RecvError;
+        loop {
+            match dispatched.recv().await {
+                Ok(line) => {
+                    if let Some(spot) = parse_spot_line(&line) {
+                        pipeline.dispatch(&spot).await;
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    let client_spots = spots.clone();
+    let client_metrics = metrics.clone();
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((socket, addr)) => {
+                    tracing::info!(%addr, "client connected");
+                    let rx = client_spots.subscribe();
+                    let metrics = client_metrics.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_cluster_client(socket, rx).await {
+                            tracing::warn!(%addr, error = %e, "client error");
+                            metrics.lock().unwrap().forwarding_errors += 1;
+                        }
+                        tracing::info!(%addr, "client disconnected");
+                    });
+                }
+                Err(e) => tracing::warn!(error = %e, "failed to accept connection"),
+            }
+        }
+    });
+
+    let login = config.telnet_login();
+    loop {
+        match connect_upstream(&login).await {
+            Ok(mut feed) => {
+                tracing::info!(
+                    "Connected to HamAlert telnet feed at {}:{}",
+                    login.host,
+                    login.port
+                );
+                let relayed = relay_upstream_spots(&mut feed, |line| {
+                    // Sending only fails when nothing is subscribed, which is fine
+                    let _ = spots.send(line);
+                })
+                .await;
+                if let Err(e) = relayed {
+                    tracing::error!(error = %e, "HamAlert telnet error");
+                }
+            }
+            Err(e) => tracing::error!(error = %e, "HamAlert telnet error"),
+        }
+        tracing::info!(
+            delay_secs = TELNET_RECONNECT_DELAY_SECS,
+            "HamAlert telnet connection closed, reconnecting"
+        );
+        tokio::time::sleep(std::time::Duration::from_secs(TELNET_RECONNECT_DELAY_SECS)).await;
+        metrics.lock().unwrap().reconnects += 1;
+    }
+}
+
+/// Receive HamAlert "url" action notifications and forward them as spots
+pub(crate) async fn serve_http(
+    client: &HamAlertClient,
+    config: &Config,
+    bind: &str,
+    port: u16,
+    options: PipelineOptions,
+) -> Result<(), CliError> {
+    let listener = tokio::net::TcpListener::bind((bind, port))
+        .await
+        .map_err(|e| CliError::io(format!("Failed to listen on {}:{}", bind, port), e))?;
+    tracing::info!(
+        "Receiving HamAlert url action notifications on http://{}:{}",
+        bind,
+        port
+    );
+
+    let pipeline = std::sync::Arc::new(build_pipeline(client, config, options).await?);
+    loop {
+        let (mut socket, addr) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to accept connection");
+                continue;
+            }
+        };
+        let pipeline = pipeline.clone();
+        tokio::spawn(async move {
+            let request = match read_http_request(&mut socket).await {
+                Ok(request) => request,
+                Err(e) => {
+                    tracing::warn!(%addr, error = %e, "bad request");
+                    return;
+                }
+            };
+
+            if request.method == "GET" && request.path == "/metrics" {
+                let body = pipeline.metrics.lock().unwrap().render();
+                let _ =
+                    write_http_response(&mut socket, "200 OK", "text/plain; version=0.0.4", &body)
+                        .await;
+                return;
+            }
+
+            // HamAlert sends the notification as a form-encoded POST body or as query parameters
+            let params: HashMap<String, String> = form_urlencoded::parse(request.query.as_bytes())
+                .chain(form_urlencoded::parse(request.body.as_bytes()))
+                .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                .collect();
+
+            match spot_from_webhook_params(&params) {
+                Some(spot) => {
+                    // Acknowledge first so slow destinations don't hold up HamAlert
+                    let _ = write_http_response(&mut socket, "200 OK", "text/plain", "OK").await;
+                    pipeline.dispatch(&spot).await;
+                }
+                None => {
+                    tracing::warn!(
+                        %addr,
+                        "ignoring notification without callsign/frequency"
+                    );
+                    let _ = write_http_response(
+                        &mut socket,
+                        "400 Bad Request",
+                        "text/plain",
+                        "missing callsign or frequency",
+                    )
+                    .await;
+                }
+            }
+        });
+    }
+}
+
+/// Run a serve subcommand
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    config: &Config,
+    command: ServeCommands,
+) -> Result<(), CliError> {
+    match command {
+        ServeCommands::Telnet {
+            port,
+            bind,
+            metrics_port,
+            pipeline,
+        } => {
+            serve_telnet(client, config, &bind, port, metrics_port, pipeline).await?;
+        }
+        ServeCommands::Http {
+            port,
+            bind,
+            pipeline,
+        } => {
+            serve_http(client, config, &bind, port, pipeline).await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,429 @@
+//! `share` and `import-url`: hand a trigger set to someone else as a link.
+
+use crate::api::{NewTrigger, fetch_triggers, map_concurrent, remember_new_triggers};
+use crate::cli::TriggerSelection;
+use crate::config::Config;
+use crate::error::CliError;
+use crate::model::{StoredTrigger, triggers_match};
+use crate::output::{confirm, format_stored_trigger_for_display, print_created};
+use crate::storage::state::ManagedTrigger;
+use hamalert_api::{HamAlertClient, SendLogged, Trigger, is_redacted_key};
+use reqwest::header::{ACCEPT, CONTENT_TYPE, LOCATION, USER_AGENT};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+const GIST_API_URL: &str = "https://api.github.com/gists";
+/// Name of the file in a shared gist
+const SHARE_FILE_NAME: &str = "hamalert-triggers.json";
+/// GitHub refuses API requests without a user agent
+const SHARE_USER_AGENT: &str = concat!("hamalert-cli/", env!("CARGO_PKG_VERSION"));
+/// Bumped when a shared set changes in a way older releases cannot import
+const SHARE_FORMAT: u32 = 1;
+/// Environment variable with a GitHub token for `share --gist`
+const ENV_GITHUB_TOKEN: &str = "GITHUB_TOKEN";
+
+/// A shared trigger set: only what is needed to recreate the triggers, with
+/// no trigger ids, match counts or account details
+#[derive(Debug, Serialize, Deserialize)]
+struct SharedSet {
+    hamalert_share: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    triggers: Vec<StoredTrigger>,
+}
+
+/// Where `share` publishes a set
+pub(crate) enum ShareTarget {
+    /// Print the set instead of publishing it
+    Stdout,
+    Gist {
+        public: bool,
+    },
+    /// Any endpoint that takes the set as the POST body and answers with its
+    /// URL, in the body or a Location header
+    Paste(String),
+}
+
+/// `trigger` without anything tied to the account it came from
+fn sanitize(trigger: &Trigger) -> StoredTrigger {
+    let mut stored = StoredTrigger::from_trigger(trigger);
+    if let Some(Value::Object(options)) = &mut stored.options {
+        options.retain(|key, _| !is_redacted_key(key));
+    }
+    stored
+}
+
+/// Id of the gist behind a gist.github.com link, if it is one
+fn gist_id(link: &str) -> Option<&str> {
+    let path = link
+        .strip_prefix("https://gist.github.com/")
+        .or_else(|| link.strip_prefix("http://gist.github.com/"))?;
+    let path = path.split(['#', '?']).next()?.trim_end_matches('/');
+    let id = path.rsplit('/').next()?;
+    (!id.is_empty() && id.chars().all(|c| c.is_ascii_hexdigit())).then_some(id)
+}
+
+fn parse_shared_set(content: &str) -> Result<SharedSet, CliError> {
+    let set: SharedSet = serde_json::from_str(content)
+        .map_err(|e| CliError::Validation(format!("Not a trigger set made with `share`: {}", e)))?;
+    if set.hamalert_share > SHARE_FORMAT {
+        return Err(CliError::Validation(format!(
+            "The trigger set is in share format {}, made by a newer release; upgrade to import it",
+            set.hamalert_share
+        )));
+    }
+    Ok(set)
+}
+
+async fn publish_gist(
+    client: &HamAlertClient,
+    token: &str,
+    content: String,
+    description: &str,
+    public: bool,
+) -> Result<String, CliError> {
+    let body = json!({
+        "description": description,
+        "public": public,
+        "files": { SHARE_FILE_NAME: { "content": content } },
+    });
+    let response = client
+        .http()
+        .post(GIST_API_URL)
+        .bearer_auth(token)
+        .header(USER_AGENT, SHARE_USER_AGENT)
+        .header(ACCEPT, "application/vnd.github+json")
+        .json(&body)
+        .send_logged()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_default();
+        return Err(CliError::Remote(format!(
+            "Creating the gist failed: {} {}",
+            status,
+            message.trim()
+        )));
+    }
+    let gist: Value = response.json().await?;
+    gist["html_url"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| {
+            CliError::Remote("GitHub did not return the URL of the new gist".to_string())
+        })
+}
+
+async fn publish_paste(
+    client: &HamAlertClient,
+    endpoint: &str,
+    content: String,
+) -> Result<String, CliError> {
+    let response = client
+        .http()
+        .post(endpoint)
+        .header(CONTENT_TYPE, "application/json")
+        .header(USER_AGENT, SHARE_USER_AGENT)
+        .body(content)
+        .send_logged()
+        .await?;
+    let status = response.status();
+    let location = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(CliError::Remote(format!(
+            "Posting to {} failed: {} {}",
+            endpoint,
+            status,
+            body.trim()
+        )));
+    }
+    location
+        .or_else(|| {
+            let body = body.trim();
+            body.starts_with("http").then(|| body.to_string())
+        })
+        .ok_or_else(|| {
+            CliError::Remote(format!(
+                "{} did not answer with the URL of the paste",
+                endpoint
+            ))
+        })
+}
+
+/// Publish the triggers, or the `selection`, and print the link
+pub(crate) async fn share(
+    client: &HamAlertClient,
+    config: &Config,
+    selection: &TriggerSelection,
+    description: Option<String>,
+    target: ShareTarget,
+    assume_yes: bool,
+) -> Result<(), CliError> {
+    let mut triggers = fetch_triggers(client).await?;
+    selection.retain(&mut triggers)?;
+    if triggers.is_empty() {
+        return Err(CliError::Validation("No triggers to share".to_string()));
+    }
+    let set = SharedSet {
+        hamalert_share: SHARE_FORMAT,
+        description,
+        triggers: triggers.iter().map(sanitize).collect(),
+    };
+    let content = serde_json::to_string_pretty(&set)?;
+
+    let destination = match &target {
+        ShareTarget::Stdout => {
+            println!("{}", content);
+            return Ok(());
+        }
+        ShareTarget::Gist { public: true } => "a public GitHub gist".to_string(),
+        ShareTarget::Gist { public: false } => "a secret GitHub gist".to_string(),
+        ShareTarget::Paste(endpoint) => endpoint.clone(),
+    };
+    println!("Sharing {} triggers:", set.triggers.len());
+    for trigger in &set.triggers {
+        println!("  {}", format_stored_trigger_for_display(trigger));
+    }
+    let question = format!(
+        "Publish these triggers to {}? Anyone with the link can read them.",
+        destination
+    );
+    if !confirm(&question, assume_yes)? {
+        return Err(CliError::Cancelled("Cancelled.".to_string()));
+    }
+
+    let url = match target {
+        ShareTarget::Stdout => unreachable!("printed above"),
+        ShareTarget::Gist { public } => {
+            let token = std::env::var(ENV_GITHUB_TOKEN)
+                .ok()
+                .filter(|t| !t.is_empty())
+                .or_else(|| config.share.github_token.clone())
+                .ok_or_else(|| {
+                    CliError::Config(format!(
+                        "share --gist needs a GitHub token with the gist scope in {} \
+                         or github_token in the [share] section of the config file",
+                        ENV_GITHUB_TOKEN
+                    ))
+                })?;
+            let description = set.description.as_deref().unwrap_or("HamAlert trigger set");
+            publish_gist(client, &token, content, description, public).await?
+        }
+        ShareTarget::Paste(endpoint) => publish_paste(client, &endpoint, content).await?,
+    };
+    println!("\nShared {} triggers: {}", set.triggers.len(), url);
+    println!("Import them with: hamalert-cli import-url {}", url);
+    Ok(())
+}
+
+/// Fetch a shared set from a gist link or any URL serving the set itself
+async fn fetch_shared_set(client: &HamAlertClient, link: &str) -> Result<SharedSet, CliError> {
+    let content = match gist_id(link) {
+        Some(id) => {
+            let url = format!("{}/{}", GIST_API_URL, id);
+            let response = client
+                .http()
+                .get(&url)
+                .header(USER_AGENT, SHARE_USER_AGENT)
+                .header(ACCEPT, "application/vnd.github+json")
+                .send_logged()
+                .await?;
+            if !response.status().is_success() {
+                return Err(CliError::Remote(format!(
+                    "Failed to fetch gist {}: {}",
+                    id,
+                    response.status()
+                )));
+            }
+            let gist: Value = response.json().await?;
+            let files = gist["files"].as_object().cloned().unwrap_or_default();
+            let file = files
+                .get(SHARE_FILE_NAME)
+                .or_else(|| files.values().next())
+                .ok_or_else(|| CliError::Remote(format!("Gist {} has no files", id)))?;
+            // GitHub leaves out the content of large files
+            match (file["truncated"].as_bool(), file["raw_url"].as_str()) {
+                (Some(true), Some(raw_url)) => {
+                    client
+                        .http()
+                        .get(raw_url)
+                        .send_logged()
+                        .await?
+                        .text()
+                        .await?
+                }
+                _ => file["content"].as_str().unwrap_or_default().to_string(),
+            }
+        }
+        None => {
+            let response = client.http().get(link).send_logged().await?;
+            if !response.status().is_success() {
+                return Err(CliError::Remote(format!(
+                    "Failed to fetch {}: {}",
+                    link,
+                    response.status()
+                )));
+            }
+            response.text().await?
+        }
+    };
+    parse_shared_set(&content)
+}
+
+/// Add the triggers of a shared set that the account does not have yet
+pub(crate) async fn import_url(
+    client: &HamAlertClient,
+    config: &Config,
+    link: &str,
+    tags: Vec<String>,
+    dry_run: bool,
+    json_output: bool,
+) -> Result<(), CliError> {
+    // With --json, stdout only carries the created triggers
+    let verbose = !json_output || dry_run;
+    let set = fetch_shared_set(client, link).await?;
+    match &set.description {
+        Some(description) if verbose => println!(
+            "Found {} triggers at {}: {}",
+            set.triggers.len(),
+            link,
+            description
+        ),
+        None if verbose => println!("Found {} triggers at {}", set.triggers.len(), link),
+        _ => {}
+    }
+
+    let existing = fetch_triggers(client).await?;
+    let existing_stored: Vec<StoredTrigger> =
+        existing.iter().map(StoredTrigger::from_trigger).collect();
+    let (present, new): (Vec<StoredTrigger>, Vec<StoredTrigger>) = set
+        .triggers
+        .into_iter()
+        .partition(|t| existing_stored.iter().any(|e| triggers_match(t, e)));
+    if !present.is_empty() && verbose {
+        println!(
+            "Skipping {} triggers the account already has",
+            present.len()
+        );
+    }
+    if new.is_empty() {
+        if !verbose {
+            return print_created(&[], json_output);
+        }
+        println!("Nothing to import.");
+        return Ok(());
+    }
+    if existing.len() + new.len() > config.limits.max_triggers {
+        return Err(CliError::Validation(format!(
+            "Importing {} triggers would take the account to {} of {} triggers",
+            new.len(),
+            existing.len() + new.len(),
+            config.limits.max_triggers
+        )));
+    }
+
+    if verbose {
+        for trigger in &new {
+            println!("  + {}", format_stored_trigger_for_display(trigger));
+        }
+    }
+    if dry_run {
+        println!("\nDry run - no triggers added.");
+        return Ok(());
+    }
+
+    let ids = map_concurrent(&new, |stored| async move {
+        let trigger = Trigger {
+            id: String::new(),
+            user_id: None,
+            conditions: stored.conditions.clone(),
+            actions: stored.actions.clone(),
+            comment: stored.comment.clone(),
+            match_count: None,
+            disabled: None,
+            options: stored.options.clone(),
+        };
+        Ok(client.create_trigger(&trigger).await?)
+    })
+    .await?;
+    if verbose {
+        println!("\nImported {} triggers from {}", new.len(), link);
+    }
+
+    let metadata = ManagedTrigger::new(format!("import-url {}", link), None, tags);
+    let created = new
+        .into_iter()
+        .zip(ids)
+        .map(|(trigger, id)| NewTrigger {
+            id,
+            name: None,
+            comment: trigger.comment,
+            metadata: metadata.clone(),
+        })
+        .collect();
+    let created = remember_new_triggers(client, created).await;
+    print_created(&created, json_output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize() {
+        let trigger: Trigger = serde_json::from_value(json!({
+            "_id": "abc123",
+            "user_id": "u1",
+            "conditions": {"callsign": "W1AW"},
+            "actions": ["app"],
+            "comment": "Club",
+            "matchCount": 12,
+            "options": {"webhookUrl": "https://example.com/secret", "timeRange": "0000-0600"},
+        }))
+        .unwrap();
+        let shared = serde_json::to_value(sanitize(&trigger)).unwrap();
+        assert_eq!(
+            shared,
+            json!({
+                "conditions": {"callsign": "W1AW"},
+                "actions": ["app"],
+                "comment": "Club",
+                "options": {"timeRange": "0000-0600"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_gist_id() {
+        assert_eq!(
+            gist_id("https://gist.github.com/k1abc/0123abcdef"),
+            Some("0123abcdef")
+        );
+        assert_eq!(
+            gist_id("https://gist.github.com/0123abcdef/#file-x"),
+            Some("0123abcdef")
+        );
+        assert_eq!(gist_id("https://paste.example.com/0123abcdef"), None);
+        assert_eq!(gist_id("https://gist.github.com/k1abc"), None);
+    }
+
+    #[test]
+    fn test_parse_shared_set() {
+        let set = parse_shared_set(
+            r#"{"hamalert_share": 1, "triggers": [
+                {"conditions": {"band": "6m"}, "actions": ["app"], "comment": "Magic band"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(set.triggers.len(), 1);
+        assert!(set.description.is_none());
+
+        assert!(parse_shared_set(r#"{"hamalert_share": 99, "triggers": []}"#).is_err());
+        assert!(parse_shared_set("[]").is_err());
+    }
+}
@@ -0,0 +1,274 @@
+//! `shell`: run subcommands interactively over one login.
+
+use crate::cli::{Cli, Commands};
+use crate::config::{Config, expand_aliases, load_early_config};
+use crate::error::CliError;
+use crate::storage::data_dir;
+use clap::{CommandFactory, Parser};
+use hamalert_api::HamAlertClient;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::path::PathBuf;
+
+/// Words that leave the shell
+const EXIT_WORDS: [&str; 2] = ["exit", "quit"];
+
+/// Completes subcommand names and long options from the clap definition
+struct ShellHelper {
+    command: clap::Command,
+}
+
+impl Completer for ShellHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let before: Vec<&str> = line[..start].split_whitespace().collect();
+        Ok((start, candidates(&self.command, &before, &line[start..])))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+/// Completions for `partial` after the words `before`: subcommands of the
+/// innermost command named so far, or its long options if `partial` starts
+/// with a dash
+fn candidates(root: &clap::Command, before: &[&str], partial: &str) -> Vec<String> {
+    let mut current = root;
+    for word in before.iter().filter(|w| !w.starts_with('-')) {
+        match current.find_subcommand(word) {
+            Some(sub) => current = sub,
+            None => break,
+        }
+    }
+
+    let mut names: Vec<String> = if partial.starts_with('-') {
+        current
+            .get_arguments()
+            .chain(root.get_arguments().filter(|a| a.is_global_set()))
+            .filter(|a| !a.is_hide_set())
+            .filter_map(|a| a.get_long().map(|long| format!("--{}", long)))
+            .collect()
+    } else {
+        let mut names: Vec<String> = current
+            .get_subcommands()
+            .filter(|c| !c.is_hide_set())
+            .map(|c| c.get_name().to_string())
+            .collect();
+        if std::ptr::eq(current, root) {
+            names.extend(EXIT_WORDS.iter().map(|w| w.to_string()));
+        }
+        names
+    };
+    names.retain(|name| name.starts_with(partial));
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Parse one shell line as the arguments after `hamalert-cli`. `Ok(None)`
+/// means the line was blank.
+fn parse_line(
+    line: &str,
+    aliases: &std::collections::HashMap<String, String>,
+    builtins: &[String],
+) -> Result<Option<Cli>, CliError> {
+    let words =
+        shlex::split(line).ok_or_else(|| CliError::Validation("Unbalanced quotes".to_string()))?;
+    if words.is_empty() {
+        return Ok(None);
+    }
+    let args = std::iter::once("hamalert-cli".to_string())
+        .chain(words)
+        .collect();
+    let args = expand_aliases(args, aliases, builtins);
+    match Cli::try_parse_from(args) {
+        Ok(cli) => match startup_flag(&cli) {
+            Some(flag) => Err(CliError::Validation(format!(
+                "{} only works when starting hamalert-cli, not inside the shell",
+                flag
+            ))),
+            None => Ok(Some(cli)),
+        },
+        Err(e) => {
+            // Help and usage errors are printed like on the command line
+            let _ = e.print();
+            Ok(None)
+        }
+    }
+}
+
+/// A global flag the line sets that only takes effect at startup, where main
+/// applies it process-wide
+fn startup_flag(cli: &Cli) -> Option<&'static str> {
+    if cli.get.is_some() {
+        Some("--get")
+    } else if cli.offline {
+        Some("--offline")
+    } else {
+        None
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    data_dir().ok().map(|dir| dir.join("shell_history"))
+}
+
+/// Read subcommands from a prompt and run them with the logged-in `client`
+/// until `exit` or end of input
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    config: &Config,
+    config_file: Option<PathBuf>,
+    assume_yes: bool,
+) -> Result<(), CliError> {
+    let aliases = load_early_config(config_file).aliases;
+    let command = Cli::command();
+    let builtins: Vec<String> = command
+        .get_subcommands()
+        .map(|c| c.get_name().to_string())
+        .collect();
+
+    let mut editor: Editor<ShellHelper, DefaultHistory> = Editor::new()
+        .map_err(|e| std::io::Error::other(format!("Failed to start the shell: {}", e)))?;
+    editor.set_helper(Some(ShellHelper { command }));
+    let history = history_path();
+    if let Some(path) = &history {
+        // A missing history file just means a first session
+        let _ = editor.load_history(path);
+    }
+
+    println!(
+        "Logged in as {}. Type a command, 'help' for a list, or 'exit' to leave.",
+        config.username
+    );
+    loop {
+        let line = match editor.readline("hamalert> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                return Err(std::io::Error::other(format!("Failed to read input: {}", e)).into());
+            }
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+        if EXIT_WORDS.contains(&line) {
+            break;
+        }
+
+        let cli = match parse_line(line, &aliases, &builtins) {
+            Ok(Some(cli)) => cli,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                continue;
+            }
+        };
+        let command = match cli.command {
+            Some(Commands::Shell) => {
+                eprintln!("Already in the shell.");
+                continue;
+            }
+            Some(Commands::Config(_) | Commands::CopyAccount { .. }) => {
+                eprintln!("Run this command outside the shell.");
+                continue;
+            }
+            Some(command) => command,
+            None => {
+                eprintln!("Error: a command is required");
+                continue;
+            }
+        };
+        // Each command sees the account as it is now, not as the last one left it
+        client.invalidate_triggers();
+        let result = Box::pin(super::dispatch(
+            command,
+            client,
+            config,
+            cli.json,
+            assume_yes || cli.yes,
+        ))
+        .await;
+        match result {
+            Ok(()) => {}
+            Err(CliError::Cancelled(message) | CliError::Drift(message)) => {
+                eprintln!("{}", message)
+            }
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+
+    if let Some(path) = &history {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        editor
+            .save_history(path)
+            .map_err(|e| std::io::Error::other(format!("Failed to save shell history: {}", e)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let root = Cli::command();
+        let top = candidates(&root, &[], "pl");
+        assert_eq!(top, vec!["plan"]);
+        assert!(candidates(&root, &[], "ex").contains(&"exit".to_string()));
+
+        let nested = candidates(&root, &["profile"], "s");
+        assert!(nested.contains(&"switch".to_string()));
+        assert!(nested.contains(&"save".to_string()));
+        assert!(!nested.contains(&"exit".to_string()));
+
+        let flags = candidates(&root, &["apply"], "--");
+        assert!(flags.contains(&"--no-dry-run".to_string()));
+        assert!(flags.contains(&"--json".to_string()));
+        assert!(
+            candidates(&root, &["apply", "--file", "x.toml"], "--d")
+                .contains(&"--delete-unmanaged".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let aliases = std::collections::HashMap::from([("ls".to_string(), "list".to_string())]);
+        let builtins = vec!["list".to_string()];
+        let cli = parse_line("ls --json", &aliases, &builtins)
+            .unwrap()
+            .unwrap();
+        assert!(cli.json);
+        assert!(matches!(cli.command, Some(Commands::List { .. })));
+        assert!(parse_line("   ", &aliases, &builtins).unwrap().is_none());
+        assert!(parse_line("add-trigger \"W1AW", &aliases, &builtins).is_err());
+        assert!(parse_line("ls --get '[].id'", &aliases, &builtins).is_err());
+        assert!(parse_line("ls --offline", &aliases, &builtins).is_err());
+    }
+}
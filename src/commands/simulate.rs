@@ -0,0 +1,102 @@
+//! `simulate`: submit test spots to HamAlert.
+
+use crate::error::CliError;
+use crate::model::{Spot, parse_spot_line};
+use hamalert_api::{HamAlertClient, SimulatedSpot};
+use std::fs;
+use std::path::PathBuf;
+
+/// Parse a line of a simulate batch file: either a DX cluster spot line or
+/// "CALL FREQ_KHZ [MODE] [COMMENT...]"
+pub(crate) fn parse_simulated_spot(line: &str, spotter: &str) -> Option<Spot> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    if let Some(spot) = parse_spot_line(line) {
+        return Some(spot);
+    }
+    let mut words = line.split_whitespace();
+    let callsign = words.next()?.to_uppercase();
+    let frequency_khz: f64 = words.next()?.parse().ok()?;
+    let mode = words.next().map(|m| m.to_uppercase());
+    Some(Spot {
+        spotter: spotter.to_string(),
+        frequency_khz,
+        callsign,
+        mode,
+        comment: words.collect::<Vec<_>>().join(" "),
+        trigger_comment: None,
+    })
+}
+
+/// Submit simulated spots so triggers can be tested
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    callsign: Option<String>,
+    freq: Option<f64>,
+    mode: Option<String>,
+    spotter: String,
+    comment: String,
+    file: Option<PathBuf>,
+) -> Result<(), CliError> {
+    let spots: Vec<Spot> = match (file, callsign, freq) {
+        (Some(path), _, _) => fs::read_to_string(&path)
+            .map_err(|e| CliError::io(format!("Failed to read {}", path.display()), e))?
+            .lines()
+            .filter_map(|line| parse_simulated_spot(line, &spotter))
+            .collect(),
+        (None, Some(callsign), Some(frequency_khz)) => vec![Spot {
+            spotter,
+            frequency_khz,
+            callsign: callsign.to_uppercase(),
+            mode: mode.map(|m| m.to_uppercase()),
+            comment,
+            trigger_comment: None,
+        }],
+        _ => {
+            return Err(CliError::Validation(
+                "Give --callsign and --freq, or --file".to_string(),
+            ));
+        }
+    };
+
+    for spot in &spots {
+        let reply = client
+            .simulate_spot(&SimulatedSpot {
+                callsign: spot.callsign.clone(),
+                frequency_khz: spot.frequency_khz,
+                mode: spot.mode.clone(),
+                spotter: spot.spotter.clone(),
+                comment: spot.comment.clone(),
+            })
+            .await?;
+        println!("Simulated {}", spot.to_cluster_line());
+        if !reply.trim().is_empty() {
+            info!("  {}", reply.trim());
+        }
+    }
+    println!("\nSubmitted {} simulated spot(s).", spots.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simulated_spot() {
+        let spot = parse_simulated_spot("w1aw 14060 cw POTA K-0001", "ME").unwrap();
+        assert_eq!(spot.callsign, "W1AW");
+        assert_eq!(spot.frequency_khz, 14060.0);
+        assert_eq!(spot.mode.as_deref(), Some("CW"));
+        assert_eq!(spot.spotter, "ME");
+        assert_eq!(spot.comment, "POTA K-0001");
+
+        let spot = parse_simulated_spot("DX de K1TTT: 7030.0 K2DEF CW", "ME").unwrap();
+        assert_eq!(spot.spotter, "K1TTT");
+
+        assert!(parse_simulated_spot("# comment", "ME").is_none());
+        assert!(parse_simulated_spot("W1AW notafrequency", "ME").is_none());
+    }
+}
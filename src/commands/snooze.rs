@@ -0,0 +1,176 @@
+//! `snooze` and `unsnooze`: disable a trigger for a while without losing it.
+
+use super::batch::resolve_target;
+use crate::api::fetch_triggers;
+use crate::error::CliError;
+use crate::storage::state::{ManagedTrigger, State, load_state, save_state, slugify};
+use chrono::{DateTime, Local, SecondsFormat};
+use hamalert_api::{HamAlertClient, Trigger};
+use std::time::Duration;
+
+/// Wake time `duration` after `now`, in the RFC 3339 form kept in the state
+pub(crate) fn wake_time(now: DateTime<Local>, duration: Duration) -> Result<String, CliError> {
+    let duration = chrono::Duration::from_std(duration)
+        .map_err(|_| CliError::Validation("The duration is too long".to_string()))?;
+    Ok((now + duration).to_rfc3339_opts(SecondsFormat::Secs, false))
+}
+
+/// Names of snoozed triggers whose wake time has come at `now`
+fn find_due(state: &State, now: &DateTime<Local>) -> Vec<String> {
+    state
+        .triggers
+        .iter()
+        .filter(|(_, managed)| managed.is_snooze_over(now))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// `trigger` enabled or disabled
+fn with_disabled(trigger: &Trigger, disabled: bool) -> Trigger {
+    Trigger {
+        disabled: Some(disabled),
+        ..trigger.clone()
+    }
+}
+
+/// Enable the snoozed triggers whose wake time has passed and forget their
+/// snoozes; with `dry_run` nothing is changed. Returns their names. A
+/// trigger deleted in the meantime only has its snooze forgotten.
+pub(crate) async fn wake_due(
+    client: &HamAlertClient,
+    dry_run: bool,
+) -> Result<Vec<String>, CliError> {
+    let mut state = load_state()?;
+    let due = find_due(&state, &Local::now());
+    if dry_run || due.is_empty() {
+        return Ok(due);
+    }
+
+    let triggers = fetch_triggers(client).await?;
+    for name in &due {
+        let managed = state
+            .triggers
+            .get_mut(name)
+            .expect("due names come from the state");
+        if let Some(trigger) = triggers.iter().find(|t| t.id == managed.id) {
+            client
+                .update_trigger(&with_disabled(trigger, false))
+                .await?;
+        }
+        managed.snoozed_until = None;
+    }
+    save_state(&state)?;
+    tracing::info!("Woke {} snoozed triggers", due.len());
+    Ok(due)
+}
+
+/// Disable the trigger with `id`, or the managed trigger `name`, until
+/// `duration` from now. Snoozing a snoozed trigger moves its wake time.
+pub(crate) async fn snooze(
+    client: &HamAlertClient,
+    id: Option<String>,
+    name: Option<String>,
+    duration: Duration,
+) -> Result<(), CliError> {
+    let id = resolve_target(id, name)?;
+    let triggers = fetch_triggers(client).await?;
+    let trigger = triggers
+        .iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| CliError::Validation(format!("No trigger with id '{}'", id)))?;
+    let mut state = load_state()?;
+    let snoozed = state
+        .name_for_id(&id)
+        .and_then(|name| state.triggers[name].snoozed_until.as_ref())
+        .is_some();
+    if trigger.disabled == Some(true) && !snoozed {
+        return Err(CliError::Validation(format!(
+            "'{}' is already disabled; snoozing would enable it later",
+            trigger.comment
+        )));
+    }
+
+    let until = wake_time(Local::now(), duration)?;
+    if !snoozed {
+        client.update_trigger(&with_disabled(trigger, true)).await?;
+    }
+    let name = match state.name_for_id(&id) {
+        Some(name) => name.to_string(),
+        None => state.unique_name(&slugify(&trigger.comment)),
+    };
+    let mut managed = state
+        .triggers
+        .get(&name)
+        .cloned()
+        .unwrap_or_else(|| ManagedTrigger {
+            id: id.clone(),
+            ..ManagedTrigger::new("snooze".to_string(), None, Vec::new())
+        });
+    managed.snoozed_until = Some(until.clone());
+    state.record(&name, managed);
+    save_state(&state)?;
+    println!("Snoozed '{}' until {}", trigger.comment, until);
+    Ok(())
+}
+
+/// Enable a snoozed trigger now and forget its snooze
+pub(crate) async fn unsnooze(
+    client: &HamAlertClient,
+    id: Option<String>,
+    name: Option<String>,
+) -> Result<(), CliError> {
+    let id = resolve_target(id, name)?;
+    let mut state = load_state()?;
+    let managed = state
+        .name_for_id(&id)
+        .map(str::to_string)
+        .and_then(|name| state.triggers.get_mut(&name))
+        .filter(|managed| managed.snoozed_until.is_some())
+        .ok_or_else(|| CliError::Validation(format!("Trigger '{}' is not snoozed", id)))?;
+    let triggers = fetch_triggers(client).await?;
+    let trigger = triggers
+        .iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| CliError::Validation(format!("No trigger with id '{}'", id)))?;
+    client
+        .update_trigger(&with_disabled(trigger, false))
+        .await?;
+    managed.snoozed_until = None;
+    save_state(&state)?;
+    println!("Enabled '{}' again", trigger.comment);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wake_time() {
+        let now = DateTime::parse_from_rfc3339("2026-10-17T12:30:00+02:00")
+            .unwrap()
+            .with_timezone(&Local);
+        let until = wake_time(now, Duration::from_secs(2 * 3600)).unwrap();
+        let until = DateTime::parse_from_rfc3339(&until).unwrap();
+        assert_eq!(until, now + chrono::Duration::hours(2));
+    }
+
+    #[test]
+    fn test_find_due() {
+        let now = Local::now();
+        let mut state = State::default();
+        for (name, id, until) in [
+            ("past", "1", Some(now - chrono::Duration::minutes(1))),
+            ("future", "2", Some(now + chrono::Duration::hours(1))),
+            ("awake", "3", None),
+        ] {
+            let mut managed = ManagedTrigger {
+                id: id.to_string(),
+                ..ManagedTrigger::new("snooze".to_string(), None, Vec::new())
+            };
+            managed.snoozed_until = until.map(|t| t.to_rfc3339());
+            state.record(name, managed);
+        }
+        assert_eq!(find_due(&state, &now), vec!["past"]);
+    }
+}
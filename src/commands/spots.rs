@@ -0,0 +1,710 @@
+//! `spots`: work with logged spots.
+
+use crate::adif::{spot_time, spots_to_adif};
+use crate::cli::SpotsCommands;
+use crate::dxcc::{PrefixTable, load_table, resolve_dxcc};
+use crate::error::CliError;
+use crate::model::{BANDS, Spot, normalize_mode, parse_spot_line};
+use crate::output::{print_json, render_csv, render_table, terminal_width};
+use chrono::{DateTime, Timelike, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum QueryOutput {
+    /// Aligned columns sized to the terminal
+    Table,
+    /// Comma-separated values with a header row, for spreadsheets
+    Csv,
+}
+
+/// A spot from a spot log, with the time the `file` destination received it
+/// (lines written by older releases have none)
+type LoggedSpot = (Option<DateTime<Utc>>, Spot);
+
+/// Parse a spot log line: a cluster line, preceded by its receive time
+fn parse_log_line(line: &str) -> Option<LoggedSpot> {
+    if let Some((time, rest)) = line.split_once(' ')
+        && let Ok(time) = DateTime::parse_from_rfc3339(time)
+    {
+        return parse_spot_line(rest).map(|spot| (Some(time.with_timezone(&Utc)), spot));
+    }
+    parse_spot_line(line).map(|spot| (None, spot))
+}
+
+/// Spots of the spot log at `input`, skipping lines that are not spots
+fn read_spot_log(input: &Path) -> Result<Vec<LoggedSpot>, CliError> {
+    let content = fs::read_to_string(input)
+        .map_err(|e| CliError::io(format!("Failed to read {}", input.display()), e))?;
+    Ok(content.lines().filter_map(parse_log_line).collect())
+}
+
+/// A --band value, lowercased, if it names a known band
+fn check_band(band: &str) -> Result<String, CliError> {
+    let band = band.to_lowercase();
+    if !BANDS.iter().any(|(_, _, name)| *name == band) {
+        let names: Vec<&str> = BANDS.iter().map(|(_, _, name)| *name).collect();
+        return Err(CliError::Validation(format!(
+            "Unknown band '{}'; use one of {}",
+            band,
+            names.join(", ")
+        )));
+    }
+    Ok(band)
+}
+
+/// Filters of `spots query`
+#[derive(Default)]
+struct SpotQuery {
+    call: Option<String>,
+    band: Option<String>,
+    mode: Option<String>,
+    since: Option<DateTime<Utc>>,
+}
+
+impl SpotQuery {
+    /// Whether a logged spot passes every filter. Spots without a receive
+    /// time never pass `since`.
+    fn matches(&self, (received_at, spot): &LoggedSpot) -> bool {
+        self.call
+            .as_ref()
+            .is_none_or(|call| spot.callsign.eq_ignore_ascii_case(call))
+            && self
+                .band
+                .as_ref()
+                .is_none_or(|band| spot.band() == Some(band.as_str()))
+            && self.mode.as_ref().is_none_or(|mode| {
+                spot.mode
+                    .as_ref()
+                    .is_some_and(|m| normalize_mode(m) == normalize_mode(mode))
+            })
+            && self
+                .since
+                .is_none_or(|since| received_at.is_some_and(|at| at >= since))
+    }
+}
+
+/// Time `age` ago, for --since
+fn since_time(age: Duration) -> Result<DateTime<Utc>, CliError> {
+    chrono::Duration::from_std(age)
+        .map(|age| Utc::now() - age)
+        .map_err(|_| CliError::Validation("--since is too long".to_string()))
+}
+
+/// Spots of one band in a spot log
+#[derive(Debug, PartialEq, Serialize)]
+struct BandActivity {
+    band: &'static str,
+    spots: usize,
+    /// Spots per UTC hour; spots without a time only count in `spots`
+    hours: [usize; 24],
+}
+
+/// What `spots stats` reports about a spot log
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct SpotStats {
+    spots: usize,
+    /// Bands with spots, lowest first
+    bands: Vec<BandActivity>,
+    /// Most spotted callsigns, busiest first
+    calls: Vec<(String, usize)>,
+    /// Spots per kind of spotter, most first
+    sources: Vec<(&'static str, usize)>,
+}
+
+/// Kind of station that reported a spot
+fn spot_source(spot: &Spot) -> &'static str {
+    if spot.spotter.ends_with("-#") {
+        "RBN skimmer"
+    } else if spot.spotter == "HamAlert" {
+        "HamAlert"
+    } else {
+        "DX cluster"
+    }
+}
+
+/// Counts sorted by count, most first, then by name
+fn by_count<K: Ord>(counts: std::collections::HashMap<K, usize>) -> Vec<(K, usize)> {
+    let mut counts: Vec<(K, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+fn collect_stats(spots: &[&LoggedSpot]) -> SpotStats {
+    let mut bands: Vec<BandActivity> = BANDS
+        .iter()
+        .map(|(_, _, band)| BandActivity {
+            band,
+            spots: 0,
+            hours: [0; 24],
+        })
+        .collect();
+    let mut calls = std::collections::HashMap::new();
+    let mut sources = std::collections::HashMap::new();
+    for (received_at, spot) in spots {
+        *calls.entry(spot.callsign.to_uppercase()).or_insert(0) += 1;
+        *sources.entry(spot_source(spot)).or_insert(0) += 1;
+        let Some(activity) = spot
+            .band()
+            .and_then(|band| bands.iter_mut().find(|a| a.band == band))
+        else {
+            continue;
+        };
+        activity.spots += 1;
+        let hour = spot_hour(spot).or_else(|| received_at.map(|at| at.hour() as usize));
+        if let Some(hour) = hour {
+            activity.hours[hour] += 1;
+        }
+    }
+    bands.retain(|activity| activity.spots > 0);
+    let mut calls = by_count(calls);
+    calls.truncate(STATS_TOP_CALLS);
+    SpotStats {
+        spots: spots.len(),
+        bands,
+        calls,
+        sources: by_count(sources),
+    }
+}
+
+/// Heatmap cell for `count` spots, scaled to the busiest cell `max`
+fn heatmap_shade(count: usize, max: usize) -> char {
+    if count == 0 || max == 0 {
+        return HEATMAP_SHADES[0];
+    }
+    let steps = HEATMAP_SHADES.len() - 1;
+    HEATMAP_SHADES[1 + (count * steps - 1) / max]
+}
+
+/// Print activity by band and hour, top callsigns and sources of a spot log
+fn stats(input: &Path, since: Option<Duration>, json_output: bool) -> Result<(), CliError> {
+    let since = since.map(since_time).transpose()?;
+    let spots = read_spot_log(input)?;
+    let filters = SpotQuery {
+        since,
+        ..Default::default()
+    };
+    let matching: Vec<&LoggedSpot> = spots.iter().filter(|s| filters.matches(s)).collect();
+    let stats = collect_stats(&matching);
+
+    if json_output {
+        return print_json(&json!({
+            "spots": stats.spots,
+            "bands": stats.bands,
+            "calls": stats.calls.iter().map(|(call, spots)| json!({"call": call, "spots": spots})).collect::<Vec<_>>(),
+            "sources": stats.sources.iter().map(|(source, spots)| json!({"source": source, "spots": spots})).collect::<Vec<_>>(),
+        }));
+    }
+
+    println!("{} spots in {}", stats.spots, input.display());
+    if stats.spots == 0 {
+        return Ok(());
+    }
+
+    if !stats.bands.is_empty() {
+        let busiest = stats.bands.iter().map(|a| a.spots).max().unwrap_or(0);
+        println!("\nBy band:");
+        for activity in &stats.bands {
+            println!(
+                "  {:<5} {:>6} {}",
+                activity.band,
+                activity.spots,
+                "#".repeat(activity.spots * STATS_BAR_WIDTH / busiest)
+            );
+        }
+
+        let max = stats.bands.iter().flat_map(|a| a.hours).max().unwrap_or(0);
+        println!("\nBy hour (UTC), '{}' is the busiest:", HEATMAP_SHADES[9]);
+        let labels: String = (0..24)
+            .step_by(6)
+            .map(|hour| format!("{:<6}", format!("{:02}", hour)))
+            .collect();
+        println!("  {:<5} {}", "", labels.trim_end());
+        for activity in &stats.bands {
+            let cells: String = activity
+                .hours
+                .iter()
+                .map(|&count| heatmap_shade(count, max))
+                .collect();
+            println!("  {:<5} {}|", activity.band, cells);
+        }
+    }
+
+    println!("\nMost spotted:");
+    for (call, spots) in &stats.calls {
+        println!("  {:<12} {:>6}", call, spots);
+    }
+
+    println!("\nSources:");
+    for (source, spots) in &stats.sources {
+        println!(
+            "  {:<12} {:>6} ({}%)",
+            source,
+            spots,
+            spots * 100 / stats.spots
+        );
+    }
+    Ok(())
+}
+
+/// Print the spots of a spot log matching the filters
+fn query(
+    input: &Path,
+    filters: SpotQuery,
+    output: QueryOutput,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let spots = read_spot_log(input)?;
+    let matching: Vec<&LoggedSpot> = spots.iter().filter(|s| filters.matches(s)).collect();
+
+    if json_output {
+        let rows: Vec<serde_json::Value> = matching
+            .iter()
+            .map(|(received_at, spot)| {
+                json!({
+                    "received_at": received_at.map(|at| at.to_rfc3339()),
+                    "call": spot.callsign,
+                    "frequency_khz": spot.frequency_khz,
+                    "band": spot.band(),
+                    "mode": spot.mode,
+                    "spotter": spot.spotter,
+                    "comment": spot.comment,
+                    "trigger": spot.trigger_comment,
+                })
+            })
+            .collect();
+        return print_json(&json!(rows));
+    }
+
+    let headers = [
+        "received", "call", "freq", "band", "mode", "spotter", "comment",
+    ];
+    let rows: Vec<Vec<String>> = matching
+        .iter()
+        .map(|(received_at, spot)| {
+            vec![
+                received_at
+                    .map(|at| at.format("%Y-%m-%d %H:%MZ").to_string())
+                    .unwrap_or_default(),
+                spot.callsign.clone(),
+                format!("{:.1}", spot.frequency_khz),
+                spot.band().unwrap_or_default().to_string(),
+                spot.mode.clone().unwrap_or_default(),
+                spot.spotter.clone(),
+                spot.comment.clone(),
+            ]
+        })
+        .collect();
+    match output {
+        QueryOutput::Csv => println!("{}", render_csv(&headers, &rows)),
+        QueryOutput::Table if rows.is_empty() => {
+            println!("No matching spots in {}", input.display())
+        }
+        QueryOutput::Table => {
+            println!("{}", render_table(&headers, &rows, terminal_width()));
+            println!("{} of {} spots", rows.len(), spots.len());
+        }
+    }
+    Ok(())
+}
+
+/// Widest bar in the `spots openings` histogram
+const OPENINGS_BAR_WIDTH: usize = 40;
+
+/// Widest bar in the `spots stats` band histogram
+const STATS_BAR_WIDTH: usize = 40;
+
+/// Callsigns listed by `spots stats`
+const STATS_TOP_CALLS: usize = 10;
+
+/// Heatmap cells from no spots to the busiest band and hour
+const HEATMAP_SHADES: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// How the spots matching `spots openings` spread over the day
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct Openings {
+    /// Spots per UTC hour
+    hours: [usize; 24],
+    /// Matching spots without a time, e.g. from webhook destinations
+    untimed: usize,
+}
+
+impl Openings {
+    fn total(&self) -> usize {
+        self.hours.iter().sum()
+    }
+
+    /// Runs of hours with at least half the spots of the busiest hour, as
+    /// (first, last) hours. A run over midnight is reported once.
+    fn peaks(&self) -> Vec<(usize, usize)> {
+        let max = self.hours.iter().copied().max().unwrap_or(0);
+        if max == 0 {
+            return Vec::new();
+        }
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for hour in (0..24).filter(|&h| self.hours[h] * 2 >= max) {
+            match runs.last_mut() {
+                Some((_, last)) if *last + 1 == hour => *last = hour,
+                _ => runs.push((hour, hour)),
+            }
+        }
+        if runs.len() > 1 && runs[0].0 == 0 && runs[runs.len() - 1].1 == 23 {
+            let (_, end) = runs.remove(0);
+            runs.last_mut().unwrap().1 = end;
+        }
+        runs
+    }
+}
+
+/// UTC hour of a spot, from the time cluster spots end with
+fn spot_hour(spot: &Spot) -> Option<usize> {
+    spot_time(spot)?[..2].parse().ok().filter(|&hour| hour < 24)
+}
+
+fn count_openings(
+    spots: &[Spot],
+    table: &PrefixTable,
+    dxcc: &[u16],
+    band: Option<&str>,
+) -> Openings {
+    let mut openings = Openings::default();
+    let matching = spots.iter().filter(|spot| {
+        band.is_none_or(|band| spot.band() == Some(band))
+            && (dxcc.is_empty()
+                || table
+                    .lookup(&spot.callsign)
+                    .is_some_and(|location| dxcc.contains(&location.entity.dxcc)))
+    });
+    for spot in matching {
+        match spot_hour(spot) {
+            Some(hour) => openings.hours[hour] += 1,
+            None => openings.untimed += 1,
+        }
+    }
+    openings
+}
+
+fn format_peak((first, last): (usize, usize)) -> String {
+    if first == last {
+        format!("{:02}Z", first)
+    } else {
+        format!("{:02}-{:02}Z", first, last)
+    }
+}
+
+/// Summarize at which hours spots of `dxcc` entities on `band` were logged
+fn openings(
+    input: &Path,
+    dxcc: &[String],
+    band: Option<String>,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let band = band.as_deref().map(check_band).transpose()?;
+    let entities = resolve_dxcc(dxcc)?;
+    let spots: Vec<Spot> = read_spot_log(input)?
+        .into_iter()
+        .map(|(_, spot)| spot)
+        .collect();
+    let openings = count_openings(&spots, &load_table()?, &entities, band.as_deref());
+    let peaks: Vec<String> = openings.peaks().into_iter().map(format_peak).collect();
+
+    if json_output {
+        let report = serde_json::json!({
+            "spots": openings.total(),
+            "untimed": openings.untimed,
+            "hours": openings.hours,
+            "peaks": peaks,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let mut filters = Vec::new();
+    if !dxcc.is_empty() {
+        filters.push(dxcc.join(", "));
+    }
+    if let Some(band) = &band {
+        filters.push(format!("on {}", band));
+    }
+    let what = if filters.is_empty() {
+        "All spots".to_string()
+    } else {
+        format!("Spots of {}", filters.join(" "))
+    };
+    println!(
+        "{} by hour (UTC), {} of {} spots in {}:",
+        what,
+        openings.total(),
+        spots.len(),
+        input.display()
+    );
+    if openings.total() == 0 {
+        println!("  none with a time");
+        return Ok(());
+    }
+    let max = openings.hours.iter().copied().max().unwrap_or(0);
+    for (hour, &count) in openings.hours.iter().enumerate() {
+        println!(
+            "  {:02}Z {:>5} {}",
+            hour,
+            count,
+            "#".repeat(count * OPENINGS_BAR_WIDTH / max)
+        );
+    }
+    println!("Busiest: {}", peaks.join(", "));
+    if openings.untimed > 0 {
+        println!(
+            "({} more matching spots have no time and are not counted)",
+            openings.untimed
+        );
+    }
+    Ok(())
+}
+
+/// Run a spots subcommand
+pub(crate) async fn run(command: SpotsCommands, json_output: bool) -> Result<(), CliError> {
+    match command {
+        SpotsCommands::Query {
+            input,
+            call,
+            band,
+            mode,
+            since,
+            output,
+        } => {
+            let filters = SpotQuery {
+                call,
+                band: band.as_deref().map(check_band).transpose()?,
+                mode,
+                since: since.map(since_time).transpose()?,
+            };
+            query(&input, filters, output, json_output)?;
+        }
+        SpotsCommands::Export {
+            input,
+            adif,
+            output,
+        } => {
+            if !adif {
+                return Err(CliError::Validation(
+                    "Specify an export format (--adif)".to_string(),
+                ));
+            }
+            let spots: Vec<Spot> = read_spot_log(&input)?
+                .into_iter()
+                .map(|(_, spot)| spot)
+                .collect();
+            let document = spots_to_adif(&spots);
+
+            match output {
+                Some(path) => {
+                    fs::write(&path, document)?;
+                    println!("Exported {} spots to {}", spots.len(), path.display());
+                }
+                None => print!("{}", document),
+            }
+        }
+        SpotsCommands::Stats { input, since } => {
+            stats(&input, since, json_output)?;
+        }
+        SpotsCommands::Openings { input, dxcc, band } => {
+            openings(&input, &dxcc, band, json_output)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_export_writes_adif() {
+        let dir = std::env::temp_dir().join(format!("hamalert-spots-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("spots.log");
+        let output = dir.join("spots.adi");
+        fs::write(
+            &input,
+            "DX de K1TTT:     14025.0  W1AW         CW 23 dB 25 WPM CQ     1234Z\nnot a spot\n",
+        )
+        .unwrap();
+
+        run(
+            SpotsCommands::Export {
+                input: input.clone(),
+                adif: true,
+                output: Some(output.clone()),
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        let document = fs::read_to_string(&output).unwrap();
+        assert_eq!(document.matches("<EOR>").count(), 1);
+        assert!(document.contains("W1AW"));
+
+        let err = run(
+            SpotsCommands::Export {
+                input,
+                adif: false,
+                output: None,
+            },
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, CliError::Validation(_)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_count_openings() {
+        let table = PrefixTable::parse(include_str!("../../data/cty.csv")).unwrap();
+        let spots: Vec<Spot> = [
+            "DX de K1TTT:     21025.0  JA1ABC       CW 599 2215Z",
+            "DX de K1TTT:     21074.0  JH1XYZ       FT8 -10 2240Z  [Japan]",
+            "DX de K1TTT:     21030.0  JA2AAA       CW 0105Z",
+            "DX de K1TTT:     14025.0  JA1ABC       CW 599 2230Z",
+            "DX de K1TTT:     21025.0  W1AW         CW 2200Z",
+            "DX de N2XYZ:     21025.0  JA3BBB       CW",
+        ]
+        .iter()
+        .filter_map(|line| parse_spot_line(line))
+        .collect();
+
+        let openings = count_openings(&spots, &table, &[339], Some("15m"));
+        assert_eq!(openings.hours[22], 2);
+        assert_eq!(openings.hours[1], 1);
+        assert_eq!(openings.total(), 3);
+        assert_eq!(openings.untimed, 1);
+
+        let everything = count_openings(&spots, &table, &[], None);
+        assert_eq!(everything.total(), 5);
+    }
+
+    #[test]
+    fn test_openings_peaks() {
+        let mut openings = Openings::default();
+        for (hour, count) in [(23, 4), (0, 6), (1, 3), (2, 1), (12, 5)] {
+            openings.hours[hour] = count;
+        }
+        assert_eq!(openings.peaks(), vec![(12, 12), (23, 1)]);
+        let peaks: Vec<String> = openings.peaks().into_iter().map(format_peak).collect();
+        assert_eq!(peaks, vec!["12Z", "23-01Z"]);
+        assert!(Openings::default().peaks().is_empty());
+    }
+
+    #[test]
+    fn test_parse_log_line() {
+        let (received_at, spot) =
+            parse_log_line("2026-10-17T12:34:56Z DX de K1TTT: 21025.0 VP8PJ CW 1234Z").unwrap();
+        assert_eq!(
+            received_at.unwrap().to_rfc3339(),
+            "2026-10-17T12:34:56+00:00"
+        );
+        assert_eq!(spot.callsign, "VP8PJ");
+        assert_eq!(spot_time(&spot).as_deref(), Some("1234"));
+
+        let (received_at, spot) = parse_log_line("DX de K1TTT: 21025.0 VP8PJ CW").unwrap();
+        assert!(received_at.is_none());
+        assert_eq!(spot.spotter, "K1TTT");
+        assert!(parse_log_line("2026-10-17T12:34:56Z not a spot").is_none());
+    }
+
+    #[test]
+    fn test_spot_query_matches() {
+        let spots: Vec<LoggedSpot> = [
+            "2026-10-17T12:00:00Z DX de K1TTT: 21025.0 VP8PJ CW",
+            "2026-10-16T08:00:00Z DX de K1TTT: 14195.0 vp8pj USB",
+            "DX de K1TTT: 21074.0 VP8PJ FT8",
+            "2026-10-17T12:05:00Z DX de K1TTT: 21025.0 W1AW CW",
+        ]
+        .iter()
+        .filter_map(|line| parse_log_line(line))
+        .collect();
+        let count = |query: SpotQuery| spots.iter().filter(|s| query.matches(s)).count();
+
+        let call = || Some("VP8PJ".to_string());
+        assert_eq!(count(SpotQuery::default()), 4);
+        assert_eq!(
+            count(SpotQuery {
+                call: call(),
+                ..Default::default()
+            }),
+            3
+        );
+        assert_eq!(
+            count(SpotQuery {
+                call: call(),
+                band: Some("15m".to_string()),
+                ..Default::default()
+            }),
+            2
+        );
+        assert_eq!(
+            count(SpotQuery {
+                call: call(),
+                mode: Some("ssb".to_string()),
+                ..Default::default()
+            }),
+            1
+        );
+        let since = DateTime::parse_from_rfc3339("2026-10-17T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            count(SpotQuery {
+                call: call(),
+                since: Some(since),
+                ..Default::default()
+            }),
+            1
+        );
+        assert!(check_band("15M").is_ok());
+        assert!(check_band("11m").is_err());
+    }
+
+    #[test]
+    fn test_collect_stats() {
+        let spots: Vec<LoggedSpot> = [
+            "DX de K1TTT: 21025.0 VP8PJ CW 599 1215Z",
+            "DX de DL1ABC-#: 21026.0 VP8PJ CW 22 dB 1230Z",
+            "2026-10-17T08:10:00Z DX de HamAlert: 14195.0 VP8PJ USB",
+            "DX de N2XYZ: 14025.0 W1AW CW",
+            "DX de N2XYZ: 11025.0 W1AW CW",
+        ]
+        .iter()
+        .filter_map(|line| parse_log_line(line))
+        .collect();
+        let stats = collect_stats(&spots.iter().collect::<Vec<_>>());
+
+        assert_eq!(stats.spots, 5);
+        let bands: Vec<(&str, usize)> = stats.bands.iter().map(|a| (a.band, a.spots)).collect();
+        assert_eq!(bands, vec![("20m", 2), ("15m", 2)]);
+        assert_eq!(stats.bands[0].hours[8], 1);
+        assert_eq!(stats.bands[0].hours.iter().sum::<usize>(), 1);
+        assert_eq!(stats.bands[1].hours[12], 2);
+        assert_eq!(
+            stats.calls,
+            vec![("VP8PJ".to_string(), 3), ("W1AW".to_string(), 2)]
+        );
+        assert_eq!(
+            stats.sources,
+            vec![("DX cluster", 3), ("HamAlert", 1), ("RBN skimmer", 1)]
+        );
+    }
+
+    #[test]
+    fn test_heatmap_shade() {
+        assert_eq!(heatmap_shade(0, 10), ' ');
+        assert_eq!(heatmap_shade(1, 10), '.');
+        assert_eq!(heatmap_shade(10, 10), '@');
+        assert_eq!(heatmap_shade(1, 1), '@');
+        assert_eq!(heatmap_shade(5, 10), '+');
+    }
+}
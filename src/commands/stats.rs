@@ -0,0 +1,217 @@
+//! `stats`: matchCount totals and, with `--trend`, alert rates over time.
+
+use crate::api::fetch_triggers;
+use crate::error::CliError;
+use crate::output::{GREEN, RED, YELLOW, paint, print_json, render_table, terminal_width};
+use crate::storage::history::{MatchSample, count_at, load_history};
+use chrono::{DateTime, Duration, Local};
+use hamalert_api::{HamAlertClient, Trigger};
+use serde::Serialize;
+
+/// Windows, in days, that alert counts are reported for
+const WINDOWS_DAYS: [i64; 3] = [1, 7, 30];
+
+/// Alerts of one trigger over the trend windows
+#[derive(Debug, Serialize)]
+pub(crate) struct TriggerTrend {
+    pub(crate) id: String,
+    pub(crate) comment: String,
+    pub(crate) matches: u64,
+    /// Alerts in the last day, week and month; `None` where the history does
+    /// not reach back that far
+    pub(crate) last_day: Option<u64>,
+    pub(crate) last_week: Option<u64>,
+    pub(crate) last_month: Option<u64>,
+    pub(crate) trend: Trend,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Trend {
+    /// Not enough history yet
+    Unknown,
+    /// No alerts in the last month
+    Dead,
+    /// The last week is well above the month's average rate
+    HeatingUp,
+    /// The last week is well below the month's average rate
+    CoolingDown,
+    Steady,
+}
+
+impl Trend {
+    fn display(&self) -> String {
+        match self {
+            Trend::Unknown => String::new(),
+            Trend::Dead => paint("dead", RED),
+            Trend::HeatingUp => paint("heating up", GREEN),
+            Trend::CoolingDown => paint("cooling down", YELLOW),
+            Trend::Steady => "steady".to_string(),
+        }
+    }
+}
+
+/// Classify from the alerts in the last week and the last month
+fn classify(last_week: Option<u64>, last_month: Option<u64>) -> Trend {
+    let (Some(week), Some(month)) = (last_week, last_month) else {
+        return Trend::Unknown;
+    };
+    if month == 0 {
+        return Trend::Dead;
+    }
+    // Daily rate of the last week against that of the three weeks before
+    let recent = week as f64 / 7.0;
+    let earlier = (month - week) as f64 / 23.0;
+    if recent > earlier * 1.5 {
+        Trend::HeatingUp
+    } else if recent < earlier * 0.5 {
+        Trend::CoolingDown
+    } else {
+        Trend::Steady
+    }
+}
+
+/// Trends of `triggers` at `now` from the matchCount history
+pub(crate) fn trends(
+    triggers: &[Trigger],
+    history: &[MatchSample],
+    now: DateTime<Local>,
+) -> Vec<TriggerTrend> {
+    triggers
+        .iter()
+        .map(|trigger| {
+            let matches = trigger.match_count.unwrap_or(0);
+            // Counts can drop when a trigger is recreated; count that as no alerts
+            let [last_day, last_week, last_month] = WINDOWS_DAYS.map(|days| {
+                count_at(history, &trigger.id, now - Duration::days(days))
+                    .map(|then| matches.saturating_sub(then))
+            });
+            TriggerTrend {
+                id: trigger.id.clone(),
+                comment: trigger.comment.clone(),
+                matches,
+                last_day,
+                last_week,
+                last_month,
+                trend: classify(last_week, last_month),
+            }
+        })
+        .collect()
+}
+
+fn display_count(count: Option<u64>) -> String {
+    count
+        .map(|c| c.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Print matchCount per trigger, busiest first; with `trend`, also the alerts
+/// in the last day, week and month from the recorded history
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    trend: bool,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let triggers = fetch_triggers(client).await?;
+    let history = if trend { load_history()? } else { Vec::new() };
+    let mut trends = trends(&triggers, &history, Local::now());
+    trends.sort_by(|a, b| {
+        b.last_week
+            .cmp(&a.last_week)
+            .then(b.matches.cmp(&a.matches))
+    });
+
+    if json_output {
+        return print_json(&serde_json::to_value(&trends)?);
+    }
+    if trends.is_empty() {
+        println!("No triggers found.");
+        return Ok(());
+    }
+
+    let total: u64 = trends.iter().map(|t| t.matches).sum();
+    if trend {
+        let rows: Vec<Vec<String>> = trends
+            .iter()
+            .map(|t| {
+                vec![
+                    t.comment.clone(),
+                    t.matches.to_string(),
+                    display_count(t.last_day),
+                    display_count(t.last_week),
+                    display_count(t.last_month),
+                    t.trend.display(),
+                ]
+            })
+            .collect();
+        println!(
+            "{}",
+            render_table(
+                &["COMMENT", "MATCHES", "24H", "7D", "30D", "TREND"],
+                &rows,
+                terminal_width()
+            )
+        );
+        if let Some(first) = history.first() {
+            println!(
+                "\nHistory since {}.",
+                first.time.get(..10).unwrap_or_default()
+            );
+        } else {
+            println!("\nNo history yet; it is recorded whenever triggers are fetched.");
+        }
+    } else {
+        let rows: Vec<Vec<String>> = trends
+            .iter()
+            .map(|t| vec![t.comment.clone(), t.matches.to_string()])
+            .collect();
+        println!(
+            "{}",
+            render_table(&["COMMENT", "MATCHES"], &rows, terminal_width())
+        );
+    }
+    println!("{} matches across {} triggers.", total, trends.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(None, Some(3)), Trend::Unknown);
+        assert_eq!(classify(Some(0), None), Trend::Unknown);
+        assert_eq!(classify(Some(0), Some(0)), Trend::Dead);
+        assert_eq!(classify(Some(20), Some(30)), Trend::HeatingUp);
+        assert_eq!(classify(Some(1), Some(40)), Trend::CoolingDown);
+        assert_eq!(classify(Some(7), Some(30)), Trend::Steady);
+    }
+
+    #[test]
+    fn test_trends() {
+        let now = Local.with_ymd_and_hms(2026, 6, 30, 12, 0, 0).unwrap();
+        let sample = |days_ago: i64, count: u64| MatchSample {
+            time: (now - Duration::days(days_ago)).to_rfc3339(),
+            counts: BTreeMap::from([("a".to_string(), count)]),
+        };
+        let history = vec![sample(40, 10), sample(10, 12), sample(3, 20)];
+        let trigger: Trigger = serde_json::from_value(json!({
+            "_id": "a",
+            "conditions": {"callsign": "W1AW"},
+            "actions": ["app"],
+            "comment": "Friends",
+            "matchCount": 30,
+        }))
+        .unwrap();
+
+        let trend = &trends(&[trigger], &history, now)[0];
+        assert_eq!(trend.last_day, Some(10));
+        assert_eq!(trend.last_week, Some(18));
+        assert_eq!(trend.last_month, Some(20));
+        assert_eq!(trend.trend, Trend::HeatingUp);
+    }
+}
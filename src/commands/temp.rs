@@ -0,0 +1,130 @@
+//! `temp-trigger`: a trigger that is deleted again after a few hours.
+
+use crate::api::{NewTrigger, add_trigger, remember_new_triggers};
+use crate::callsign::validate_patterns;
+use crate::cli::TempTriggerOptions;
+use crate::config::Config;
+use crate::error::CliError;
+use crate::storage::state::ManagedTrigger;
+use chrono::{DateTime, Local, SecondsFormat};
+use hamalert_api::{Conditions, HamAlertClient};
+
+/// Tag recorded with every temporary trigger
+pub(crate) const TEMP_TAG: &str = "temp";
+
+/// Comment used when none is given
+fn default_comment(callsigns: &[String]) -> String {
+    format!("Temporary: {}", callsigns.join(", "))
+}
+
+/// Expiry time `hours` after `now`, in the RFC 3339 form kept in the state
+fn expiry(now: DateTime<Local>, hours: u32) -> String {
+    (now + chrono::Duration::hours(hours.into())).to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+/// Add a trigger for `callsign` that expires after `hours`. With `wait`,
+/// stay running and delete it when the window ends; otherwise it is left to
+/// `prune-expired`.
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    config: &Config,
+    callsign: Vec<String>,
+    options: TempTriggerOptions,
+) -> Result<(), CliError> {
+    let TempTriggerOptions {
+        hours,
+        comment,
+        mut actions,
+        mut mode,
+        wait,
+    } = options;
+    let callsign = validate_patterns(&callsign)?;
+    if actions.is_empty() {
+        actions = config.defaults.actions.clone();
+    }
+    if mode.is_empty() {
+        mode = config.defaults.mode.clone();
+    }
+    let comment = comment.unwrap_or_else(|| default_comment(&callsign));
+    let expires = expiry(Local::now(), hours);
+
+    let conditions = Conditions {
+        callsign: callsign.join(","),
+        mode: (!mode.is_empty()).then(|| {
+            mode.iter()
+                .map(|m| m.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        }),
+        ..Default::default()
+    };
+    let id = add_trigger(
+        client,
+        conditions,
+        &comment,
+        actions.iter().map(|a| a.as_str().to_string()).collect(),
+    )
+    .await?;
+    remember_new_triggers(
+        client,
+        vec![NewTrigger {
+            id,
+            name: None,
+            comment: comment.clone(),
+            metadata: ManagedTrigger::new(
+                "temp-trigger".to_string(),
+                Some(expires.clone()),
+                vec![TEMP_TAG.to_string()],
+            ),
+        }],
+    )
+    .await;
+    println!("Added '{}' until {}", comment, expires);
+
+    if !wait {
+        println!(
+            "It is deleted by the next prune-expired run after that (see auto_prune_expired)."
+        );
+        return Ok(());
+    }
+
+    info!(
+        "Waiting {} hour(s) to delete it; press Ctrl-C to leave it to prune-expired",
+        hours
+    );
+    tokio::time::sleep(std::time::Duration::from_secs(u64::from(hours) * 3600)).await;
+    // The session has most likely timed out by now
+    client.login(&config.username, &config.password).await?;
+    for expired in super::prune::prune_expired(client, false).await? {
+        println!(
+            "Deleted expired trigger '{}' ({})",
+            expired.name, expired.trigger.comment
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_expiry() {
+        let now = Local.with_ymd_and_hms(2026, 10, 17, 20, 30, 15).unwrap();
+        let expires = expiry(now, 6);
+        let parsed = DateTime::parse_from_rfc3339(&expires).unwrap();
+        assert_eq!(
+            parsed,
+            Local.with_ymd_and_hms(2026, 10, 18, 2, 30, 15).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_default_comment() {
+        assert_eq!(
+            default_comment(&["W1AW".to_string(), "K3LR".to_string()]),
+            "Temporary: W1AW, K3LR"
+        );
+    }
+}
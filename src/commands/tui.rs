@@ -0,0 +1,568 @@
+//! `tui`: the live spot dashboard.
+
+use crate::adif::{WorkedBefore, WorkedStatus, parse_adif};
+use crate::api::fetch_triggers;
+use crate::commands::edit::{edit_trigger_guided, edit_trigger_in_editor};
+use crate::config::Config;
+use crate::dxcc::{PrefixTable, load_table};
+use crate::error::CliError;
+use crate::feed::{TELNET_RECONNECT_DELAY_SECS, connect_upstream, relay_upstream_spots};
+use crate::model::{EditableTrigger, Spot, parse_spot_line, trigger_callsigns};
+use crate::output::format_trigger_for_display;
+use crate::rig::{rig_mode_for_spot, tune_rig};
+use chrono::Local;
+use hamalert_api::{HamAlertClient, Trigger};
+use std::fs;
+use std::path::PathBuf;
+
+/// Check whether a spot matches a dashboard filter (case-insensitive substring
+/// of callsign, band, mode, spotter or comment)
+pub(crate) fn spot_matches_filter(spot: &Spot, filter: &str) -> bool {
+    if filter.is_empty() {
+        return true;
+    }
+    let filter = filter.to_lowercase();
+    [
+        spot.callsign.as_str(),
+        spot.band().unwrap_or_default(),
+        spot.mode.as_deref().unwrap_or_default(),
+        spot.spotter.as_str(),
+        spot.comment.as_str(),
+    ]
+    .iter()
+    .any(|field| field.to_lowercase().contains(&filter))
+}
+
+/// Events delivered from the HamAlert telnet feed to the dashboard
+pub(crate) enum FeedEvent {
+    Status(String),
+    Spot(Spot),
+}
+
+pub(crate) const DASHBOARD_MAX_SPOTS: usize = 500;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum DashboardPane {
+    Spots,
+    Triggers,
+}
+
+/// State of the full-screen spot dashboard
+pub(crate) struct Dashboard {
+    /// Received spots, newest first, with the local time they arrived
+    pub(crate) spots: std::collections::VecDeque<(String, Spot)>,
+    pub(crate) spot_state: ratatui::widgets::ListState,
+    pub(crate) triggers: Vec<Trigger>,
+    pub(crate) trigger_state: ratatui::widgets::ListState,
+    pub(crate) focus: DashboardPane,
+    pub(crate) filter: String,
+    pub(crate) editing_filter: bool,
+    pub(crate) muted: std::collections::HashSet<String>,
+    pub(crate) status: String,
+    pub(crate) rigctld: Option<String>,
+    pub(crate) worked: Option<WorkedBefore>,
+    pub(crate) hide_confirmed: bool,
+    /// DXCC prefix table used to show where a spotted station is
+    pub(crate) prefixes: PrefixTable,
+}
+
+impl Dashboard {
+    pub(crate) fn new(
+        triggers: Vec<Trigger>,
+        rigctld: Option<String>,
+        worked: Option<WorkedBefore>,
+    ) -> Self {
+        let mut trigger_state = ratatui::widgets::ListState::default();
+        if !triggers.is_empty() {
+            trigger_state.select(Some(0));
+        }
+        Self {
+            spots: std::collections::VecDeque::new(),
+            spot_state: ratatui::widgets::ListState::default(),
+            prefixes: load_table().unwrap_or_default(),
+            triggers,
+            trigger_state,
+            focus: DashboardPane::Spots,
+            filter: String::new(),
+            editing_filter: false,
+            muted: std::collections::HashSet::new(),
+            status: "Connecting to HamAlert...".to_string(),
+            rigctld,
+            worked,
+            hide_confirmed: false,
+        }
+    }
+
+    pub(crate) fn visible_spots(&self) -> Vec<&(String, Spot)> {
+        self.spots
+            .iter()
+            .filter(|(_, spot)| !self.muted.contains(&spot.callsign.to_uppercase()))
+            .filter(|(_, spot)| spot_matches_filter(spot, &self.filter))
+            .filter(|(_, spot)| {
+                !self.hide_confirmed || self.worked_status(spot) != Some(WorkedStatus::Confirmed)
+            })
+            .collect()
+    }
+
+    pub(crate) fn worked_status(&self, spot: &Spot) -> Option<WorkedStatus> {
+        self.worked.as_ref().map(|w| w.status(spot))
+    }
+
+    pub(crate) fn push_spot(&mut self, spot: Spot) {
+        let received = Local::now().format("%H:%M:%S").to_string();
+        self.spots.push_front((received, spot));
+        self.spots.truncate(DASHBOARD_MAX_SPOTS);
+        // Keep the same spot selected as new ones arrive on top
+        if let Some(selected) = self.spot_state.selected() {
+            let visible = self.visible_spots().len();
+            self.spot_state
+                .select(Some((selected + 1).min(visible.saturating_sub(1))));
+        }
+    }
+
+    pub(crate) fn selected_spot(&self) -> Option<Spot> {
+        let selected = self.spot_state.selected()?;
+        self.visible_spots()
+            .get(selected)
+            .map(|(_, spot)| spot.clone())
+    }
+
+    pub(crate) fn selected_trigger(&self) -> Option<&Trigger> {
+        self.triggers.get(self.trigger_state.selected()?)
+    }
+
+    pub(crate) fn move_selection(&mut self, delta: isize) {
+        let (len, state) = match self.focus {
+            DashboardPane::Spots => (self.visible_spots().len(), &mut self.spot_state),
+            DashboardPane::Triggers => (self.triggers.len(), &mut self.trigger_state),
+        };
+        if len == 0 {
+            state.select(None);
+            return;
+        }
+        let current = state.selected().unwrap_or(0) as isize;
+        state.select(Some((current + delta).clamp(0, len as isize - 1) as usize));
+    }
+
+    pub(crate) fn toggle_mute_selected(&mut self) {
+        let Some(spot) = self.selected_spot() else {
+            return;
+        };
+        let call = spot.callsign.to_uppercase();
+        if self.muted.remove(&call) {
+            self.status = format!("Unmuted {}", call);
+        } else {
+            self.status = format!("Muted {}", call);
+            self.muted.insert(call);
+        }
+        self.spot_state.select(None);
+    }
+
+    /// Tune the rig to the selected spot on a blocking thread, reporting the
+    /// outcome through `events` so a slow rigctld cannot freeze the UI
+    pub(crate) fn tune_selected(
+        &mut self,
+        runtime: &tokio::runtime::Handle,
+        events: &tokio::sync::mpsc::UnboundedSender<FeedEvent>,
+    ) {
+        let Some(spot) = self.selected_spot() else {
+            self.status = "Select a spot to tune to".to_string();
+            return;
+        };
+        let Some(address) = &self.rigctld else {
+            self.status = "Start with --rigctld host:port to tune the rig".to_string();
+            return;
+        };
+        self.status = format!("Tuning to {}...", spot.callsign);
+        let address = address.clone();
+        let events = events.clone();
+        runtime.spawn_blocking(move || {
+            let status = match tune_rig(&address, &spot) {
+                Ok(()) => format!(
+                    "Tuned to {} on {:.1} kHz {}",
+                    spot.callsign,
+                    spot.frequency_khz,
+                    rig_mode_for_spot(&spot).unwrap_or_default()
+                ),
+                Err(e) => format!("Failed to tune rig: {}", e),
+            };
+            let _ = events.send(FeedEvent::Status(status));
+        });
+    }
+}
+
+pub(crate) fn draw_dashboard(frame: &mut ratatui::Frame, dashboard: &mut Dashboard) {
+    use ratatui::layout::{Constraint, Layout};
+    use ratatui::style::{Color, Modifier, Style};
+    use ratatui::widgets::{Block, List, ListItem, Paragraph, Wrap};
+
+    let [main, details, status] = Layout::vertical([
+        Constraint::Min(5),
+        Constraint::Length(9),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [spots_area, triggers_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(main);
+
+    let pane_block = |title: String, focused: bool| {
+        let style = if focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        Block::bordered().title(title).border_style(style)
+    };
+    let highlight = Style::default().add_modifier(Modifier::REVERSED);
+
+    let spot_items: Vec<ListItem> = dashboard
+        .visible_spots()
+        .iter()
+        .map(|(received, spot)| {
+            let status = dashboard.worked_status(spot);
+            let item = ListItem::new(format!(
+                "{} {:>9.1} {:<12} {:<5} {}{}",
+                received,
+                spot.frequency_khz,
+                spot.callsign,
+                spot.mode.as_deref().unwrap_or(""),
+                status
+                    .map(|s| format!("{} ", s.marker()))
+                    .unwrap_or_default(),
+                spot.comment
+            ));
+            match status {
+                Some(WorkedStatus::NewOne) => item.style(
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Some(WorkedStatus::NewBand | WorkedStatus::NewMode) => {
+                    item.style(Style::default().fg(Color::Cyan))
+                }
+                Some(WorkedStatus::Confirmed) => item.style(Style::default().fg(Color::DarkGray)),
+                _ => item,
+            }
+        })
+        .collect();
+    let spots_title = if dashboard.filter.is_empty() && !dashboard.editing_filter {
+        format!(" Spots ({}) ", spot_items.len())
+    } else {
+        format!(
+            " Spots ({}) filter: {}{} ",
+            spot_items.len(),
+            dashboard.filter,
+            if dashboard.editing_filter { "_" } else { "" }
+        )
+    };
+    let spots_list = List::new(spot_items)
+        .block(pane_block(
+            spots_title,
+            dashboard.focus == DashboardPane::Spots,
+        ))
+        .highlight_style(highlight);
+    frame.render_stateful_widget(spots_list, spots_area, &mut dashboard.spot_state);
+
+    let trigger_items: Vec<ListItem> = dashboard
+        .triggers
+        .iter()
+        .map(|t| ListItem::new(format_trigger_for_display(t)))
+        .collect();
+    let triggers_list = List::new(trigger_items)
+        .block(pane_block(
+            format!(" Triggers ({}) ", dashboard.triggers.len()),
+            dashboard.focus == DashboardPane::Triggers,
+        ))
+        .highlight_style(highlight);
+    frame.render_stateful_widget(triggers_list, triggers_area, &mut dashboard.trigger_state);
+
+    let detail_text = match (dashboard.focus, dashboard.selected_spot()) {
+        (DashboardPane::Spots, Some(spot)) => {
+            let matching: Vec<String> = dashboard
+                .triggers
+                .iter()
+                .filter(|t| trigger_callsigns(t).contains(&spot.callsign.to_uppercase()))
+                .map(|t| format!("\"{}\"", t.comment))
+                .collect();
+            let entity = match dashboard.prefixes.lookup(&spot.callsign) {
+                Some(location) => format!(
+                    "{} ({}), {}, CQ {}, ITU {}",
+                    location.entity.name,
+                    location.entity.prefix,
+                    location.continent,
+                    location.cq_zone,
+                    location.itu_zone
+                ),
+                None => "?".to_string(),
+            };
+            format!(
+                "Callsign: {}\nEntity: {}\nFrequency: {:.1} kHz ({})\nMode: {}\nSpotter: {}\nComment: {}\nTriggers: {}",
+                spot.callsign,
+                entity,
+                spot.frequency_khz,
+                spot.band().unwrap_or("?"),
+                spot.mode.as_deref().unwrap_or("?"),
+                spot.spotter,
+                spot.comment,
+                if matching.is_empty() {
+                    "(none by callsign)".to_string()
+                } else {
+                    matching.join(", ")
+                }
+            )
+        }
+        (DashboardPane::Triggers, _) => dashboard
+            .selected_trigger()
+            .and_then(|t| serde_json::to_string_pretty(&EditableTrigger::from_trigger(t)).ok())
+            .unwrap_or_default(),
+        _ => "Select a spot with j/k to see details.".to_string(),
+    };
+    frame.render_widget(
+        Paragraph::new(detail_text)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(" Details ")),
+        details,
+    );
+
+    let help = if dashboard.editing_filter {
+        "type to filter, Enter=apply, Esc=clear"
+    } else {
+        "q=quit Tab=pane j/k=move /=filter m=mute t=tune w=hide confirmed e=edit g=guided edit r=refresh"
+    };
+    let muted = if dashboard.muted.is_empty() {
+        String::new()
+    } else {
+        format!(" | muted: {}", dashboard.muted.len())
+    };
+    frame.render_widget(
+        Paragraph::new(format!("{}{} | {}", dashboard.status, muted, help)),
+        status,
+    );
+}
+
+/// Run the dashboard UI loop. Blocks until the user quits, so it is run on a
+/// blocking thread; API calls are driven through the runtime handle.
+pub(crate) fn run_dashboard(
+    client: HamAlertClient,
+    triggers: Vec<Trigger>,
+    mut feed: tokio::sync::mpsc::UnboundedReceiver<FeedEvent>,
+    events: tokio::sync::mpsc::UnboundedSender<FeedEvent>,
+    rigctld: Option<String>,
+    worked: Option<WorkedBefore>,
+    runtime: tokio::runtime::Handle,
+) -> std::io::Result<()> {
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+
+    let mut dashboard = Dashboard::new(triggers, rigctld, worked);
+    let mut terminal = ratatui::try_init()?;
+
+    let result = loop {
+        while let Ok(event) = feed.try_recv() {
+            match event {
+                FeedEvent::Status(status) => dashboard.status = status,
+                FeedEvent::Spot(spot) => dashboard.push_spot(spot),
+            }
+        }
+
+        if let Err(e) = terminal.draw(|frame| draw_dashboard(frame, &mut dashboard)) {
+            break Err(e);
+        }
+
+        match event::poll(std::time::Duration::from_millis(200)) {
+            Ok(false) => continue,
+            Ok(true) => {}
+            Err(e) => break Err(e),
+        }
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+            Ok(_) => continue,
+            Err(e) => break Err(e),
+        };
+
+        if dashboard.editing_filter {
+            match key.code {
+                KeyCode::Char(c) => dashboard.filter.push(c),
+                KeyCode::Backspace => {
+                    dashboard.filter.pop();
+                }
+                KeyCode::Enter => dashboard.editing_filter = false,
+                KeyCode::Esc => {
+                    dashboard.filter.clear();
+                    dashboard.editing_filter = false;
+                }
+                _ => {}
+            }
+            dashboard.spot_state.select(None);
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => break Ok(()),
+            KeyCode::Tab => {
+                dashboard.focus = match dashboard.focus {
+                    DashboardPane::Spots => DashboardPane::Triggers,
+                    DashboardPane::Triggers => DashboardPane::Spots,
+                }
+            }
+            KeyCode::Char('j') | KeyCode::Down => dashboard.move_selection(1),
+            KeyCode::Char('k') | KeyCode::Up => dashboard.move_selection(-1),
+            KeyCode::Char('/') => dashboard.editing_filter = true,
+            KeyCode::Char('m') => dashboard.toggle_mute_selected(),
+            KeyCode::Char('t') => dashboard.tune_selected(&runtime, &events),
+            KeyCode::Char('w') if dashboard.worked.is_some() => {
+                dashboard.hide_confirmed = !dashboard.hide_confirmed;
+                dashboard.spot_state.select(None);
+                dashboard.status = if dashboard.hide_confirmed {
+                    "Hiding confirmed spots".to_string()
+                } else {
+                    "Showing confirmed spots".to_string()
+                };
+            }
+            KeyCode::Char('r') => {
+                client.invalidate_triggers();
+                dashboard.status = match runtime.block_on(fetch_triggers(&client)) {
+                    Ok(triggers) => {
+                        dashboard.triggers = triggers;
+                        dashboard.move_selection(0);
+                        "Refreshed triggers".to_string()
+                    }
+                    Err(e) => format!("Failed to refresh triggers: {}", e),
+                };
+            }
+            KeyCode::Char(key @ ('e' | 'g')) if dashboard.focus == DashboardPane::Triggers => {
+                let Some(trigger) = dashboard.selected_trigger().cloned() else {
+                    continue;
+                };
+                // Hand the terminal to the editor or the prompts, then come back
+                ratatui::restore();
+                let edited = runtime.block_on(async {
+                    if key == 'g' {
+                        edit_trigger_guided(&client, &trigger).await?;
+                    } else {
+                        edit_trigger_in_editor(&client, &trigger).await?;
+                    }
+                    fetch_triggers(&client).await
+                });
+                terminal = match ratatui::try_init() {
+                    Ok(terminal) => terminal,
+                    Err(e) => break Err(e),
+                };
+                terminal.clear()?;
+                dashboard.status = match edited {
+                    Ok(triggers) => {
+                        dashboard.triggers = triggers;
+                        dashboard.move_selection(0);
+                        format!("Finished editing \"{}\"", trigger.comment)
+                    }
+                    Err(e) => format!("Edit failed: {}", e),
+                };
+            }
+            _ => {}
+        }
+    };
+
+    ratatui::restore();
+    result
+}
+
+/// Run the full-screen spot dashboard fed by the HamAlert telnet feed
+pub(crate) async fn run_tui(
+    config: &Config,
+    client: &HamAlertClient,
+    rigctld: Option<String>,
+    worked: Option<WorkedBefore>,
+) -> Result<(), CliError> {
+    let triggers = fetch_triggers(client).await?;
+    let (events, feed_events) = tokio::sync::mpsc::unbounded_channel();
+
+    let login = config.telnet_login();
+    let ui_events = events.clone();
+    let feed = tokio::spawn(async move {
+        loop {
+            let _ = events.send(FeedEvent::Status("Connecting to HamAlert...".to_string()));
+            let status = match connect_upstream(&login).await {
+                Ok(mut upstream) => {
+                    let _ = events.send(FeedEvent::Status(
+                        "Connected to HamAlert telnet feed".to_string(),
+                    ));
+                    let relayed = relay_upstream_spots(&mut upstream, |line| {
+                        if let Some(spot) = parse_spot_line(&line) {
+                            let _ = events.send(FeedEvent::Spot(spot));
+                        }
+                    })
+                    .await;
+                    match relayed {
+                        Ok(()) => "HamAlert connection closed".to_string(),
+                        Err(e) => format!("HamAlert telnet error: {}", e),
+                    }
+                }
+                Err(e) => format!("HamAlert telnet error: {}", e),
+            };
+            let _ = events.send(FeedEvent::Status(format!(
+                "{}, reconnecting in {} seconds",
+                status, TELNET_RECONNECT_DELAY_SECS
+            )));
+            tokio::time::sleep(std::time::Duration::from_secs(TELNET_RECONNECT_DELAY_SECS)).await;
+        }
+    });
+
+    let client = client.clone();
+    let runtime = tokio::runtime::Handle::current();
+    let ui = tokio::task::spawn_blocking(move || {
+        run_dashboard(
+            client,
+            triggers,
+            feed_events,
+            ui_events,
+            rigctld,
+            worked,
+            runtime,
+        )
+    });
+    let result = ui.await;
+    feed.abort();
+    result??;
+    Ok(())
+}
+
+/// Load the worked-before log, if any, and open the dashboard
+pub(crate) async fn run(
+    config: &Config,
+    client: &HamAlertClient,
+    rigctld: Option<String>,
+    adif: Option<PathBuf>,
+) -> Result<(), CliError> {
+    let worked = match adif {
+        Some(path) => {
+            let content = fs::read_to_string(&path).map_err(|e| {
+                CliError::io(format!("Failed to read ADIF log {}", path.display()), e)
+            })?;
+            let worked = WorkedBefore::from_records(&parse_adif(&content));
+            info!(
+                "Loaded {} worked callsigns from {}",
+                worked.calls.len(),
+                path.display()
+            );
+            Some(worked)
+        }
+        None => None,
+    };
+    run_tui(config, client, rigctld, worked).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::parse_spot_line;
+
+    #[test]
+    fn test_spot_matches_filter() {
+        let spot = parse_spot_line("DX de K1TTT: 14074.0 W1AW FT8 -10 dB").unwrap();
+        assert!(spot_matches_filter(&spot, ""));
+        assert!(spot_matches_filter(&spot, "w1aw"));
+        assert!(spot_matches_filter(&spot, "20m"));
+        assert!(spot_matches_filter(&spot, "ft8"));
+        assert!(!spot_matches_filter(&spot, "40m"));
+    }
+}
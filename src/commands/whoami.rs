@@ -0,0 +1,67 @@
+//! `whoami`: account summary and limit usage.
+
+use crate::api::fetch_triggers;
+use crate::config::{Config, LIMIT_WARNING_PERCENT};
+use crate::error::CliError;
+use crate::model::trigger_callsign_count;
+use crate::output::{YELLOW, paint, print_json};
+use hamalert_api::HamAlertClient;
+use serde_json::json;
+
+/// Summarize the account and its usage of the HamAlert limits
+pub(crate) async fn run(
+    client: &HamAlertClient,
+    config: &Config,
+    json_output: bool,
+) -> Result<(), CliError> {
+    let triggers = fetch_triggers(client).await?;
+    let limits = &config.limits;
+    let callsign_counts: Vec<usize> = triggers.iter().map(trigger_callsign_count).collect();
+    let total_callsigns: usize = callsign_counts.iter().sum();
+    let largest = callsign_counts.iter().copied().max().unwrap_or(0);
+    let disabled = triggers
+        .iter()
+        .filter(|t| t.disabled.unwrap_or(false))
+        .count();
+
+    if json_output {
+        print_json(&json!({
+            "username": config.username,
+            "triggers": triggers.len(),
+            "disabled": disabled,
+            "callsigns": total_callsigns,
+            "largest_trigger_callsigns": largest,
+            "max_triggers": limits.max_triggers,
+            "max_callsigns_per_trigger": limits.max_callsigns_per_trigger,
+        }))?;
+        return Ok(());
+    }
+
+    let usage = |used: usize, limit: usize| {
+        let percent = (used * 100).checked_div(limit).unwrap_or(0);
+        let text = format!("{}/{} ({}%)", used, limit, percent);
+        if percent >= LIMIT_WARNING_PERCENT {
+            paint(&text, YELLOW)
+        } else {
+            text
+        }
+    };
+    println!("Username:  {}", config.username);
+    println!(
+        "Triggers:  {} ({} disabled)",
+        usage(triggers.len(), limits.max_triggers),
+        disabled
+    );
+    println!(
+        "Callsigns: {} total, largest trigger {}",
+        total_callsigns,
+        usage(largest, limits.max_callsigns_per_trigger)
+    );
+    if triggers.len() * 100 >= limits.max_triggers * LIMIT_WARNING_PERCENT {
+        println!(
+            "\n{} You are close to the trigger limit.",
+            paint("⚠", YELLOW)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,303 @@
+//! Guided, field-by-field editing of a trigger's conditions.
+
+use crate::callsign::validate_patterns;
+use crate::cli::Action;
+use crate::error::CliError;
+use crate::model::{BANDS, EditableTrigger};
+use clap::ValueEnum;
+use hamalert_api::Trigger;
+use inquire::{MultiSelect, Select, Text};
+use serde_json::{Map, Value};
+
+/// Modes offered in the mode list, besides any the trigger already uses
+const MODES: [&str; 8] = ["cw", "ssb", "ft8", "ft4", "rtty", "psk", "am", "fm"];
+
+/// Highest CQ zone number
+const CQ_ZONES: u8 = 40;
+
+/// Highest ITU zone number
+const ITU_ZONES: u8 = 90;
+
+/// Conditions HamAlert stores as comma-separated strings rather than arrays
+const STRING_LISTS: [&str; 2] = ["callsign", "mode"];
+
+/// Items of list condition `key`, given as a string or an array
+fn list_items(conditions: &Map<String, Value>, key: &str) -> Vec<String> {
+    match conditions.get(key) {
+        Some(Value::String(s)) => s
+            .split([',', '\n'])
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| v.to_string())
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Set list condition `key` to `items`, as a comma-separated string where
+/// HamAlert uses one and an array otherwise. No items removes the condition.
+fn set_list(conditions: &mut Map<String, Value>, key: &str, items: Vec<Value>) {
+    if items.is_empty() {
+        conditions.remove(key);
+        return;
+    }
+    let as_string =
+        STRING_LISTS.contains(&key) || matches!(conditions.get(key), Some(Value::String(_)));
+    let value = if as_string {
+        let items: Vec<String> = items
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| v.to_string())
+            })
+            .collect();
+        Value::from(items.join(","))
+    } else {
+        Value::Array(items)
+    };
+    conditions.insert(key.to_string(), value);
+}
+
+/// Zone numbers from a comma- or space-separated list, each from 1 to `max`
+fn parse_zones(input: &str, max: u8) -> Result<Vec<u8>, String> {
+    let mut zones = input
+        .split([',', ' '])
+        .filter(|z| !z.trim().is_empty())
+        .map(|z| match z.trim().parse::<u8>() {
+            Ok(zone) if (1..=max).contains(&zone) => Ok(zone),
+            _ => Err(format!("'{}' is not a zone from 1 to {}", z.trim(), max)),
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+    zones.sort();
+    zones.dedup();
+    Ok(zones)
+}
+
+/// Append the callsigns in `new` that are not in `current` yet
+fn add_callsigns(current: &mut Vec<String>, new: Vec<String>) {
+    for callsign in new {
+        if !current.iter().any(|c| c.eq_ignore_ascii_case(&callsign)) {
+            current.push(callsign);
+        }
+    }
+}
+
+/// Short description of a condition's items for the field menu
+fn summary(items: &[String]) -> String {
+    const SHOWN: usize = 5;
+    match items.len() {
+        0 => "any".to_string(),
+        n if n <= SHOWN => items.join(", "),
+        n => format!("{}, ... ({} total)", items[..SHOWN].join(", "), n),
+    }
+}
+
+/// Let the user pick from `options`, with the items in `current` preselected.
+/// Current items missing from `options` are offered too.
+fn choose_items(
+    prompt: &str,
+    options: &[&str],
+    current: &[String],
+) -> Result<Vec<String>, CliError> {
+    let mut options: Vec<String> = options.iter().map(|o| o.to_string()).collect();
+    for item in current {
+        if !options.iter().any(|o| o.eq_ignore_ascii_case(item)) {
+            options.push(item.clone());
+        }
+    }
+    let selected: Vec<usize> = options
+        .iter()
+        .enumerate()
+        .filter(|(_, o)| current.iter().any(|c| c.eq_ignore_ascii_case(o)))
+        .map(|(i, _)| i)
+        .collect();
+    Ok(MultiSelect::new(prompt, options)
+        .with_default(&selected)
+        .prompt()?)
+}
+
+fn edit_callsigns(conditions: &mut Map<String, Value>) -> Result<(), CliError> {
+    const ADD: &str = "Add callsigns";
+    const REMOVE: &str = "Remove callsigns";
+    const BACK: &str = "Back";
+
+    let mut callsigns = list_items(conditions, "callsign");
+    match Select::new("Callsigns:", vec![ADD, REMOVE, BACK]).prompt()? {
+        ADD => {
+            let input = Text::new("Callsigns to add (separated by spaces or commas):").prompt()?;
+            let new: Vec<String> = input
+                .split([',', ' ', '\n'])
+                .filter(|c| !c.trim().is_empty())
+                .map(|c| c.trim().to_string())
+                .collect();
+            match validate_patterns(&new) {
+                Ok(new) => add_callsigns(&mut callsigns, new),
+                Err(e) => println!("{}", e),
+            }
+        }
+        REMOVE if !callsigns.is_empty() => {
+            let removed = MultiSelect::new("Callsigns to remove:", callsigns.clone()).prompt()?;
+            callsigns.retain(|c| !removed.contains(c));
+        }
+        _ => return Ok(()),
+    }
+    set_list(
+        conditions,
+        "callsign",
+        callsigns.into_iter().map(Value::from).collect(),
+    );
+    Ok(())
+}
+
+fn edit_zones(
+    conditions: &mut Map<String, Value>,
+    key: &str,
+    label: &str,
+    max: u8,
+) -> Result<(), CliError> {
+    let current = list_items(conditions, key).join(", ");
+    loop {
+        let input = Text::new(&format!("{} (1-{}, empty for any):", label, max))
+            .with_initial_value(&current)
+            .prompt()?;
+        match parse_zones(&input, max) {
+            Ok(zones) => {
+                set_list(
+                    conditions,
+                    key,
+                    zones.into_iter().map(Value::from).collect(),
+                );
+                return Ok(());
+            }
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+/// Walk the user through changing `trigger` field by field. Returns the
+/// edited trigger, or None if the changes were discarded.
+pub(crate) fn guided_edit(trigger: &Trigger) -> Result<Option<EditableTrigger>, CliError> {
+    const SAVE: &str = "Save";
+    const DISCARD: &str = "Discard changes";
+
+    let mut editable = EditableTrigger::from_trigger(trigger);
+    let mut conditions = match &editable.conditions {
+        Value::Object(map) => map.clone(),
+        _ => Map::new(),
+    };
+    let bands: Vec<&str> = BANDS.iter().map(|(_, _, band)| *band).collect();
+    let actions: Vec<&str> = Action::value_variants()
+        .iter()
+        .map(|a| a.as_str())
+        .collect();
+
+    loop {
+        let fields = vec![
+            format!(
+                "Callsigns: {}",
+                summary(&list_items(&conditions, "callsign"))
+            ),
+            format!("Bands: {}", summary(&list_items(&conditions, "band"))),
+            format!("Modes: {}", summary(&list_items(&conditions, "mode"))),
+            format!("CQ zones: {}", summary(&list_items(&conditions, "cq"))),
+            format!("ITU zones: {}", summary(&list_items(&conditions, "itu"))),
+            format!("Actions: {}", summary(&editable.actions)),
+            format!("Comment: {}", editable.comment),
+            SAVE.to_string(),
+            DISCARD.to_string(),
+        ];
+        let choice = Select::new("Change which field?", fields).raw_prompt()?;
+        match choice.index {
+            0 => edit_callsigns(&mut conditions)?,
+            1 => {
+                let chosen = choose_items("Bands:", &bands, &list_items(&conditions, "band"))?;
+                set_list(
+                    &mut conditions,
+                    "band",
+                    chosen.into_iter().map(Value::from).collect(),
+                );
+            }
+            2 => {
+                let chosen = choose_items("Modes:", &MODES, &list_items(&conditions, "mode"))?;
+                set_list(
+                    &mut conditions,
+                    "mode",
+                    chosen.into_iter().map(Value::from).collect(),
+                );
+            }
+            3 => edit_zones(&mut conditions, "cq", "CQ zones", CQ_ZONES)?,
+            4 => edit_zones(&mut conditions, "itu", "ITU zones", ITU_ZONES)?,
+            5 => editable.actions = choose_items("Actions:", &actions, &editable.actions)?,
+            6 => {
+                editable.comment = Text::new("Comment:")
+                    .with_initial_value(&editable.comment)
+                    .prompt()?;
+            }
+            7 => break,
+            _ => return Ok(None),
+        }
+    }
+    editable.conditions = Value::Object(conditions);
+    Ok(Some(editable))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn map(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_set_list() {
+        let mut conditions = map(json!({"callsign": "W1AW", "band": "20m,40m"}));
+        set_list(
+            &mut conditions,
+            "callsign",
+            vec![json!("W1AW"), json!("K3LR")],
+        );
+        set_list(&mut conditions, "band", vec![json!("15m")]);
+        set_list(&mut conditions, "cq", vec![json!(5), json!(14)]);
+        assert_eq!(
+            Value::Object(conditions.clone()),
+            json!({"callsign": "W1AW,K3LR", "band": "15m", "cq": [5, 14]})
+        );
+        assert_eq!(list_items(&conditions, "cq"), vec!["5", "14"]);
+
+        set_list(&mut conditions, "band", Vec::new());
+        assert_eq!(conditions.get("band"), None);
+    }
+
+    #[test]
+    fn test_parse_zones() {
+        assert_eq!(parse_zones("14, 5 5", CQ_ZONES).unwrap(), vec![5, 14]);
+        assert_eq!(parse_zones("", CQ_ZONES).unwrap(), Vec::<u8>::new());
+        assert!(parse_zones("41", CQ_ZONES).is_err());
+        assert!(parse_zones("0", ITU_ZONES).is_err());
+        assert!(parse_zones("eu", ITU_ZONES).is_err());
+    }
+
+    #[test]
+    fn test_add_callsigns_and_summary() {
+        let mut callsigns = vec!["W1AW".to_string()];
+        add_callsigns(&mut callsigns, vec!["w1aw".to_string(), "K3LR".to_string()]);
+        assert_eq!(callsigns, vec!["W1AW", "K3LR"]);
+        assert_eq!(summary(&callsigns), "W1AW, K3LR");
+        assert_eq!(summary(&[]), "any");
+        let many: Vec<String> = (1..=7).map(|n| format!("K{}A", n)).collect();
+        assert_eq!(summary(&many), "K1A, K2A, K3A, K4A, K5A, ... (7 total)");
+    }
+}
@@ -0,0 +1,642 @@
+//! The config file: loading, credentials, aliases and per-account settings.
+
+use crate::cli::{Action, CallsignFormat, Cli, Mode};
+use crate::error::CliError;
+use crate::feed::{HAMALERT_TELNET_HOST, HAMALERT_TELNET_PORT};
+use crate::pipeline::{Destination, Route, shell_command};
+use hamalert_api::HamAlertClient;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Default)]
+pub(crate) struct Config {
+    #[serde(default)]
+    pub(crate) username: String,
+    #[serde(default)]
+    pub(crate) password: String,
+    /// Command whose output is used as the password (e.g. "pass show hamalert")
+    pub(crate) password_command: Option<String>,
+    /// Overall timeout for each HamAlert request, in seconds
+    pub(crate) timeout_secs: Option<u64>,
+    /// Timeout for establishing connections, in seconds
+    pub(crate) connect_timeout_secs: Option<u64>,
+    /// Where received spots are forwarded by the serve commands
+    #[serde(default)]
+    pub(crate) destinations: Vec<Destination>,
+    /// Routing of received spots by trigger
+    #[serde(default)]
+    pub(crate) watch: WatchConfig,
+    /// Telnet feed settings, for accounts with a separate telnet password
+    #[serde(default)]
+    pub(crate) telnet: TelnetConfig,
+    /// Defaults for add-trigger and the import commands
+    #[serde(default)]
+    pub(crate) defaults: TriggerDefaults,
+    /// Account limits used by whoami and the import warnings
+    #[serde(default)]
+    pub(crate) limits: AccountLimits,
+    /// Run prune-expired before every command that talks to HamAlert
+    #[serde(default)]
+    pub(crate) auto_prune_expired: bool,
+    /// Additional named accounts (`[accounts.<name>]`), used by copy-account
+    #[serde(default)]
+    pub(crate) accounts: HashMap<String, AccountCredentials>,
+    /// Settings for publishing trigger sets with `share`
+    #[serde(default)]
+    pub(crate) share: ShareConfig,
+}
+
+/// Credentials for one `[accounts.<name>]` section
+#[derive(Deserialize, Default)]
+pub(crate) struct AccountCredentials {
+    pub(crate) username: String,
+    #[serde(default)]
+    pub(crate) password: String,
+    pub(crate) password_command: Option<String>,
+}
+
+/// The subset of the config read before argument parsing
+#[derive(Deserialize, Default)]
+pub(crate) struct EarlyConfig {
+    /// User-defined command aliases (`[aliases]` table)
+    #[serde(default)]
+    pub(crate) aliases: HashMap<String, String>,
+    /// Directory for backups, profiles and state instead of the platform one
+    pub(crate) data_dir: Option<PathBuf>,
+    /// Directory for backups, e.g. a synced folder, instead of the data directory
+    pub(crate) backup_dir: Option<PathBuf>,
+}
+
+/// Optional `[defaults]` section of the config file. Flags given on the
+/// command line always win.
+#[derive(Deserialize, Default)]
+pub(crate) struct TriggerDefaults {
+    #[serde(default)]
+    pub(crate) actions: Vec<Action>,
+    #[serde(default)]
+    pub(crate) mode: Vec<Mode>,
+    pub(crate) callsign_format: Option<CallsignFormat>,
+    /// Keep a "(N calls)" suffix on the comments of multi-callsign triggers
+    #[serde(default)]
+    pub(crate) count_suffix: bool,
+}
+
+/// Approximate HamAlert account limits, overridable in a `[limits]` section
+/// in case HamAlert changes them
+#[derive(Deserialize)]
+pub(crate) struct AccountLimits {
+    #[serde(default = "default_max_triggers")]
+    pub(crate) max_triggers: usize,
+    #[serde(default = "default_max_callsigns_per_trigger")]
+    pub(crate) max_callsigns_per_trigger: usize,
+}
+
+pub(crate) fn default_max_triggers() -> usize {
+    500
+}
+
+pub(crate) fn default_max_callsigns_per_trigger() -> usize {
+    1000
+}
+
+impl Default for AccountLimits {
+    fn default() -> Self {
+        Self {
+            max_triggers: default_max_triggers(),
+            max_callsigns_per_trigger: default_max_callsigns_per_trigger(),
+        }
+    }
+}
+
+/// Share of a limit at which whoami and imports start warning
+pub(crate) const LIMIT_WARNING_PERCENT: usize = 90;
+
+/// Optional `[share]` section of the config file
+#[derive(Deserialize, Default)]
+pub(crate) struct ShareConfig {
+    /// GitHub token with the gist scope, used when GITHUB_TOKEN is not set
+    pub(crate) github_token: Option<String>,
+}
+
+/// Optional `[watch]` section of the config file
+#[derive(Deserialize, Default)]
+pub(crate) struct WatchConfig {
+    /// Destinations for the spots of particular triggers (`[[watch.routes]]`)
+    #[serde(default)]
+    pub(crate) routes: Vec<Route>,
+    /// Drop repeats of the same call and frequency seen within this many minutes
+    pub(crate) dedupe_minutes: Option<u64>,
+}
+
+/// Optional `[telnet]` section of the config file
+#[derive(Deserialize, Default)]
+pub(crate) struct TelnetConfig {
+    /// Telnet password, if different from the web password
+    pub(crate) password: Option<String>,
+    /// Telnet host override
+    pub(crate) host: Option<String>,
+    /// Telnet port override
+    pub(crate) port: Option<u16>,
+}
+
+/// Everything needed to log in to the HamAlert telnet feed
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TelnetLogin {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) username: String,
+    pub(crate) password: String,
+}
+
+impl Config {
+    /// Whether a route selects triggers by tag, which takes a HamAlert
+    /// session to look up the tagged triggers
+    pub(crate) fn routes_by_tag(&self) -> bool {
+        self.watch.routes.iter().any(|route| route.tag.is_some())
+    }
+
+    /// Username and password of a named account from the `[accounts]` table
+    pub(crate) fn account_login(&self, name: &str) -> Result<(String, String), CliError> {
+        let Some(account) = self.accounts.get(name) else {
+            let mut known: Vec<&str> = self.accounts.keys().map(|k| k.as_str()).collect();
+            known.sort();
+            return Err(CliError::Config(format!(
+                "No account named '{}' in the config (known accounts: {})",
+                name,
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            )));
+        };
+        let password = match &account.password_command {
+            Some(command) if account.password.is_empty() => run_password_command(command)?,
+            _ => account.password.clone(),
+        };
+        if account.username.is_empty() || password.is_empty() {
+            return Err(CliError::Config(format!(
+                "Account '{}' needs a username and a password or password_command",
+                name
+            )));
+        }
+        Ok((account.username.clone(), password))
+    }
+
+    /// HamAlert client with the configured timeouts.
+    /// `timeout_override` comes from --timeout and wins over the config file.
+    pub(crate) fn api_client(
+        &self,
+        timeout_override: Option<u64>,
+    ) -> hamalert_api::Result<HamAlertClient> {
+        let timeout = timeout_override
+            .or(self.timeout_secs)
+            .unwrap_or(hamalert_api::DEFAULT_TIMEOUT_SECS);
+        let connect_timeout = self
+            .connect_timeout_secs
+            .unwrap_or(hamalert_api::DEFAULT_CONNECT_TIMEOUT_SECS);
+        HamAlertClient::with_timeouts(
+            std::time::Duration::from_secs(timeout),
+            std::time::Duration::from_secs(connect_timeout),
+        )
+    }
+
+    /// Telnet login details, falling back to the web credentials and the default feed address
+    pub(crate) fn telnet_login(&self) -> TelnetLogin {
+        TelnetLogin {
+            host: self
+                .telnet
+                .host
+                .clone()
+                .unwrap_or_else(|| HAMALERT_TELNET_HOST.to_string()),
+            port: self.telnet.port.unwrap_or(HAMALERT_TELNET_PORT),
+            username: self.username.clone(),
+            password: self
+                .telnet
+                .password
+                .clone()
+                .unwrap_or_else(|| self.password.clone()),
+        }
+    }
+}
+
+/// Resolve the config file location: --config-file, or ~/.config/hamalert/config.toml
+pub(crate) fn resolve_config_path(config_file: Option<PathBuf>) -> Result<PathBuf, CliError> {
+    if let Some(path) = config_file {
+        return Ok(path);
+    }
+    // Use XDG_CONFIG_HOME or default to ~/.config
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| CliError::Config("Could not determine config directory".to_string()))?
+        .join("hamalert");
+    Ok(config_dir.join("config.toml"))
+}
+
+/// Split an alias definition into words, honoring single and double quotes
+pub(crate) fn split_words(line: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+/// Replace a user-defined alias in the command position with its expansion.
+/// Built-in commands always win over aliases of the same name.
+pub(crate) fn expand_aliases(
+    args: Vec<String>,
+    aliases: &HashMap<String, String>,
+    builtins: &[String],
+) -> Vec<String> {
+    let value_options = Cli::value_options();
+    let mut index = 1;
+    while index < args.len() {
+        let arg = &args[index];
+        if value_options.contains(arg) {
+            index += 2;
+        } else if arg.starts_with('-') {
+            index += 1;
+        } else {
+            break;
+        }
+    }
+
+    let Some(expansion) = args
+        .get(index)
+        .filter(|name| !builtins.contains(name))
+        .and_then(|name| aliases.get(name))
+    else {
+        return args;
+    };
+
+    let mut expanded = args[..index].to_vec();
+    expanded.extend(split_words(expansion));
+    expanded.extend(args[index + 1..].iter().cloned());
+    expanded
+}
+
+/// Find --config-file in raw arguments, before clap has parsed them
+pub(crate) fn config_file_arg(args: &[String]) -> Option<PathBuf> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == "--config-file" {
+            args.get(i + 1).map(PathBuf::from)
+        } else {
+            arg.strip_prefix("--config-file=").map(PathBuf::from)
+        }
+    })
+}
+
+/// Read aliases and the directories from the config file, ignoring a
+/// missing or invalid file so that the normal error reporting happens after
+/// parsing
+pub(crate) fn load_early_config(config_file: Option<PathBuf>) -> EarlyConfig {
+    resolve_config_path(config_file)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| toml::from_str::<EarlyConfig>(&content).ok())
+        .unwrap_or_default()
+}
+
+/// `path` with a leading `~` replaced by the home directory
+pub(crate) fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Environment variables that override the config file credentials
+pub(crate) const ENV_USERNAME: &str = "HAMALERT_USERNAME";
+
+pub(crate) const ENV_PASSWORD: &str = "HAMALERT_PASSWORD";
+
+/// Override config credentials with values from the environment
+pub(crate) fn apply_env_credentials(config: &mut Config, env: impl Fn(&str) -> Option<String>) {
+    if let Some(username) = env(ENV_USERNAME) {
+        config.username = username;
+    }
+    if let Some(password) = env(ENV_PASSWORD) {
+        config.password = password;
+    }
+}
+
+fn parse_config(content: &str) -> Result<Config, CliError> {
+    toml::from_str(content)
+        .map_err(|e| CliError::Config(format!("Failed to parse config file: {}", e)))
+}
+
+/// Load the config file without credentials, for commands that only log in
+/// when their settings call for it. Without a config file, the defaults apply.
+pub(crate) fn load_settings(config_file: Option<PathBuf>) -> Result<Config, CliError> {
+    let config_path = resolve_config_path(config_file)?;
+    match fs::read_to_string(&config_path) {
+        Ok(content) => parse_config(&content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(CliError::Config(format!(
+            "Failed to read config file at {}: {}",
+            config_path.display(),
+            e
+        ))),
+    }
+}
+
+/// Run a `password_command` and use the first line of its output as the password
+pub(crate) fn run_password_command(command: &str) -> Result<String, CliError> {
+    let output = shell_command(command)
+        .as_std_mut()
+        .stderr(std::process::Stdio::inherit())
+        .output()
+        .map_err(|e| {
+            CliError::Config(format!(
+                "Failed to run password_command '{}': {}",
+                command, e
+            ))
+        })?;
+    if !output.status.success() {
+        return Err(CliError::Config(format!(
+            "password_command '{}' failed with {}",
+            command, output.status
+        )));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let password = stdout
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim_end()
+        .to_string();
+    if password.is_empty() {
+        return Err(CliError::Config(format!(
+            "password_command '{}' printed no password",
+            command
+        )));
+    }
+    Ok(password)
+}
+
+/// Load the config file, applying `password_command`, HAMALERT_USERNAME/HAMALERT_PASSWORD
+/// and, with `prompt_password`, asking for the password on the terminal. The
+/// file may be missing when the credentials come from the environment.
+pub(crate) fn load_config(
+    config_file: Option<PathBuf>,
+    prompt_password: bool,
+) -> Result<Config, CliError> {
+    let config_path = resolve_config_path(config_file)?;
+    let env = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+
+    let mut config = match fs::read_to_string(&config_path) {
+        Ok(content) => parse_config(&content)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && env(ENV_USERNAME).is_some() => {
+            Config::default()
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(CliError::Config(format!(
+                "Config file not found at: {}\n\n\
+                Run 'hamalert-cli config init' to create one, or create it by hand \
+                with the following format:\n\n\
+                username = \"your_username\"\n\
+                password = \"your_password\"\n\n\
+                Alternatively set {} and {}.",
+                config_path.display(),
+                ENV_USERNAME,
+                ENV_PASSWORD
+            )));
+        }
+        Err(e) => {
+            return Err(CliError::Config(format!(
+                "Failed to read config file at {}: {}",
+                config_path.display(),
+                e
+            )));
+        }
+    };
+
+    if let Some(command) = &config.password_command
+        && env(ENV_PASSWORD).is_none()
+        && !prompt_password
+    {
+        config.password = run_password_command(command)?;
+    }
+    apply_env_credentials(&mut config, env);
+    if prompt_password {
+        config.password =
+            inquire::Password::new(&format!("HamAlert password for {}:", config.username))
+                .without_confirmation()
+                .prompt()?;
+    }
+
+    if config.username.is_empty() {
+        return Err(CliError::Config(format!(
+            "No username in {} (set username or {})",
+            config_path.display(),
+            ENV_USERNAME
+        )));
+    }
+    if config.password.is_empty() {
+        return Err(CliError::Config(format!(
+            "No password in {} (set password, {} or use --prompt-password)",
+            config_path.display(),
+            ENV_PASSWORD
+        )));
+    }
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{CliError, EXIT_CONFIG};
+
+    #[test]
+    fn test_telnet_login_defaults_to_web_credentials() {
+        let config: Config = toml::from_str("username = \"W1AW\"\npassword = \"web\"").unwrap();
+        assert_eq!(
+            config.telnet_login(),
+            TelnetLogin {
+                host: HAMALERT_TELNET_HOST.to_string(),
+                port: HAMALERT_TELNET_PORT,
+                username: "W1AW".to_string(),
+                password: "web".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_telnet_login_overrides() {
+        let config: Config = toml::from_str(
+            r#"
+            username = "W1AW"
+            password = "web"
+
+            [telnet]
+            password = "telnet"
+            port = 7000
+            "#,
+        )
+        .unwrap();
+        let login = config.telnet_login();
+        assert_eq!(login.password, "telnet");
+        assert_eq!(login.port, 7000);
+        assert_eq!(login.host, HAMALERT_TELNET_HOST);
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            split_words(r#"add-trigger --comment "SOTA chasers" --actions 'app'"#),
+            vec![
+                "add-trigger",
+                "--comment",
+                "SOTA chasers",
+                "--actions",
+                "app"
+            ]
+        );
+        assert!(split_words("   ").is_empty());
+    }
+
+    #[test]
+    fn test_expand_aliases() {
+        let aliases = HashMap::from([
+            ("sota".to_string(), "add-trigger --actions app".to_string()),
+            ("list".to_string(), "backup".to_string()),
+        ]);
+        let builtins = vec!["list".to_string(), "add-trigger".to_string()];
+        let args = |s: &str| s.split(' ').map(String::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            expand_aliases(
+                args("hamalert-cli --config-file c.toml sota --callsign W1AW"),
+                &aliases,
+                &builtins
+            ),
+            args("hamalert-cli --config-file c.toml add-trigger --actions app --callsign W1AW")
+        );
+        // Built-in commands cannot be shadowed
+        assert_eq!(
+            expand_aliases(args("hamalert-cli list"), &aliases, &builtins),
+            args("hamalert-cli list")
+        );
+        assert_eq!(
+            expand_aliases(args("hamalert-cli -q backup"), &aliases, &builtins),
+            args("hamalert-cli -q backup")
+        );
+        assert_eq!(
+            expand_aliases(args("hamalert-cli --timeout 60 sota"), &aliases, &builtins),
+            args("hamalert-cli --timeout 60 add-trigger --actions app")
+        );
+        assert_eq!(
+            expand_aliases(
+                args("hamalert-cli --log-file cli.log sota"),
+                &aliases,
+                &builtins
+            ),
+            args("hamalert-cli --log-file cli.log add-trigger --actions app")
+        );
+    }
+
+    #[test]
+    fn test_apply_env_credentials() {
+        let mut config: Config =
+            toml::from_str("username = \"W1AW\"\npassword = \"file\"").unwrap();
+        apply_env_credentials(&mut config, |key| {
+            (key == ENV_PASSWORD).then(|| "env".to_string())
+        });
+        assert_eq!(config.username, "W1AW");
+        assert_eq!(config.password, "env");
+
+        let mut config = Config::default();
+        apply_env_credentials(&mut config, |key| Some(key.to_lowercase()));
+        assert_eq!(config.username, "hamalert_username");
+        assert_eq!(config.password, "hamalert_password");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_password_command() {
+        assert_eq!(
+            run_password_command("printf 'hunter2\\nurl: example'").unwrap(),
+            "hunter2"
+        );
+        assert!(run_password_command("exit 1").is_err());
+        assert!(run_password_command("true").is_err());
+    }
+
+    #[test]
+    fn test_password_command_failure_is_a_config_error() {
+        let err = run_password_command("exit 3").unwrap_err();
+        assert!(matches!(err, CliError::Config(_)), "{:?}", err);
+        assert_eq!(err.exit_code(), EXIT_CONFIG);
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home(Path::new("~/hamalert")), home.join("hamalert"));
+        assert_eq!(expand_home(Path::new("~")), home);
+        assert_eq!(
+            expand_home(Path::new("/media/usb/~data")),
+            PathBuf::from("/media/usb/~data")
+        );
+    }
+
+    #[test]
+    fn test_early_config_data_dir() {
+        let early: EarlyConfig = toml::from_str(
+            "username = \"W1AW\"\ndata_dir = \"~/dotfiles/hamalert\"\nbackup_dir = \"/mnt/nas\"\n[aliases]\nls = \"list\"\n",
+        )
+        .unwrap();
+        assert_eq!(early.data_dir, Some(PathBuf::from("~/dotfiles/hamalert")));
+        assert_eq!(early.backup_dir, Some(PathBuf::from("/mnt/nas")));
+        assert_eq!(early.aliases["ls"], "list");
+    }
+
+    #[test]
+    fn test_routes_by_tag() {
+        let config: Config = toml::from_str(
+            r#"
+            [[watch.routes]]
+            comment = "Club"
+            destinations = [{ type = "stdout" }]
+            "#,
+        )
+        .unwrap();
+        assert!(!config.routes_by_tag());
+        let config: Config = toml::from_str(
+            r#"
+            [[watch.routes]]
+            tag = "friends"
+            destinations = [{ type = "stdout" }]
+            "#,
+        )
+        .unwrap();
+        assert!(config.routes_by_tag());
+    }
+}
@@ -0,0 +1,608 @@
+//! Declarative trigger files and reconciling them against the live account.
+
+use crate::cli::{Action, Mode};
+use crate::config::TriggerDefaults;
+use crate::error::CliError;
+use crate::model::{StoredTrigger, callsign_count, strip_callsign_count, with_callsign_count};
+use crate::output::{GREEN, RED, YELLOW, format_trigger_fields, paint};
+use crate::storage::profiles::partition_permanent;
+use crate::storage::state::{State, slugify};
+use hamalert_api::Trigger;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// A trigger file for `apply`: every trigger the account should have
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TriggerFile {
+    #[serde(default)]
+    trigger: Vec<DesiredTrigger>,
+}
+
+/// One `[[trigger]]` entry. The name (by default derived from the comment)
+/// identifies the trigger, so names and comments must be unique in the file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DesiredTrigger {
+    pub(crate) name: Option<String>,
+    pub(crate) comment: String,
+    #[serde(default)]
+    pub(crate) callsign: Vec<String>,
+    #[serde(default)]
+    pub(crate) mode: Vec<Mode>,
+    #[serde(default)]
+    pub(crate) actions: Vec<Action>,
+    /// Further HamAlert conditions (band, continent, ...) passed through as-is
+    #[serde(default)]
+    pub(crate) conditions: serde_json::Map<String, Value>,
+    pub(crate) options: Option<Value>,
+    pub(crate) disabled: Option<bool>,
+    /// Recorded in the local state only
+    #[serde(default)]
+    pub(crate) tags: Vec<String>,
+    pub(crate) expires: Option<String>,
+}
+
+impl DesiredTrigger {
+    /// Name the trigger is tracked under in the local state
+    pub(crate) fn state_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| slugify(&self.comment))
+    }
+    /// Conditions in the shape HamAlert stores them
+    pub(crate) fn conditions_value(&self) -> Value {
+        let mut conditions = self.conditions.clone();
+        if !self.callsign.is_empty() {
+            conditions.insert("callsign".to_string(), json!(self.callsign.join(",")));
+        }
+        if !self.mode.is_empty() {
+            let modes: Vec<&str> = self.mode.iter().map(|m| m.as_str()).collect();
+            conditions.insert("mode".to_string(), json!(modes.join(",")));
+        }
+        Value::Object(conditions)
+    }
+
+    pub(crate) fn action_strings(&self) -> Vec<String> {
+        self.actions
+            .iter()
+            .map(|a| a.as_str().to_string())
+            .collect()
+    }
+
+    /// The trigger to send: `live` with the desired fields applied, or a new
+    /// trigger when there is no live counterpart
+    pub(crate) fn to_trigger(&self, live: Option<&Trigger>) -> Trigger {
+        let mut trigger = live.cloned().unwrap_or_else(|| Trigger {
+            id: String::new(),
+            user_id: None,
+            conditions: Value::Null,
+            actions: Vec::new(),
+            comment: String::new(),
+            match_count: None,
+            disabled: None,
+            options: None,
+        });
+        trigger.conditions = self.conditions_value();
+        trigger.actions = self.action_strings();
+        trigger.comment = self.comment.clone();
+        if self.options.is_some() {
+            trigger.options = self.options.clone();
+        }
+        if self.disabled.is_some() {
+            trigger.disabled = self.disabled;
+        }
+        trigger
+    }
+
+    /// The entry with a "(N calls)" suffix on its comment. The name stays the
+    /// one derived from the plain comment, so it survives count changes.
+    pub(crate) fn with_count_suffix(mut self) -> Self {
+        self.name = Some(self.state_name());
+        self.comment = with_callsign_count(&self.comment, callsign_count(&self.conditions_value()));
+        self
+    }
+
+    /// Whether `live` differs from this entry. Callsign and mode lists are
+    /// compared as sets; options and disabled only when the file sets them.
+    pub(crate) fn drifted_from(&self, live: &Trigger) -> bool {
+        !changed_fields(live, self).is_empty()
+    }
+}
+
+/// Conditions with every list (comma-separated or array) turned into a
+/// sorted, upper-cased array so equivalent triggers compare equal
+pub(crate) fn normalize_conditions(conditions: &Value) -> Value {
+    let Value::Object(map) = conditions else {
+        return conditions.clone();
+    };
+    let items = |value: &Value| -> Option<Vec<String>> {
+        let mut items: Vec<String> = match value {
+            Value::String(s) => s
+                .split([',', '\n'])
+                .map(|item| item.trim().to_uppercase())
+                .filter(|item| !item.is_empty())
+                .collect(),
+            Value::Array(values) => values
+                .iter()
+                .map(|v| match v {
+                    Value::String(s) => s.trim().to_uppercase(),
+                    other => other.to_string(),
+                })
+                .collect(),
+            _ => return None,
+        };
+        items.sort();
+        items.dedup();
+        Some(items)
+    };
+    Value::Object(
+        map.iter()
+            .map(|(key, value)| {
+                let value = items(value).map(|i| json!(i)).unwrap_or(value.clone());
+                (key.clone(), value)
+            })
+            .collect(),
+    )
+}
+
+/// Read and validate a trigger file
+pub(crate) fn load_trigger_file(path: &Path) -> Result<Vec<DesiredTrigger>, CliError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| CliError::io(format!("Failed to read {}", path.display()), e))?;
+    parse_trigger_file(&content)
+        .map_err(|e| CliError::Validation(format!("{}: {}", path.display(), e)))
+}
+
+/// The entries of a trigger file as they should be on the account, with
+/// comment count suffixes when `[defaults] count_suffix` is set
+pub(crate) fn desired_triggers(
+    path: &Path,
+    defaults: &TriggerDefaults,
+) -> Result<Vec<DesiredTrigger>, CliError> {
+    let desired = load_trigger_file(path)?;
+    Ok(if defaults.count_suffix {
+        desired
+            .into_iter()
+            .map(DesiredTrigger::with_count_suffix)
+            .collect()
+    } else {
+        desired
+    })
+}
+
+fn parse_trigger_file(content: &str) -> Result<Vec<DesiredTrigger>, String> {
+    let file: TriggerFile = toml::from_str(content).map_err(|e| e.to_string())?;
+    let mut seen = HashSet::new();
+    let mut names = HashSet::new();
+    for trigger in &file.trigger {
+        if trigger.comment.trim().is_empty() {
+            return Err("every [[trigger]] needs a comment to identify it".to_string());
+        }
+        if !seen.insert(trigger.comment.as_str()) {
+            return Err(format!("duplicate trigger comment '{}'", trigger.comment));
+        }
+        if !names.insert(trigger.state_name()) {
+            return Err(format!("duplicate trigger name '{}'", trigger.state_name()));
+        }
+        if let Some(date) = &trigger.expires
+            && chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err()
+        {
+            return Err(format!(
+                "trigger '{}' expires must be YYYY-MM-DD, got '{}'",
+                trigger.comment, date
+            ));
+        }
+        if trigger.callsign.is_empty() && trigger.conditions.is_empty() {
+            return Err(format!(
+                "trigger '{}' needs a callsign or other conditions",
+                trigger.comment
+            ));
+        }
+    }
+    Ok(file.trigger)
+}
+
+/// Changes needed to make the account match a trigger file
+#[derive(Debug, Default)]
+pub(crate) struct Plan {
+    pub(crate) create: Vec<DesiredTrigger>,
+    /// Live triggers paired with the entry they must be updated to
+    pub(crate) update: Vec<(Trigger, DesiredTrigger)>,
+    pub(crate) delete: Vec<Trigger>,
+    /// Live triggers that already match their entry
+    pub(crate) unchanged: Vec<(Trigger, DesiredTrigger)>,
+    /// Live triggers not in the file, left alone without --delete-unmanaged
+    pub(crate) unmanaged: Vec<Trigger>,
+}
+
+impl Plan {
+    pub(crate) fn has_changes(&self) -> bool {
+        !(self.create.is_empty() && self.update.is_empty() && self.delete.is_empty())
+    }
+
+    /// Leave permanent triggers alone instead of deleting them; returns how
+    /// many were spared
+    pub(crate) fn spare_permanent(&mut self, permanent: &[StoredTrigger]) -> usize {
+        let (spared, delete) = partition_permanent(std::mem::take(&mut self.delete), permanent);
+        self.delete = delete;
+        let count = spared.len();
+        self.unmanaged.extend(spared);
+        count
+    }
+}
+
+/// Match `desired` against `live`: by the id recorded in `state` for the
+/// entry's name, otherwise by comment, ignoring "(N calls)" suffixes. Live triggers sharing a comment beyond
+/// the first count as unmanaged.
+pub(crate) fn plan(
+    desired: &[DesiredTrigger],
+    live: &[Trigger],
+    delete_unmanaged: bool,
+    state: &State,
+) -> Plan {
+    let mut plan = Plan::default();
+    let mut matched = HashSet::new();
+    let by_id: Vec<Option<usize>> = desired
+        .iter()
+        .map(|entry| {
+            let id = &state.triggers.get(&entry.state_name())?.id;
+            live.iter().position(|t| &t.id == id)
+        })
+        .collect();
+    matched.extend(by_id.iter().flatten().copied());
+    for (entry, by_id) in desired.iter().zip(by_id) {
+        let counterpart = by_id.or_else(|| {
+            let comment = strip_callsign_count(&entry.comment);
+            (0..live.len()).find(|i| {
+                strip_callsign_count(&live[*i].comment) == comment && !matched.contains(i)
+            })
+        });
+        match counterpart {
+            Some(i) => {
+                matched.insert(i);
+                let trigger = live[i].clone();
+                if entry.drifted_from(&trigger) {
+                    plan.update.push((trigger, entry.clone()));
+                } else {
+                    plan.unchanged.push((trigger, entry.clone()));
+                }
+            }
+            None => plan.create.push(entry.clone()),
+        }
+    }
+    for (i, trigger) in live.iter().enumerate() {
+        if !matched.contains(&i) {
+            if delete_unmanaged {
+                plan.delete.push(trigger.clone());
+            } else {
+                plan.unmanaged.push(trigger.clone());
+            }
+        }
+    }
+    plan
+}
+
+/// A value as shown in a diff: lists comma-separated, strings unquoted
+fn display_value(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "(none)".to_string(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| display_value(Some(v)))
+            .collect::<Vec<_>>()
+            .join(","),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Fields an update changes, as (field, current, desired)
+pub(crate) fn changed_fields(
+    live: &Trigger,
+    entry: &DesiredTrigger,
+) -> Vec<(String, String, String)> {
+    let desired = entry.conditions_value();
+    let (current_norm, desired_norm) = (
+        normalize_conditions(&live.conditions),
+        normalize_conditions(&desired),
+    );
+    let mut keys: Vec<&String> = desired_norm
+        .as_object()
+        .into_iter()
+        .chain(current_norm.as_object())
+        .flat_map(|map| map.keys())
+        .collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut changes = Vec::new();
+    if live.comment != entry.comment {
+        changes.push((
+            "comment".to_string(),
+            live.comment.clone(),
+            entry.comment.clone(),
+        ));
+    }
+    for key in keys {
+        if current_norm.get(key) != desired_norm.get(key) {
+            changes.push((
+                key.clone(),
+                display_value(live.conditions.get(key)),
+                display_value(desired.get(key)),
+            ));
+        }
+    }
+    let (mut current_actions, mut desired_actions) = (live.actions.clone(), entry.action_strings());
+    current_actions.sort();
+    desired_actions.sort();
+    if current_actions != desired_actions {
+        changes.push((
+            "actions".to_string(),
+            current_actions.join(","),
+            desired_actions.join(","),
+        ));
+    }
+    if let Some(options) = &entry.options
+        && Some(options) != live.options.as_ref().or(Some(&json!({})))
+    {
+        changes.push((
+            "options".to_string(),
+            display_value(live.options.as_ref()),
+            options.to_string(),
+        ));
+    }
+    if let Some(disabled) = entry.disabled
+        && disabled != live.disabled.unwrap_or(false)
+    {
+        changes.push((
+            "disabled".to_string(),
+            live.disabled.unwrap_or(false).to_string(),
+            disabled.to_string(),
+        ));
+    }
+    changes
+}
+
+/// Print the planned changes, one line per trigger and one pair of lines per
+/// changed field of an update
+pub(crate) fn print_plan(plan: &Plan) {
+    for entry in &plan.create {
+        println!(
+            "  {} {}",
+            paint("+", GREEN),
+            format_trigger_fields(&entry.conditions_value(), &entry.comment, true)
+        );
+    }
+    for (live, entry) in &plan.update {
+        println!(
+            "  {} {}",
+            paint("~", YELLOW),
+            format_trigger_fields(&live.conditions, &entry.comment, true)
+        );
+        for (field, current, desired) in changed_fields(live, entry) {
+            println!("      {}", paint(&format!("- {}: {}", field, current), RED));
+            println!(
+                "      {}",
+                paint(&format!("+ {}: {}", field, desired), GREEN)
+            );
+        }
+    }
+    for trigger in &plan.delete {
+        println!(
+            "  {} {}",
+            paint("-", RED),
+            format_trigger_fields(&trigger.conditions, &trigger.comment, true)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live(id: &str, conditions: Value, actions: &[&str], comment: &str) -> Trigger {
+        serde_json::from_value(json!({
+            "_id": id,
+            "conditions": conditions,
+            "actions": actions,
+            "comment": comment,
+        }))
+        .unwrap()
+    }
+
+    const FILE: &str = r#"
+        [[trigger]]
+        comment = "Club stations"
+        callsign = ["W1AW", "K3LR"]
+        mode = ["cw"]
+        actions = ["app"]
+
+        [[trigger]]
+        comment = "20m DX"
+        actions = ["app", "threema"]
+        conditions = { band = "20m", continent = ["AF", "OC"] }
+    "#;
+
+    #[test]
+    fn test_parse_trigger_file() {
+        let triggers = parse_trigger_file(FILE).unwrap();
+        assert_eq!(triggers.len(), 2);
+        assert_eq!(
+            triggers[0].conditions_value(),
+            json!({"callsign": "W1AW,K3LR", "mode": "cw"})
+        );
+        assert_eq!(
+            triggers[1].conditions_value(),
+            json!({"band": "20m", "continent": ["AF", "OC"]})
+        );
+        assert_eq!(triggers[1].action_strings(), vec!["app", "threema"]);
+    }
+
+    #[test]
+    fn test_parse_trigger_file_rejects_invalid_entries() {
+        let duplicate = "[[trigger]]\ncomment = \"A\"\ncallsign = [\"W1AW\"]\n\
+                         [[trigger]]\ncomment = \"A\"\ncallsign = [\"K3LR\"]\n";
+        assert!(
+            parse_trigger_file(duplicate)
+                .unwrap_err()
+                .contains("duplicate")
+        );
+        let empty = "[[trigger]]\ncomment = \"A\"\n";
+        assert!(parse_trigger_file(empty).unwrap_err().contains("callsign"));
+        let unnamed = "[[trigger]]\ncomment = \"\"\ncallsign = [\"W1AW\"]\n";
+        assert!(parse_trigger_file(unnamed).is_err());
+        assert!(parse_trigger_file("[[trigger]]\ncomment = \"A\"\nmode = [\"psk\"]\n").is_err());
+    }
+
+    #[test]
+    fn test_drift_ignores_order_and_case() {
+        let desired = &parse_trigger_file(FILE).unwrap()[0];
+        let same = live(
+            "1",
+            json!({"callsign": "k3lr, w1aw", "mode": "cw"}),
+            &["app"],
+            "Club stations",
+        );
+        assert!(!desired.drifted_from(&same));
+        let fewer = live(
+            "1",
+            json!({"callsign": "W1AW", "mode": "cw"}),
+            &["app"],
+            "Club stations",
+        );
+        assert!(desired.drifted_from(&fewer));
+        let actions = live(
+            "1",
+            json!({"callsign": "W1AW,K3LR", "mode": "cw"}),
+            &["app", "url"],
+            "Club stations",
+        );
+        assert!(desired.drifted_from(&actions));
+    }
+
+    #[test]
+    fn test_plan() {
+        let desired = parse_trigger_file(FILE).unwrap();
+        let current = vec![
+            live("1", json!({"callsign": "W1AW"}), &["app"], "Club stations"),
+            live("2", json!({"callsign": "VP8PJ"}), &["app"], "DXpedition"),
+        ];
+
+        let plan = plan(&desired, &current, false, &State::default());
+        assert!(plan.has_changes());
+        assert_eq!(plan.create.len(), 1);
+        assert_eq!(plan.create[0].comment, "20m DX");
+        assert_eq!(plan.update.len(), 1);
+        assert_eq!(plan.update[0].0.id, "1");
+        assert!(plan.delete.is_empty());
+        assert_eq!(plan.unmanaged[0].id, "2");
+
+        let plan = super::plan(&desired, &current, true, &State::default());
+        assert_eq!(plan.delete[0].id, "2");
+        assert!(plan.unmanaged.is_empty());
+
+        // Permanent triggers are spared even with --delete-unmanaged
+        let mut spared = super::plan(&desired, &current, true, &State::default());
+        let permanent = [StoredTrigger::from_trigger(&current[1])];
+        assert_eq!(spared.spare_permanent(&permanent), 1);
+        assert!(spared.delete.is_empty());
+        assert_eq!(spared.unmanaged[0].id, "2");
+
+        let updated = plan.update[0].1.to_trigger(Some(&plan.update[0].0));
+        assert_eq!(updated.id, "1");
+        assert!(!plan.update[0].1.drifted_from(&updated));
+    }
+
+    #[test]
+    fn test_changed_fields() {
+        let desired = &parse_trigger_file(FILE).unwrap()[0];
+        let current = live(
+            "1",
+            json!({"callsign": "W1AW", "mode": "cw", "band": "20m"}),
+            &["app", "url"],
+            "Club stations",
+        );
+        assert_eq!(
+            changed_fields(&current, desired),
+            vec![
+                ("band".to_string(), "20m".to_string(), "(none)".to_string()),
+                (
+                    "callsign".to_string(),
+                    "W1AW".to_string(),
+                    "W1AW,K3LR".to_string()
+                ),
+                (
+                    "actions".to_string(),
+                    "app,url".to_string(),
+                    "app".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_matches_recorded_names_before_comments() {
+        use crate::storage::state::ManagedTrigger;
+
+        let desired = parse_trigger_file(
+            "[[trigger]]\nname = \"club\"\ncomment = \"Club stations (renamed)\"\n\
+             callsign = [\"W1AW\"]\nactions = [\"app\"]\n",
+        )
+        .unwrap();
+        let current = vec![live(
+            "1",
+            json!({"callsign": "W1AW"}),
+            &["app"],
+            "Club stations",
+        )];
+        let mut state = State::default();
+        state.record(
+            "club",
+            ManagedTrigger {
+                id: "1".to_string(),
+                ..ManagedTrigger::new("apply".to_string(), None, vec![])
+            },
+        );
+
+        let plan = plan(&desired, &current, true, &state);
+        assert!(plan.create.is_empty() && plan.delete.is_empty());
+        assert_eq!(plan.update.len(), 1);
+        assert_eq!(
+            changed_fields(&plan.update[0].0, &plan.update[0].1)[0],
+            (
+                "comment".to_string(),
+                "Club stations".to_string(),
+                "Club stations (renamed)".to_string()
+            )
+        );
+
+        // Without the recorded id the renamed entry is a new trigger
+        let plan = super::plan(&desired, &current, true, &State::default());
+        assert_eq!((plan.create.len(), plan.delete.len()), (1, 1));
+    }
+
+    #[test]
+    fn test_count_suffix_keeps_identity() {
+        let desired: Vec<DesiredTrigger> = parse_trigger_file(FILE)
+            .unwrap()
+            .into_iter()
+            .map(DesiredTrigger::with_count_suffix)
+            .collect();
+        assert_eq!(desired[0].comment, "Club stations (2 calls)");
+        assert_eq!(desired[0].state_name(), "club-stations");
+        assert_eq!(desired[1].comment, "20m DX");
+
+        // A live trigger with an outdated count is updated, not duplicated
+        let current = vec![live(
+            "a",
+            json!({"callsign": "W1AW", "mode": "cw"}),
+            &["app"],
+            "Club stations (1 calls)",
+        )];
+        let plan = plan(&desired[..1], &current, false, &State::default());
+        assert!(plan.create.is_empty());
+        assert_eq!(plan.update.len(), 1);
+    }
+}
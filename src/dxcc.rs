@@ -0,0 +1,339 @@
+//! DXCC entity lookup from a prefix table in the country-files.com `cty.csv`
+//! format. A subset is bundled; `lookup --update` caches the full table.
+
+use crate::callsign::parse_callsign;
+use crate::error::CliError;
+use hamalert_api::{HamAlertClient, SendLogged};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Bundled table covering the most common entities
+const BUNDLED_TABLE: &str = include_str!("../data/cty.csv");
+
+/// Where `lookup --update` downloads the full table from
+pub(crate) const CTY_URL: &str = "https://www.country-files.com/cty/cty.csv";
+
+/// A DXCC entity
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Entity {
+    pub(crate) name: String,
+    /// ADIF DXCC entity number
+    pub(crate) dxcc: u16,
+    /// Primary prefix, e.g. `VP8`
+    pub(crate) prefix: String,
+    pub(crate) continent: String,
+    pub(crate) cq_zone: u8,
+    pub(crate) itu_zone: u8,
+    pub(crate) latitude: f64,
+    /// Degrees east (the table itself counts west as positive)
+    pub(crate) longitude: f64,
+}
+
+/// Zone and continent exceptions attached to a single prefix or callsign
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Overrides {
+    cq_zone: Option<u8>,
+    itu_zone: Option<u8>,
+    continent: Option<String>,
+}
+
+/// A callsign resolved to its entity, with any per-prefix exceptions applied
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Location {
+    pub(crate) entity: Entity,
+    pub(crate) continent: String,
+    pub(crate) cq_zone: u8,
+    pub(crate) itu_zone: u8,
+}
+
+/// Prefix to entity table
+#[derive(Debug, Default)]
+pub(crate) struct PrefixTable {
+    entities: Vec<Entity>,
+    prefixes: HashMap<String, (usize, Overrides)>,
+    /// Full callsigns listed with `=`, which take precedence over prefixes
+    exact: HashMap<String, (usize, Overrides)>,
+}
+
+/// Split a prefix entry such as `=K1ABC(4)[7]{NA}` into whether it is a full
+/// callsign, the prefix itself and its overrides
+fn parse_prefix_entry(entry: &str) -> (bool, String, Overrides) {
+    let (exact, entry) = match entry.strip_prefix('=') {
+        Some(rest) => (true, rest),
+        None => (false, entry),
+    };
+    let end = entry.find(['(', '[', '{', '<', '~']).unwrap_or(entry.len());
+    let enclosed = |open: char, close: char| {
+        let start = entry[end..].find(open)? + end + 1;
+        let stop = entry[start..].find(close)? + start;
+        Some(&entry[start..stop])
+    };
+    let overrides = Overrides {
+        cq_zone: enclosed('(', ')').and_then(|z| z.parse().ok()),
+        itu_zone: enclosed('[', ']').and_then(|z| z.parse().ok()),
+        continent: enclosed('{', '}').map(str::to_string),
+    };
+    (exact, entry[..end].to_string(), overrides)
+}
+
+impl PrefixTable {
+    /// Parse `cty.csv` content: one entity per line with its primary prefix,
+    /// name, DXCC number, continent, CQ and ITU zones, latitude, longitude,
+    /// UTC offset and a space-separated prefix list ending in `;`
+    pub(crate) fn parse(content: &str) -> Result<Self, String> {
+        let mut table = PrefixTable::default();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim().trim_end_matches(';');
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.splitn(10, ',').collect();
+            let [
+                prefix,
+                name,
+                dxcc,
+                continent,
+                cq,
+                itu,
+                lat,
+                lon,
+                _offset,
+                prefixes,
+            ] = fields[..]
+            else {
+                return Err(format!("line {}: expected 10 fields", index + 1));
+            };
+            let number = |field: &str, what: &str| {
+                field
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("line {}: invalid {} '{}'", index + 1, what, field))
+            };
+            let entity = Entity {
+                name: name.to_string(),
+                dxcc: number(dxcc, "DXCC number")? as u16,
+                prefix: prefix.trim_start_matches('*').to_string(),
+                continent: continent.to_string(),
+                cq_zone: number(cq, "CQ zone")? as u8,
+                itu_zone: number(itu, "ITU zone")? as u8,
+                latitude: number(lat, "latitude")?,
+                longitude: -number(lon, "longitude")?,
+            };
+            let id = table.entities.len();
+            for entry in prefixes.split_whitespace() {
+                let (exact, key, overrides) = parse_prefix_entry(entry);
+                let map = if exact {
+                    &mut table.exact
+                } else {
+                    &mut table.prefixes
+                };
+                map.insert(key, (id, overrides));
+            }
+            table.entities.push(entity);
+        }
+        if table.entities.is_empty() {
+            return Err("no entities found".to_string());
+        }
+        Ok(table)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    fn location(&self, (id, overrides): &(usize, Overrides)) -> Location {
+        let entity = self.entities[*id].clone();
+        Location {
+            continent: overrides
+                .continent
+                .clone()
+                .unwrap_or_else(|| entity.continent.clone()),
+            cq_zone: overrides.cq_zone.unwrap_or(entity.cq_zone),
+            itu_zone: overrides.itu_zone.unwrap_or(entity.itu_zone),
+            entity,
+        }
+    }
+
+    /// Entity for a callsign (or a bare prefix such as `VP8`), using the
+    /// prefix the station operates under when portable. Maritime and
+    /// aeronautical mobile stations have none.
+    pub(crate) fn lookup(&self, call: &str) -> Option<Location> {
+        let call = call.trim().to_ascii_uppercase();
+        if let Some(entry) = self.exact.get(&call) {
+            return Some(self.location(entry));
+        }
+        let target = match parse_callsign(&call) {
+            Ok(parsed) if parsed.is_maritime() => return None,
+            Ok(parsed) => {
+                if let Some(entry) = self.exact.get(&parsed.base) {
+                    return Some(self.location(entry));
+                }
+                parsed.location_prefix().unwrap_or(&parsed.base).to_string()
+            }
+            Err(_) => call,
+        };
+        (1..=target.len())
+            .rev()
+            .filter_map(|len| target.get(..len))
+            .find_map(|prefix| self.prefixes.get(prefix))
+            .map(|entry| self.location(entry))
+    }
+
+    /// Entity named by a DXCC number, a name or a prefix
+    pub(crate) fn find_entity(&self, query: &str) -> Option<Entity> {
+        let query = query.trim();
+        if let Ok(number) = query.parse::<u16>() {
+            return self.entities.iter().find(|e| e.dxcc == number).cloned();
+        }
+        self.entities
+            .iter()
+            .find(|e| e.name.eq_ignore_ascii_case(query))
+            .cloned()
+            .or_else(|| self.lookup(query).map(|location| location.entity))
+    }
+}
+
+/// Path of the table downloaded by `lookup --update`
+pub(crate) fn cty_path() -> Result<PathBuf, CliError> {
+    let dir = dirs::cache_dir()
+        .ok_or_else(|| CliError::Config("Could not determine cache directory".to_string()))?
+        .join("hamalert");
+    Ok(dir.join("cty.csv"))
+}
+
+/// The downloaded table if there is a usable one, otherwise the bundled one
+pub(crate) fn load_table() -> Result<PrefixTable, CliError> {
+    let path = cty_path()?;
+    if let Ok(content) = fs::read_to_string(&path) {
+        match PrefixTable::parse(&content) {
+            Ok(table) => return Ok(table),
+            Err(e) => info!(
+                "Ignoring {} ({}); using the bundled prefix table",
+                path.display(),
+                e
+            ),
+        }
+    }
+    PrefixTable::parse(BUNDLED_TABLE)
+        .map_err(|e| CliError::Other(format!("Bundled prefix table: {}", e)))
+}
+
+/// Download the full table and cache it, returning the number of entities
+pub(crate) async fn update_table(client: &HamAlertClient) -> Result<usize, CliError> {
+    let response = client.http().get(CTY_URL).send_logged().await?;
+    if !response.status().is_success() {
+        return Err(CliError::Remote(format!(
+            "Failed to download {}: {}",
+            CTY_URL,
+            response.status()
+        )));
+    }
+    let content = response.text().await?;
+    let table = PrefixTable::parse(&content)
+        .map_err(|e| CliError::Remote(format!("Downloaded prefix table is invalid: {}", e)))?;
+    let path = cty_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, content)?;
+    Ok(table.len())
+}
+
+/// DXCC numbers for `--dxcc` values given as prefixes, names or numbers
+pub(crate) fn resolve_dxcc(queries: &[String]) -> Result<Vec<u16>, CliError> {
+    if queries.is_empty() {
+        return Ok(Vec::new());
+    }
+    let table = load_table()?;
+    queries
+        .iter()
+        .map(|query| {
+            // Numbers missing from a partial table are still valid entities
+            if let Ok(number) = query.trim().parse::<u16>() {
+                return Ok(number);
+            }
+            table.find_entity(query).map(|e| e.dxcc).ok_or_else(|| {
+                CliError::Validation(format!(
+                    "Unknown DXCC entity '{}'; use a prefix, name or DXCC number \
+                     (run 'lookup --update' for the full prefix table)",
+                    query
+                ))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = "\
+K,United States,291,NA,05,08,37.53,91.67,5.0,AA K N W =W1AW/KH6 =KG4XX(8)[11];
+KH6,Hawaii,110,OC,31,61,21.12,157.48,10.0,AH6 KH6 NH6 WH6;
+EA8,Canary Islands,29,AF,33,36,28.32,15.85,0.0,AM8 EA8 EB8;
+*TA1,European Turkey,390,EU,20,39,41.02,-28.97,-2.0,TA1{EU};
+";
+
+    #[test]
+    fn test_parse_prefix_entry() {
+        let (exact, prefix, overrides) = parse_prefix_entry("=KG4XX(8)[11]{NA}");
+        assert!(exact);
+        assert_eq!(prefix, "KG4XX");
+        assert_eq!(overrides.cq_zone, Some(8));
+        assert_eq!(overrides.itu_zone, Some(11));
+        assert_eq!(overrides.continent.as_deref(), Some("NA"));
+        assert_eq!(parse_prefix_entry("VP8").1, "VP8");
+    }
+
+    #[test]
+    fn test_lookup() {
+        let table = PrefixTable::parse(TABLE).unwrap();
+        let name = |call: &str| table.lookup(call).map(|l| l.entity.name);
+        assert_eq!(name("w1aw").as_deref(), Some("United States"));
+        assert_eq!(name("KH6ABC").as_deref(), Some("Hawaii"));
+        assert_eq!(name("EA8/K1ABC/P").as_deref(), Some("Canary Islands"));
+        assert_eq!(name("K1ABC/KH6").as_deref(), Some("Hawaii"));
+        assert_eq!(name("K1ABC/4").as_deref(), Some("United States"));
+        assert_eq!(name("K1ABC/MM"), None);
+        assert_eq!(name("ZZ9ZZ"), None);
+        assert_eq!(name("TA1"), Some("European Turkey".to_string()));
+
+        // Exact calls win over prefixes and carry their own zones
+        assert_eq!(name("W1AW/KH6").as_deref(), Some("United States"));
+        let location = table.lookup("KG4XX").unwrap();
+        assert_eq!((location.cq_zone, location.itu_zone), (8, 11));
+        assert_eq!(location.entity.longitude, -91.67);
+    }
+
+    #[test]
+    fn test_find_entity() {
+        let table = PrefixTable::parse(TABLE).unwrap();
+        assert_eq!(table.find_entity("110").unwrap().name, "Hawaii");
+        assert_eq!(table.find_entity("canary islands").unwrap().dxcc, 29);
+        assert_eq!(table.find_entity("AM8").unwrap().dxcc, 29);
+        assert!(table.find_entity("Atlantis").is_none());
+    }
+
+    #[test]
+    fn test_bundled_table() {
+        let table = PrefixTable::parse(BUNDLED_TABLE).unwrap();
+        let dxcc = |query: &str| table.find_entity(query).map(|e| e.dxcc);
+        assert_eq!(dxcc("VK"), Some(150));
+        assert_eq!(dxcc("ZL"), Some(170));
+        assert_eq!(dxcc("JA"), Some(339));
+        assert_eq!(dxcc("VP8"), Some(141));
+        assert_eq!(dxcc("2E0ABC"), Some(223));
+        assert_eq!(dxcc("GM4ABC"), Some(279));
+        assert_eq!(dxcc("KL7ABC"), Some(6));
+        assert_eq!(dxcc("RA9ABC"), Some(15));
+        assert_eq!(dxcc("UA3ABC"), Some(54));
+        assert_eq!(dxcc("BV2AA"), Some(386));
+        assert_eq!(dxcc("BG4AA"), Some(318));
+        assert_eq!(
+            table.lookup("EA8ABC").unwrap().entity.name,
+            "Canary Islands"
+        );
+    }
+}
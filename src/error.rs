@@ -0,0 +1,156 @@
+//! The CLI error type and its process exit codes.
+
+use inquire::InquireError;
+
+/// Exit status for generic failures
+pub(crate) const EXIT_FAILURE: i32 = 1;
+
+/// Exit status when the config file is missing or invalid
+pub(crate) const EXIT_CONFIG: i32 = 3;
+
+/// Exit status when logging in to HamAlert fails
+pub(crate) const EXIT_AUTH: i32 = 4;
+
+/// Exit status when a HamAlert request fails or is rejected
+pub(crate) const EXIT_API: i32 = 5;
+
+/// Exit status when command-line input is invalid
+pub(crate) const EXIT_VALIDATION: i32 = 6;
+
+/// Exit status when `plan` finds triggers that differ from the trigger file
+pub(crate) const EXIT_DRIFT: i32 = 7;
+
+/// Exit status when the user cancels an operation
+pub(crate) const EXIT_CANCELLED: i32 = 130;
+
+/// Everything that can make a command fail. Each kind has its own exit
+/// status, so wrapper scripts can tell failure types apart.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum CliError {
+    /// The config file is missing or invalid
+    #[error("{0}")]
+    Config(String),
+    /// Logging in or talking to HamAlert failed
+    #[error(transparent)]
+    HamAlert(#[from] hamalert_api::Error),
+    /// Command-line input is invalid
+    #[error("{0}")]
+    Validation(String),
+    /// The account does not match the trigger file given to `plan`
+    #[error("{0}")]
+    Drift(String),
+    /// The user backed out of a prompt
+    #[error("{0}")]
+    Cancelled(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A request to something other than HamAlert (PoLo notes, webhooks) failed
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    /// A service answered with an error status or not with what was asked
+    /// for (GitHub, SOTA/POTA, MQTT brokers)
+    #[error("{0}")]
+    Remote(String),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Prompt(InquireError),
+    #[error(transparent)]
+    Task(#[from] tokio::task::JoinError),
+    /// Any other failure, described by its message
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<InquireError> for CliError {
+    fn from(e: InquireError) -> Self {
+        match e {
+            InquireError::OperationCanceled | InquireError::OperationInterrupted => {
+                CliError::Cancelled("Cancelled.".to_string())
+            }
+            e => CliError::Prompt(e),
+        }
+    }
+}
+
+impl CliError {
+    /// An I/O error with what was being done, keeping its kind
+    pub(crate) fn io(context: impl std::fmt::Display, e: std::io::Error) -> Self {
+        CliError::Io(std::io::Error::new(e.kind(), format!("{}: {}", context, e)))
+    }
+
+    /// Process exit status for this error
+    pub(crate) fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Config(_) => EXIT_CONFIG,
+            CliError::HamAlert(hamalert_api::Error::Auth(_)) => EXIT_AUTH,
+            CliError::HamAlert(_) | CliError::Http(_) | CliError::Remote(_) => EXIT_API,
+            CliError::Validation(_) => EXIT_VALIDATION,
+            CliError::Drift(_) => EXIT_DRIFT,
+            CliError::Cancelled(_) => EXIT_CANCELLED,
+            CliError::Io(_)
+            | CliError::Json(_)
+            | CliError::Prompt(_)
+            | CliError::Task(_)
+            | CliError::Other(_) => EXIT_FAILURE,
+        }
+    }
+
+    /// Whether a request gave up waiting for a response
+    pub(crate) fn is_timeout(&self) -> bool {
+        match self {
+            CliError::HamAlert(e) => e.is_timeout(),
+            CliError::Http(e) => e.is_timeout(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        let code = |e: CliError| e.exit_code();
+        assert_eq!(code(CliError::Config("x".into())), EXIT_CONFIG);
+        assert_eq!(
+            code(hamalert_api::Error::Auth("x".into()).into()),
+            EXIT_AUTH
+        );
+        assert_eq!(
+            code(
+                hamalert_api::Error::Api {
+                    message: "x".into(),
+                    status: reqwest::StatusCode::BAD_GATEWAY,
+                    body: String::new(),
+                }
+                .into()
+            ),
+            EXIT_API
+        );
+        assert_eq!(
+            code(
+                hamalert_api::Error::Rejected {
+                    message: "x".into(),
+                    reason: hamalert_api::Rejection::Duplicate,
+                }
+                .into()
+            ),
+            EXIT_API
+        );
+        assert_eq!(code(CliError::Validation("x".into())), EXIT_VALIDATION);
+        assert_eq!(code(CliError::Drift("x".into())), EXIT_DRIFT);
+        assert_eq!(code(CliError::Cancelled("x".into())), EXIT_CANCELLED);
+        assert_eq!(code(InquireError::OperationCanceled.into()), EXIT_CANCELLED);
+        assert_eq!(code(CliError::Remote("x".into())), EXIT_API);
+        assert_eq!(
+            code(CliError::io(
+                "Failed to read x",
+                std::io::ErrorKind::NotFound.into()
+            )),
+            EXIT_FAILURE
+        );
+        assert_eq!(code(CliError::Other("x".into())), EXIT_FAILURE);
+    }
+}
@@ -0,0 +1,128 @@
+//! The upstream HamAlert telnet spot feed.
+
+use crate::config::TelnetLogin;
+use crate::error::CliError;
+use crate::model::parse_spot_line;
+
+pub(crate) const HAMALERT_TELNET_HOST: &str = "hamalert.org";
+
+pub(crate) const HAMALERT_TELNET_PORT: u16 = 7300;
+
+pub(crate) const TELNET_RECONNECT_DELAY_SECS: u64 = 10;
+
+/// Decode a raw line received from a telnet client, dropping telnet
+/// negotiation (IAC) sequences and other non-printable bytes.
+pub(crate) fn decode_telnet_line(raw: &[u8]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < raw.len() {
+        match raw[i] {
+            // IAC: skip the command byte and, for option negotiation, the option byte
+            0xFF => {
+                let command = raw.get(i + 1).copied().unwrap_or(0);
+                i += if (0xFB..=0xFE).contains(&command) {
+                    3
+                } else {
+                    2
+                };
+            }
+            b if b.is_ascii_graphic() || b == b' ' => {
+                out.push(b as char);
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Read from the upstream telnet connection until the given prompt appears
+pub(crate) async fn wait_for_prompt<R: tokio::io::AsyncRead + Unpin>(
+    reader: &mut R,
+    prompt: &str,
+) -> Result<(), CliError> {
+    use tokio::io::AsyncReadExt;
+
+    let mut seen = String::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("Connection closed while waiting for '{}'", prompt),
+            )
+            .into());
+        }
+        seen.push_str(&String::from_utf8_lossy(&buf[..n]).to_lowercase());
+        if seen.contains(prompt) {
+            return Ok(());
+        }
+    }
+}
+
+/// Connect to the HamAlert telnet feed and log in
+pub(crate) async fn connect_upstream(
+    login: &TelnetLogin,
+) -> Result<tokio::io::BufReader<tokio::net::TcpStream>, CliError> {
+    use tokio::io::AsyncWriteExt;
+
+    let stream = tokio::net::TcpStream::connect((login.host.as_str(), login.port)).await?;
+    let mut feed = tokio::io::BufReader::new(stream);
+
+    wait_for_prompt(&mut feed, "login:").await?;
+    feed.get_mut()
+        .write_all(format!("{}\r\n", login.username).as_bytes())
+        .await?;
+    wait_for_prompt(&mut feed, "password:").await?;
+    feed.get_mut()
+        .write_all(format!("{}\r\n", login.password).as_bytes())
+        .await?;
+
+    Ok(feed)
+}
+
+/// Read the logged-in HamAlert telnet feed and pass every spot line to `on_spot_line`.
+/// Returns when the upstream connection is closed.
+pub(crate) async fn relay_upstream_spots(
+    feed: &mut tokio::io::BufReader<tokio::net::TcpStream>,
+    mut on_spot_line: impl FnMut(String),
+) -> Result<(), CliError> {
+    use tokio::io::AsyncBufReadExt;
+
+    let mut raw = Vec::new();
+    loop {
+        raw.clear();
+        if feed.read_until(b'\n', &mut raw).await? == 0 {
+            return Ok(());
+        }
+        let line = String::from_utf8_lossy(&raw).trim_end().to_string();
+        if line.to_lowercase().contains("login failed") {
+            return Err(
+                hamalert_api::Error::Auth("HamAlert telnet login failed".to_string()).into(),
+            );
+        }
+        if parse_spot_line(&line).is_some() {
+            on_spot_line(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_telnet_line_plain() {
+        assert_eq!(decode_telnet_line(b"w1aw\r\n"), "w1aw");
+    }
+
+    #[test]
+    fn test_decode_telnet_line_strips_negotiation() {
+        // IAC WILL SUPPRESS-GO-AHEAD, IAC DO ECHO, then the callsign
+        let raw = [
+            0xFF, 0xFB, 0x03, 0xFF, 0xFD, 0x01, b'W', b'1', b'A', b'W', b'\r', b'\n',
+        ];
+        assert_eq!(decode_telnet_line(&raw), "W1AW");
+    }
+}
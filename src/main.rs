@@ -1,1754 +1,277 @@
-use chrono::Local;
-use clap::{Parser, Subcommand, ValueEnum};
-use inquire::{InquireError, MultiSelect};
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-use std::error::Error;
+use crate::api::OFFLINE;
+use crate::cli::{Cli, Commands, ServeCommands, require_terminal};
+use crate::commands::menu::prompt_main_menu;
+use crate::config::{
+    Config, config_file_arg, expand_aliases, expand_home, load_config, load_early_config,
+    load_settings,
+};
+use crate::error::CliError;
+use crate::output::{COLOR, JSON_PATH, parse_json_path};
+use clap::Parser;
 use std::fs;
-use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
 
-#[derive(Deserialize)]
-struct Config {
-    username: String,
-    password: String,
-}
-
-#[derive(Parser)]
-#[command(name = "hamalert-cli")]
-#[command(about = "CLI for HamAlert API", long_about = None)]
-struct Cli {
-    #[arg(long)]
-    config_file: Option<PathBuf>,
-
-    #[command(subcommand)]
-    command: Commands,
-}
-
-/// Shared options for trigger creation
-#[derive(Parser, Clone)]
-struct TriggerOptions {
-    #[arg(long)]
-    comment: String,
-
-    /// Notification actions (e.g., --actions app telnet)
-    #[arg(long, value_enum, num_args = 1..)]
-    actions: Vec<Action>,
-
-    /// Filter by mode (e.g., --mode cw ft8)
-    #[arg(long, value_enum, num_args = 1..)]
-    mode: Vec<Mode>,
-
-    /// Use compact format (comma-only, no spaces) for callsigns
-    #[arg(long, conflicts_with = "one_per_line")]
-    compact: bool,
-
-    /// Send callsigns one per line instead of comma-separated
-    #[arg(long, conflicts_with = "compact")]
-    one_per_line: bool,
-}
-
-/// Shared options for import commands
-#[derive(Parser, Clone)]
-struct ImportOptions {
-    #[command(flatten)]
-    trigger: TriggerOptions,
-
-    /// Show what would be added without actually adding triggers
-    #[arg(long)]
-    dry_run: bool,
-}
-
-#[derive(Subcommand)]
-enum Commands {
-    /// Add a trigger for one or more callsigns
-    AddTrigger {
-        #[arg(long)]
-        callsign: Vec<String>,
+/// Output verbosity: 0 with --quiet, 1 by default, plus one per -v
+static VERBOSITY: AtomicU8 = AtomicU8::new(1);
 
-        #[command(flatten)]
-        options: TriggerOptions,
-    },
-    /// Add triggers for all callsigns in a Ham2K PoLo callsign notes file (fetched from URL)
-    ImportPoloNotes {
-        /// URL to the Ham2K PoLo callsign notes file
-        #[arg(long)]
-        url: String,
-
-        #[command(flatten)]
-        options: ImportOptions,
-    },
-    /// Import callsigns from a local file (one callsign per line)
-    ImportFile {
-        /// Path to the callsign file
-        #[arg(long)]
-        file: PathBuf,
-
-        #[command(flatten)]
-        options: ImportOptions,
-    },
-    /// Backup all triggers to a JSON file
-    Backup {
-        /// Output file path (default: hamalert-backup-YYYY-MM-DD.json)
-        #[arg(long)]
-        output: Option<PathBuf>,
-    },
-    /// Restore triggers from a JSON backup file
-    Restore {
-        /// Input backup file path
-        #[arg(long)]
-        input: PathBuf,
-
-        /// Actually perform the restore (default is dry-run)
-        #[arg(long)]
-        no_dry_run: bool,
-    },
-    /// Interactively edit an existing trigger
-    Edit,
-    /// Interactively delete multiple triggers with TUI selection
-    BulkDelete {
-        /// Show what would be deleted without actually deleting
-        #[arg(long)]
-        dry_run: bool,
-    },
-    /// Manage trigger profiles for different locations/activities
-    #[command(subcommand)]
-    Profile(ProfileCommands),
-}
-
-#[derive(Subcommand)]
-enum ProfileCommands {
-    /// List all available profiles
-    List,
-    /// Show triggers in a profile
-    Show {
-        /// Profile name
-        name: String,
-    },
-    /// Show current profile status and match analysis
-    Status,
-    /// Save current triggers as a profile
-    Save {
-        /// Profile name
-        name: String,
-        /// Create from backup file instead of current triggers
-        #[arg(long)]
-        from_backup: Option<PathBuf>,
-    },
-    /// Switch to a different profile
-    Switch {
-        /// Profile name to switch to
-        name: String,
-        /// Actually perform the switch (default is dry-run)
-        #[arg(long)]
-        no_dry_run: bool,
-    },
-    /// Delete a profile
-    Delete {
-        /// Profile name
-        name: String,
-    },
-    /// Interactively select permanent triggers
-    SetPermanent {
-        /// Set from backup file instead of current triggers
-        #[arg(long)]
-        from_backup: Option<PathBuf>,
-    },
-    /// Show current permanent triggers
-    ShowPermanent,
-}
-
-#[derive(Clone, ValueEnum)]
-enum Action {
-    Url,
-    App,
-    Threema,
-    Telnet,
+fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
 }
 
-#[derive(Clone, ValueEnum)]
-#[allow(clippy::upper_case_acronyms)]
-enum Mode {
-    CW,
-    FT8,
-    SSB,
-}
-
-impl Action {
-    fn as_str(&self) -> &str {
-        match self {
-            Action::Url => "url",
-            Action::App => "app",
-            Action::Threema => "threema",
-            Action::Telnet => "telnet",
+/// Print informational status to stderr, suppressed by --quiet
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::verbosity() >= 1 {
+            eprintln!($($arg)*);
         }
-    }
-}
-
-impl Mode {
-    fn as_str(&self) -> &str {
-        match self {
-            Mode::CW => "cw",
-            Mode::FT8 => "ft8",
-            Mode::SSB => "ssb",
-        }
-    }
-}
-
-/// Determines how multiple callsigns are formatted when sent to HamAlert
-#[derive(Clone, Copy, Default)]
-enum CallsignFormat {
-    /// Comma-space separated: "N0CALL, K0TEST, W0XYZ"
-    #[default]
-    Default,
-    /// Comma-only (compact): "N0CALL,K0TEST,W0XYZ"
-    Compact,
-    /// One per line: "N0CALL\nK0TEST\nW0XYZ"
-    OnePerLine,
+    };
 }
 
-impl CallsignFormat {
-    fn separator(&self) -> &'static str {
-        match self {
-            CallsignFormat::Default => ", ",
-            CallsignFormat::Compact => ",",
-            CallsignFormat::OnePerLine => "\n",
+mod adif;
+mod api;
+mod callsign;
+mod cli;
+mod commands;
+mod config;
+mod declarative;
+mod dxcc;
+mod error;
+mod feed;
+mod model;
+mod output;
+mod pipeline;
+mod references;
+mod rig;
+mod solar;
+mod storage;
+
+/// Environment variable overriding the log filter (e.g. "hamalert_api=trace")
+const ENV_LOG: &str = "HAMALERT_LOG";
+
+/// Log filter for a verbosity level: warnings by default, API requests at -v
+/// and payloads at -vv. Log files also record routine activity such as
+/// trigger changes and connections.
+fn default_log_filter(level: u8, to_file: bool) -> &'static str {
+    match level {
+        0 => "error",
+        1 if to_file => "warn,hamalert_cli=info,hamalert_api=info",
+        1 => "warn,hamalert_cli=info",
+        2 => "warn,hamalert_cli=debug,hamalert_api=debug",
+        _ => "warn,hamalert_cli=trace,hamalert_api=trace",
+    }
+}
+
+/// Send timestamped logs to stderr, or to `log_file` when given
+fn init_logging(level: u8, log_file: Option<&std::path::Path>) -> Result<(), CliError> {
+    use tracing_subscriber::EnvFilter;
+
+    let filter = match std::env::var(ENV_LOG) {
+        Ok(directives) if !directives.is_empty() => EnvFilter::try_new(&directives)
+            .map_err(|e| CliError::Validation(format!("Invalid {}: {}", ENV_LOG, e)))?,
+        _ => EnvFilter::new(default_log_filter(level, log_file.is_some())),
+    };
+    let logs = tracing_subscriber::fmt().with_env_filter(filter);
+    match log_file {
+        Some(path) => {
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| {
+                    CliError::io(format!("Failed to open log file {}", path.display()), e)
+                })?;
+            logs.with_ansi(false)
+                .with_writer(std::sync::Mutex::new(file))
+                .init();
         }
-    }
-
-    fn from_flags(compact: bool, one_per_line: bool) -> Self {
-        match (compact, one_per_line) {
-            (true, _) => CallsignFormat::Compact,
-            (_, true) => CallsignFormat::OnePerLine,
-            _ => CallsignFormat::Default,
+        None => {
+            use std::io::IsTerminal;
+            logs.with_ansi(COLOR.load(Ordering::Relaxed) && std::io::stderr().is_terminal())
+                .with_writer(std::io::stderr)
+                .init();
         }
     }
-}
-
-#[derive(Serialize)]
-struct TriggerData {
-    conditions: Conditions,
-    comment: String,
-    actions: Vec<String>,
-    options: serde_json::Value,
-}
-
-#[derive(Serialize)]
-struct Conditions {
-    callsign: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    mode: Option<String>,
-}
-
-fn backup_dir() -> Result<PathBuf, Box<dyn Error>> {
-    let data_dir = dirs::data_dir()
-        .ok_or("Could not determine data directory")?
-        .join("hamalert")
-        .join("backups");
-    fs::create_dir_all(&data_dir)?;
-    Ok(data_dir)
-}
-
-#[allow(dead_code)]
-fn profiles_dir() -> Result<PathBuf, Box<dyn Error>> {
-    let dir = dirs::data_dir()
-        .ok_or("Could not determine data directory")?
-        .join("hamalert")
-        .join("profiles");
-    fs::create_dir_all(&dir)?;
-    Ok(dir)
-}
-
-#[allow(dead_code)]
-fn permanent_triggers_path() -> Result<PathBuf, Box<dyn Error>> {
-    let path = dirs::data_dir()
-        .ok_or("Could not determine data directory")?
-        .join("hamalert")
-        .join("permanent.json");
-    Ok(path)
-}
-
-#[allow(dead_code)]
-fn current_profile_path() -> Result<PathBuf, Box<dyn Error>> {
-    let path = dirs::data_dir()
-        .ok_or("Could not determine data directory")?
-        .join("hamalert")
-        .join("current-profile");
-    Ok(path)
-}
-
-#[allow(dead_code)]
-fn load_profile(name: &str) -> Result<Vec<StoredTrigger>, Box<dyn Error>> {
-    let path = profiles_dir()?.join(format!("{}.json", name));
-    let content =
-        fs::read_to_string(&path).map_err(|e| format!("Profile '{}' not found: {}", name, e))?;
-    let triggers: Vec<StoredTrigger> = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse profile '{}': {}", name, e))?;
-    Ok(triggers)
-}
-
-#[allow(dead_code)]
-fn save_profile(name: &str, triggers: &[StoredTrigger]) -> Result<PathBuf, Box<dyn Error>> {
-    let path = profiles_dir()?.join(format!("{}.json", name));
-    let json = serde_json::to_string_pretty(triggers)?;
-    fs::write(&path, json)?;
-    Ok(path)
-}
-
-#[allow(dead_code)]
-fn load_permanent_triggers() -> Result<Vec<StoredTrigger>, Box<dyn Error>> {
-    let path = permanent_triggers_path()?;
-    if !path.exists() {
-        return Ok(vec![]);
-    }
-    let content = fs::read_to_string(&path)?;
-    let triggers: Vec<StoredTrigger> = serde_json::from_str(&content)?;
-    Ok(triggers)
-}
-
-#[allow(dead_code)]
-fn save_permanent_triggers(triggers: &[StoredTrigger]) -> Result<(), Box<dyn Error>> {
-    let path = permanent_triggers_path()?;
-    // Ensure parent directory exists
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    let json = serde_json::to_string_pretty(triggers)?;
-    fs::write(&path, json)?;
-    Ok(())
-}
-
-#[allow(dead_code)]
-fn load_current_profile_name() -> Result<Option<String>, Box<dyn Error>> {
-    let path = current_profile_path()?;
-    if !path.exists() {
-        return Ok(None);
-    }
-    let name = fs::read_to_string(&path)?.trim().to_string();
-    if name.is_empty() {
-        return Ok(None);
-    }
-    Ok(Some(name))
-}
-
-#[allow(dead_code)]
-fn save_current_profile_name(name: &str) -> Result<(), Box<dyn Error>> {
-    let path = current_profile_path()?;
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    fs::write(&path, name)?;
     Ok(())
 }
 
-#[allow(dead_code)]
-fn list_profiles() -> Result<Vec<String>, Box<dyn Error>> {
-    let dir = profiles_dir()?;
-    let mut profiles = vec![];
-    if dir.exists() {
-        for entry in fs::read_dir(&dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.extension().map(|e| e == "json").unwrap_or(false)
-                && let Some(stem) = path.file_stem()
-            {
-                profiles.push(stem.to_string_lossy().to_string());
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        match &e {
+            CliError::Cancelled(message) | CliError::Drift(message) => eprintln!("{}", message),
+            e if e.is_timeout() => {
+                eprintln!(
+                    "Error: {}\nHamAlert did not respond in time; raise the limit with --timeout.",
+                    e
+                )
             }
+            _ => eprintln!("Error: {}", e),
         }
+        std::process::exit(e.exit_code());
     }
-    profiles.sort();
-    Ok(profiles)
-}
-
-#[allow(dead_code)]
-fn delete_profile(name: &str) -> Result<(), Box<dyn Error>> {
-    let path = profiles_dir()?.join(format!("{}.json", name));
-    if !path.exists() {
-        return Err(format!("Profile '{}' not found", name).into());
-    }
-    fs::remove_file(&path)?;
-    Ok(())
-}
-
-/// Calculate how many triggers from a profile are present in current triggers
-/// Returns (matched_count, profile_total)
-#[allow(dead_code)]
-fn calculate_profile_match(current: &[StoredTrigger], profile: &[StoredTrigger]) -> (usize, usize) {
-    let matched = profile
-        .iter()
-        .filter(|p| current.iter().any(|c| triggers_match(c, p)))
-        .count();
-    (matched, profile.len())
-}
-
-/// Filter out permanent triggers from a list
-#[allow(dead_code)]
-fn filter_out_permanent(
-    triggers: &[StoredTrigger],
-    permanent: &[StoredTrigger],
-) -> Vec<StoredTrigger> {
-    triggers
-        .iter()
-        .filter(|t| !permanent.iter().any(|p| triggers_match(t, p)))
-        .cloned()
-        .collect()
-}
-
-/// Find triggers that don't match any profile or permanent triggers
-#[allow(dead_code)]
-fn find_unexpected_triggers(
-    current: &[StoredTrigger],
-    permanent: &[StoredTrigger],
-    profile: Option<&[StoredTrigger]>,
-) -> Vec<StoredTrigger> {
-    current
-        .iter()
-        .filter(|t| {
-            let is_permanent = permanent.iter().any(|p| triggers_match(t, p));
-            let is_in_profile = profile
-                .map(|p| p.iter().any(|pt| triggers_match(t, pt)))
-                .unwrap_or(false);
-            !is_permanent && !is_in_profile
-        })
-        .cloned()
-        .collect()
-}
-
-fn load_config(config_file: Option<PathBuf>) -> Result<Config, Box<dyn Error>> {
-    let config_path = if let Some(path) = config_file {
-        path
-    } else {
-        // Use XDG_CONFIG_HOME or default to ~/.config
-        let config_dir = dirs::config_dir()
-            .ok_or("Could not determine config directory")?
-            .join("hamalert");
-        config_dir.join("config.toml")
-    };
-
-    let config_content = fs::read_to_string(&config_path).map_err(|e| {
-        if e.kind() == std::io::ErrorKind::NotFound {
-            format!(
-                "Config file not found at: {}\n\n\
-                Please create a config file with the following format:\n\n\
-                username = \"your_username\"\n\
-                password = \"your_password\"\n",
-                config_path.display()
-            )
-        } else {
-            format!(
-                "Failed to read config file at {}: {}",
-                config_path.display(),
-                e
-            )
-        }
-    })?;
-
-    let config: Config = toml::from_str(&config_content)
-        .map_err(|e| format!("Failed to parse config file: {}", e))?;
-
-    Ok(config)
-}
-
-async fn login(client: &Client, username: &str, password: &str) -> Result<(), Box<dyn Error>> {
-    let params = [("username", username), ("password", password)];
-
-    let response = client
-        .post("https://hamalert.org/login")
-        .form(&params)
-        .send()
-        .await?;
-
-    println!("Login status: {}", response.status());
-
-    if !response.status().is_success() {
-        return Err("Login failed".into());
-    }
-
-    Ok(())
 }
 
-/// Parse Ham2K PoLo callsign notes content and extract callsigns.
-/// Each line's first word is treated as a callsign.
-/// Empty lines and comment lines (starting with # or //) are skipped.
-fn parse_polo_notes_content(content: &str) -> Vec<String> {
-    content
-        .lines()
-        .filter_map(|line| {
-            let trimmed = line.trim();
-            // Skip empty lines
-            if trimmed.is_empty() {
-                return None;
-            }
-            // Skip comment lines
-            if trimmed.starts_with('#') || trimmed.starts_with("//") {
-                return None;
-            }
-            // Extract the first word (callsign)
-            trimmed.split_whitespace().next().map(|s| s.to_string())
-        })
-        .collect()
-}
-
-/// Fetch and parse Ham2K PoLo callsign notes from a URL.
-async fn fetch_polo_notes(client: &Client, url: &str) -> Result<Vec<String>, Box<dyn Error>> {
-    let response = client.get(url).send().await?;
-
-    if !response.status().is_success() {
-        return Err(format!(
-            "Failed to fetch PoLo notes from {}: {}",
-            url,
-            response.status()
-        )
-        .into());
-    }
-
-    let content = response.text().await?;
-    Ok(parse_polo_notes_content(&content))
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Trigger {
-    #[serde(rename = "_id")]
-    id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    user_id: Option<String>,
-    conditions: serde_json::Value,
-    actions: Vec<String>,
-    comment: String,
-    #[serde(skip_serializing_if = "Option::is_none", rename = "matchCount")]
-    match_count: Option<u64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    disabled: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    options: Option<serde_json::Value>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct EditableTrigger {
-    conditions: serde_json::Value,
-    actions: Vec<String>,
-    comment: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    options: Option<serde_json::Value>,
-}
-
-impl EditableTrigger {
-    fn from_trigger(trigger: &Trigger) -> Self {
-        Self {
-            conditions: trigger.conditions.clone(),
-            actions: trigger.actions.clone(),
-            comment: trigger.comment.clone(),
-            options: trigger.options.clone(),
+async fn run() -> Result<(), CliError> {
+    let args: Vec<String> = std::env::args().collect();
+    let early_config = load_early_config(config_file_arg(&args));
+    let builtins: Vec<String> = <Cli as clap::CommandFactory>::command()
+        .get_subcommands()
+        .map(|c| c.get_name().to_string())
+        .collect();
+    let args = expand_aliases(args, &early_config.aliases, &builtins);
+    let mut cli = Cli::parse_from(&args);
+    if cli.command.is_none() {
+        use std::io::IsTerminal;
+        if !(std::io::stdin().is_terminal() && std::io::stdout().is_terminal()) {
+            <Cli as clap::CommandFactory>::command()
+                .error(
+                    clap::error::ErrorKind::MissingSubcommand,
+                    "a command is required",
+                )
+                .exit();
         }
-    }
-
-    fn apply_to_trigger(self, trigger: &mut Trigger) {
-        trigger.conditions = self.conditions;
-        trigger.actions = self.actions;
-        trigger.comment = self.comment;
-        trigger.options = self.options;
-    }
-}
-
-/// Trigger data for storage in profile files (without runtime fields like _id)
-#[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-struct StoredTrigger {
-    conditions: serde_json::Value,
-    actions: Vec<String>,
-    comment: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    options: Option<serde_json::Value>,
-}
-
-impl StoredTrigger {
-    #[allow(dead_code)]
-    fn from_trigger(trigger: &Trigger) -> Self {
-        Self {
-            conditions: trigger.conditions.clone(),
-            actions: trigger.actions.clone(),
-            comment: trigger.comment.clone(),
-            options: trigger.options.clone(),
+        match prompt_main_menu()? {
+            Some(words) => cli = Cli::parse_from(args.iter().cloned().chain(words)),
+            None => return Ok(()),
         }
     }
-}
-
-/// Check if two triggers match by conditions and comment (identity match)
-#[allow(dead_code)]
-fn triggers_match(a: &StoredTrigger, b: &StoredTrigger) -> bool {
-    a.conditions == b.conditions && a.comment == b.comment
-}
-
-fn format_trigger_for_display(trigger: &Trigger) -> String {
-    let mode = trigger
-        .conditions
-        .get("mode")
-        .and_then(|v| v.as_str())
-        .unwrap_or("any");
-    let callsign = trigger
-        .conditions
-        .get("callsign")
-        .and_then(|v| v.as_str())
-        .unwrap_or("?");
-    format!("[{}] {} - \"{}\"", mode, callsign, trigger.comment)
-}
-
-async fn fetch_triggers(client: &Client) -> Result<Vec<Trigger>, Box<dyn Error>> {
-    let response = client
-        .get("https://hamalert.org/ajax/triggers")
-        .send()
-        .await?;
-
-    if !response.status().is_success() {
-        return Err(format!("Failed to fetch triggers: {}", response.status()).into());
-    }
-
-    let triggers: Vec<Trigger> = response.json().await?;
-    Ok(triggers)
-}
-
-async fn add_trigger(
-    client: &Client,
-    callsign: &str,
-    comment: &str,
-    actions: Vec<String>,
-    mode: Option<String>,
-) -> Result<(), Box<dyn Error>> {
-    let trigger_data = TriggerData {
-        conditions: Conditions {
-            callsign: callsign.to_string(),
-            mode,
-        },
-        comment: comment.to_string(),
-        actions,
-        options: json!({}),
+    let Some(command) = cli.command else {
+        unreachable!("a command was given or chosen from the menu");
     };
-
-    let response = client
-        .post("https://hamalert.org/ajax/trigger_update")
-        .json(&trigger_data)
-        .send()
-        .await?;
-
-    println!("Add trigger status for {}: {}", callsign, response.status());
-
-    // Optionally print the response body
-    let body = response.text().await?;
-    if !body.is_empty() {
-        println!("Response: {}", body);
-    }
-
-    Ok(())
-}
-
-async fn delete_trigger(client: &Client, id: &str) -> Result<(), Box<dyn Error>> {
-    let response = client
-        .post("https://hamalert.org/ajax/trigger_delete")
-        .form(&[("id", id)])
-        .send()
-        .await?;
-
-    if !response.status().is_success() {
-        return Err(format!("Failed to delete trigger {}: {}", id, response.status()).into());
-    }
-
-    Ok(())
-}
-
-async fn create_trigger_from_backup(
-    client: &Client,
-    trigger: &Trigger,
-) -> Result<(), Box<dyn Error>> {
-    // Build trigger data without _id so a new one is created
-    let trigger_data = serde_json::json!({
-        "conditions": trigger.conditions,
-        "actions": trigger.actions,
-        "comment": trigger.comment,
-        "options": trigger.options.clone().unwrap_or(serde_json::json!({})),
-    });
-
-    let response = client
-        .post("https://hamalert.org/ajax/trigger_update")
-        .json(&trigger_data)
-        .send()
-        .await?;
-
-    if !response.status().is_success() {
-        return Err(format!(
-            "Failed to create trigger '{}': {}",
-            trigger.comment,
-            response.status()
+    if let Some(command) = command.interactive_name() {
+        require_terminal(command)?;
+    }
+    if let Some(dir) = cli.data_dir.as_ref().or(early_config.data_dir.as_ref()) {
+        storage::DATA_DIR.set(expand_home(dir)).ok();
+    }
+    if let Some(dir) = &early_config.backup_dir {
+        storage::backups::BACKUP_DIR.set(expand_home(dir)).ok();
+    }
+    if let Some(path) = &cli.get {
+        let path = parse_json_path(path).map_err(CliError::Validation)?;
+        JSON_PATH.set(path).ok();
+    }
+    let json_output = cli.json || cli.get.is_some();
+    let assume_yes = cli.yes;
+    let level = if cli.quiet { 0 } else { 1 + cli.verbose };
+    VERBOSITY.store(level, Ordering::Relaxed);
+    COLOR.store(cli.color.enabled() && !json_output, Ordering::Relaxed);
+    init_logging(level, cli.log_file.as_deref())?;
+
+    // Config commands must work before a valid config exists
+    if let Commands::Config(config_cmd) = command {
+        return commands::config::run(
+            config_cmd,
+            cli.config_file,
+            assume_yes,
+            cli.prompt_password,
+            cli.timeout,
         )
-        .into());
-    }
-
-    Ok(())
-}
-
-async fn update_trigger(client: &Client, trigger: &Trigger) -> Result<(), Box<dyn Error>> {
-    let trigger_data = serde_json::json!({
-        "_id": trigger.id,
-        "conditions": trigger.conditions,
-        "actions": trigger.actions,
-        "comment": trigger.comment,
-        "options": trigger.options.clone().unwrap_or(serde_json::json!({})),
-    });
-
-    let response = client
-        .post("https://hamalert.org/ajax/trigger_update")
-        .json(&trigger_data)
-        .send()
-        .await?;
-
-    if !response.status().is_success() {
-        return Err(format!(
-            "Failed to update trigger '{}': {}",
-            trigger.comment,
-            response.status()
+        .await;
+    }
+
+    if let Commands::CopyAccount {
+        from,
+        to,
+        filter,
+        no_dry_run,
+    } = command
+    {
+        return commands::copy_account::run(
+            cli.config_file,
+            cli.timeout,
+            &from,
+            &to,
+            filter.as_deref(),
+            no_dry_run,
+            assume_yes,
         )
-        .into());
-    }
-
-    Ok(())
-}
-
-/// Shared logic for importing callsigns from any source
-async fn import_callsigns(
-    client: &Client,
-    callsigns: Vec<String>,
-    options: &ImportOptions,
-) -> Result<(), Box<dyn Error>> {
-    let action_strings: Vec<String> = options
-        .trigger
-        .actions
-        .iter()
-        .map(|a| a.as_str().to_string())
-        .collect();
-
-    let mode_string = if options.trigger.mode.is_empty() {
-        None
-    } else {
-        Some(
-            options
-                .trigger
-                .mode
-                .iter()
-                .map(|m| m.as_str())
-                .collect::<Vec<_>>()
-                .join(","),
-        )
-    };
-    let format = CallsignFormat::from_flags(options.trigger.compact, options.trigger.one_per_line);
-
-    if options.dry_run {
-        println!("\nDry run - would add triggers for:");
-        for cs in &callsigns {
-            println!(
-                "  {} (comment: {:?}, actions: {:?}, mode: {:?})",
-                cs, options.trigger.comment, action_strings, mode_string
-            );
+        .await;
+    }
+
+    // A fresh backup makes `backup --if-stale` a no-op, so don't log in for
+    // it; `backup::backup` makes the same check when it is dispatched
+    if let Commands::Backup {
+        command: None,
+        output,
+        if_stale: Some(max_age),
+        ..
+    } = &command
+        && let Some((path, age)) = commands::backup::fresh_backup(output.as_deref(), *max_age)?
+    {
+        commands::backup::report_fresh(&path, age);
+        return Ok(());
+    }
+
+    if cli.offline {
+        if !command.works_offline() {
+            let (last, rest) = Commands::OFFLINE_COMMANDS.split_last().unwrap();
+            return Err(CliError::Validation(format!(
+                "This command needs HamAlert; --offline supports {} and {}",
+                rest.join(", "),
+                last
+            )));
+        }
+        OFFLINE.store(true, Ordering::Relaxed);
+    }
+
+    // Only commands that talk to HamAlert need credentials and a session.
+    // `serve http` only receives webhooks, unless a route selects triggers
+    // by tag, which are looked up on HamAlert.
+    let mut uses_api = command.needs_api() && !cli.offline;
+    let config = if uses_api {
+        load_config(cli.config_file.clone(), cli.prompt_password)?
+    } else if let Commands::Serve(ServeCommands::Http { .. }) = &command {
+        let settings = load_settings(cli.config_file.clone())?;
+        uses_api = settings.routes_by_tag();
+        if uses_api {
+            load_config(cli.config_file.clone(), cli.prompt_password)?
+        } else {
+            settings
         }
     } else {
-        let combined_callsigns = callsigns.join(format.separator());
-        add_trigger(
-            client,
-            &combined_callsigns,
-            &options.trigger.comment,
-            action_strings,
-            mode_string,
-        )
-        .await?;
-    }
-
-    Ok(())
-}
-
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let cli = Cli::parse();
-
-    // Load config from file
-    let config = load_config(cli.config_file)?;
+        Config::default()
+    };
 
     // Create a client with cookie jar to maintain session
-    let client = Client::builder().cookie_store(true).build()?;
-
-    // Login first
-    login(&client, &config.username, &config.password).await?;
-
-    // Execute the subcommand
-    match cli.command {
-        Commands::AddTrigger { callsign, options } => {
-            let action_strings: Vec<String> = options
-                .actions
-                .iter()
-                .map(|a| a.as_str().to_string())
-                .collect();
-
-            let mode_string = if options.mode.is_empty() {
-                None
-            } else {
-                Some(
-                    options
-                        .mode
-                        .iter()
-                        .map(|m| m.as_str())
-                        .collect::<Vec<_>>()
-                        .join(","),
-                )
-            };
-
-            if callsign.is_empty() {
-                return Err("At least one --callsign must be provided".into());
-            }
-            // Join callsigns with the specified format
-            let format = CallsignFormat::from_flags(options.compact, options.one_per_line);
-            let combined_callsigns = callsign.join(format.separator());
-            add_trigger(
-                &client,
-                &combined_callsigns,
-                &options.comment,
-                action_strings,
-                mode_string,
-            )
-            .await?;
-        }
-        Commands::ImportPoloNotes { url, options } => {
-            let callsigns = fetch_polo_notes(&client, &url).await?;
-
-            if callsigns.is_empty() {
-                println!("No callsigns found at {}", url);
-                return Ok(());
-            }
-
-            println!("Found {} callsigns at {}", callsigns.len(), url);
-
-            import_callsigns(&client, callsigns, &options).await?;
-        }
-        Commands::ImportFile { file, options } => {
-            let content = fs::read_to_string(&file)
-                .map_err(|e| format!("Failed to read file {}: {}", file.display(), e))?;
-            let callsigns = parse_polo_notes_content(&content);
-
-            if callsigns.is_empty() {
-                println!("No callsigns found in {}", file.display());
-                return Ok(());
-            }
-
-            println!("Found {} callsigns in {}", callsigns.len(), file.display());
-
-            import_callsigns(&client, callsigns, &options).await?;
-        }
-        Commands::Backup { output } => {
-            let triggers = fetch_triggers(&client).await?;
-
-            let output_path = match output {
-                Some(path) => path,
-                None => {
-                    let date = Local::now().format("%Y-%m-%d");
-                    backup_dir()?.join(format!("hamalert-backup-{}.json", date))
-                }
-            };
-
-            let json = serde_json::to_string_pretty(&triggers)?;
-            fs::write(&output_path, json)?;
-
-            println!(
-                "Backed up {} triggers to {}",
-                triggers.len(),
-                output_path.display()
-            );
-        }
-        Commands::Restore { input, no_dry_run } => {
-            // Read and parse backup file
-            let backup_content = fs::read_to_string(&input)
-                .map_err(|e| format!("Failed to read backup file {}: {}", input.display(), e))?;
-            let backup_triggers: Vec<Trigger> = serde_json::from_str(&backup_content)
-                .map_err(|e| format!("Failed to parse backup file: {}", e))?;
-
-            // Fetch current triggers
-            let current_triggers = fetch_triggers(&client).await?;
-
-            if !no_dry_run {
-                println!("DRY RUN - No changes will be made\n");
-                println!(
-                    "This will DELETE {} existing triggers and restore {} triggers from backup.\n",
-                    current_triggers.len(),
-                    backup_triggers.len()
+    let client = config.api_client(cli.timeout)?;
+    if uses_api {
+        client.login(&config.username, &config.password).await?;
+        if config.auto_prune_expired && !matches!(command, Commands::PruneExpired { .. }) {
+            for expired in commands::prune::prune_expired(&client, false).await? {
+                info!(
+                    "Deleted expired trigger '{}' (expired {})",
+                    expired.name, expired.expires
                 );
-                println!("Triggers to be restored:");
-                for trigger in &backup_triggers {
-                    println!("  {}", format_trigger_for_display(trigger));
-                }
-                println!("\nRun with --no-dry-run to execute.");
-                return Ok(());
-            }
-
-            // Create auto-backup before destructive operation
-            let backup_path = backup_dir()?.join(format!(
-                "hamalert-backup-before-restore-{}.json",
-                Local::now().format("%Y-%m-%d-%H%M%S")
-            ));
-            let backup_json = serde_json::to_string_pretty(&current_triggers)?;
-            fs::write(&backup_path, backup_json)?;
-            println!(
-                "Backed up {} existing triggers to {}",
-                current_triggers.len(),
-                backup_path.display()
-            );
-
-            // Delete all existing triggers
-            for trigger in &current_triggers {
-                delete_trigger(&client, &trigger.id).await?;
-            }
-            println!("Deleted {} existing triggers", current_triggers.len());
-
-            // Restore from backup
-            for trigger in &backup_triggers {
-                create_trigger_from_backup(&client, trigger).await?;
-                println!("Restored trigger: {}", trigger.comment);
             }
-            println!(
-                "\nRestored {} triggers from {}",
-                backup_triggers.len(),
-                input.display()
-            );
         }
-        Commands::Edit => {
-            let triggers = fetch_triggers(&client).await?;
-
-            if triggers.is_empty() {
-                println!("No triggers found.");
-                return Ok(());
-            }
-
-            // Display numbered list
-            println!("Select a trigger to edit:\n");
-            for (i, trigger) in triggers.iter().enumerate() {
-                println!("  {}. {}", i + 1, format_trigger_for_display(trigger));
-            }
-            println!("\nEnter number (1-{}), or q to quit: ", triggers.len());
-
-            // Read user selection
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-            let input = input.trim();
-
-            if input.eq_ignore_ascii_case("q") {
-                println!("Cancelled.");
-                return Ok(());
-            }
-
-            let selection: usize = input.parse().map_err(|_| "Invalid selection")?;
-
-            if selection < 1 || selection > triggers.len() {
-                return Err(format!("Selection must be between 1 and {}", triggers.len()).into());
-            }
-
-            let mut trigger = triggers[selection - 1].clone();
-            let original_editable = EditableTrigger::from_trigger(&trigger);
-
-            // Create temp file with editable JSON
-            let temp_dir = std::env::temp_dir();
-            let temp_path = temp_dir.join(format!("hamalert-edit-{}.json", trigger.id));
-            let json = serde_json::to_string_pretty(&original_editable)?;
-            fs::write(&temp_path, &json)?;
-
-            // Open in editor
-            let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
-
-            loop {
-                let status = std::process::Command::new(&editor)
-                    .arg(&temp_path)
-                    .status()
-                    .map_err(|e| format!("Failed to open editor '{}': {}", editor, e))?;
-
-                if !status.success() {
-                    fs::remove_file(&temp_path).ok();
-                    return Err("Editor exited with error".into());
-                }
-
-                // Read and parse edited content
-                let edited_content = fs::read_to_string(&temp_path)?;
-
-                match serde_json::from_str::<EditableTrigger>(&edited_content) {
-                    Ok(edited) => {
-                        // Check if anything changed
-                        let edited_json = serde_json::to_string(&edited)?;
-                        let original_json = serde_json::to_string(&original_editable)?;
-
-                        if edited_json == original_json {
-                            println!("No changes made.");
-                        } else {
-                            edited.apply_to_trigger(&mut trigger);
-                            update_trigger(&client, &trigger).await?;
-                            println!("Updated trigger: {}", trigger.comment);
-                        }
-
-                        fs::remove_file(&temp_path).ok();
-                        break;
-                    }
-                    Err(e) => {
-                        println!("Invalid JSON: {}", e);
-                        println!("Press Enter to re-edit, or 'q' to quit without saving: ");
-
-                        let mut retry_input = String::new();
-                        std::io::stdin().read_line(&mut retry_input)?;
-
-                        if retry_input.trim().eq_ignore_ascii_case("q") {
-                            fs::remove_file(&temp_path).ok();
-                            println!("Cancelled without saving.");
-                            break;
-                        }
+        // Lifting snoozes and mutes changes triggers, so read-only commands
+        // and dry runs leave it to the next command that changes something.
+        // A failure here must not stop the command itself.
+        if !command.works_offline() && !command.is_dry_run() {
+            match commands::snooze::wake_due(&client, false).await {
+                Ok(names) => {
+                    for name in names {
+                        info!("Enabled snoozed trigger '{}' again", name);
                     }
                 }
+                Err(e) => tracing::warn!("Could not enable snoozed triggers again: {}", e),
             }
-        }
-        Commands::BulkDelete { dry_run } => {
-            let triggers = fetch_triggers(&client).await?;
-
-            if triggers.is_empty() {
-                println!("No triggers found.");
-                return Ok(());
-            }
-
-            println!("Fetched {} triggers.\n", triggers.len());
-            println!("Instructions:");
-            println!("  j/k or arrows: Navigate up/down");
-            println!("  Space: Toggle selection (unchecked = will be DELETED)");
-            println!("  Enter: Confirm");
-            println!("  Esc: Cancel\n");
-
-            // Build display items
-            let display_items: Vec<String> =
-                triggers.iter().map(format_trigger_for_display).collect();
-
-            // All items start selected (checked = keep)
-            let default_selections: Vec<usize> = (0..triggers.len()).collect();
-
-            // Run the interactive multi-select
-            let kept_result = MultiSelect::new(
-                "Select triggers to KEEP (unchecked will be deleted):",
-                display_items.clone(),
-            )
-            .with_default(&default_selections)
-            .with_vim_mode(true)
-            .with_page_size(15)
-            .with_help_message("Space=toggle, j/k=navigate, Enter=confirm, Esc=cancel")
-            .prompt();
-
-            let kept_displays: Vec<String> = match kept_result {
-                Ok(selected) => selected,
-                Err(InquireError::OperationCanceled) | Err(InquireError::OperationInterrupted) => {
-                    println!("Operation cancelled.");
-                    return Ok(());
-                }
-                Err(e) => return Err(e.into()),
-            };
-
-            // Find triggers to delete (those NOT in kept list)
-            let kept_set: std::collections::HashSet<&str> =
-                kept_displays.iter().map(|s| s.as_str()).collect();
-            let to_delete: Vec<&Trigger> = triggers
-                .iter()
-                .filter(|t| !kept_set.contains(format_trigger_for_display(t).as_str()))
-                .collect();
-
-            if to_delete.is_empty() {
-                println!("No triggers selected for deletion.");
-                return Ok(());
-            }
-
-            // Show summary
-            println!("\nTriggers to DELETE ({}):", to_delete.len());
-            for trigger in &to_delete {
-                println!("  - {}", format_trigger_for_display(trigger));
-            }
-
-            // Dry run mode
-            if dry_run {
-                println!("\n[DRY RUN] No triggers were deleted.");
-                return Ok(());
-            }
-
-            // Confirmation prompt
-            println!();
-            print!("Proceed with deletion? [y/N]: ");
-            std::io::Write::flush(&mut std::io::stdout())?;
-            let mut confirm_input = String::new();
-            std::io::stdin().read_line(&mut confirm_input)?;
-            if !confirm_input.trim().eq_ignore_ascii_case("y") {
-                println!("Deletion cancelled.");
-                return Ok(());
-            }
-
-            // Auto-backup before deletion
-            let backup_path = backup_dir()?.join(format!(
-                "hamalert-backup-before-bulk-delete-{}.json",
-                Local::now().format("%Y-%m-%d-%H%M%S")
-            ));
-            let backup_json = serde_json::to_string_pretty(&triggers)?;
-            fs::write(&backup_path, backup_json)?;
-            println!(
-                "Backed up {} triggers to {}",
-                triggers.len(),
-                backup_path.display()
-            );
-
-            // Delete the selected triggers
-            for trigger in &to_delete {
-                delete_trigger(&client, &trigger.id).await?;
-                println!("Deleted: {}", format_trigger_for_display(trigger));
-            }
-
-            println!(
-                "\nDeleted {} trigger(s). Kept {} trigger(s).",
-                to_delete.len(),
-                triggers.len() - to_delete.len()
-            );
-        }
-        Commands::Profile(profile_cmd) => match profile_cmd {
-            ProfileCommands::List => {
-                let profiles = list_profiles()?;
-                let current_profile = load_current_profile_name()?;
-                let permanent = load_permanent_triggers()?;
-
-                if profiles.is_empty() {
-                    println!("No profiles saved.");
-                    println!("\nUse 'hamalert-cli profile save <name>' to create one.");
-                    return Ok(());
-                }
-
-                // Fetch current triggers to calculate match percentages
-                let current_triggers = fetch_triggers(&client).await?;
-                let current_stored: Vec<StoredTrigger> = current_triggers
-                    .iter()
-                    .map(StoredTrigger::from_trigger)
-                    .collect();
-
-                // Filter out permanent triggers for matching
-                let current_non_permanent = filter_out_permanent(&current_stored, &permanent);
-
-                println!("Profiles:");
-                let mut best_match: Option<(&str, usize, usize)> = None;
-
-                for profile_name in &profiles {
-                    let profile = load_profile(profile_name).unwrap_or_default();
-                    let (matched, total) =
-                        calculate_profile_match(&current_non_permanent, &profile);
-                    let percentage = if total > 0 {
-                        (matched * 100) / total
-                    } else {
-                        100
-                    };
-
-                    let is_current = current_profile.as_ref() == Some(profile_name);
-                    let marker = if is_current { "*" } else { " " };
-
-                    println!(
-                        "  {} {:<15} ({}/{}  {}% match){}",
-                        marker,
-                        profile_name,
-                        matched,
-                        total,
-                        percentage,
-                        if is_current { " <- current" } else { "" }
-                    );
-
-                    // Track best match
-                    if best_match.is_none() || matched > best_match.unwrap().1 {
-                        best_match = Some((profile_name, matched, total));
-                    }
-                }
-
-                // Warn if recorded profile doesn't match best
-                if let Some(current) = &current_profile
-                    && let Some((best_name, best_matched, best_total)) = best_match
-                    && best_name != current
-                    && best_matched == best_total
-                    && best_total > 0
-                {
-                    let current_profile_data = load_profile(current).unwrap_or_default();
-                    let (current_matched, current_total) =
-                        calculate_profile_match(&current_non_permanent, &current_profile_data);
-                    if current_matched < current_total {
-                        println!(
-                            "\n⚠ Current triggers match '{}' better than recorded '{}'",
-                            best_name, current
-                        );
-                        println!("Run 'profile status' for details.");
-                    }
-                }
-
-                println!("\nPermanent triggers: {}", permanent.len());
-            }
-            ProfileCommands::Show { name } => {
-                let profile = load_profile(&name)?;
-                if profile.is_empty() {
-                    println!("Profile '{}' is empty.", name);
-                } else {
-                    println!("Profile '{}' ({} triggers):", name, profile.len());
-                    for trigger in &profile {
-                        let mode = trigger
-                            .conditions
-                            .get("mode")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("any");
-                        let callsign = trigger
-                            .conditions
-                            .get("callsign")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("?");
-                        println!("  - [{}] {} - \"{}\"", mode, callsign, trigger.comment);
+            match commands::mute::unmute_due(&client, false).await {
+                Ok(calls) => {
+                    for call in calls {
+                        info!("Unmuted {}", call);
                     }
                 }
+                Err(e) => tracing::warn!("Could not lift expired mutes: {}", e),
             }
-            ProfileCommands::Status => {
-                let current_triggers = fetch_triggers(&client).await?;
-                let permanent = load_permanent_triggers()?;
-                let current_profile_name = load_current_profile_name()?;
-                let profiles = list_profiles()?;
-
-                let current_stored: Vec<StoredTrigger> = current_triggers
-                    .iter()
-                    .map(StoredTrigger::from_trigger)
-                    .collect();
-
-                // Count permanent matches
-                let permanent_matched = current_stored
-                    .iter()
-                    .filter(|t| permanent.iter().any(|p| triggers_match(t, p)))
-                    .count();
-
-                println!("Current triggers on HamAlert: {}", current_triggers.len());
-                println!(
-                    "Permanent triggers matched: {}/{}",
-                    permanent_matched,
-                    permanent.len()
-                );
-
-                let current_non_permanent = filter_out_permanent(&current_stored, &permanent);
-
-                if profiles.is_empty() {
-                    println!("\nNo profiles saved.");
-                    return Ok(());
-                }
-
-                println!("\nProfile match analysis:");
-                let mut best_match: Option<(String, usize, usize)> = None;
-
-                for profile_name in &profiles {
-                    let profile = load_profile(profile_name).unwrap_or_default();
-                    let (matched, total) =
-                        calculate_profile_match(&current_non_permanent, &profile);
-                    let percentage = if total > 0 {
-                        (matched * 100) / total
-                    } else {
-                        100
-                    };
-
-                    let marker = if matched == total && total > 0 {
-                        " <- best match"
-                    } else {
-                        ""
-                    };
-                    println!(
-                        "  {:<15} {}/{} ({}% match){}",
-                        profile_name, matched, total, percentage, marker
-                    );
-
-                    if best_match.is_none() || matched > best_match.as_ref().unwrap().1 {
-                        best_match = Some((profile_name.clone(), matched, total));
-                    }
-                }
-
-                // Current profile status
-                println!(
-                    "\nRecorded current profile: {}",
-                    current_profile_name.as_deref().unwrap_or("(none)")
-                );
-
-                // Check for mismatch
-                if let Some((best_name, best_matched, best_total)) = &best_match {
-                    let is_in_sync = current_profile_name.as_ref() == Some(best_name)
-                        && *best_matched == *best_total;
-
-                    if is_in_sync {
-                        println!("Status: ✓ In sync");
-                    } else if current_profile_name.is_some()
-                        && best_matched == best_total
-                        && *best_total > 0
-                    {
-                        println!(
-                            "Status: ⚠ Mismatch - HamAlert matches '{}' better",
-                            best_name
-                        );
-                        println!("\nActions:");
-                        println!(
-                            "  [U]pdate record to '{}' (no changes to HamAlert)",
-                            best_name
-                        );
-                        println!("  [S]ave current triggers as new profile");
-                        println!("  [I]gnore");
-
-                        print!("\nChoice: ");
-                        std::io::Write::flush(&mut std::io::stdout())?;
-                        let mut choice = String::new();
-                        std::io::stdin().read_line(&mut choice)?;
-
-                        match choice.trim().to_lowercase().as_str() {
-                            "u" => {
-                                save_current_profile_name(best_name)?;
-                                println!("Updated current profile record to '{}'.", best_name);
-                            }
-                            "s" => {
-                                print!("Enter profile name: ");
-                                std::io::Write::flush(&mut std::io::stdout())?;
-                                let mut new_name = String::new();
-                                std::io::stdin().read_line(&mut new_name)?;
-                                let new_name = new_name.trim();
-                                if !new_name.is_empty() {
-                                    let profile_triggers =
-                                        filter_out_permanent(&current_stored, &permanent);
-                                    save_profile(new_name, &profile_triggers)?;
-                                    save_current_profile_name(new_name)?;
-                                    println!("Saved and set '{}' as current profile.", new_name);
-                                }
-                            }
-                            _ => {
-                                println!("No changes made.");
-                            }
-                        }
-                    } else {
-                        println!("Status: No exact profile match");
-                    }
-                }
-
-                // Show unexpected triggers
-                let current_profile_data = current_profile_name
-                    .as_ref()
-                    .and_then(|n| load_profile(n).ok());
-                let unexpected = find_unexpected_triggers(
-                    &current_stored,
-                    &permanent,
-                    current_profile_data.as_deref(),
-                );
-
-                if !unexpected.is_empty() {
-                    println!("\nUnmatched triggers ({}):", unexpected.len());
-                    for t in &unexpected {
-                        let mode = t
-                            .conditions
-                            .get("mode")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("any");
-                        let callsign = t
-                            .conditions
-                            .get("callsign")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("?");
-                        println!("  - [{}] {} - \"{}\"", mode, callsign, t.comment);
-                    }
-                }
-            }
-            ProfileCommands::Save { name, from_backup } => {
-                let permanent = load_permanent_triggers()?;
-
-                let triggers: Vec<StoredTrigger> = match &from_backup {
-                    Some(path) => {
-                        let content = fs::read_to_string(path)
-                            .map_err(|e| format!("Failed to read backup file: {}", e))?;
-                        let backup_triggers: Vec<Trigger> = serde_json::from_str(&content)
-                            .map_err(|e| format!("Failed to parse backup file: {}", e))?;
-                        backup_triggers
-                            .iter()
-                            .map(StoredTrigger::from_trigger)
-                            .collect()
-                    }
-                    None => {
-                        let fetched = fetch_triggers(&client).await?;
-                        fetched.iter().map(StoredTrigger::from_trigger).collect()
-                    }
-                };
-
-                // Filter out permanent triggers
-                let profile_triggers = filter_out_permanent(&triggers, &permanent);
-
-                // Check if profile already exists
-                let profile_path = profiles_dir()?.join(format!("{}.json", name));
-                if profile_path.exists() {
-                    let existing = load_profile(&name)?;
-                    if existing == profile_triggers {
-                        // Identical content - no need to re-save
-                        println!(
-                            "Profile '{}' already has identical content. No changes needed.",
-                            name
-                        );
-                        // Still set as current profile if saving from live state
-                        if from_backup.is_none() {
-                            save_current_profile_name(&name)?;
-                            println!("Set '{}' as current profile.", name);
-                        }
-                        return Ok(());
-                    }
-                    // Different content - prompt for confirmation
-                    println!("Profile '{}' already exists with different content.", name);
-                    println!(
-                        "Existing: {} triggers, New: {} triggers",
-                        existing.len(),
-                        profile_triggers.len()
-                    );
-                    print!("Overwrite? [y/N]: ");
-                    std::io::Write::flush(&mut std::io::stdout())?;
-                    let mut confirm = String::new();
-                    std::io::stdin().read_line(&mut confirm)?;
-                    if !confirm.trim().eq_ignore_ascii_case("y") {
-                        println!("Cancelled.");
-                        return Ok(());
-                    }
-                }
-
-                let _path = save_profile(&name, &profile_triggers)?;
-                println!(
-                    "Saved {} triggers to profile '{}' (excluded {} permanent)",
-                    profile_triggers.len(),
-                    name,
-                    triggers.len() - profile_triggers.len()
-                );
-
-                // Set as current profile if saving from live state
-                if from_backup.is_none() {
-                    save_current_profile_name(&name)?;
-                    println!("Set '{}' as current profile.", name);
-                }
-            }
-            ProfileCommands::Switch { name, no_dry_run } => {
-                // Track whether we modified the profile during dry-run
-                let mut profile_modified = false;
-
-                // Load all data
-                let target_profile = load_profile(&name)?;
-                let permanent = load_permanent_triggers()?;
-                let current_profile_name = load_current_profile_name()?;
-                let current_triggers = fetch_triggers(&client).await?;
-
-                let current_stored: Vec<StoredTrigger> = current_triggers
-                    .iter()
-                    .map(StoredTrigger::from_trigger)
-                    .collect();
-
-                // Categorize current triggers
-                let permanent_triggers: Vec<&StoredTrigger> = current_stored
-                    .iter()
-                    .filter(|t| permanent.iter().any(|p| triggers_match(t, p)))
-                    .collect();
-
-                let current_profile_data = current_profile_name
-                    .as_ref()
-                    .and_then(|n| load_profile(n).ok());
-
-                let unexpected = find_unexpected_triggers(
-                    &current_stored,
-                    &permanent,
-                    current_profile_data.as_deref(),
-                );
-
-                // Triggers to delete (non-permanent current triggers)
-                let to_delete: Vec<&Trigger> = current_triggers
-                    .iter()
-                    .filter(|t| {
-                        let stored = StoredTrigger::from_trigger(t);
-                        !permanent.iter().any(|p| triggers_match(&stored, p))
-                    })
-                    .collect();
-
-                // Display plan
-                println!(
-                    "Current profile: {}",
-                    current_profile_name.as_deref().unwrap_or("(none)")
-                );
-                println!("Switching to: {}\n", name);
-
-                println!(
-                    "Permanent triggers (unchanged): {}",
-                    permanent_triggers.len()
-                );
-                if !permanent_triggers.is_empty() {
-                    for t in &permanent_triggers {
-                        let mode = t
-                            .conditions
-                            .get("mode")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("any");
-                        let callsign = t
-                            .conditions
-                            .get("callsign")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("?");
-                        println!("  - [{}] {} - \"{}\"", mode, callsign, t.comment);
-                    }
-                }
-
-                println!("\nWill DELETE {} triggers:", to_delete.len());
-                for t in &to_delete {
-                    println!("  - {}", format_trigger_for_display(t));
-                }
-
-                println!(
-                    "\nWill CREATE {} triggers from '{}':",
-                    target_profile.len(),
-                    name
-                );
-                for t in &target_profile {
-                    let mode = t
-                        .conditions
-                        .get("mode")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("any");
-                    let callsign = t
-                        .conditions
-                        .get("callsign")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("?");
-                    println!("  - [{}] {} - \"{}\"", mode, callsign, t.comment);
-                }
-
-                // Handle unexpected triggers
-                if !unexpected.is_empty() {
-                    println!(
-                        "\n⚠ Found {} unexpected triggers (not permanent, not in current profile):",
-                        unexpected.len()
-                    );
-                    for t in &unexpected {
-                        let mode = t
-                            .conditions
-                            .get("mode")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("any");
-                        let callsign = t
-                            .conditions
-                            .get("callsign")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("?");
-                        println!("  - [{}] {} - \"{}\"", mode, callsign, t.comment);
-                    }
-
-                    if !no_dry_run {
-                        println!("\n  [D]elete them");
-                        if let Some(ref current_name) = current_profile_name {
-                            println!("  [S]ave to '{}' profile first", current_name);
-                        }
-                        println!("  [C]ancel");
-
-                        print!("\nChoice: ");
-                        std::io::Write::flush(&mut std::io::stdout())?;
-                        let mut choice = String::new();
-                        std::io::stdin().read_line(&mut choice)?;
-
-                        match choice.trim().to_lowercase().as_str() {
-                            "d" => {
-                                // Continue with deletion
-                            }
-                            "s" => {
-                                if let Some(ref current_name) = current_profile_name {
-                                    // Update current profile to include unexpected triggers
-                                    let mut updated_profile =
-                                        current_profile_data.unwrap_or_default();
-                                    for t in &unexpected {
-                                        if !updated_profile.iter().any(|p| triggers_match(p, t)) {
-                                            updated_profile.push(t.clone());
-                                        }
-                                    }
-                                    save_profile(current_name, &updated_profile)?;
-                                    println!(
-                                        "Updated '{}' profile with {} additional triggers.",
-                                        current_name,
-                                        unexpected.len()
-                                    );
-                                    profile_modified = true;
-                                }
-                            }
-                            _ => {
-                                println!("Cancelled.");
-                                return Ok(());
-                            }
-                        }
-                    }
-                }
-
-                if !no_dry_run {
-                    if profile_modified {
-                        println!("\nNote: Profile was updated with unexpected triggers.");
-                    }
-                    println!("DRY RUN - No trigger changes made on HamAlert.");
-                    println!("Run with --no-dry-run to execute the switch.");
-                    return Ok(());
-                }
-
-                // Execute the switch
-                // 1. Create backup
-                let backup_path = backup_dir()?.join(format!(
-                    "hamalert-backup-before-switch-{}.json",
-                    Local::now().format("%Y-%m-%d-%H%M%S")
-                ));
-                let backup_json = serde_json::to_string_pretty(&current_triggers)?;
-                fs::write(&backup_path, backup_json)?;
-                println!(
-                    "\nBacked up {} triggers to {}",
-                    current_triggers.len(),
-                    backup_path.display()
-                );
-
-                // 2. Delete non-permanent triggers
-                for trigger in &to_delete {
-                    delete_trigger(&client, &trigger.id).await?;
-                }
-                println!("Deleted {} triggers.", to_delete.len());
-
-                // 3. Create triggers from target profile
-                for stored in &target_profile {
-                    // Convert StoredTrigger to Trigger for API
-                    let trigger = Trigger {
-                        id: String::new(),
-                        user_id: None,
-                        conditions: stored.conditions.clone(),
-                        actions: stored.actions.clone(),
-                        comment: stored.comment.clone(),
-                        match_count: None,
-                        disabled: None,
-                        options: stored.options.clone(),
-                    };
-                    create_trigger_from_backup(&client, &trigger).await?;
-                }
-                println!("Created {} triggers from '{}'.", target_profile.len(), name);
-
-                // 4. Update current profile
-                save_current_profile_name(&name)?;
-                println!("\nSwitched to profile '{}'.", name);
-            }
-            ProfileCommands::Delete { name } => {
-                // Check if it's the current profile
-                let current = load_current_profile_name()?;
-                if current.as_ref() == Some(&name) {
-                    println!("Warning: '{}' is the current profile.", name);
-                    print!("Delete anyway? [y/N]: ");
-                    std::io::Write::flush(&mut std::io::stdout())?;
-                    let mut confirm = String::new();
-                    std::io::stdin().read_line(&mut confirm)?;
-                    if !confirm.trim().eq_ignore_ascii_case("y") {
-                        println!("Cancelled.");
-                        return Ok(());
-                    }
-                    // Clear current profile
-                    let path = current_profile_path()?;
-                    if path.exists() {
-                        fs::remove_file(&path)?;
-                    }
-                }
-
-                delete_profile(&name)?;
-                println!("Deleted profile '{}'.", name);
-            }
-            ProfileCommands::SetPermanent { from_backup } => {
-                // Load triggers from backup file or fetch from HamAlert
-                let triggers: Vec<Trigger> = match from_backup {
-                    Some(path) => {
-                        let content = fs::read_to_string(&path)
-                            .map_err(|e| format!("Failed to read backup file: {}", e))?;
-                        serde_json::from_str(&content)
-                            .map_err(|e| format!("Failed to parse backup file: {}", e))?
-                    }
-                    None => fetch_triggers(&client).await?,
-                };
-
-                if triggers.is_empty() {
-                    println!("No triggers found.");
-                    return Ok(());
-                }
-
-                // Load existing permanent triggers
-                let existing_permanent = load_permanent_triggers()?;
-
-                // Convert to StoredTrigger for comparison
-                let stored_triggers: Vec<StoredTrigger> =
-                    triggers.iter().map(StoredTrigger::from_trigger).collect();
-
-                // Build display items
-                let display_items: Vec<String> =
-                    triggers.iter().map(format_trigger_for_display).collect();
-
-                // Pre-select triggers that are already permanent
-                let default_selections: Vec<usize> = stored_triggers
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, t)| existing_permanent.iter().any(|p| triggers_match(t, p)))
-                    .map(|(i, _)| i)
-                    .collect();
-
-                println!(
-                    "Select triggers to mark as PERMANENT (always active across all profiles):\n"
-                );
-
-                let selected_result = MultiSelect::new(
-                    "Permanent triggers (checked = permanent):",
-                    display_items.clone(),
-                )
-                .with_default(&default_selections)
-                .with_vim_mode(true)
-                .with_page_size(15)
-                .with_help_message("Space=toggle, j/k=navigate, Enter=confirm, Esc=cancel")
-                .prompt();
-
-                let selected_displays: Vec<String> = match selected_result {
-                    Ok(selected) => selected,
-                    Err(InquireError::OperationCanceled)
-                    | Err(InquireError::OperationInterrupted) => {
-                        println!("Operation cancelled.");
-                        return Ok(());
-                    }
-                    Err(e) => return Err(e.into()),
-                };
-
-                // Find which triggers were selected
-                let selected_set: std::collections::HashSet<&str> =
-                    selected_displays.iter().map(|s| s.as_str()).collect();
-                let new_permanent: Vec<StoredTrigger> = triggers
-                    .iter()
-                    .filter(|t| selected_set.contains(format_trigger_for_display(t).as_str()))
-                    .map(StoredTrigger::from_trigger)
-                    .collect();
-
-                save_permanent_triggers(&new_permanent)?;
-                println!("\nSaved {} permanent triggers.", new_permanent.len());
-            }
-            ProfileCommands::ShowPermanent => {
-                let permanent = load_permanent_triggers()?;
-                if permanent.is_empty() {
-                    println!("No permanent triggers set.");
-                    println!(
-                        "\nUse 'hamalert-cli profile set-permanent' to select permanent triggers."
-                    );
-                } else {
-                    println!("Permanent triggers ({}):", permanent.len());
-                    for trigger in &permanent {
-                        let mode = trigger
-                            .conditions
-                            .get("mode")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("any");
-                        let callsign = trigger
-                            .conditions
-                            .get("callsign")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("?");
-                        println!("  - [{}] {} - \"{}\"", mode, callsign, trigger.comment);
-                    }
-                }
-            }
-        },
+        }
     }
 
-    Ok(())
+    if let Commands::Shell = command {
+        return commands::shell::run(&client, &config, cli.config_file, assume_yes).await;
+    }
+    commands::dispatch(command, &client, &config, json_output, assume_yes).await
 }
 
 #[cfg(test)]
@@ -1756,236 +279,16 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_parse_polo_notes_simple_callsigns() {
-        let content = "W1ABC\nK2DEF\nN3GHI";
-        let result = parse_polo_notes_content(content);
-        assert_eq!(result, vec!["W1ABC", "K2DEF", "N3GHI"]);
-    }
-
-    #[test]
-    fn test_parse_polo_notes_callsigns_with_notes() {
-        let content = "W1ABC friend from club\nK2DEF met at field day\nN3GHI";
-        let result = parse_polo_notes_content(content);
-        assert_eq!(result, vec!["W1ABC", "K2DEF", "N3GHI"]);
-    }
-
-    #[test]
-    fn test_parse_polo_notes_empty_content() {
-        let content = "";
-        let result = parse_polo_notes_content(content);
-        assert!(result.is_empty());
-    }
-
-    #[test]
-    fn test_parse_polo_notes_only_empty_lines() {
-        let content = "\n\n\n";
-        let result = parse_polo_notes_content(content);
-        assert!(result.is_empty());
-    }
-
-    #[test]
-    fn test_parse_polo_notes_hash_comments() {
-        let content = "# This is a comment\nW1ABC\n# Another comment\nK2DEF";
-        let result = parse_polo_notes_content(content);
-        assert_eq!(result, vec!["W1ABC", "K2DEF"]);
-    }
-
-    #[test]
-    fn test_parse_polo_notes_slash_comments() {
-        let content = "// This is a comment\nW1ABC\n// Another comment\nK2DEF";
-        let result = parse_polo_notes_content(content);
-        assert_eq!(result, vec!["W1ABC", "K2DEF"]);
-    }
-
-    #[test]
-    fn test_parse_polo_notes_mixed_comments() {
-        let content = "# Hash comment\n// Slash comment\nW1ABC";
-        let result = parse_polo_notes_content(content);
-        assert_eq!(result, vec!["W1ABC"]);
-    }
-
-    #[test]
-    fn test_parse_polo_notes_whitespace_handling() {
-        let content = "  W1ABC  \n\tK2DEF\t\n   N3GHI   notes here";
-        let result = parse_polo_notes_content(content);
-        assert_eq!(result, vec!["W1ABC", "K2DEF", "N3GHI"]);
-    }
-
-    #[test]
-    fn test_parse_polo_notes_mixed_content() {
-        let content = "# Header comment\n\nW1ABC friend\n\n// Another comment\nK2DEF\n\n";
-        let result = parse_polo_notes_content(content);
-        assert_eq!(result, vec!["W1ABC", "K2DEF"]);
-    }
-
-    #[test]
-    fn test_parse_polo_notes_only_comments() {
-        let content = "# Comment 1\n// Comment 2\n# Comment 3";
-        let result = parse_polo_notes_content(content);
-        assert!(result.is_empty());
-    }
-
-    #[test]
-    fn test_parse_polo_notes_indented_comments() {
-        let content = "  # Indented hash comment\n  // Indented slash comment\nW1ABC";
-        let result = parse_polo_notes_content(content);
-        assert_eq!(result, vec!["W1ABC"]);
-    }
-
-    #[test]
-    fn test_parse_polo_notes_single_callsign() {
-        let content = "W1ABC";
-        let result = parse_polo_notes_content(content);
-        assert_eq!(result, vec!["W1ABC"]);
-    }
-
-    #[test]
-    fn test_parse_polo_notes_callsign_with_hash_in_note() {
-        // A hash in the middle of a note (not at start) should not be treated as comment
-        let content = "W1ABC note with #hashtag";
-        let result = parse_polo_notes_content(content);
-        assert_eq!(result, vec!["W1ABC"]);
-    }
-
-    #[test]
-    fn test_triggers_match_identical() {
-        let t1 = StoredTrigger {
-            conditions: serde_json::json!({"callsign": "W1ABC"}),
-            actions: vec!["app".to_string()],
-            comment: "Test trigger".to_string(),
-            options: None,
-        };
-        let t2 = StoredTrigger {
-            conditions: serde_json::json!({"callsign": "W1ABC"}),
-            actions: vec!["app".to_string()],
-            comment: "Test trigger".to_string(),
-            options: None,
-        };
-        assert!(triggers_match(&t1, &t2));
-    }
-
-    #[test]
-    fn test_triggers_match_different_callsign() {
-        let t1 = StoredTrigger {
-            conditions: serde_json::json!({"callsign": "W1ABC"}),
-            actions: vec!["app".to_string()],
-            comment: "Test trigger".to_string(),
-            options: None,
-        };
-        let t2 = StoredTrigger {
-            conditions: serde_json::json!({"callsign": "K2DEF"}),
-            actions: vec!["app".to_string()],
-            comment: "Test trigger".to_string(),
-            options: None,
-        };
-        assert!(!triggers_match(&t1, &t2));
-    }
-
-    #[test]
-    fn test_triggers_match_different_comment() {
-        let t1 = StoredTrigger {
-            conditions: serde_json::json!({"callsign": "W1ABC"}),
-            actions: vec!["app".to_string()],
-            comment: "Comment A".to_string(),
-            options: None,
-        };
-        let t2 = StoredTrigger {
-            conditions: serde_json::json!({"callsign": "W1ABC"}),
-            actions: vec!["app".to_string()],
-            comment: "Comment B".to_string(),
-            options: None,
-        };
-        assert!(!triggers_match(&t1, &t2));
-    }
-
-    #[test]
-    fn test_triggers_match_ignores_actions() {
-        let t1 = StoredTrigger {
-            conditions: serde_json::json!({"callsign": "W1ABC"}),
-            actions: vec!["app".to_string()],
-            comment: "Test".to_string(),
-            options: None,
-        };
-        let t2 = StoredTrigger {
-            conditions: serde_json::json!({"callsign": "W1ABC"}),
-            actions: vec!["url".to_string(), "app".to_string()],
-            comment: "Test".to_string(),
-            options: None,
-        };
-        assert!(triggers_match(&t1, &t2));
-    }
-
-    #[test]
-    fn test_profiles_dir_is_under_data_dir() {
-        let dir = profiles_dir().unwrap();
-        assert!(dir.to_string_lossy().contains("hamalert"));
-        assert!(dir.to_string_lossy().contains("profiles"));
-    }
-
-    #[test]
-    fn test_permanent_triggers_path_is_json() {
-        let path = permanent_triggers_path().unwrap();
-        assert!(path.to_string_lossy().ends_with("permanent.json"));
-    }
-
-    #[test]
-    fn test_current_profile_path_exists() {
-        let path = current_profile_path().unwrap();
-        assert!(path.to_string_lossy().contains("current-profile"));
-    }
-
-    #[test]
-    fn test_load_profile_not_found() {
-        let result = load_profile("nonexistent_profile_xyz");
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn test_calculate_profile_match_full_match() {
-        let current = vec![
-            StoredTrigger {
-                conditions: serde_json::json!({"callsign": "W1ABC"}),
-                actions: vec!["app".to_string()],
-                comment: "A".to_string(),
-                options: None,
-            },
-            StoredTrigger {
-                conditions: serde_json::json!({"callsign": "K2DEF"}),
-                actions: vec!["app".to_string()],
-                comment: "B".to_string(),
-                options: None,
-            },
-        ];
-        let profile = current.clone();
-        let (matched, total) = calculate_profile_match(&current, &profile);
-        assert_eq!(matched, 2);
-        assert_eq!(total, 2);
-    }
-
-    #[test]
-    fn test_calculate_profile_match_partial() {
-        let current = vec![StoredTrigger {
-            conditions: serde_json::json!({"callsign": "W1ABC"}),
-            actions: vec!["app".to_string()],
-            comment: "A".to_string(),
-            options: None,
-        }];
-        let profile = vec![
-            StoredTrigger {
-                conditions: serde_json::json!({"callsign": "W1ABC"}),
-                actions: vec!["app".to_string()],
-                comment: "A".to_string(),
-                options: None,
-            },
-            StoredTrigger {
-                conditions: serde_json::json!({"callsign": "K2DEF"}),
-                actions: vec!["app".to_string()],
-                comment: "B".to_string(),
-                options: None,
-            },
-        ];
-        let (matched, total) = calculate_profile_match(&current, &profile);
-        assert_eq!(matched, 1);
-        assert_eq!(total, 2);
+    fn test_default_log_filter() {
+        assert_eq!(default_log_filter(0, true), "error");
+        assert_eq!(default_log_filter(1, false), "warn,hamalert_cli=info");
+        assert!(default_log_filter(1, true).contains("hamalert_api=info"));
+        assert!(default_log_filter(2, false).contains("hamalert_api=debug"));
+        assert!(default_log_filter(5, false).contains("hamalert_api=trace"));
+        for level in 0..4 {
+            for to_file in [false, true] {
+                tracing_subscriber::EnvFilter::try_new(default_log_filter(level, to_file)).unwrap();
+            }
+        }
     }
 }
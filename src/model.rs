@@ -0,0 +1,476 @@
+//! Triggers and spots as the CLI works with them.
+
+use hamalert_api::Trigger;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Number of callsigns in a trigger's callsign condition
+pub(crate) fn trigger_callsign_count(trigger: &Trigger) -> usize {
+    callsign_count(&trigger.conditions)
+}
+
+/// Number of callsigns in the callsign condition of `conditions`
+pub(crate) fn callsign_count(conditions: &serde_json::Value) -> usize {
+    match conditions.get("callsign") {
+        Some(serde_json::Value::Array(items)) => items.len(),
+        Some(serde_json::Value::String(s)) => s
+            .split([',', '\n'])
+            .filter(|c| !c.trim().is_empty())
+            .count(),
+        _ => 0,
+    }
+}
+
+/// Comment without its "(N calls)" suffix, if it has one
+pub(crate) fn strip_callsign_count(comment: &str) -> &str {
+    comment
+        .strip_suffix(" calls)")
+        .and_then(|head| head.rsplit_once(" ("))
+        .filter(|(_, count)| !count.is_empty() && count.chars().all(|c| c.is_ascii_digit()))
+        .map_or(comment, |(base, _)| base)
+}
+
+/// `comment` with a "(N calls)" suffix for `count` callsigns, replacing any
+/// earlier count. Triggers for a single callsign get no suffix.
+pub(crate) fn with_callsign_count(comment: &str, count: usize) -> String {
+    let base = strip_callsign_count(comment);
+    if count > 1 {
+        format!("{} ({} calls)", base, count)
+    } else {
+        base.to_string()
+    }
+}
+
+/// Bring the "(N calls)" suffix of a trigger's comment up to date with its
+/// callsigns. Comments without a suffix are left alone.
+pub(crate) fn refresh_callsign_count(trigger: &mut Trigger) {
+    if strip_callsign_count(&trigger.comment) != trigger.comment {
+        trigger.comment = with_callsign_count(&trigger.comment, trigger_callsign_count(trigger));
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct EditableTrigger {
+    pub(crate) conditions: serde_json::Value,
+    pub(crate) actions: Vec<String>,
+    pub(crate) comment: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) options: Option<serde_json::Value>,
+}
+
+impl EditableTrigger {
+    pub(crate) fn from_trigger(trigger: &Trigger) -> Self {
+        Self {
+            conditions: trigger.conditions.clone(),
+            actions: trigger.actions.clone(),
+            comment: trigger.comment.clone(),
+            options: trigger.options.clone(),
+        }
+    }
+
+    pub(crate) fn apply_to_trigger(self, trigger: &mut Trigger) {
+        trigger.conditions = self.conditions;
+        trigger.actions = self.actions;
+        trigger.comment = self.comment;
+        trigger.options = self.options;
+    }
+}
+
+/// Trigger data for storage in profile files (without runtime fields like _id)
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct StoredTrigger {
+    pub(crate) conditions: serde_json::Value,
+    pub(crate) actions: Vec<String>,
+    pub(crate) comment: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) options: Option<serde_json::Value>,
+}
+
+impl StoredTrigger {
+    #[allow(dead_code)]
+    pub(crate) fn from_trigger(trigger: &Trigger) -> Self {
+        Self {
+            conditions: trigger.conditions.clone(),
+            actions: trigger.actions.clone(),
+            comment: trigger.comment.clone(),
+            options: trigger.options.clone(),
+        }
+    }
+}
+
+/// Check if two triggers match by conditions and comment (identity match)
+pub(crate) fn triggers_match(a: &StoredTrigger, b: &StoredTrigger) -> bool {
+    a.conditions == b.conditions && a.comment == b.comment
+}
+
+/// Modes recognized in spot comments, checked in order
+pub(crate) const SPOT_MODES: &[&str] = &[
+    "CW", "SSB", "USB", "LSB", "FT8", "FT4", "RTTY", "PSK31", "PSK", "JS8", "JT65", "FM", "AM",
+];
+
+/// A single spot received from HamAlert, via telnet or the "url" action
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Spot {
+    pub(crate) spotter: String,
+    pub(crate) frequency_khz: f64,
+    pub(crate) callsign: String,
+    pub(crate) mode: Option<String>,
+    pub(crate) comment: String,
+    /// Comment of the trigger that matched (only known for webhook spots)
+    pub(crate) trigger_comment: Option<String>,
+}
+
+impl Spot {
+    pub(crate) fn band(&self) -> Option<&'static str> {
+        band_for_frequency(self.frequency_khz)
+    }
+
+    /// Format the spot as a DX-cluster line
+    pub(crate) fn to_cluster_line(&self) -> String {
+        let comment = match &self.mode {
+            Some(mode) if !self.comment.contains(mode.as_str()) => {
+                format!("{} {}", mode, self.comment)
+            }
+            _ => self.comment.clone(),
+        };
+        format!(
+            "DX de {}:{:>11.1}  {:<12} {}",
+            self.spotter,
+            self.frequency_khz,
+            self.callsign,
+            comment.trim()
+        )
+    }
+}
+
+/// Find a known mode mentioned in a spot comment
+pub(crate) fn mode_from_comment(comment: &str) -> Option<String> {
+    comment.split_whitespace().find_map(|word| {
+        SPOT_MODES
+            .iter()
+            .find(|m| word.eq_ignore_ascii_case(m))
+            .map(|m| m.to_string())
+    })
+}
+
+/// Parse a DX-cluster spot line, e.g.
+/// "DX de K1TTT:     14025.0  W1AW         CW 23 dB 25 WPM CQ     1234Z",
+/// optionally followed by the matching trigger as written to spot logs
+pub(crate) fn parse_spot_line(line: &str) -> Option<Spot> {
+    let (line, trigger_comment) = match line.trim_end().strip_suffix(']') {
+        Some(head) => match head.rsplit_once("  [") {
+            Some((line, trigger)) => (line, Some(trigger.to_string())),
+            None => (line, None),
+        },
+        None => (line, None),
+    };
+    let rest = line.strip_prefix("DX de ")?;
+    let (spotter, rest) = rest.split_once(':')?;
+    let mut words = rest.split_whitespace();
+    let frequency_khz: f64 = words.next()?.parse().ok()?;
+    let callsign = words.next()?.to_string();
+    let comment = words.collect::<Vec<_>>().join(" ");
+    Some(Spot {
+        spotter: spotter.trim().to_string(),
+        frequency_khz,
+        callsign,
+        mode: mode_from_comment(&comment),
+        comment,
+        trigger_comment,
+    })
+}
+
+/// Build a spot from the parameters of a HamAlert "url" action notification.
+/// HamAlert sends the frequency in MHz.
+pub(crate) fn spot_from_webhook_params(params: &HashMap<String, String>) -> Option<Spot> {
+    let non_empty = |key: &str| params.get(key).filter(|v| !v.trim().is_empty()).cloned();
+
+    let callsign = non_empty("fullCallsign").or_else(|| non_empty("callsign"))?;
+    let frequency_mhz: f64 = non_empty("frequency")?.trim().parse().ok()?;
+    let comment = non_empty("comment").unwrap_or_default();
+    let mode = non_empty("mode")
+        .map(|m| m.to_uppercase())
+        .or_else(|| mode_from_comment(&comment));
+    Some(Spot {
+        spotter: non_empty("spotter").unwrap_or_else(|| "HamAlert".to_string()),
+        frequency_khz: frequency_mhz * 1000.0,
+        callsign,
+        mode,
+        comment,
+        trigger_comment: non_empty("triggerComment"),
+    })
+}
+
+/// Amateur bands as (lowest kHz, highest kHz, name)
+pub(crate) const BANDS: &[(f64, f64, &str)] = &[
+    (1800.0, 2000.0, "160m"),
+    (3500.0, 4000.0, "80m"),
+    (5250.0, 5450.0, "60m"),
+    (7000.0, 7300.0, "40m"),
+    (10100.0, 10150.0, "30m"),
+    (14000.0, 14350.0, "20m"),
+    (18068.0, 18168.0, "17m"),
+    (21000.0, 21450.0, "15m"),
+    (24890.0, 24990.0, "12m"),
+    (28000.0, 29700.0, "10m"),
+    (50000.0, 54000.0, "6m"),
+    (70000.0, 70500.0, "4m"),
+    (144000.0, 148000.0, "2m"),
+    (420000.0, 450000.0, "70cm"),
+];
+
+/// Map a frequency in kHz to its amateur band name
+pub(crate) fn band_for_frequency(khz: f64) -> Option<&'static str> {
+    BANDS
+        .iter()
+        .find(|(low, high, _)| khz >= *low && khz <= *high)
+        .map(|(_, _, band)| *band)
+}
+
+/// Callsigns listed in a trigger's callsign condition
+pub(crate) fn trigger_callsigns(trigger: &Trigger) -> Vec<String> {
+    trigger
+        .conditions
+        .get("callsign")
+        .and_then(|v| v.as_str())
+        .map(|calls| {
+            calls
+                .split([',', '\n'])
+                .map(|c| c.trim().to_uppercase())
+                .filter(|c| !c.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Normalize mode names so ADIF and spot modes compare equal
+pub(crate) fn normalize_mode(mode: &str) -> String {
+    match mode.to_uppercase().as_str() {
+        "USB" | "LSB" => "SSB".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// A trigger as HamAlert returns it, with the `app` action, for tests
+#[cfg(test)]
+pub(crate) fn test_trigger(id: &str, comment: &str, conditions: serde_json::Value) -> Trigger {
+    serde_json::from_value(serde_json::json!({
+        "_id": id,
+        "conditions": conditions,
+        "actions": ["app"],
+        "comment": comment,
+    }))
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_triggers_match_identical() {
+        let t1 = StoredTrigger {
+            conditions: serde_json::json!({"callsign": "W1ABC"}),
+            actions: vec!["app".to_string()],
+            comment: "Test trigger".to_string(),
+            options: None,
+        };
+        let t2 = StoredTrigger {
+            conditions: serde_json::json!({"callsign": "W1ABC"}),
+            actions: vec!["app".to_string()],
+            comment: "Test trigger".to_string(),
+            options: None,
+        };
+        assert!(triggers_match(&t1, &t2));
+    }
+
+    #[test]
+    fn test_triggers_match_different_callsign() {
+        let t1 = StoredTrigger {
+            conditions: serde_json::json!({"callsign": "W1ABC"}),
+            actions: vec!["app".to_string()],
+            comment: "Test trigger".to_string(),
+            options: None,
+        };
+        let t2 = StoredTrigger {
+            conditions: serde_json::json!({"callsign": "K2DEF"}),
+            actions: vec!["app".to_string()],
+            comment: "Test trigger".to_string(),
+            options: None,
+        };
+        assert!(!triggers_match(&t1, &t2));
+    }
+
+    #[test]
+    fn test_triggers_match_different_comment() {
+        let t1 = StoredTrigger {
+            conditions: serde_json::json!({"callsign": "W1ABC"}),
+            actions: vec!["app".to_string()],
+            comment: "Comment A".to_string(),
+            options: None,
+        };
+        let t2 = StoredTrigger {
+            conditions: serde_json::json!({"callsign": "W1ABC"}),
+            actions: vec!["app".to_string()],
+            comment: "Comment B".to_string(),
+            options: None,
+        };
+        assert!(!triggers_match(&t1, &t2));
+    }
+
+    #[test]
+    fn test_triggers_match_ignores_actions() {
+        let t1 = StoredTrigger {
+            conditions: serde_json::json!({"callsign": "W1ABC"}),
+            actions: vec!["app".to_string()],
+            comment: "Test".to_string(),
+            options: None,
+        };
+        let t2 = StoredTrigger {
+            conditions: serde_json::json!({"callsign": "W1ABC"}),
+            actions: vec!["url".to_string(), "app".to_string()],
+            comment: "Test".to_string(),
+            options: None,
+        };
+        assert!(triggers_match(&t1, &t2));
+    }
+
+    #[test]
+    fn test_parse_spot_line() {
+        let spot =
+            parse_spot_line("DX de K1TTT:     14025.0  W1AW         CW 23 dB 25 WPM CQ     1234Z")
+                .unwrap();
+        assert_eq!(spot.spotter, "K1TTT");
+        assert_eq!(spot.frequency_khz, 14025.0);
+        assert_eq!(spot.callsign, "W1AW");
+        assert_eq!(spot.comment, "CW 23 dB 25 WPM CQ 1234Z");
+        assert_eq!(spot.band(), Some("20m"));
+        assert_eq!(spot.mode.as_deref(), Some("CW"));
+    }
+
+    #[test]
+    fn test_callsign_count_suffix() {
+        assert_eq!(with_callsign_count("Club", 3), "Club (3 calls)");
+        assert_eq!(with_callsign_count("Club (3 calls)", 40), "Club (40 calls)");
+        assert_eq!(with_callsign_count("Club (3 calls)", 1), "Club");
+        assert_eq!(strip_callsign_count("Net (Tuesday)"), "Net (Tuesday)");
+        assert_eq!(strip_callsign_count("Club (1/2) (9 calls)"), "Club (1/2)");
+        assert_eq!(strip_callsign_count("Club ( calls)"), "Club ( calls)");
+
+        let mut trigger: Trigger = serde_json::from_value(serde_json::json!({
+            "_id": "1",
+            "conditions": {"callsign": "W1AW,K3LR"},
+            "actions": ["app"],
+            "comment": "Club (5 calls)",
+        }))
+        .unwrap();
+        refresh_callsign_count(&mut trigger);
+        assert_eq!(trigger.comment, "Club (2 calls)");
+        trigger.comment = "Friends".to_string();
+        refresh_callsign_count(&mut trigger);
+        assert_eq!(trigger.comment, "Friends");
+    }
+
+    #[test]
+    fn test_parse_spot_line_with_trigger() {
+        let spot = parse_spot_line(
+            "DX de K1TTT:     21025.0  JA1ABC       CW 599 2215Z  [Asia on 15m [CW]]",
+        )
+        .unwrap();
+        assert_eq!(spot.callsign, "JA1ABC");
+        assert_eq!(spot.comment, "CW 599 2215Z");
+        assert_eq!(spot.trigger_comment.as_deref(), Some("Asia on 15m [CW]"));
+        assert_eq!(
+            parse_spot_line(&spot.to_cluster_line())
+                .unwrap()
+                .trigger_comment,
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_spot_line_rejects_non_spots() {
+        assert!(parse_spot_line("Hello W1AW, this is HamAlert").is_none());
+        assert!(parse_spot_line("DX de K1TTT: notafreq W1AW").is_none());
+        assert!(parse_spot_line("").is_none());
+    }
+
+    #[test]
+    fn test_band_for_frequency() {
+        assert_eq!(band_for_frequency(7074.0), Some("40m"));
+        assert_eq!(band_for_frequency(50313.0), Some("6m"));
+        assert_eq!(band_for_frequency(9000.0), None);
+    }
+
+    #[test]
+    fn test_spot_from_webhook_params() {
+        let params: HashMap<String, String> = [
+            ("fullCallsign", "EA8/W1AW"),
+            ("callsign", "W1AW"),
+            ("frequency", "14.0625"),
+            ("mode", "cw"),
+            ("spotter", "K1TTT"),
+            ("comment", "23 dB 25 WPM"),
+            ("triggerComment", "Friends"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let spot = spot_from_webhook_params(&params).unwrap();
+        assert_eq!(spot.callsign, "EA8/W1AW");
+        assert_eq!(spot.frequency_khz, 14062.5);
+        assert_eq!(spot.mode.as_deref(), Some("CW"));
+        assert_eq!(spot.trigger_comment.as_deref(), Some("Friends"));
+        assert_eq!(spot.band(), Some("20m"));
+    }
+
+    #[test]
+    fn test_spot_from_webhook_params_missing_frequency() {
+        let params: HashMap<String, String> = [("callsign".to_string(), "W1AW".to_string())].into();
+        assert!(spot_from_webhook_params(&params).is_none());
+    }
+
+    #[test]
+    fn test_spot_to_cluster_line_round_trip() {
+        let spot = parse_spot_line("DX de K1TTT: 7030.0 K2DEF CW 12 dB").unwrap();
+        let line = spot.to_cluster_line();
+        assert_eq!(parse_spot_line(&line), Some(spot));
+    }
+
+    #[test]
+    fn test_trigger_callsigns() {
+        let trigger = Trigger {
+            id: "1".to_string(),
+            user_id: None,
+            conditions: serde_json::json!({"callsign": "W1AW, k2def\nN3GHI"}),
+            actions: vec!["app".to_string()],
+            comment: "Friends".to_string(),
+            match_count: None,
+            disabled: None,
+            options: None,
+        };
+        assert_eq!(trigger_callsigns(&trigger), vec!["W1AW", "K2DEF", "N3GHI"]);
+    }
+
+    #[test]
+    fn test_trigger_callsign_count() {
+        let trigger = |callsign: serde_json::Value| -> Trigger {
+            serde_json::from_value(json!({
+                "_id": "a",
+                "conditions": { "callsign": callsign },
+                "actions": [],
+                "comment": ""
+            }))
+            .unwrap()
+        };
+        assert_eq!(
+            trigger_callsign_count(&trigger(json!("W1AW, K3LR,N0CALL"))),
+            3
+        );
+        assert_eq!(trigger_callsign_count(&trigger(json!("W1AW\nK3LR\n"))), 2);
+        assert_eq!(trigger_callsign_count(&trigger(json!(["W1AW", "K3LR"]))), 2);
+        assert_eq!(trigger_callsign_count(&trigger(json!(null))), 0);
+    }
+}
@@ -0,0 +1,475 @@
+//! Terminal output helpers: colors, tables, CSV and confirmation prompts.
+
+use crate::api::CreatedTrigger;
+use crate::error::CliError;
+use crate::model::StoredTrigger;
+use clap::ValueEnum;
+use hamalert_api::Trigger;
+use serde_json::Value;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether human output is colorized, resolved once from --color and NO_COLOR
+pub(crate) static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Path given with --get, applied to every --json value before printing
+pub(crate) static JSON_PATH: OnceLock<Vec<PathSegment>> = OnceLock::new();
+
+pub(crate) const RED: &str = "31";
+
+pub(crate) const GREEN: &str = "32";
+
+pub(crate) const YELLOW: &str = "33";
+
+pub(crate) const MAGENTA: &str = "35";
+
+pub(crate) const CYAN: &str = "36";
+
+pub(crate) const BLUE: &str = "34";
+
+pub(crate) const BOLD: &str = "1";
+
+/// Wrap text in an ANSI color code when color output is enabled
+pub(crate) fn paint(text: &str, code: &str) -> String {
+    if COLOR.load(Ordering::Relaxed) && !code.is_empty() {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Color used for a trigger mode
+pub(crate) fn mode_color(mode: &str) -> &'static str {
+    match mode {
+        "cw" => YELLOW,
+        "ft8" => CYAN,
+        "ssb" => MAGENTA,
+        _ => "",
+    }
+}
+
+/// Color used for a band in spot output, from red on the low bands to
+/// magenta from 6m up
+pub(crate) fn band_color(band: &str) -> &'static str {
+    match band {
+        "160m" | "80m" => RED,
+        "60m" | "40m" => YELLOW,
+        "30m" | "20m" => GREEN,
+        "17m" | "15m" => CYAN,
+        "12m" | "10m" => BLUE,
+        "" => "",
+        _ => MAGENTA,
+    }
+}
+
+/// Minimum width a column is shrunk to when the table does not fit
+pub(crate) const TABLE_MIN_COLUMN_WIDTH: usize = 6;
+
+/// Text without the ANSI color codes added by `paint`
+pub(crate) fn strip_ansi(text: &str) -> String {
+    let mut plain = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| *c == 'm');
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// Number of characters shown on screen, ignoring color codes
+pub(crate) fn visible_width(text: &str) -> usize {
+    strip_ansi(text).chars().count()
+}
+
+/// Truncate text to a display width, marking the cut with an ellipsis.
+/// Colors are dropped from truncated cells.
+pub(crate) fn truncate_cell(text: &str, width: usize) -> String {
+    if visible_width(text) <= width {
+        text.to_string()
+    } else {
+        let mut cut: String = strip_ansi(text)
+            .chars()
+            .take(width.saturating_sub(1))
+            .collect();
+        cut.push('…');
+        cut
+    }
+}
+
+/// Render rows as aligned columns. When `max_width` is given, the widest
+/// columns are shrunk (and their cells truncated) until the table fits.
+pub(crate) fn render_table(
+    headers: &[&str],
+    rows: &[Vec<String>],
+    max_width: Option<usize>,
+) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(visible_width(cell));
+        }
+    }
+
+    if let Some(max_width) = max_width {
+        let separators = 2 * widths.len().saturating_sub(1);
+        while widths.iter().sum::<usize>() + separators > max_width {
+            let (widest, width) = widths
+                .iter()
+                .copied()
+                .enumerate()
+                .max_by_key(|(_, w)| *w)
+                .unwrap_or_default();
+            if width <= TABLE_MIN_COLUMN_WIDTH {
+                break;
+            }
+            widths[widest] -= 1;
+        }
+    }
+
+    let format_row = |cells: Vec<String>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| {
+                let cell = truncate_cell(cell, *width);
+                let padding = width.saturating_sub(visible_width(&cell));
+                format!("{}{}", cell, " ".repeat(padding))
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![format_row(headers.iter().map(|h| h.to_string()).collect())];
+    lines.extend(rows.iter().map(|row| format_row(row.clone())));
+    lines.join("\n")
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Render rows as CSV with a header row
+pub(crate) fn render_csv(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut lines = vec![
+        headers
+            .iter()
+            .map(|h| csv_field(h))
+            .collect::<Vec<_>>()
+            .join(","),
+    ];
+    lines.extend(rows.iter().map(|row| {
+        row.iter()
+            .map(|c| csv_field(c))
+            .collect::<Vec<_>>()
+            .join(",")
+    }));
+    lines.join("\n")
+}
+
+/// Width of the terminal stdout is attached to, if any
+pub(crate) fn terminal_width() -> Option<usize> {
+    use std::io::IsTerminal;
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    ratatui::crossterm::terminal::size()
+        .ok()
+        .map(|(columns, _)| columns as usize)
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub(crate) enum ColorChoice {
+    /// Color when stdout is a terminal and NO_COLOR is not set
+    Auto,
+    Always,
+    Never,
+}
+
+/// Whether the console understands ANSI colors. Windows consoles only do
+/// once virtual terminal processing is switched on, which this does.
+fn console_supports_ansi() -> bool {
+    #[cfg(windows)]
+    {
+        ratatui::crossterm::ansi_support::supports_ansi()
+    }
+    #[cfg(not(windows))]
+    {
+        true
+    }
+}
+
+impl ColorChoice {
+    pub(crate) fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => {
+                console_supports_ansi();
+                true
+            }
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                use std::io::IsTerminal;
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::io::stdout().is_terminal()
+                    && console_supports_ansi()
+            }
+        }
+    }
+}
+
+pub(crate) fn format_trigger_for_display(trigger: &Trigger) -> String {
+    format_trigger_fields(&trigger.conditions, &trigger.comment, false)
+}
+
+/// Like `format_trigger_for_display`, with the mode colorized for terminal output
+pub(crate) fn format_stored_trigger_for_display(trigger: &StoredTrigger) -> String {
+    format_trigger_fields(&trigger.conditions, &trigger.comment, true)
+}
+
+pub(crate) fn format_trigger_fields(
+    conditions: &serde_json::Value,
+    comment: &str,
+    color: bool,
+) -> String {
+    let mode = conditions
+        .get("mode")
+        .and_then(|v| v.as_str())
+        .unwrap_or("any");
+    let callsign = conditions
+        .get("callsign")
+        .and_then(|v| v.as_str())
+        .unwrap_or("?");
+    let mode = if color {
+        paint(mode, mode_color(mode))
+    } else {
+        mode.to_string()
+    };
+    format!("[{}] {} - \"{}\"", mode, callsign, comment)
+}
+
+/// Ask a yes/no question on stdout, defaulting to no. With --yes the
+/// question is answered automatically.
+pub(crate) fn confirm(prompt: &str, assume_yes: bool) -> Result<bool, CliError> {
+    if assume_yes {
+        println!("{} [y/N]: y (--yes)", prompt);
+        return Ok(true);
+    }
+    print!("{} [y/N]: ", prompt);
+    std::io::Write::flush(&mut std::io::stdout())?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}
+
+/// One step of a --get path
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PathSegment {
+    /// `.key`: a field of an object
+    Key(String),
+    /// `[N]`: an element of an array
+    Index(usize),
+    /// `[]`: every element of an array or value of an object
+    Each,
+}
+
+/// Parse a jq-like path such as `[].comment` or `.triggers[0].conditions`.
+/// The leading `.` and spaces between steps are optional.
+pub(crate) fn parse_json_path(path: &str) -> Result<Vec<PathSegment>, String> {
+    let mut segments = Vec::new();
+    let mut chars = path.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            ' ' | '.' => {
+                chars.next();
+            }
+            '[' => {
+                chars.next();
+                let mut inner = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => inner.push(c),
+                        None => return Err(format!("Unclosed '[' in path '{}'", path)),
+                    }
+                }
+                let inner = inner.trim();
+                if inner.is_empty() {
+                    segments.push(PathSegment::Each);
+                } else {
+                    let index = inner
+                        .parse()
+                        .map_err(|_| format!("'[{}]' in path '{}' is not an index", inner, path))?;
+                    segments.push(PathSegment::Index(index));
+                }
+            }
+            ']' => return Err(format!("Unexpected ']' in path '{}'", path)),
+            _ => {
+                let mut key = String::new();
+                while let Some(&c) = chars.peek() {
+                    if matches!(c, '.' | '[' | ']' | ' ') {
+                        break;
+                    }
+                    key.push(c);
+                    chars.next();
+                }
+                segments.push(PathSegment::Key(key));
+            }
+        }
+    }
+    Ok(segments)
+}
+
+/// Values at `path` in `value`. A missing field or element gives null,
+/// as in jq; `[]` on anything but an array or object gives nothing.
+pub(crate) fn select_json(value: &Value, path: &[PathSegment]) -> Vec<Value> {
+    let Some((first, rest)) = path.split_first() else {
+        return vec![value.clone()];
+    };
+    match first {
+        PathSegment::Key(key) => select_json(value.get(key).unwrap_or(&Value::Null), rest),
+        PathSegment::Index(index) => select_json(value.get(index).unwrap_or(&Value::Null), rest),
+        PathSegment::Each => match value {
+            Value::Array(items) => items.iter().flat_map(|v| select_json(v, rest)).collect(),
+            Value::Object(map) => map.values().flat_map(|v| select_json(v, rest)).collect(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+/// List the triggers a command created with their ids, or with --json as
+/// `{"created": [{"id", "name", "comment"}]}`
+pub(crate) fn print_created(created: &[CreatedTrigger], json_output: bool) -> Result<(), CliError> {
+    if json_output {
+        return print_json(&serde_json::json!({ "created": created }));
+    }
+    for trigger in created {
+        let id = trigger.id.as_deref().unwrap_or("(id unknown)");
+        match &trigger.name {
+            Some(name) => println!("Created trigger {} \"{}\" as {}", id, trigger.comment, name),
+            None => println!("Created trigger {} \"{}\"", id, trigger.comment),
+        }
+    }
+    Ok(())
+}
+
+/// Print a value as pretty JSON on stdout, for --json output. With --get,
+/// print the selected values one per line instead, strings without quotes.
+pub(crate) fn print_json(value: &serde_json::Value) -> Result<(), CliError> {
+    let Some(path) = JSON_PATH.get() else {
+        println!("{}", serde_json::to_string_pretty(value)?);
+        return Ok(());
+    };
+    for selected in select_json(value, path) {
+        match selected {
+            Value::String(s) => println!("{}", s),
+            other => println!("{}", serde_json::to_string(&other)?),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paint_respects_color_setting() {
+        COLOR.store(false, Ordering::Relaxed);
+        assert_eq!(paint("cw", YELLOW), "cw");
+        assert_eq!(paint("any", mode_color("any")), "any");
+    }
+
+    #[test]
+    fn test_render_table_aligns_columns() {
+        let rows = vec![
+            vec!["Friends".to_string(), "cw".to_string()],
+            vec!["DX".to_string(), "any".to_string()],
+        ];
+        assert_eq!(
+            render_table(&["COMMENT", "MODE"], &rows, None),
+            "COMMENT  MODE\nFriends  cw\nDX       any"
+        );
+    }
+
+    #[test]
+    fn test_render_table_truncates_to_width() {
+        let rows = vec![vec![
+            "A very long trigger comment".to_string(),
+            "W1AW".to_string(),
+        ]];
+        let table = render_table(&["COMMENT", "CALLSIGNS"], &rows, Some(24));
+        assert!(table.lines().all(|line| line.chars().count() <= 24));
+        assert!(table.contains('…'));
+        assert!(table.contains("W1AW"));
+    }
+
+    #[test]
+    fn test_render_csv_quotes_fields() {
+        let rows = vec![vec!["Say \"hi\"".to_string(), "W1AW,K3LR".to_string()]];
+        assert_eq!(
+            render_csv(&["comment", "callsigns"], &rows),
+            "comment,callsigns\n\"Say \"\"hi\"\"\",\"W1AW,K3LR\""
+        );
+    }
+
+    #[test]
+    fn test_render_table_ignores_color_codes() {
+        let rows = vec![
+            vec!["\x1b[36mJA*\x1b[0m".to_string(), "cw".to_string()],
+            vec!["W1AW".to_string(), "any".to_string()],
+        ];
+        assert_eq!(visible_width(&rows[0][0]), 3);
+        assert_eq!(
+            render_table(&["CALLSIGNS", "MODE"], &rows, None),
+            "CALLSIGNS  MODE\n\x1b[36mJA*\x1b[0m        cw\nW1AW       any"
+        );
+        assert_eq!(truncate_cell("\x1b[36mVP8ABCDEF\x1b[0m", 4), "VP8…");
+    }
+
+    #[test]
+    fn test_parse_json_path() {
+        use PathSegment::*;
+        assert_eq!(
+            parse_json_path("[].comment").unwrap(),
+            vec![Each, Key("comment".to_string())]
+        );
+        assert_eq!(
+            parse_json_path("triggers[] .conditions.callsign").unwrap(),
+            vec![
+                Key("triggers".to_string()),
+                Each,
+                Key("conditions".to_string()),
+                Key("callsign".to_string())
+            ]
+        );
+        assert_eq!(parse_json_path(".[2]").unwrap(), vec![Index(2)]);
+        assert_eq!(parse_json_path(".").unwrap(), Vec::new());
+        assert!(parse_json_path("[x]").is_err());
+        assert!(parse_json_path("[0").is_err());
+    }
+
+    #[test]
+    fn test_select_json() {
+        let value = serde_json::json!([
+            {"comment": "Friends", "conditions": {"callsign": "W1AW"}},
+            {"comment": "Japan"}
+        ]);
+        let select = |path: &str| select_json(&value, &parse_json_path(path).unwrap());
+        assert_eq!(select("[].comment"), vec!["Friends", "Japan"]);
+        assert_eq!(
+            select("[].conditions.callsign"),
+            vec![Value::from("W1AW"), Value::Null]
+        );
+        assert_eq!(select("[1]"), vec![value[1].clone()]);
+        assert_eq!(select("[].comment[]"), Vec::<Value>::new());
+    }
+}
@@ -0,0 +1,1298 @@
+//! Delivery of received spots to the configured destinations.
+
+use crate::adif::spot_time;
+use crate::cli::PipelineOptions;
+use crate::config::Config;
+use crate::dxcc::{Location, PrefixTable, load_table};
+use crate::error::CliError;
+use crate::model::{Spot, normalize_mode};
+use crate::output::{BOLD, band_color, mode_color, paint};
+use crate::rig::tune_rig;
+use crate::storage::state::State;
+use hamalert_api::{SendLogged, Trigger};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A Prometheus label value with `\`, `"` and newlines escaped as the text
+/// exposition format requires
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Counters exposed on the Prometheus /metrics endpoint
+#[derive(Default)]
+pub(crate) struct SpotMetrics {
+    pub(crate) spots_received: u64,
+    pub(crate) spots_by_band: std::collections::BTreeMap<String, u64>,
+    pub(crate) spots_by_mode: std::collections::BTreeMap<String, u64>,
+    pub(crate) reconnects: u64,
+    pub(crate) forwarding_errors: u64,
+}
+
+pub(crate) type SharedMetrics = std::sync::Arc<std::sync::Mutex<SpotMetrics>>;
+
+impl SpotMetrics {
+    pub(crate) fn record_spot(&mut self, spot: &Spot) {
+        self.spots_received += 1;
+        *self
+            .spots_by_band
+            .entry(spot.band().unwrap_or("unknown").to_string())
+            .or_default() += 1;
+        *self
+            .spots_by_mode
+            .entry(spot.mode.as_deref().unwrap_or("unknown").to_lowercase())
+            .or_default() += 1;
+    }
+
+    /// Render the counters in the Prometheus text exposition format
+    pub(crate) fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP hamalert_spots_received_total Spots received from HamAlert.\n");
+        out.push_str("# TYPE hamalert_spots_received_total counter\n");
+        out.push_str(&format!(
+            "hamalert_spots_received_total {}\n",
+            self.spots_received
+        ));
+        out.push_str("# HELP hamalert_spots_by_band_total Spots received per band.\n");
+        out.push_str("# TYPE hamalert_spots_by_band_total counter\n");
+        for (band, count) in &self.spots_by_band {
+            out.push_str(&format!(
+                "hamalert_spots_by_band_total{{band=\"{}\"}} {}\n",
+                escape_label_value(band),
+                count
+            ));
+        }
+        out.push_str("# HELP hamalert_spots_by_mode_total Spots received per mode.\n");
+        out.push_str("# TYPE hamalert_spots_by_mode_total counter\n");
+        for (mode, count) in &self.spots_by_mode {
+            out.push_str(&format!(
+                "hamalert_spots_by_mode_total{{mode=\"{}\"}} {}\n",
+                escape_label_value(mode),
+                count
+            ));
+        }
+        out.push_str("# HELP hamalert_reconnects_total Reconnects to the HamAlert feed.\n");
+        out.push_str("# TYPE hamalert_reconnects_total counter\n");
+        out.push_str(&format!("hamalert_reconnects_total {}\n", self.reconnects));
+        out.push_str(
+            "# HELP hamalert_forwarding_errors_total Errors forwarding spots to clients.\n",
+        );
+        out.push_str("# TYPE hamalert_forwarding_errors_total counter\n");
+        out.push_str(&format!(
+            "hamalert_forwarding_errors_total {}\n",
+            self.forwarding_errors
+        ));
+        out
+    }
+}
+
+/// A sink that received spots are forwarded to, configured as `[[destinations]]`
+/// tables in config.toml
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub(crate) enum Destination {
+    /// Print spots to stdout
+    Stdout,
+    /// Append spots to a file
+    File { path: PathBuf },
+    /// Send each spot as a UDP datagram
+    Udp { address: String },
+    /// Post spots to a Discord channel webhook
+    Discord { webhook_url: String },
+    /// Publish spots to an ntfy topic
+    Ntfy {
+        topic: String,
+        #[serde(default = "default_ntfy_server")]
+        server: String,
+        priority: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    /// Publish spots to a topic on an MQTT broker (QoS 0)
+    Mqtt {
+        address: String,
+        topic: String,
+        username: Option<String>,
+        password: Option<String>,
+    },
+    /// Tune a radio to each spot through hamlib's rigctld
+    Rigctld { address: String },
+    /// Run a shell command per spot with the spot line on stdin and the
+    /// spot fields in HAMALERT_* environment variables
+    Exec { command: String },
+    /// Show a desktop notification: a toast on Windows, Notification Center
+    /// on macOS and notify-send elsewhere
+    Desktop,
+}
+
+/// Destinations for the spots of some triggers instead of `[[destinations]]`,
+/// configured as `[[watch.routes]]` tables. Only spots that name their trigger (from
+/// the "url" action) can be routed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Route {
+    /// Text the trigger comment contains, ignoring case
+    pub(crate) comment: Option<String>,
+    /// Tag the trigger was recorded with
+    pub(crate) tag: Option<String>,
+    pub(crate) destinations: Vec<Destination>,
+    /// Comments of the triggers with `tag`, filled in by `tag_routes`
+    #[serde(skip)]
+    pub(crate) tagged: Vec<String>,
+}
+
+impl Route {
+    fn matches(&self, trigger_comment: &str) -> bool {
+        self.comment.as_ref().is_none_or(|text| {
+            trigger_comment
+                .to_lowercase()
+                .contains(&text.to_lowercase())
+        }) && (self.tag.is_none() || self.tagged.iter().any(|c| c == trigger_comment))
+    }
+}
+
+pub(crate) fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+impl Destination {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Destination::Stdout => "stdout",
+            Destination::File { .. } => "file",
+            Destination::Udp { .. } => "udp",
+            Destination::Discord { .. } => "discord",
+            Destination::Ntfy { .. } => "ntfy",
+            Destination::Mqtt { .. } => "mqtt",
+            Destination::Rigctld { .. } => "rigctld",
+            Destination::Exec { .. } => "exec",
+            Destination::Desktop => "desktop",
+        }
+    }
+}
+
+/// Title used for push-style notifications: the matching trigger's comment when known
+pub(crate) fn spot_title(spot: &Spot) -> String {
+    match &spot.trigger_comment {
+        Some(trigger) => trigger.clone(),
+        None => format!("{} spotted", spot.callsign),
+    }
+}
+
+/// Entity, continent and CQ zone of a spotted station, e.g. `Japan, AS, CQ 25`
+pub(crate) fn location_label(location: &Location) -> String {
+    format!(
+        "{}, {}, CQ {}",
+        location.entity.name, location.continent, location.cq_zone
+    )
+}
+
+/// Human-readable one-line summary used by chat destinations
+pub(crate) fn spot_summary(spot: &Spot, location: Option<&Location>) -> String {
+    let mut summary = format!("**{}**", spot.callsign);
+    if let Some(location) = location {
+        summary.push_str(&format!(" ({})", location_label(location)));
+    }
+    summary.push_str(&format!(" on {:.1} kHz", spot.frequency_khz));
+    if let Some(mode) = &spot.mode {
+        summary.push_str(&format!(" {}", mode));
+    }
+    summary.push_str(&format!(" (spotted by {})", spot.spotter));
+    if !spot.comment.is_empty() {
+        summary.push_str(&format!(": {}", spot.comment));
+    }
+    summary
+}
+
+/// Placeholders accepted in `--format` templates
+pub(crate) const SPOT_FORMAT_FIELDS: &[&str] = &[
+    "time",
+    "call",
+    "freq",
+    "band",
+    "mode",
+    "spotter",
+    "comment",
+    "trigger",
+    "entity",
+    "continent",
+    "cqzone",
+];
+
+/// Check that a `--format` template only uses known placeholders
+pub(crate) fn validate_spot_format(template: &str) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed '{{' in format: {}", template))?;
+        let name = &rest[start + 1..start + end];
+        if !SPOT_FORMAT_FIELDS.contains(&name) {
+            return Err(format!(
+                "Unknown format field {{{}}} (available: {})",
+                name,
+                SPOT_FORMAT_FIELDS.join(", ")
+            ));
+        }
+        rest = &rest[start + end + 1..];
+    }
+    Ok(())
+}
+
+/// Render a spot with a `--format` template. `time` is used for {time}
+/// when the spot itself does not carry one; the location fields are empty
+/// for calls the prefix table does not resolve.
+pub(crate) fn format_spot(
+    template: &str,
+    spot: &Spot,
+    location: Option<&Location>,
+    time: &str,
+) -> String {
+    spot_fields(spot, location, time)
+        .iter()
+        .fold(template.to_string(), |line, (name, value)| {
+            line.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+/// Values of the `SPOT_FORMAT_FIELDS` for a spot
+fn spot_fields(
+    spot: &Spot,
+    location: Option<&Location>,
+    time: &str,
+) -> [(&'static str, String); 11] {
+    [
+        (
+            "time",
+            spot_time(spot).map_or_else(|| time.to_string(), |t| format!("{}Z", t)),
+        ),
+        ("call", spot.callsign.clone()),
+        ("freq", format!("{:.1}", spot.frequency_khz)),
+        ("band", spot.band().unwrap_or("").to_string()),
+        ("mode", spot.mode.clone().unwrap_or_default()),
+        ("spotter", spot.spotter.clone()),
+        ("comment", spot.comment.clone()),
+        ("trigger", spot.trigger_comment.clone().unwrap_or_default()),
+        (
+            "entity",
+            location.map_or_else(String::new, |l| l.entity.name.clone()),
+        ),
+        (
+            "continent",
+            location.map_or_else(String::new, |l| l.continent.clone()),
+        ),
+        (
+            "cqzone",
+            location.map_or_else(String::new, |l| l.cq_zone.to_string()),
+        ),
+    ]
+}
+
+/// A `--highlight` expression: space-separated terms that all have to match.
+/// A `field=value` term compares one of the `--format` fields, ignoring case,
+/// with `*` matching any text; a bare term is looked for in the call, band,
+/// mode, spotter and comment, like the TUI filter.
+#[derive(Clone, Debug)]
+pub(crate) struct Highlight {
+    terms: Vec<(Option<String>, String)>,
+}
+
+/// Parse a `--highlight` expression
+pub(crate) fn parse_highlight(expression: &str) -> Result<Highlight, String> {
+    let mut terms = Vec::new();
+    for term in expression.split_whitespace() {
+        let term = term.to_lowercase();
+        match term.split_once('=') {
+            Some((field, value)) => {
+                if !SPOT_FORMAT_FIELDS.contains(&field) {
+                    return Err(format!(
+                        "Unknown highlight field '{}' (available: {})",
+                        field,
+                        SPOT_FORMAT_FIELDS.join(", ")
+                    ));
+                }
+                terms.push((Some(field.to_string()), value.to_string()));
+            }
+            None => terms.push((None, term)),
+        }
+    }
+    if terms.is_empty() {
+        return Err("Empty highlight expression".to_string());
+    }
+    Ok(Highlight { terms })
+}
+
+/// Whether `text` matches `pattern`, where `*` stands for any text
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((head, rest)) => {
+            let Some(text) = text.strip_prefix(head) else {
+                return false;
+            };
+            (0..=text.len())
+                .filter(|&i| text.is_char_boundary(i))
+                .any(|i| wildcard_match(rest, &text[i..]))
+        }
+    }
+}
+
+impl Highlight {
+    pub(crate) fn matches(&self, spot: &Spot, location: Option<&Location>) -> bool {
+        let fields = spot_fields(spot, location, "");
+        self.terms.iter().all(|(field, value)| match field {
+            Some(field) => fields
+                .iter()
+                .find(|(name, _)| name == field)
+                .is_some_and(|(_, text)| wildcard_match(value, &text.to_lowercase())),
+            None => ["call", "band", "mode", "spotter", "comment"]
+                .iter()
+                .filter_map(|name| fields.iter().find(|(n, _)| n == name))
+                .any(|(_, text)| text.to_lowercase().contains(value.as_str())),
+        })
+    }
+}
+
+/// Terminal line for a spot without `--format`: aligned columns with the
+/// band and mode in color, and highlighted spots marked with `*`
+fn spot_columns(spot: &Spot, location: Option<&Location>, highlighted: bool, now: &str) -> String {
+    let time = spot_time(spot).map_or_else(|| now.to_string(), |t| format!("{}Z", t));
+    let band = spot.band().unwrap_or("");
+    let mode = spot.mode.as_deref().unwrap_or("");
+    let mode_code = mode_color(&normalize_mode(mode).to_lowercase());
+    // The time already has its own column
+    let comment = match spot.comment.rsplit_once(' ') {
+        Some((comment, last)) if last == time => comment,
+        _ if spot.comment == time => "",
+        _ => spot.comment.as_str(),
+    };
+    let call = format!("{:<12}", spot.callsign);
+    let mut line = format!(
+        "{} {} {:>9.1}  {} {} {} {:<10} {}",
+        if highlighted { "*" } else { " " },
+        time,
+        spot.frequency_khz,
+        if highlighted {
+            paint(&call, BOLD)
+        } else {
+            call
+        },
+        paint(&format!("{:<4}", band), band_color(band)),
+        paint(&format!("{:<5}", mode), mode_code),
+        spot.spotter,
+        comment
+    );
+    if let Some(trigger) = &spot.trigger_comment {
+        line.push_str(&format!("  [{}]", trigger));
+    }
+    if let Some(location) = location {
+        line.push_str(&format!("  <{}>", location_label(location)));
+    }
+    line.trim_end().to_string()
+}
+
+/// Routes every received spot to the configured destinations and records metrics.
+/// Shared by the telnet and webhook servers.
+pub(crate) struct SpotPipeline {
+    pub(crate) client: Client,
+    pub(crate) destinations: Vec<Destination>,
+    /// Checked in order before `destinations`; the first match wins
+    pub(crate) routes: Vec<Route>,
+    pub(crate) metrics: SharedMetrics,
+    pub(crate) format: Option<String>,
+    /// Resolves spotted calls to their entity, continent and zone
+    pub(crate) prefixes: PrefixTable,
+    /// Spots to mark in terminal output, from --highlight
+    pub(crate) highlights: Vec<Highlight>,
+    /// How long a call and frequency count as already seen
+    pub(crate) dedupe_window: Option<Duration>,
+    /// When each call and frequency (whole kHz) was first seen in the window
+    seen: Mutex<HashMap<(String, i64), Instant>>,
+}
+
+impl SpotPipeline {
+    /// Build a pipeline from the config plus any destinations given on the command line,
+    /// printing to stdout when nothing is configured
+    pub(crate) fn from_config(
+        config: &Config,
+        extra: Vec<Destination>,
+        format: Option<String>,
+    ) -> Result<Self, String> {
+        if let Some(template) = &format {
+            validate_spot_format(template)?;
+        }
+        if let Some(index) = config
+            .watch
+            .routes
+            .iter()
+            .position(|r| r.comment.is_none() && r.tag.is_none())
+        {
+            return Err(format!("Route {} needs a comment or a tag", index + 1));
+        }
+        let mut destinations = if config.destinations.is_empty() {
+            vec![Destination::Stdout]
+        } else {
+            config.destinations.clone()
+        };
+        destinations.extend(extra);
+        Ok(Self {
+            client: Client::new(),
+            destinations,
+            routes: config.watch.routes.clone(),
+            metrics: SharedMetrics::default(),
+            format,
+            prefixes: load_table().unwrap_or_default(),
+            dedupe_window: config
+                .watch
+                .dedupe_minutes
+                .filter(|&minutes| minutes > 0)
+                .map(|minutes| Duration::from_secs(minutes * 60)),
+            highlights: Vec::new(),
+            seen: Mutex::default(),
+        })
+    }
+
+    /// Whether any route selects triggers by tag
+    pub(crate) fn routes_by_tag(&self) -> bool {
+        self.routes.iter().any(|route| route.tag.is_some())
+    }
+
+    /// Look up the triggers of tag routes: those of `triggers` recorded with
+    /// the tag in `state`
+    pub(crate) fn tag_routes(&mut self, triggers: &[Trigger], state: &State) {
+        for route in &mut self.routes {
+            if let Some(tag) = &route.tag {
+                route.tagged = triggers
+                    .iter()
+                    .filter(|t| state.has_tags(&t.id, std::slice::from_ref(tag)))
+                    .map(|t| t.comment.clone())
+                    .collect();
+            }
+        }
+    }
+
+    /// Destinations of the first route matching the spot's trigger, or the
+    /// default ones
+    fn destinations_for(&self, spot: &Spot) -> &[Destination] {
+        spot.trigger_comment
+            .as_deref()
+            .and_then(|comment| self.routes.iter().find(|r| r.matches(comment)))
+            .map_or(&self.destinations, |route| &route.destinations)
+    }
+
+    /// Whether the spot's call was already seen on the same frequency within
+    /// the dedupe window. Only the first spot starts the window, so a station
+    /// that stays on frequency is forwarded again once it has passed.
+    fn is_repeat(&self, spot: &Spot, now: Instant) -> bool {
+        let Some(window) = self.dedupe_window else {
+            return false;
+        };
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, first| now.duration_since(*first) < window);
+        let key = (
+            spot.callsign.to_uppercase(),
+            spot.frequency_khz.round() as i64,
+        );
+        if seen.contains_key(&key) {
+            return true;
+        }
+        seen.insert(key, now);
+        false
+    }
+
+    pub(crate) async fn dispatch(&self, spot: &Spot) {
+        tracing::debug!(
+            callsign = %spot.callsign,
+            frequency_khz = spot.frequency_khz,
+            spotter = %spot.spotter,
+            "received spot"
+        );
+        self.metrics.lock().unwrap().record_spot(spot);
+        if self.is_repeat(spot, Instant::now()) {
+            tracing::debug!(callsign = %spot.callsign, "dropped repeat spot");
+            return;
+        }
+        for destination in self.destinations_for(spot) {
+            if let Err(e) = self.forward(destination, spot).await {
+                tracing::warn!(
+                    destination = destination.name(),
+                    callsign = %spot.callsign,
+                    error = %e,
+                    "failed to forward spot"
+                );
+                self.metrics.lock().unwrap().forwarding_errors += 1;
+            }
+        }
+    }
+
+    pub(crate) async fn forward(
+        &self,
+        destination: &Destination,
+        spot: &Spot,
+    ) -> Result<(), CliError> {
+        let location = self.prefixes.lookup(&spot.callsign);
+        let line = match (&self.format, &spot.trigger_comment) {
+            (Some(template), _) => {
+                let now = chrono::Utc::now().format("%H%MZ").to_string();
+                format_spot(template, spot, location.as_ref(), &now)
+            }
+            (None, Some(trigger)) => format!("{}  [{}]", spot.to_cluster_line(), trigger),
+            (None, None) => spot.to_cluster_line(),
+        };
+
+        match destination {
+            // Only the terminal gets the location; the other destinations
+            // feed loggers and spot logs that expect cluster lines
+            Destination::Stdout => match &self.format {
+                None => {
+                    let highlighted = self
+                        .highlights
+                        .iter()
+                        .any(|h| h.matches(spot, location.as_ref()));
+                    let now = chrono::Utc::now().format("%H%MZ").to_string();
+                    println!(
+                        "{}",
+                        spot_columns(spot, location.as_ref(), highlighted, &now)
+                    );
+                }
+                Some(_) => println!("{}", line),
+            },
+            Destination::File { path } => {
+                use std::io::Write;
+                let mut file = fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)?;
+                // The receive time lets `spots query --since` date the spot
+                let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+                writeln!(file, "{} {}", now, line)?;
+            }
+            Destination::Udp { address } => {
+                let socket = tokio::net::UdpSocket::bind("0.0.0.0:0").await?;
+                socket.send_to(line.as_bytes(), address.as_str()).await?;
+            }
+            Destination::Discord { webhook_url } => {
+                self.client
+                    .post(webhook_url)
+                    .json(&json!({ "content": spot_summary(spot, location.as_ref()) }))
+                    .send_logged()
+                    .await?
+                    .error_for_status()?;
+            }
+            Destination::Ntfy {
+                topic,
+                server,
+                priority,
+                tags,
+            } => {
+                let mut request = self
+                    .client
+                    .post(format!("{}/{}", server.trim_end_matches('/'), topic))
+                    .header("Title", spot_title(spot))
+                    .body(line);
+                if let Some(priority) = priority {
+                    request = request.header("Priority", priority);
+                }
+                if !tags.is_empty() {
+                    request = request.header("Tags", tags.join(","));
+                }
+                request.send_logged().await?.error_for_status()?;
+            }
+            Destination::Mqtt {
+                address,
+                topic,
+                username,
+                password,
+            } => {
+                mqtt_publish(
+                    address,
+                    topic,
+                    username.as_deref(),
+                    password.as_deref(),
+                    &line,
+                )
+                .await?;
+            }
+            Destination::Rigctld { address } => {
+                // tune_rig blocks on its socket, bounded by its own timeouts
+                let (address, spot) = (address.clone(), spot.clone());
+                tokio::task::spawn_blocking(move || tune_rig(&address, &spot)).await??;
+            }
+            Destination::Exec { command } => {
+                use tokio::io::AsyncWriteExt;
+                let mut child = shell_command(command)
+                    .envs(spot_env_vars(spot, location.as_ref()))
+                    .stdin(std::process::Stdio::piped())
+                    .spawn()?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(format!("{}\n", line).as_bytes()).await?;
+                }
+                let status = child.wait().await?;
+                if !status.success() {
+                    return Err(std::io::Error::other(format!(
+                        "'{}' exited with {}",
+                        command, status
+                    ))
+                    .into());
+                }
+            }
+            Destination::Desktop => {
+                let body = match &location {
+                    Some(location) => format!("{}\n{}", line, location_label(location)),
+                    None => line,
+                };
+                let status = desktop_notification(&spot_title(spot), &body)
+                    .status()
+                    .await?;
+                if !status.success() {
+                    return Err(std::io::Error::other(format!(
+                        "Desktop notification failed: {}",
+                        status
+                    ))
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Appends an MQTT length-prefixed UTF-8 string
+fn mqtt_string(packet: &mut Vec<u8>, value: &str) {
+    packet.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    packet.extend_from_slice(value.as_bytes());
+}
+
+/// Prefixes an MQTT packet body with its fixed header: the packet type byte
+/// and the variable-length remaining length
+fn mqtt_packet(kind: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut packet = vec![kind];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+/// MQTT 3.1.1 CONNECT with a clean session and no keep-alive
+fn mqtt_connect_packet(client_id: &str, username: Option<&str>, password: Option<&str>) -> Vec<u8> {
+    let mut flags = 0x02;
+    if username.is_some() {
+        flags |= 0x80;
+    }
+    if password.is_some() {
+        flags |= 0x40;
+    }
+    let mut body = Vec::new();
+    mqtt_string(&mut body, "MQTT");
+    body.extend_from_slice(&[0x04, flags, 0x00, 0x00]);
+    mqtt_string(&mut body, client_id);
+    for value in [username, password].into_iter().flatten() {
+        mqtt_string(&mut body, value);
+    }
+    mqtt_packet(0x10, body)
+}
+
+/// MQTT PUBLISH at QoS 0, which needs no packet id or acknowledgement
+fn mqtt_publish_packet(topic: &str, payload: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    mqtt_string(&mut body, topic);
+    body.extend_from_slice(payload.as_bytes());
+    mqtt_packet(0x30, body)
+}
+
+/// Publishes one message over a short-lived MQTT connection
+async fn mqtt_publish(
+    address: &str,
+    topic: &str,
+    username: Option<&str>,
+    password: Option<&str>,
+    payload: &str,
+) -> Result<(), CliError> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    let mut stream = tokio::net::TcpStream::connect(address).await?;
+    let client_id = format!("hamalert-cli-{}", std::process::id());
+    stream
+        .write_all(&mqtt_connect_packet(&client_id, username, password))
+        .await?;
+    let mut connack = [0u8; 4];
+    stream.read_exact(&mut connack).await?;
+    if connack[0] != 0x20 {
+        return Err(CliError::Remote(format!(
+            "{} is not an MQTT broker",
+            address
+        )));
+    }
+    if connack[3] != 0 {
+        return Err(CliError::Remote(format!(
+            "MQTT broker {} refused the connection (code {})",
+            address, connack[3]
+        )));
+    }
+    stream
+        .write_all(&mqtt_publish_packet(topic, payload))
+        .await?;
+    // DISCONNECT
+    stream.write_all(&[0xe0, 0x00]).await?;
+    Ok(())
+}
+
+/// PowerShell showing a toast through the WinRT notification API, which
+/// Windows PowerShell can use without extra modules. The text comes from
+/// environment variables so it needs no quoting.
+const WINDOWS_TOAST_SCRIPT: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+$template = [Windows.UI.Notifications.ToastTemplateType]::ToastText02
+$xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent($template)
+$text = $xml.GetElementsByTagName('text')
+$text.Item(0).AppendChild($xml.CreateTextNode($env:HAMALERT_TITLE)) | Out-Null
+$text.Item(1).AppendChild($xml.CreateTextNode($env:HAMALERT_BODY)) | Out-Null
+$app = '{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe'
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($app).Show([Windows.UI.Notifications.ToastNotification]::new($xml))
+"#;
+
+/// Command showing a desktop notification with the platform's own tools
+fn desktop_notification(title: &str, body: &str) -> tokio::process::Command {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = tokio::process::Command::new("powershell");
+        cmd.args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            WINDOWS_TOAST_SCRIPT,
+        ]);
+        cmd
+    } else if cfg!(target_os = "macos") {
+        let mut cmd = tokio::process::Command::new("osascript");
+        cmd.args([
+            "-e",
+            "display notification (system attribute \"HAMALERT_BODY\") with title (system attribute \"HAMALERT_TITLE\")",
+        ]);
+        cmd
+    } else {
+        let mut cmd = tokio::process::Command::new("notify-send");
+        cmd.args(["--app-name=hamalert-cli", title, body]);
+        cmd
+    };
+    cmd.env("HAMALERT_TITLE", title).env("HAMALERT_BODY", body);
+    cmd
+}
+
+/// Environment variables describing a spot for exec destinations
+pub(crate) fn spot_env_vars(
+    spot: &Spot,
+    location: Option<&Location>,
+) -> Vec<(&'static str, String)> {
+    vec![
+        ("HAMALERT_CALL", spot.callsign.clone()),
+        ("HAMALERT_FREQ", format!("{:.1}", spot.frequency_khz)),
+        ("HAMALERT_BAND", spot.band().unwrap_or_default().to_string()),
+        ("HAMALERT_MODE", spot.mode.clone().unwrap_or_default()),
+        ("HAMALERT_SPOTTER", spot.spotter.clone()),
+        ("HAMALERT_COMMENT", spot.comment.clone()),
+        (
+            "HAMALERT_TRIGGER_COMMENT",
+            spot.trigger_comment.clone().unwrap_or_default(),
+        ),
+        ("HAMALERT_LINE", spot.to_cluster_line()),
+        (
+            "HAMALERT_ENTITY",
+            location.map(|l| l.entity.name.clone()).unwrap_or_default(),
+        ),
+        (
+            "HAMALERT_CONTINENT",
+            location.map(|l| l.continent.clone()).unwrap_or_default(),
+        ),
+        (
+            "HAMALERT_CQ_ZONE",
+            location.map(|l| l.cq_zone.to_string()).unwrap_or_default(),
+        ),
+    ]
+}
+
+/// Build a command that runs through the platform shell
+pub(crate) fn shell_command(command: &str) -> tokio::process::Command {
+    if cfg!(windows) {
+        let mut cmd = tokio::process::Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
+
+/// Destinations given on the serve command line with --exec, --udp-forward,
+/// --discord-webhook and --ntfy
+pub(crate) fn option_destinations(options: &PipelineOptions) -> Vec<Destination> {
+    let exec = options.exec.iter().map(|command| Destination::Exec {
+        command: command.clone(),
+    });
+    let udp = options.udp_forward.iter().map(|address| Destination::Udp {
+        address: address.clone(),
+    });
+    let discord = options
+        .discord_webhook
+        .iter()
+        .map(|url| Destination::Discord {
+            webhook_url: url.clone(),
+        });
+    let ntfy = options.ntfy.iter().map(|topic| Destination::Ntfy {
+        topic: topic.clone(),
+        server: default_ntfy_server(),
+        priority: None,
+        tags: Vec::new(),
+    });
+    exec.chain(udp).chain(discord).chain(ntfy).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::model::parse_spot_line;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_spot_metrics_render() {
+        let mut metrics = SpotMetrics::default();
+        metrics.record_spot(&parse_spot_line("DX de K1TTT: 14074.0 W1AW FT8 -10 dB").unwrap());
+        metrics.record_spot(&parse_spot_line("DX de K1TTT: 7030.0 K2DEF CW 12 dB").unwrap());
+        let rendered = metrics.render();
+        assert!(rendered.contains("hamalert_spots_received_total 2\n"));
+        assert!(rendered.contains("hamalert_spots_by_band_total{band=\"20m\"} 1\n"));
+        assert!(rendered.contains("hamalert_spots_by_mode_total{mode=\"ft8\"} 1\n"));
+
+        metrics.spots_by_mode.insert("a\"b\\c\nd".to_string(), 1);
+        assert!(
+            metrics
+                .render()
+                .contains(r#"hamalert_spots_by_mode_total{mode="a\"b\\c\nd"} 1"#)
+        );
+    }
+
+    #[test]
+    fn test_config_destinations() {
+        let config: Config = toml::from_str(
+            r#"
+            username = "W1AW"
+            password = "secret"
+
+            [[destinations]]
+            type = "stdout"
+
+            [[destinations]]
+            type = "ntfy"
+            topic = "spots"
+            tags = ["radio"]
+
+            [[destinations]]
+            type = "udp"
+            address = "127.0.0.1:2333"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.destinations.len(), 3);
+        match &config.destinations[1] {
+            Destination::Ntfy {
+                topic,
+                server,
+                priority,
+                tags,
+            } => {
+                assert_eq!(topic, "spots");
+                assert_eq!(server, "https://ntfy.sh");
+                assert!(priority.is_none());
+                assert_eq!(tags, &vec!["radio".to_string()]);
+            }
+            other => panic!("unexpected destination: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_config_without_destinations() {
+        let config: Config = toml::from_str("username = \"W1AW\"\npassword = \"x\"").unwrap();
+        assert!(config.destinations.is_empty());
+    }
+
+    #[test]
+    fn test_spot_title_uses_trigger_comment() {
+        let mut spot = parse_spot_line("DX de K1TTT: 7030.0 K2DEF CW 12 dB").unwrap();
+        assert_eq!(spot_title(&spot), "K2DEF spotted");
+        spot.trigger_comment = Some("Friends".to_string());
+        assert_eq!(spot_title(&spot), "Friends");
+    }
+
+    #[test]
+    fn test_spot_env_vars() {
+        let spot = parse_spot_line("DX de K1TTT: 7030.0 K2DEF CW 12 dB").unwrap();
+        let vars: HashMap<&str, String> = spot_env_vars(&spot, None).into_iter().collect();
+        assert_eq!(vars["HAMALERT_CALL"], "K2DEF");
+        assert_eq!(vars["HAMALERT_FREQ"], "7030.0");
+        assert_eq!(vars["HAMALERT_BAND"], "40m");
+        assert_eq!(vars["HAMALERT_MODE"], "CW");
+        assert_eq!(vars["HAMALERT_SPOTTER"], "K1TTT");
+        assert_eq!(vars["HAMALERT_TRIGGER_COMMENT"], "");
+        assert_eq!(vars["HAMALERT_ENTITY"], "");
+
+        let table = PrefixTable::parse(include_str!("../data/cty.csv")).unwrap();
+        let location = table.lookup("JA1ABC");
+        let vars: HashMap<&str, String> = spot_env_vars(&spot, location.as_ref())
+            .into_iter()
+            .collect();
+        assert_eq!(vars["HAMALERT_ENTITY"], "Japan");
+        assert_eq!(vars["HAMALERT_CONTINENT"], "AS");
+        assert_eq!(vars["HAMALERT_CQ_ZONE"], "25");
+    }
+
+    #[test]
+    fn test_format_spot() {
+        let spot = parse_spot_line("DX de K1TTT:    14025.0  W1AW         CW 599 1234Z").unwrap();
+        assert_eq!(
+            format_spot(
+                "{time} {call} {freq} {mode} via {spotter}",
+                &spot,
+                None,
+                "0000Z"
+            ),
+            "1234Z W1AW 14025.0 CW via K1TTT"
+        );
+
+        let spot = parse_spot_line("DX de K1TTT: 7030.0 W1AW").unwrap();
+        assert_eq!(
+            format_spot("{time} {band} [{mode}]", &spot, None, "0815Z"),
+            "0815Z 40m []"
+        );
+
+        let spot = parse_spot_line("DX de K1TTT: 14025.0 JA1ABC CW").unwrap();
+        let table = PrefixTable::parse(include_str!("../data/cty.csv")).unwrap();
+        let location = table.lookup(&spot.callsign);
+        assert_eq!(
+            format_spot(
+                "{call} {entity} {continent} CQ{cqzone}",
+                &spot,
+                location.as_ref(),
+                "0000Z"
+            ),
+            "JA1ABC Japan AS CQ25"
+        );
+        assert_eq!(
+            spot_summary(&spot, location.as_ref()),
+            "**JA1ABC** (Japan, AS, CQ 25) on 14025.0 kHz CW (spotted by K1TTT): CW"
+        );
+    }
+
+    #[test]
+    fn test_validate_spot_format() {
+        assert!(validate_spot_format("{call} on {freq} ({band})").is_ok());
+        assert!(validate_spot_format("no placeholders").is_ok());
+        assert!(validate_spot_format("{call} {snr}").is_err());
+        assert!(validate_spot_format("{call").is_err());
+    }
+
+    #[test]
+    fn test_routes() {
+        let config: Config = toml::from_str(
+            r#"
+            username = "W1AW"
+            password = "secret"
+
+            [[destinations]]
+            type = "stdout"
+
+            [[watch.routes]]
+            comment = "sota"
+            destinations = [{ type = "ntfy", topic = "summits" }]
+
+            [[watch.routes]]
+            tag = "friends"
+            destinations = [{ type = "udp", address = "127.0.0.1:2333" }, { type = "stdout" }]
+            "#,
+        )
+        .unwrap();
+        let mut pipeline = SpotPipeline::from_config(&config, Vec::new(), None).unwrap();
+        assert!(pipeline.routes_by_tag());
+
+        let triggers: Vec<Trigger> = serde_json::from_value(json!([
+            {"_id": "1", "conditions": {}, "actions": ["url"], "comment": "Club friends"},
+        ]))
+        .unwrap();
+        let state: State = serde_json::from_value(json!({"triggers": {"club-friends": {
+            "id": "1", "created_at": "2026-01-01T00:00:00+00:00", "source": "add-trigger",
+            "tags": ["friends"],
+        }}}))
+        .unwrap();
+        pipeline.tag_routes(&triggers, &state);
+
+        let mut spot = parse_spot_line("DX de K1TTT: 14062.0 W7ABC CW").unwrap();
+        let routed = |spot: &Spot| -> Vec<&str> {
+            pipeline
+                .destinations_for(spot)
+                .iter()
+                .map(Destination::name)
+                .collect()
+        };
+        assert_eq!(routed(&spot), vec!["stdout"]);
+        spot.trigger_comment = Some("W7 SOTA activators".to_string());
+        assert_eq!(routed(&spot), vec!["ntfy"]);
+        spot.trigger_comment = Some("Club friends".to_string());
+        assert_eq!(routed(&spot), vec!["udp", "stdout"]);
+        spot.trigger_comment = Some("Other".to_string());
+        assert_eq!(routed(&spot), vec!["stdout"]);
+
+        let config: Config = toml::from_str(
+            "username = \"W1AW\"\npassword = \"x\"\n[[watch.routes]]\ndestinations = []",
+        )
+        .unwrap();
+        assert!(SpotPipeline::from_config(&config, Vec::new(), None).is_err());
+    }
+
+    #[test]
+    fn test_mqtt_packets() {
+        assert_eq!(
+            mqtt_connect_packet("id", None, None),
+            vec![
+                0x10, 14, 0, 4, b'M', b'Q', b'T', b'T', 4, 0x02, 0, 0, 0, 2, b'i', b'd'
+            ]
+        );
+        let packet = mqtt_connect_packet("id", Some("u"), Some("p"));
+        assert_eq!(packet[9], 0xc2);
+        assert!(packet.ends_with(&[0, 1, b'u', 0, 1, b'p']));
+
+        assert_eq!(
+            mqtt_publish_packet("t/s", "W1AW"),
+            vec![0x30, 9, 0, 3, b't', b'/', b's', b'W', b'1', b'A', b'W']
+        );
+        let long = "x".repeat(200);
+        let packet = mqtt_publish_packet("t", &long);
+        assert_eq!(&packet[..3], &[0x30, 0xcb, 0x01]);
+    }
+
+    #[tokio::test]
+    async fn test_mqtt_publish() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let broker = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut header = [0u8; 2];
+            socket.read_exact(&mut header).await.unwrap();
+            let mut connect = vec![0u8; header[1] as usize];
+            socket.read_exact(&mut connect).await.unwrap();
+            socket.write_all(&[0x20, 0x02, 0x00, 0x00]).await.unwrap();
+            let mut rest = Vec::new();
+            socket.read_to_end(&mut rest).await.unwrap();
+            rest
+        });
+        mqtt_publish(&address, "spots", None, None, "DX de K1TTT: 14025.0 W1AW")
+            .await
+            .unwrap();
+        let received = broker.await.unwrap();
+        assert_eq!(
+            received,
+            [
+                mqtt_publish_packet("spots", "DX de K1TTT: 14025.0 W1AW"),
+                vec![0xe0, 0x00]
+            ]
+            .concat()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ntfy_uses_spot_format() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+        let received = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !String::from_utf8_lossy(&request).ends_with("W1AW on 14025.0") {
+                let n = socket.read(&mut buf).await.unwrap();
+                assert!(n > 0, "connection closed early");
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let pipeline = SpotPipeline::from_config(
+            &Config::default(),
+            Vec::new(),
+            Some("{call} on {freq}".to_string()),
+        )
+        .unwrap();
+        let destination = Destination::Ntfy {
+            topic: "spots".to_string(),
+            server,
+            priority: None,
+            tags: Vec::new(),
+        };
+        let spot = parse_spot_line("DX de K1TTT: 14025.0 W1AW CW").unwrap();
+        pipeline.forward(&destination, &spot).await.unwrap();
+        let request = received.await.unwrap();
+        assert!(request.starts_with("POST /spots "));
+        assert!(request.ends_with("\r\n\r\nW1AW on 14025.0"));
+    }
+
+    #[test]
+    fn test_dedupe_window() {
+        let config: Config = toml::from_str(
+            r#"
+            [watch]
+            dedupe_minutes = 10
+            "#,
+        )
+        .unwrap();
+        let pipeline = SpotPipeline::from_config(&config, Vec::new(), None).unwrap();
+        let spot = |line: &str| parse_spot_line(line).unwrap();
+        let start = Instant::now();
+        let minutes = |m: u64| start + Duration::from_secs(m * 60);
+
+        assert!(!pipeline.is_repeat(&spot("DX de K1TTT: 14025.0 W1AW CW"), start));
+        assert!(pipeline.is_repeat(&spot("DX de DL1ABC-#: 14025.1 w1aw CW"), minutes(3)));
+        assert!(!pipeline.is_repeat(&spot("DX de K1TTT: 14030.0 W1AW CW"), minutes(3)));
+        assert!(!pipeline.is_repeat(&spot("DX de K1TTT: 14025.0 K2DEF CW"), minutes(3)));
+        assert!(!pipeline.is_repeat(&spot("DX de K1TTT: 14025.0 W1AW CW"), minutes(11)));
+
+        let pipeline = SpotPipeline::from_config(&Config::default(), Vec::new(), None).unwrap();
+        assert!(pipeline.dedupe_window.is_none());
+        assert!(!pipeline.is_repeat(&spot("DX de K1TTT: 14025.0 W1AW CW"), start));
+        assert!(!pipeline.is_repeat(&spot("DX de K1TTT: 14025.0 W1AW CW"), start));
+    }
+
+    #[test]
+    fn test_highlight() {
+        let table = PrefixTable::parse(include_str!("../data/cty.csv")).unwrap();
+        let spot = parse_spot_line("DX de K1TTT: 21025.0 JA1ABC CW 599 1234Z").unwrap();
+        let location = table.lookup(&spot.callsign);
+        let matches = |expression: &str| {
+            parse_highlight(expression)
+                .unwrap()
+                .matches(&spot, location.as_ref())
+        };
+        assert!(matches("call=JA*"));
+        assert!(matches("band=15m mode=cw"));
+        assert!(matches("continent=as"));
+        assert!(matches("k1t"));
+        assert!(!matches("call=JA"));
+        assert!(!matches("band=15m mode=ssb"));
+        assert!(!matches("entity=Japan call=W*"));
+        assert!(parse_highlight("snr=10").is_err());
+        assert!(parse_highlight("  ").is_err());
+
+        assert!(wildcard_match("vp8*", "vp8pj"));
+        assert!(wildcard_match("*/p", "w1aw/p"));
+        assert!(wildcard_match("w*w", "w1aw"));
+        assert!(!wildcard_match("w*x", "w1aw"));
+    }
+
+    #[test]
+    fn test_spot_columns() {
+        let spot = parse_spot_line("DX de K1TTT: 14025.0 W1AW CW 599 1234Z").unwrap();
+        assert_eq!(
+            spot_columns(&spot, None, false, "0000Z"),
+            "  1234Z   14025.0  W1AW         20m  CW    K1TTT      CW 599"
+        );
+        let mut spot = parse_spot_line("DX de DL1ABC-#: 21074.0 JA1ABC FT8").unwrap();
+        spot.trigger_comment = Some("Japan".to_string());
+        let table = PrefixTable::parse(include_str!("../data/cty.csv")).unwrap();
+        let location = table.lookup(&spot.callsign);
+        assert_eq!(
+            spot_columns(&spot, location.as_ref(), true, "0815Z"),
+            "* 0815Z   21074.0  JA1ABC       15m  FT8   DL1ABC-#   FT8  [Japan]  <Japan, AS, CQ 25>"
+        );
+    }
+
+    #[test]
+    fn test_option_destinations() {
+        use clap::Parser;
+        let options = PipelineOptions::try_parse_from([
+            "serve",
+            "--exec",
+            "logger",
+            "--udp-forward",
+            "127.0.0.1:2333",
+            "--udp-forward",
+            "[::1]:12060",
+            "--discord-webhook",
+            "https://discord.com/api/webhooks/1/x",
+            "--ntfy",
+            "my-spots",
+        ])
+        .unwrap();
+        let names: Vec<&str> = option_destinations(&options)
+            .iter()
+            .map(Destination::name)
+            .collect();
+        assert_eq!(names, vec!["exec", "udp", "udp", "discord", "ntfy"]);
+        assert!(PipelineOptions::try_parse_from(["serve", "--udp-forward", "localhost"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rigctld_destination() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let rig = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = socket.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let mut commands = Vec::new();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                commands.push(line);
+                writer.write_all(b"RPRT 0\n").await.unwrap();
+            }
+            commands
+        });
+
+        let pipeline = SpotPipeline::from_config(&Config::default(), Vec::new(), None).unwrap();
+        let spot = parse_spot_line("DX de K1TTT: 14025.0 W1AW CW").unwrap();
+        pipeline
+            .forward(&Destination::Rigctld { address }, &spot)
+            .await
+            .unwrap();
+        assert_eq!(rig.await.unwrap(), vec!["F 14025000", "M CW 0"]);
+    }
+
+    #[tokio::test]
+    async fn test_rigctld_destination_times_out() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        // Accepts the connection and never answers
+        let rig = tokio::spawn(async move { listener.accept().await.unwrap() });
+
+        let pipeline = SpotPipeline::from_config(&Config::default(), Vec::new(), None).unwrap();
+        let spot = parse_spot_line("DX de K1TTT: 14025.0 W1AW CW").unwrap();
+        let forwarded = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            pipeline.forward(&Destination::Rigctld { address }, &spot),
+        )
+        .await
+        .expect("a silent rigctld must not hang the pipeline");
+        assert!(forwarded.is_err());
+        drop(rig.await.unwrap());
+    }
+}
@@ -0,0 +1,351 @@
+//! Activation references used in trigger conditions: SOTA summits and POTA parks.
+
+use crate::error::CliError;
+use hamalert_api::{HamAlertClient, SendLogged};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// SOTA summit lookup endpoint; the reference is appended
+pub(crate) const SOTA_API_URL: &str = "https://api2.sota.org.uk/api/summits";
+
+/// POTA park lookup endpoint; the reference is appended
+pub(crate) const POTA_API_URL: &str = "https://api.pota.app/park";
+
+/// Summit details from the SOTA API
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Summit {
+    pub(crate) summit_code: String,
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) alt_m: Option<i64>,
+}
+
+/// Park details from the POTA API
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Park {
+    pub(crate) reference: String,
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) location_desc: Option<String>,
+}
+
+/// Whether `reference` has the `ASSOCIATION/RR-NNN` shape, e.g. `W7W/LC-001`
+fn is_summit_ref(reference: &str) -> bool {
+    let Some((association, rest)) = reference.split_once('/') else {
+        return false;
+    };
+    let Some((region, number)) = rest.split_once('-') else {
+        return false;
+    };
+    (1..=4).contains(&association.len())
+        && association.chars().all(|c| c.is_ascii_alphanumeric())
+        && association.chars().any(|c| c.is_ascii_uppercase())
+        && region.len() == 2
+        && region.chars().all(|c| c.is_ascii_alphanumeric())
+        && number.len() == 3
+        && number.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Uppercased summit reference, or a validation error if it is malformed
+pub(crate) fn validate_summit_ref(input: &str) -> Result<String, CliError> {
+    let reference = input.trim().to_ascii_uppercase();
+    if !is_summit_ref(&reference) {
+        return Err(CliError::Validation(format!(
+            "Invalid summit reference '{}': expected ASSOCIATION/REGION-NUMBER, like W7W/LC-001",
+            input
+        )));
+    }
+    Ok(reference)
+}
+
+/// Look a summit up on the SOTA API; `None` if SOTA does not know it
+pub(crate) async fn fetch_summit(
+    client: &HamAlertClient,
+    reference: &str,
+) -> Result<Option<Summit>, CliError> {
+    let url = format!("{}/{}", SOTA_API_URL, reference);
+    let response = client.http().get(&url).send_logged().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(CliError::Remote(format!(
+            "SOTA lookup of {} failed: {}",
+            reference,
+            response.status()
+        )));
+    }
+    // Unknown summits may also come back as an empty body or `null`
+    let body = response.text().await?;
+    if body.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// Validate summit references and, with `check`, confirm each exists on the
+/// SOTA API. Returns the references and the names of the checked summits.
+pub(crate) async fn resolve_summits(
+    client: &HamAlertClient,
+    references: &[String],
+    check: bool,
+) -> Result<(Vec<String>, Vec<String>), CliError> {
+    let references = references
+        .iter()
+        .map(|r| validate_summit_ref(r))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut names = Vec::new();
+    if check {
+        for reference in &references {
+            let summit = fetch_summit(client, reference).await?.ok_or_else(|| {
+                CliError::Validation(format!("SOTA does not know summit {}", reference))
+            })?;
+            info!(
+                "{}: {}{}",
+                summit.summit_code,
+                summit.name,
+                summit
+                    .alt_m
+                    .map(|alt| format!(" ({} m)", alt))
+                    .unwrap_or_default()
+            );
+            names.push(summit.name);
+        }
+    }
+    Ok((references, names))
+}
+
+/// Whether `reference` has the `PREFIX-NNNN` shape, e.g. `US-0001` or `VE-12345`
+fn is_park_ref(reference: &str) -> bool {
+    let Some((prefix, number)) = reference.split_once('-') else {
+        return false;
+    };
+    (1..=4).contains(&prefix.len())
+        && prefix.chars().all(|c| c.is_ascii_alphanumeric())
+        && prefix.chars().any(|c| c.is_ascii_uppercase())
+        && (4..=5).contains(&number.len())
+        && number.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Uppercased park reference, or a validation error if it is malformed
+pub(crate) fn validate_park_ref(input: &str) -> Result<String, CliError> {
+    let reference = input.trim().to_ascii_uppercase();
+    if !is_park_ref(&reference) {
+        return Err(CliError::Validation(format!(
+            "Invalid park reference '{}': expected PREFIX-NUMBER, like US-0001",
+            input
+        )));
+    }
+    Ok(reference)
+}
+
+/// Path of the park names learned from the POTA API
+pub(crate) fn park_cache_path() -> Result<PathBuf, CliError> {
+    let dir = dirs::cache_dir()
+        .ok_or_else(|| CliError::Config("Could not determine cache directory".to_string()))?
+        .join("hamalert");
+    Ok(dir.join("pota_parks.json"))
+}
+
+/// Cached park names by reference; empty if nothing has been looked up yet
+pub(crate) fn load_park_names() -> BTreeMap<String, String> {
+    park_cache_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_park_names(names: &BTreeMap<String, String>) -> Result<(), CliError> {
+    let path = park_cache_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(names)?)?;
+    Ok(())
+}
+
+/// Look a park up on the POTA API; `None` if POTA does not know it
+pub(crate) async fn fetch_park(
+    client: &HamAlertClient,
+    reference: &str,
+) -> Result<Option<Park>, CliError> {
+    let url = format!("{}/{}", POTA_API_URL, reference);
+    let response = client.http().get(&url).send_logged().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(CliError::Remote(format!(
+            "POTA lookup of {} failed: {}",
+            reference,
+            response.status()
+        )));
+    }
+    // Unknown parks come back as `null`
+    let body = response.text().await?;
+    if body.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// Validate park references and confirm each exists, asking the POTA API
+/// only for parks that are not cached yet. Returns the references and the
+/// park names.
+pub(crate) async fn resolve_parks(
+    client: &HamAlertClient,
+    references: &[String],
+) -> Result<(Vec<String>, Vec<String>), CliError> {
+    let references = references
+        .iter()
+        .map(|r| validate_park_ref(r))
+        .collect::<Result<Vec<_>, _>>()?;
+    if references.is_empty() {
+        return Ok((references, Vec::new()));
+    }
+    let mut cache = load_park_names();
+    let mut learned = false;
+    let mut names = Vec::new();
+    for reference in &references {
+        if let Some(name) = cache.get(reference) {
+            names.push(name.clone());
+            continue;
+        }
+        let park = fetch_park(client, reference).await?.ok_or_else(|| {
+            CliError::Validation(format!(
+                "POTA does not know park {}; check the reference for typos",
+                reference
+            ))
+        })?;
+        info!(
+            "{}: {}{}",
+            park.reference,
+            park.name,
+            park.location_desc
+                .map(|location| format!(" ({})", location))
+                .unwrap_or_default()
+        );
+        cache.insert(reference.clone(), park.name.clone());
+        names.push(park.name);
+        learned = true;
+    }
+    if learned {
+        save_park_names(&cache)?;
+    }
+    Ok((references, names))
+}
+
+/// Comma-separated park references with their cached names, e.g.
+/// `US-0001 (Acadia National Park),US-0002`
+pub(crate) fn annotate_parks(references: &str, names: &BTreeMap<String, String>) -> String {
+    references
+        .split(',')
+        .filter(|r| !r.is_empty())
+        .map(|r| match names.get(r) {
+            Some(name) => format!("{} ({})", r, name),
+            None => r.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// `comment` followed by the reference names in parentheses, if any
+pub(crate) fn comment_with_names(comment: &str, names: &[String]) -> String {
+    if names.is_empty() {
+        comment.to_string()
+    } else {
+        format!("{} ({})", comment, names.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_summit_ref() {
+        assert_eq!(validate_summit_ref("w7w/lc-001").unwrap(), "W7W/LC-001");
+        assert_eq!(validate_summit_ref("G/LD-001").unwrap(), "G/LD-001");
+        assert_eq!(validate_summit_ref("9A/DH-014").unwrap(), "9A/DH-014");
+        for reference in [
+            "W7W-LC-001",
+            "W7W/LC001",
+            "W7W/LCX-001",
+            "W7W/LC-01",
+            "/LC-001",
+        ] {
+            assert!(
+                validate_summit_ref(reference).is_err(),
+                "{} should be invalid",
+                reference
+            );
+        }
+    }
+
+    #[test]
+    fn test_summit_response() {
+        let summit: Summit = serde_json::from_str(
+            r#"{"summitCode": "W7W/LC-001", "name": "Example Peak", "altM": 4392, "points": 10}"#,
+        )
+        .unwrap();
+        assert_eq!(summit.name, "Example Peak");
+        assert_eq!(summit.alt_m, Some(4392));
+    }
+
+    #[test]
+    fn test_comment_with_names() {
+        assert_eq!(comment_with_names("Summits", &[]), "Summits");
+        assert_eq!(
+            comment_with_names("Summits", &["Mount Si".to_string(), "Tiger".to_string()]),
+            "Summits (Mount Si, Tiger)"
+        );
+    }
+
+    #[test]
+    fn test_validate_park_ref() {
+        assert_eq!(validate_park_ref("us-0001").unwrap(), "US-0001");
+        assert_eq!(validate_park_ref("VE-12345").unwrap(), "VE-12345");
+        assert_eq!(validate_park_ref("9A-0123").unwrap(), "9A-0123");
+        for reference in [
+            "US0001",
+            "US-001",
+            "US-123456",
+            "-0001",
+            "US-00A1",
+            "W7W/LC-001",
+        ] {
+            assert!(
+                validate_park_ref(reference).is_err(),
+                "{} should be invalid",
+                reference
+            );
+        }
+    }
+
+    #[test]
+    fn test_park_response() {
+        let park: Park = serde_json::from_str(
+            r#"{"parkId": 1, "reference": "US-0001", "name": "Example Park", "locationDesc": "US-ME"}"#,
+        )
+        .unwrap();
+        assert_eq!(park.name, "Example Park");
+        assert_eq!(park.location_desc.as_deref(), Some("US-ME"));
+        let unknown: Option<Park> = serde_json::from_str("null").unwrap();
+        assert!(unknown.is_none());
+    }
+
+    #[test]
+    fn test_annotate_parks() {
+        let names = BTreeMap::from([("US-0001".to_string(), "Example Park".to_string())]);
+        assert_eq!(
+            annotate_parks("US-0001,US-0002", &names),
+            "US-0001 (Example Park),US-0002"
+        );
+        assert_eq!(annotate_parks("", &names), "");
+    }
+}
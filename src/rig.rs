@@ -0,0 +1,132 @@
+//! Tuning a radio to spots through hamlib's rigctld.
+
+use crate::model::Spot;
+use std::time::Duration;
+
+/// How long to wait for rigctld to accept, take or answer a command
+const RIGCTLD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Map a spot's mode to a hamlib rigctld mode name
+pub(crate) fn rig_mode_for_spot(spot: &Spot) -> Option<&'static str> {
+    let mode = spot.mode.as_deref()?;
+    let rig_mode = match mode {
+        "CW" => "CW",
+        "USB" => "USB",
+        "LSB" => "LSB",
+        // Conventional sideband: LSB below 10 MHz, USB above
+        "SSB" if spot.frequency_khz < 10000.0 => "LSB",
+        "SSB" => "USB",
+        "FM" => "FM",
+        "AM" => "AM",
+        "RTTY" => "RTTY",
+        "FT8" | "FT4" | "PSK31" | "PSK" | "JS8" | "JT65" => "PKTUSB",
+        _ => return None,
+    };
+    Some(rig_mode)
+}
+
+/// Connect to rigctld, trying each address `address` resolves to in turn
+fn connect(address: &str) -> std::io::Result<std::net::TcpStream> {
+    use std::net::ToSocketAddrs;
+
+    let mut last_error = None;
+    for addr in address.to_socket_addrs()? {
+        match std::net::TcpStream::connect_timeout(&addr, RIGCTLD_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| std::io::Error::other(format!("{} resolved to no addresses", address))))
+}
+
+/// Send a spot's frequency (and mode, when known) to a hamlib rigctld
+/// instance. Blocks for at most a few seconds per step, so async callers run
+/// it on a blocking thread.
+pub(crate) fn tune_rig(address: &str, spot: &Spot) -> std::io::Result<()> {
+    use std::io::{BufRead, Write};
+
+    let stream = connect(address)?;
+    stream.set_read_timeout(Some(RIGCTLD_TIMEOUT))?;
+    stream.set_write_timeout(Some(RIGCTLD_TIMEOUT))?;
+    let mut reader = std::io::BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    let mut commands = vec![format!(
+        "F {}",
+        (spot.frequency_khz * 1000.0).round() as u64
+    )];
+    if let Some(mode) = rig_mode_for_spot(spot) {
+        commands.push(format!("M {} 0", mode));
+    }
+
+    for command in commands {
+        writeln!(writer, "{}", command)?;
+        let mut reply = String::new();
+        reader.read_line(&mut reply)?;
+        let reply = reply.trim();
+        if reply != "RPRT 0" {
+            return Err(std::io::Error::other(format!(
+                "rigctld rejected '{}': {}",
+                command, reply
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::parse_spot_line;
+
+    #[test]
+    fn test_rig_mode_for_spot() {
+        let mut spot = parse_spot_line("DX de K1TTT: 7185.0 K2DEF SSB 59").unwrap();
+        assert_eq!(rig_mode_for_spot(&spot), Some("LSB"));
+        spot.frequency_khz = 14250.0;
+        assert_eq!(rig_mode_for_spot(&spot), Some("USB"));
+        spot.mode = Some("FT8".to_string());
+        assert_eq!(rig_mode_for_spot(&spot), Some("PKTUSB"));
+        spot.mode = None;
+        assert_eq!(rig_mode_for_spot(&spot), None);
+    }
+
+    #[test]
+    fn test_tune_rig_sends_frequency_and_mode() {
+        use std::io::{BufRead, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut received = vec![];
+            for _ in 0..2 {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                received.push(line.trim().to_string());
+                writer.write_all(b"RPRT 0\n").unwrap();
+            }
+            received
+        });
+
+        let spot = parse_spot_line("DX de K1TTT: 14025.5 W1AW CW 23 dB").unwrap();
+        tune_rig(&address, &spot).unwrap();
+        assert_eq!(server.join().unwrap(), vec!["F 14025500", "M CW 0"]);
+    }
+
+    #[test]
+    fn test_tune_rig_gives_up_on_a_silent_rigctld() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = std::thread::spawn(move || listener.accept().unwrap());
+
+        let spot = parse_spot_line("DX de K1TTT: 14025.5 W1AW CW 23 dB").unwrap();
+        let started = std::time::Instant::now();
+        assert!(tune_rig(&address, &spot).is_err());
+        assert!(started.elapsed() < RIGCTLD_TIMEOUT * 2);
+        drop(server.join().unwrap());
+    }
+}
@@ -0,0 +1,117 @@
+//! Maidenhead locators and sunrise/sunset times, for grey-line triggers.
+
+use chrono::{Datelike, NaiveDate};
+
+/// Solar zenith angle at sunrise and sunset, allowing for refraction and the
+/// size of the sun's disc
+const SUNRISE_ZENITH_DEG: f64 = 90.833;
+
+const MINUTES_PER_DAY: f64 = 1440.0;
+
+/// Latitude and longitude (degrees north and east) of the centre of a 4- or
+/// 6-character Maidenhead locator such as `FN31` or `FN31pr`
+pub(crate) fn grid_to_lat_lon(grid: &str) -> Result<(f64, f64), String> {
+    let chars: Vec<char> = grid.trim().to_ascii_uppercase().chars().collect();
+    let invalid = || format!("Invalid grid locator '{}'; use e.g. FN31 or FN31pr", grid);
+    if chars.len() != 4 && chars.len() != 6 {
+        return Err(invalid());
+    }
+    let field = |c: char| ('A'..='R').contains(&c).then(|| f64::from(c as u8 - b'A'));
+    let square = |c: char| c.to_digit(10).map(f64::from);
+    let subsquare = |c: char| ('A'..='X').contains(&c).then(|| f64::from(c as u8 - b'A'));
+
+    let mut lon = field(chars[0]).ok_or_else(invalid)? * 20.0 - 180.0;
+    let mut lat = field(chars[1]).ok_or_else(invalid)? * 10.0 - 90.0;
+    lon += square(chars[2]).ok_or_else(invalid)? * 2.0;
+    lat += square(chars[3]).ok_or_else(invalid)?;
+    if chars.len() == 6 {
+        lon += subsquare(chars[4]).ok_or_else(invalid)? * 5.0 / 60.0 + 2.5 / 60.0;
+        lat += subsquare(chars[5]).ok_or_else(invalid)? * 2.5 / 60.0 + 1.25 / 60.0;
+    } else {
+        lon += 1.0;
+        lat += 0.5;
+    }
+    Ok((lat, lon))
+}
+
+/// Sunrise and sunset on `date` at `lat`/`lon`, in minutes after midnight
+/// UTC, using NOAA's approximate solar position equations (accurate to a
+/// minute or two). `None` during polar day or night.
+pub(crate) fn sun_times(date: NaiveDate, lat: f64, lon: f64) -> Option<(u32, u32)> {
+    let gamma = 2.0 * std::f64::consts::PI / 365.0 * (f64::from(date.ordinal()) - 1.0);
+    let equation_of_time = 229.18
+        * (0.000075 + 0.001868 * gamma.cos()
+            - 0.032077 * gamma.sin()
+            - 0.014615 * (2.0 * gamma).cos()
+            - 0.040849 * (2.0 * gamma).sin());
+    let declination = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin()
+        - 0.006758 * (2.0 * gamma).cos()
+        + 0.000907 * (2.0 * gamma).sin()
+        - 0.002697 * (3.0 * gamma).cos()
+        + 0.00148 * (3.0 * gamma).sin();
+
+    let lat = lat.to_radians();
+    let cos_hour_angle = SUNRISE_ZENITH_DEG.to_radians().cos() / (lat.cos() * declination.cos())
+        - lat.tan() * declination.tan();
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+    let minutes = |m: f64| m.rem_euclid(MINUTES_PER_DAY).round() as u32 % 1440;
+    Some((
+        minutes(720.0 - 4.0 * (lon + hour_angle) - equation_of_time),
+        minutes(720.0 - 4.0 * (lon - hour_angle) - equation_of_time),
+    ))
+}
+
+/// Minutes after midnight as `HHMM`
+pub(crate) fn hhmm(minutes: u32) -> String {
+    format!("{:02}{:02}", minutes / 60 % 24, minutes % 60)
+}
+
+/// The window of `half_width` minutes either side of `center`, wrapping
+/// around midnight, as (from, to) minutes after midnight
+pub(crate) fn window(center: u32, half_width: u32) -> (u32, u32) {
+    let day = MINUTES_PER_DAY as u32;
+    let half_width = half_width % day;
+    (
+        (center + day - half_width) % day,
+        (center + half_width) % day,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_to_lat_lon() {
+        assert_eq!(grid_to_lat_lon("FN31").unwrap(), (41.5, -73.0));
+        let (lat, lon) = grid_to_lat_lon("fn31pr").unwrap();
+        assert!((lat - 41.729).abs() < 0.01, "{}", lat);
+        assert!((lon - -72.708).abs() < 0.01, "{}", lon);
+        assert!(grid_to_lat_lon("FN3").is_err());
+        assert!(grid_to_lat_lon("ZZ99").is_err());
+    }
+
+    #[test]
+    fn test_sun_times() {
+        // Around 05:20 EDT and 20:30 EDT in Connecticut at midsummer
+        let midsummer = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let (sunrise, sunset) = sun_times(midsummer, 41.5, -73.0).unwrap();
+        assert!((550..=570).contains(&sunrise), "{}", hhmm(sunrise));
+        assert!((20..=40).contains(&sunset), "{}", hhmm(sunset));
+
+        // Polar night in Svalbard
+        let midwinter = NaiveDate::from_ymd_opt(2026, 12, 21).unwrap();
+        assert_eq!(sun_times(midwinter, 78.2, 15.6), None);
+    }
+
+    #[test]
+    fn test_window() {
+        assert_eq!(window(600, 30), (570, 630));
+        assert_eq!(window(10, 30), (1420, 40));
+        assert_eq!(hhmm(1420), "2340");
+        assert_eq!(hhmm(40), "0040");
+    }
+}
@@ -0,0 +1,283 @@
+//! Trigger backups and the offline snapshot.
+
+use crate::error::CliError;
+use chrono::Local;
+use hamalert_api::Trigger;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+/// Version of the backup file layout written by this release
+const BACKUP_FORMAT: u32 = 1;
+
+/// Backup directory set with the `backup_dir` config key
+pub(crate) static BACKUP_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// The configured backup directory, or `backups` in the data directory
+pub(crate) fn backup_dir() -> Result<PathBuf, CliError> {
+    let dir = match BACKUP_DIR.get() {
+        Some(dir) => dir.clone(),
+        None => super::data_dir()?.join("backups"),
+    };
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// A backup file: the triggers with a checksum to detect damage. Backups
+/// from before checksums are a bare array of triggers.
+#[derive(Serialize, Deserialize)]
+struct BackupEnvelope {
+    format: u32,
+    /// RFC 3339 time the backup was written
+    created_at: String,
+    count: usize,
+    /// SHA-256 of the triggers as compact JSON
+    sha256: String,
+    triggers: Value,
+}
+
+/// What `check_backup` found in an intact backup
+#[derive(Debug)]
+pub(crate) struct BackupCheck {
+    pub(crate) triggers: Vec<Trigger>,
+    /// When the backup was written, if it has a checksum; older backups
+    /// can only be checked for valid JSON
+    pub(crate) created_at: Option<String>,
+}
+
+fn checksum(triggers: &Value) -> String {
+    Sha256::digest(triggers.to_string().as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Write `triggers` to a backup file at `path` with a checksum. The file is
+/// written next to `path` first and renamed, so an interrupted write leaves
+/// any earlier file intact.
+pub(crate) fn write_backup<T: Serialize>(path: &Path, triggers: &[T]) -> Result<(), CliError> {
+    let triggers = serde_json::to_value(triggers)?;
+    let envelope = BackupEnvelope {
+        format: BACKUP_FORMAT,
+        created_at: Local::now().to_rfc3339(),
+        count: triggers.as_array().map_or(0, Vec::len),
+        sha256: checksum(&triggers),
+        triggers,
+    };
+    let partial = path.with_extension("json.partial");
+    fs::write(&partial, serde_json::to_string_pretty(&envelope)?)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Check the contents of a backup file for truncation or corruption,
+/// returning its triggers or what is wrong with it
+pub(crate) fn check_backup(content: &str) -> Result<BackupCheck, String> {
+    let value: Value = serde_json::from_str(content)
+        .map_err(|e| format!("not valid JSON, probably truncated ({})", e))?;
+    let parse_triggers = |triggers: Value| {
+        serde_json::from_value::<Vec<Trigger>>(triggers)
+            .map_err(|e| format!("unexpected trigger data ({})", e))
+    };
+    if value.is_array() {
+        return Ok(BackupCheck {
+            triggers: parse_triggers(value)?,
+            created_at: None,
+        });
+    }
+
+    let envelope: BackupEnvelope =
+        serde_json::from_value(value).map_err(|e| format!("not a HamAlert backup ({})", e))?;
+    if envelope.format > BACKUP_FORMAT {
+        return Err(format!(
+            "written in backup format {} by a newer release; upgrade to read it",
+            envelope.format
+        ));
+    }
+    let found = envelope.triggers.as_array().map_or(0, Vec::len);
+    if found != envelope.count {
+        return Err(format!(
+            "has {} of the {} triggers it was written with",
+            found, envelope.count
+        ));
+    }
+    if checksum(&envelope.triggers) != envelope.sha256 {
+        return Err("checksum mismatch; the contents changed after it was written".to_string());
+    }
+    Ok(BackupCheck {
+        triggers: parse_triggers(envelope.triggers)?,
+        created_at: Some(envelope.created_at),
+    })
+}
+
+/// The most recently modified backup in `dir` and when it was written
+pub(crate) fn newest_backup(dir: &Path) -> Result<Option<(PathBuf, SystemTime)>, CliError> {
+    let mut newest: Option<(PathBuf, SystemTime)> = None;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let modified = fs::metadata(&path)?.modified()?;
+        if newest.as_ref().is_none_or(|(_, time)| modified > *time) {
+            newest = Some((path, modified));
+        }
+    }
+    Ok(newest)
+}
+
+/// When the backup at `path` was written, if it holds exactly `triggers`.
+/// Damaged backups and those from before checksums never match.
+pub(crate) fn unchanged_since(path: &Path, triggers: &[Trigger]) -> Option<String> {
+    let check = check_backup(&fs::read_to_string(path).ok()?).ok()?;
+    let same =
+        serde_json::to_value(&check.triggers).ok()? == serde_json::to_value(triggers).ok()?;
+    if same { check.created_at } else { None }
+}
+
+/// Triggers of the backup file at `path`, refusing damaged files
+pub(crate) fn read_backup(path: &Path) -> Result<Vec<Trigger>, CliError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| CliError::io(format!("Failed to read backup file {}", path.display()), e))?;
+    check_backup(&content)
+        .map(|check| check.triggers)
+        .map_err(|e| CliError::Validation(format!("Backup {} is damaged: {}", path.display(), e)))
+}
+
+/// Triggers as last fetched from HamAlert, for --offline
+#[derive(Serialize, Deserialize)]
+pub(crate) struct TriggerSnapshot {
+    /// RFC 3339 time of the fetch
+    pub(crate) fetched_at: String,
+    pub(crate) triggers: Vec<Trigger>,
+}
+
+pub(crate) fn trigger_snapshot_path() -> Result<PathBuf, CliError> {
+    let dir = dirs::cache_dir()
+        .ok_or_else(|| CliError::Config("Could not determine cache directory".to_string()))?
+        .join("hamalert");
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join("triggers.json"))
+}
+
+pub(crate) fn save_trigger_snapshot(triggers: &[Trigger]) -> Result<(), CliError> {
+    let snapshot = TriggerSnapshot {
+        fetched_at: Local::now().to_rfc3339(),
+        triggers: triggers.to_vec(),
+    };
+    fs::write(
+        trigger_snapshot_path()?,
+        serde_json::to_string_pretty(&snapshot)?,
+    )?;
+    Ok(())
+}
+
+pub(crate) fn load_trigger_snapshot() -> Result<TriggerSnapshot, CliError> {
+    let path = trigger_snapshot_path()?;
+    let content = fs::read_to_string(&path).map_err(|_| {
+        CliError::Validation(format!(
+            "No cached triggers at {}; run any command online first",
+            path.display()
+        ))
+    })?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn triggers() -> Vec<Trigger> {
+        serde_json::from_value(json!([
+            {"_id": "1", "conditions": {"callsign": "W1AW"}, "actions": ["app"], "comment": "Club"},
+            {"_id": "2", "conditions": {"band": "20m"}, "actions": ["telnet"], "comment": "20m"},
+        ]))
+        .unwrap()
+    }
+
+    fn envelope_json(triggers: &[Trigger]) -> String {
+        let triggers = serde_json::to_value(triggers).unwrap();
+        serde_json::to_string_pretty(&BackupEnvelope {
+            format: BACKUP_FORMAT,
+            created_at: "2026-10-17T12:00:00+00:00".to_string(),
+            count: 2,
+            sha256: checksum(&triggers),
+            triggers,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_backup_intact() {
+        let check = check_backup(&envelope_json(&triggers())).unwrap();
+        assert_eq!(check.triggers.len(), 2);
+        assert_eq!(
+            check.created_at.as_deref(),
+            Some("2026-10-17T12:00:00+00:00")
+        );
+
+        // Backups from before checksums are bare arrays
+        let legacy = serde_json::to_string(&triggers()).unwrap();
+        assert_eq!(check_backup(&legacy).unwrap().created_at, None);
+    }
+
+    #[test]
+    fn test_check_backup_damaged() {
+        let content = envelope_json(&triggers());
+        let truncated = &content[..content.len() / 2];
+        assert!(check_backup(truncated).unwrap_err().contains("truncated"));
+
+        let edited = content.replace("W1AW", "K3LR");
+        assert!(check_backup(&edited).unwrap_err().contains("checksum"));
+
+        let mut value: Value = serde_json::from_str(&content).unwrap();
+        value["triggers"].as_array_mut().unwrap().pop();
+        let err = check_backup(&value.to_string()).unwrap_err();
+        assert!(err.contains("has 1 of the 2"), "{}", err);
+
+        assert!(check_backup("{\"triggers\": []}").is_err());
+    }
+
+    #[test]
+    fn test_newest_backup() {
+        let dir = std::env::temp_dir().join(format!("hamalert-backups-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        assert!(newest_backup(&dir).unwrap().is_none());
+
+        let now = SystemTime::now();
+        for (name, age) in [
+            ("hamalert-backup-2026-10-15.json", 7200),
+            ("hamalert-backup-2026-10-16.json", 60),
+            ("notes.txt", 0),
+        ] {
+            let file = fs::File::create(dir.join(name)).unwrap();
+            file.set_modified(now - std::time::Duration::from_secs(age))
+                .unwrap();
+        }
+        let (path, _) = newest_backup(&dir).unwrap().unwrap();
+        assert!(path.ends_with("hamalert-backup-2026-10-16.json"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unchanged_since() {
+        let path =
+            std::env::temp_dir().join(format!("hamalert-unchanged-{}.json", std::process::id()));
+        assert_eq!(unchanged_since(&path, &triggers()), None);
+
+        write_backup(&path, &triggers()).unwrap();
+        assert!(unchanged_since(&path, &triggers()).is_some());
+        let mut changed = triggers();
+        changed[1].comment = "20m CW".to_string();
+        assert_eq!(unchanged_since(&path, &changed), None);
+
+        fs::write(&path, serde_json::to_string(&triggers()).unwrap()).unwrap();
+        assert_eq!(unchanged_since(&path, &triggers()), None);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,132 @@
+//! matchCount history, sampled whenever triggers are fetched.
+
+use crate::error::CliError;
+use chrono::{DateTime, Duration, Local};
+use hamalert_api::Trigger;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Samples older than this many days are dropped
+const HISTORY_DAYS: i64 = 90;
+
+/// matchCount of every trigger at one point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct MatchSample {
+    /// RFC 3339 time of the fetch
+    pub(crate) time: String,
+    /// matchCount by trigger id
+    pub(crate) counts: BTreeMap<String, u64>,
+}
+
+impl MatchSample {
+    fn parsed_time(&self) -> Option<DateTime<Local>> {
+        DateTime::parse_from_rfc3339(&self.time)
+            .ok()
+            .map(|time| time.with_timezone(&Local))
+    }
+}
+
+pub(crate) fn history_path() -> Result<PathBuf, CliError> {
+    Ok(super::data_dir()?.join("match_history.jsonl"))
+}
+
+/// All samples, oldest first; lines that do not parse are skipped
+pub(crate) fn load_history() -> Result<Vec<MatchSample>, CliError> {
+    let path = history_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_to_string(&path)?
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Add a sample of `triggers` at `now` unless nothing changed since the
+/// last one, dropping samples past the retention window
+fn add_sample(history: &mut Vec<MatchSample>, triggers: &[Trigger], now: DateTime<Local>) -> bool {
+    let counts: BTreeMap<String, u64> = triggers
+        .iter()
+        .filter_map(|t| Some((t.id.clone(), t.match_count?)))
+        .collect();
+    if history.last().is_some_and(|last| last.counts == counts) {
+        return false;
+    }
+    let cutoff = now - Duration::days(HISTORY_DAYS);
+    history.retain(|s| s.parsed_time().is_some_and(|time| time >= cutoff));
+    history.push(MatchSample {
+        time: now.to_rfc3339(),
+        counts,
+    });
+    true
+}
+
+/// Record the current matchCounts in the history file
+pub(crate) fn record_match_counts(triggers: &[Trigger]) -> Result<(), CliError> {
+    let mut history = load_history()?;
+    if !add_sample(&mut history, triggers, Local::now()) {
+        return Ok(());
+    }
+    let path = history_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut content = String::new();
+    for sample in &history {
+        content.push_str(&serde_json::to_string(sample)?);
+        content.push('\n');
+    }
+    fs::write(&path, content)?;
+    Ok(())
+}
+
+/// matchCount of trigger `id` in the latest sample taken at or before `time`.
+/// A trigger missing from that sample did not exist yet and counts as 0;
+/// `None` means the history does not reach back to `time`.
+pub(crate) fn count_at(history: &[MatchSample], id: &str, time: DateTime<Local>) -> Option<u64> {
+    history
+        .iter()
+        .rev()
+        .find(|s| s.parsed_time().is_some_and(|t| t <= time))
+        .map(|s| s.counts.get(id).copied().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::test_trigger;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn trigger(id: &str, count: u64) -> Trigger {
+        Trigger {
+            match_count: Some(count),
+            ..test_trigger(id, id, json!({"callsign": "W1AW"}))
+        }
+    }
+
+    #[test]
+    fn test_add_sample() {
+        let day = |d: u32| Local.with_ymd_and_hms(2026, 6, d, 12, 0, 0).unwrap();
+        let mut history = Vec::new();
+        assert!(add_sample(&mut history, &[trigger("a", 1)], day(1)));
+        assert!(!add_sample(&mut history, &[trigger("a", 1)], day(2)));
+        assert!(add_sample(
+            &mut history,
+            &[trigger("a", 4), trigger("b", 0)],
+            day(3)
+        ));
+        assert_eq!(history.len(), 2);
+
+        assert_eq!(count_at(&history, "a", day(2)), Some(1));
+        assert_eq!(count_at(&history, "a", day(3)), Some(4));
+        assert_eq!(count_at(&history, "b", day(2)), Some(0));
+        assert_eq!(count_at(&history, "a", day(1) - Duration::hours(1)), None);
+
+        let later = Local.with_ymd_and_hms(2026, 9, 15, 12, 0, 0).unwrap();
+        assert!(add_sample(&mut history, &[trigger("a", 9)], later));
+        assert_eq!(history.len(), 1);
+    }
+}
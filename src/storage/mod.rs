@@ -0,0 +1,24 @@
+//! Files kept in the local data directory.
+
+use crate::error::CliError;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+pub(crate) mod backups;
+pub(crate) mod history;
+pub(crate) mod profiles;
+pub(crate) mod state;
+
+/// Data directory set with --data-dir or the `data_dir` config key
+pub(crate) static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Directory for backups, profiles, state and history: the override if one
+/// is set, otherwise `hamalert` in the platform data directory
+pub(crate) fn data_dir() -> Result<PathBuf, CliError> {
+    if let Some(dir) = DATA_DIR.get() {
+        return Ok(dir.clone());
+    }
+    Ok(dirs::data_dir()
+        .ok_or_else(|| CliError::Config("Could not determine data directory".to_string()))?
+        .join("hamalert"))
+}
@@ -0,0 +1,335 @@
+//! Saved trigger profiles and the permanent trigger set.
+
+use crate::error::CliError;
+use crate::model::{StoredTrigger, triggers_match};
+use hamalert_api::Trigger;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// A running contest, recorded by `contest start` for `contest end`
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ContestSession {
+    pub(crate) name: String,
+    /// RFC 3339 time the contest started
+    pub(crate) started_at: String,
+    /// Profile holding the triggers from before the contest
+    pub(crate) restore_profile: String,
+    /// Profile that was current before the contest
+    pub(crate) previous_profile: Option<String>,
+}
+
+#[allow(dead_code)]
+pub(crate) fn profiles_dir() -> Result<PathBuf, CliError> {
+    let dir = super::data_dir()?.join("profiles");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+#[allow(dead_code)]
+pub(crate) fn permanent_triggers_path() -> Result<PathBuf, CliError> {
+    Ok(super::data_dir()?.join("permanent.json"))
+}
+
+#[allow(dead_code)]
+pub(crate) fn current_profile_path() -> Result<PathBuf, CliError> {
+    Ok(super::data_dir()?.join("current-profile"))
+}
+
+#[allow(dead_code)]
+pub(crate) fn load_profile(name: &str) -> Result<Vec<StoredTrigger>, CliError> {
+    let path = profiles_dir()?.join(format!("{}.json", name));
+    let content = fs::read_to_string(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
+            CliError::Validation(format!("Profile '{}' not found", name))
+        }
+        _ => CliError::io(format!("Failed to read profile '{}'", name), e),
+    })?;
+    let triggers: Vec<StoredTrigger> = serde_json::from_str(&content)
+        .map_err(|e| CliError::Config(format!("Failed to parse profile '{}': {}", name, e)))?;
+    Ok(triggers)
+}
+
+#[allow(dead_code)]
+pub(crate) fn save_profile(name: &str, triggers: &[StoredTrigger]) -> Result<PathBuf, CliError> {
+    let path = profiles_dir()?.join(format!("{}.json", name));
+    let json = serde_json::to_string_pretty(triggers)?;
+    fs::write(&path, json)?;
+    Ok(path)
+}
+
+#[allow(dead_code)]
+pub(crate) fn load_permanent_triggers() -> Result<Vec<StoredTrigger>, CliError> {
+    let path = permanent_triggers_path()?;
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = fs::read_to_string(&path)?;
+    let triggers: Vec<StoredTrigger> = serde_json::from_str(&content)?;
+    Ok(triggers)
+}
+
+#[allow(dead_code)]
+pub(crate) fn save_permanent_triggers(triggers: &[StoredTrigger]) -> Result<(), CliError> {
+    let path = permanent_triggers_path()?;
+    // Ensure parent directory exists
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(triggers)?;
+    fs::write(&path, json)?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub(crate) fn load_current_profile_name() -> Result<Option<String>, CliError> {
+    let path = current_profile_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let name = fs::read_to_string(&path)?.trim().to_string();
+    if name.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(name))
+}
+
+#[allow(dead_code)]
+pub(crate) fn save_current_profile_name(name: &str) -> Result<(), CliError> {
+    let path = current_profile_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, name)?;
+    Ok(())
+}
+
+fn contest_path() -> Result<PathBuf, CliError> {
+    Ok(super::data_dir()?.join("contest.json"))
+}
+
+/// The running contest, if any
+pub(crate) fn load_contest() -> Result<Option<ContestSession>, CliError> {
+    let path = contest_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path)?;
+    Ok(Some(serde_json::from_str(&content).map_err(|e| {
+        CliError::Config(format!("Failed to parse {}: {}", path.display(), e))
+    })?))
+}
+
+pub(crate) fn save_contest(session: &ContestSession) -> Result<(), CliError> {
+    let path = contest_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(session)?)?;
+    Ok(())
+}
+
+pub(crate) fn clear_contest() -> Result<(), CliError> {
+    let path = contest_path()?;
+    if path.exists() {
+        fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub(crate) fn list_profiles() -> Result<Vec<String>, CliError> {
+    let dir = profiles_dir()?;
+    let mut profiles = vec![];
+    if dir.exists() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().map(|e| e == "json").unwrap_or(false)
+                && let Some(stem) = path.file_stem()
+            {
+                profiles.push(stem.to_string_lossy().to_string());
+            }
+        }
+    }
+    profiles.sort();
+    Ok(profiles)
+}
+
+#[allow(dead_code)]
+pub(crate) fn delete_profile(name: &str) -> Result<(), CliError> {
+    let path = profiles_dir()?.join(format!("{}.json", name));
+    if !path.exists() {
+        return Err(CliError::Validation(format!(
+            "Profile '{}' not found",
+            name
+        )));
+    }
+    fs::remove_file(&path)?;
+    Ok(())
+}
+
+/// Calculate how many triggers from a profile are present in current triggers
+/// Returns (matched_count, profile_total)
+#[allow(dead_code)]
+pub(crate) fn calculate_profile_match(
+    current: &[StoredTrigger],
+    profile: &[StoredTrigger],
+) -> (usize, usize) {
+    let matched = profile
+        .iter()
+        .filter(|p| current.iter().any(|c| triggers_match(c, p)))
+        .count();
+    (matched, profile.len())
+}
+
+/// Filter out permanent triggers from a list
+#[allow(dead_code)]
+pub(crate) fn filter_out_permanent(
+    triggers: &[StoredTrigger],
+    permanent: &[StoredTrigger],
+) -> Vec<StoredTrigger> {
+    triggers
+        .iter()
+        .filter(|t| !permanent.iter().any(|p| triggers_match(t, p)))
+        .cloned()
+        .collect()
+}
+
+/// Whether the live `trigger` is one of the `permanent` triggers
+pub(crate) fn is_permanent(trigger: &Trigger, permanent: &[StoredTrigger]) -> bool {
+    let stored = StoredTrigger::from_trigger(trigger);
+    permanent.iter().any(|p| triggers_match(&stored, p))
+}
+
+/// Split live triggers into the permanent ones and the rest, so destructive
+/// commands can leave the permanent set alone
+pub(crate) fn partition_permanent(
+    triggers: Vec<Trigger>,
+    permanent: &[StoredTrigger],
+) -> (Vec<Trigger>, Vec<Trigger>) {
+    triggers
+        .into_iter()
+        .partition(|t| is_permanent(t, permanent))
+}
+
+/// Find triggers that don't match any profile or permanent triggers
+#[allow(dead_code)]
+pub(crate) fn find_unexpected_triggers(
+    current: &[StoredTrigger],
+    permanent: &[StoredTrigger],
+    profile: Option<&[StoredTrigger]>,
+) -> Vec<StoredTrigger> {
+    current
+        .iter()
+        .filter(|t| {
+            let is_permanent = permanent.iter().any(|p| triggers_match(t, p));
+            let is_in_profile = profile
+                .map(|p| p.iter().any(|pt| triggers_match(t, pt)))
+                .unwrap_or(false);
+            !is_permanent && !is_in_profile
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::StoredTrigger;
+
+    #[test]
+    fn test_profiles_dir_is_under_data_dir() {
+        let dir = profiles_dir().unwrap();
+        assert!(dir.to_string_lossy().contains("hamalert"));
+        assert!(dir.to_string_lossy().contains("profiles"));
+    }
+
+    #[test]
+    fn test_permanent_triggers_path_is_json() {
+        let path = permanent_triggers_path().unwrap();
+        assert!(path.to_string_lossy().ends_with("permanent.json"));
+    }
+
+    #[test]
+    fn test_current_profile_path_exists() {
+        let path = current_profile_path().unwrap();
+        assert!(path.to_string_lossy().contains("current-profile"));
+    }
+
+    #[test]
+    fn test_load_profile_not_found() {
+        let result = load_profile("nonexistent_profile_xyz");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_calculate_profile_match_full_match() {
+        let current = vec![
+            StoredTrigger {
+                conditions: serde_json::json!({"callsign": "W1ABC"}),
+                actions: vec!["app".to_string()],
+                comment: "A".to_string(),
+                options: None,
+            },
+            StoredTrigger {
+                conditions: serde_json::json!({"callsign": "K2DEF"}),
+                actions: vec!["app".to_string()],
+                comment: "B".to_string(),
+                options: None,
+            },
+        ];
+        let profile = current.clone();
+        let (matched, total) = calculate_profile_match(&current, &profile);
+        assert_eq!(matched, 2);
+        assert_eq!(total, 2);
+    }
+
+    #[test]
+    fn test_calculate_profile_match_partial() {
+        let current = vec![StoredTrigger {
+            conditions: serde_json::json!({"callsign": "W1ABC"}),
+            actions: vec!["app".to_string()],
+            comment: "A".to_string(),
+            options: None,
+        }];
+        let profile = vec![
+            StoredTrigger {
+                conditions: serde_json::json!({"callsign": "W1ABC"}),
+                actions: vec!["app".to_string()],
+                comment: "A".to_string(),
+                options: None,
+            },
+            StoredTrigger {
+                conditions: serde_json::json!({"callsign": "K2DEF"}),
+                actions: vec!["app".to_string()],
+                comment: "B".to_string(),
+                options: None,
+            },
+        ];
+        let (matched, total) = calculate_profile_match(&current, &profile);
+        assert_eq!(matched, 1);
+        assert_eq!(total, 2);
+    }
+
+    #[test]
+    fn test_partition_permanent() {
+        let trigger = |id: &str, comment: &str| -> Trigger {
+            serde_json::from_value(serde_json::json!({
+                "_id": id,
+                "conditions": {"band": "20m"},
+                "actions": ["app"],
+                "comment": comment,
+            }))
+            .unwrap()
+        };
+        let permanent = vec![StoredTrigger::from_trigger(&trigger("", "Club"))];
+        let (kept, rest) = partition_permanent(
+            vec![trigger("1", "Club"), trigger("2", "20m DX")],
+            &permanent,
+        );
+        assert_eq!(kept[0].id, "1");
+        assert_eq!(rest[0].id, "2");
+        assert_eq!((kept.len(), rest.len()), (1, 1));
+    }
+}
@@ -0,0 +1,385 @@
+//! Friendly names and metadata for the triggers the CLI created or manages.
+
+use crate::error::CliError;
+use chrono::{DateTime, Local};
+use hamalert_api::Trigger;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// What the CLI knows about one managed trigger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ManagedTrigger {
+    /// HamAlert trigger id
+    pub(crate) id: String,
+    /// RFC 3339 time the trigger was first recorded
+    pub(crate) created_at: String,
+    /// Command (and file or URL) that created or last applied the trigger
+    pub(crate) source: String,
+    /// Date (YYYY-MM-DD) after which the trigger is no longer wanted, or an
+    /// RFC 3339 time for triggers that only live for a few hours
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) expires: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) tags: Vec<String>,
+    /// RFC 3339 time at which a snoozed (disabled) trigger is enabled again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) snoozed_until: Option<String>,
+}
+
+impl ManagedTrigger {
+    pub(crate) fn new(source: String, expires: Option<String>, tags: Vec<String>) -> Self {
+        Self {
+            id: String::new(),
+            created_at: Local::now().to_rfc3339(),
+            source,
+            expires,
+            tags,
+            snoozed_until: None,
+        }
+    }
+
+    /// Whether the trigger has expired at `now`. A date expires at the end of
+    /// that day, a time at that moment.
+    pub(crate) fn is_expired(&self, now: &DateTime<Local>) -> bool {
+        let Some(expires) = self.expires.as_deref() else {
+            return false;
+        };
+        match DateTime::parse_from_rfc3339(expires) {
+            Ok(time) => time <= *now,
+            Err(_) => expires < now.format("%Y-%m-%d").to_string().as_str(),
+        }
+    }
+
+    /// Whether the trigger is snoozed and its wake time has come at `now`.
+    /// An unreadable wake time counts as due, so the trigger is not lost.
+    pub(crate) fn is_snooze_over(&self, now: &DateTime<Local>) -> bool {
+        self.snoozed_until
+            .as_deref()
+            .is_some_and(|until| DateTime::parse_from_rfc3339(until).map_or(true, |t| t <= *now))
+    }
+}
+
+/// A callsign kept out of alerts for a while by `mute-callsign`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Mute {
+    /// RFC 3339 time at which the mute is lifted
+    pub(crate) until: String,
+    /// Ids of the triggers the callsign was added to as `notCallsign`
+    pub(crate) triggers: Vec<String>,
+}
+
+impl Mute {
+    /// Whether the mute has run out at `now`. An unreadable time counts as
+    /// run out, so the callsign is not muted forever.
+    pub(crate) fn is_over(&self, now: &DateTime<Local>) -> bool {
+        DateTime::parse_from_rfc3339(&self.until).map_or(true, |t| t <= *now)
+    }
+}
+
+/// The state file: managed triggers keyed by name, and muted callsigns
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct State {
+    #[serde(default)]
+    pub(crate) triggers: BTreeMap<String, ManagedTrigger>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) mutes: BTreeMap<String, Mute>,
+}
+
+impl State {
+    /// Name of the managed trigger with this id
+    pub(crate) fn name_for_id(&self, id: &str) -> Option<&str> {
+        self.triggers
+            .iter()
+            .find(|(_, t)| t.id == id)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// `base`, or `base-2`, `base-3`, ... if that name is taken
+    pub(crate) fn unique_name(&self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut n = 2;
+        while self.triggers.contains_key(&name) {
+            name = format!("{}-{}", base, n);
+            n += 1;
+        }
+        name
+    }
+
+    /// Record `trigger` under `name`, dropping any other name for the same id.
+    /// An existing entry for the id keeps its creation time and any snooze.
+    pub(crate) fn record(&mut self, name: &str, mut trigger: ManagedTrigger) {
+        if let Some(old_name) = self.name_for_id(&trigger.id).map(str::to_string)
+            && let Some(old) = self.triggers.remove(&old_name)
+        {
+            trigger.created_at = old.created_at;
+            trigger.snoozed_until = trigger.snoozed_until.or(old.snoozed_until);
+        }
+        self.triggers.insert(name.to_string(), trigger);
+    }
+
+    /// Whether the trigger with this id is managed and carries every one of `tags`
+    pub(crate) fn has_tags(&self, id: &str, tags: &[String]) -> bool {
+        self.triggers
+            .values()
+            .find(|t| t.id == id)
+            .is_some_and(|t| tags.iter().all(|tag| t.tags.contains(tag)))
+    }
+
+    /// Ids of the triggers called `names`, failing on names that are not recorded
+    pub(crate) fn ids_for_names(&self, names: &[String]) -> Result<Vec<&str>, CliError> {
+        names
+            .iter()
+            .map(|name| {
+                self.triggers
+                    .get(name)
+                    .map(|managed| managed.id.as_str())
+                    .ok_or_else(|| {
+                        CliError::Validation(format!("No managed trigger named '{}'", name))
+                    })
+            })
+            .collect()
+    }
+
+    /// Forget the trigger with this id after it was deleted: its name, tags,
+    /// expiry and snooze. Returns whether it was managed.
+    pub(crate) fn forget(&mut self, id: &str) -> bool {
+        let before = self.triggers.len();
+        self.triggers.retain(|_, t| t.id != id);
+        self.triggers.len() != before
+    }
+
+    /// Names whose trigger no longer exists on the account
+    pub(crate) fn missing(&self, live: &[Trigger]) -> Vec<String> {
+        self.triggers
+            .iter()
+            .filter(|(_, t)| !live.iter().any(|l| l.id == t.id))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+/// Id of the trigger just created with `comment`, for save replies that carry
+/// no id: the newest untracked trigger with that comment. Ids start with
+/// their creation time, so the largest is the newest.
+pub(crate) fn find_new_trigger_id<'a>(
+    state: &State,
+    live: &'a [Trigger],
+    comment: &str,
+) -> Option<&'a str> {
+    live.iter()
+        .filter(|t| t.comment == comment && state.name_for_id(&t.id).is_none())
+        .map(|t| t.id.as_str())
+        .max()
+}
+
+/// Default name for a trigger: its comment in lowercase with runs of other
+/// characters replaced by dashes
+pub(crate) fn slugify(comment: &str) -> String {
+    let slug: Vec<String> = comment
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect();
+    if slug.is_empty() {
+        "trigger".to_string()
+    } else {
+        slug.join("-")
+    }
+}
+
+/// Fail if `name` already refers to a managed trigger
+pub(crate) fn ensure_name_available(name: &str) -> Result<(), CliError> {
+    if load_state()?.triggers.contains_key(name) {
+        return Err(CliError::Validation(format!(
+            "A trigger named '{}' already exists; choose another --name",
+            name
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn state_path() -> Result<PathBuf, CliError> {
+    Ok(super::data_dir()?.join("state.json"))
+}
+
+pub(crate) fn load_state() -> Result<State, CliError> {
+    let path = state_path()?;
+    if !path.exists() {
+        return Ok(State::default());
+    }
+    let content = fs::read_to_string(&path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| CliError::Config(format!("Failed to parse {}: {}", path.display(), e)))
+}
+
+/// Keep only the triggers recorded with all of `tags`; no-op without tags
+pub(crate) fn retain_tagged(triggers: &mut Vec<Trigger>, tags: &[String]) -> Result<(), CliError> {
+    if tags.is_empty() {
+        return Ok(());
+    }
+    let state = load_state()?;
+    triggers.retain(|t| state.has_tags(&t.id, tags));
+    Ok(())
+}
+
+/// Keep only the managed triggers called one of `names`; no-op without names
+pub(crate) fn retain_named(triggers: &mut Vec<Trigger>, names: &[String]) -> Result<(), CliError> {
+    if names.is_empty() {
+        return Ok(());
+    }
+    let state = load_state()?;
+    let ids = state.ids_for_names(names)?;
+    triggers.retain(|t| ids.contains(&t.id.as_str()));
+    Ok(())
+}
+
+pub(crate) fn save_state(state: &State) -> Result<(), CliError> {
+    let path = state_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(state)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn live(id: &str, comment: &str) -> Trigger {
+        serde_json::from_value(json!({
+            "_id": id,
+            "conditions": {"callsign": "W1AW"},
+            "actions": ["app"],
+            "comment": comment,
+        }))
+        .unwrap()
+    }
+
+    fn managed(id: &str) -> ManagedTrigger {
+        ManagedTrigger {
+            id: id.to_string(),
+            ..ManagedTrigger::new("add-trigger".to_string(), None, vec![])
+        }
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Club stations"), "club-stations");
+        assert_eq!(slugify("  POTA: K-1234 / CW "), "pota-k-1234-cw");
+        assert_eq!(slugify("!!"), "trigger");
+    }
+
+    #[test]
+    fn test_state_record_and_names() {
+        let mut state = State::default();
+        state.record("club", managed("a1"));
+        assert_eq!(state.unique_name("club"), "club-2");
+        assert_eq!(state.unique_name("dx"), "dx");
+
+        // Renaming keeps the original creation time
+        let created_at = state.triggers["club"].created_at.clone();
+        let mut renamed = managed("a1");
+        renamed.created_at = "later".to_string();
+        state.record("club-stations", renamed);
+        assert!(!state.triggers.contains_key("club"));
+        assert_eq!(state.triggers["club-stations"].created_at, created_at);
+        assert_eq!(state.name_for_id("a1"), Some("club-stations"));
+
+        state.record("gone", managed("b2"));
+        assert_eq!(state.missing(&[live("a1", "Club")]), vec!["gone"]);
+
+        assert!(state.forget("b2"));
+        assert!(!state.forget("b2"));
+        assert_eq!(state.name_for_id("b2"), None);
+    }
+
+    #[test]
+    fn test_snooze_survives_record() {
+        let mut state = State::default();
+        let mut snoozed = managed("a1");
+        snoozed.snoozed_until = Some("2026-10-17T14:00:00+00:00".to_string());
+        state.record("club", snoozed);
+        state.record("club", managed("a1"));
+        let club = &state.triggers["club"];
+        assert_eq!(
+            club.snoozed_until.as_deref(),
+            Some("2026-10-17T14:00:00+00:00")
+        );
+
+        let before = DateTime::parse_from_rfc3339("2026-10-17T13:59:00+00:00").unwrap();
+        let after = DateTime::parse_from_rfc3339("2026-10-17T14:00:00+00:00").unwrap();
+        assert!(!club.is_snooze_over(&before.with_timezone(&Local)));
+        assert!(club.is_snooze_over(&after.with_timezone(&Local)));
+        assert!(!managed("b2").is_snooze_over(&after.with_timezone(&Local)));
+    }
+
+    #[test]
+    fn test_find_new_trigger_id() {
+        let mut state = State::default();
+        state.record("old", managed("65a1f0c2e4b0a1b2c3d4e5f6"));
+        let triggers = vec![
+            live("65a1f0c2e4b0a1b2c3d4e5f6", "Club"),
+            live("65b000000000000000000001", "Club"),
+            live("65a200000000000000000000", "Club"),
+            live("65c000000000000000000000", "Other"),
+        ];
+        assert_eq!(
+            find_new_trigger_id(&state, &triggers, "Club"),
+            Some("65b000000000000000000001")
+        );
+        assert_eq!(find_new_trigger_id(&state, &triggers, "Missing"), None);
+    }
+
+    #[test]
+    fn test_is_expired() {
+        use chrono::TimeZone;
+
+        let at = |day: u32, hour: u32| Local.with_ymd_and_hms(2026, 10, day, hour, 0, 0).unwrap();
+        let mut trigger = managed("a1");
+        assert!(!trigger.is_expired(&at(17, 12)));
+        trigger.expires = Some("2026-10-16".to_string());
+        assert!(trigger.is_expired(&at(17, 0)));
+        assert!(!trigger.is_expired(&at(16, 23)));
+        trigger.expires = Some(at(17, 18).to_rfc3339());
+        assert!(!trigger.is_expired(&at(17, 17)));
+        assert!(trigger.is_expired(&at(17, 18)));
+    }
+
+    #[test]
+    fn test_has_tags() {
+        let mut state = State::default();
+        let mut trip = ManagedTrigger::new(
+            "add-trigger".to_string(),
+            None,
+            vec!["trip".to_string(), "pota".to_string()],
+        );
+        trip.id = "1".to_string();
+        state.record("trip", trip);
+        let tags = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(state.has_tags("1", &tags(&["trip"])));
+        assert!(state.has_tags("1", &tags(&["pota", "trip"])));
+        assert!(!state.has_tags("1", &tags(&["trip", "contest"])));
+        assert!(!state.has_tags("2", &tags(&["trip"])));
+        assert!(state.has_tags("1", &[]));
+    }
+
+    #[test]
+    fn test_ids_for_names() {
+        let mut state = State::default();
+        for (name, id) in [("friends", "1"), ("vp8pj", "2")] {
+            let mut trigger = ManagedTrigger::new("add-trigger".to_string(), None, Vec::new());
+            trigger.id = id.to_string();
+            state.record(name, trigger);
+        }
+        let names = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            state.ids_for_names(&names(&["vp8pj", "friends"])).unwrap(),
+            vec!["2", "1"]
+        );
+        assert!(state.ids_for_names(&names(&["friends", "nobody"])).is_err());
+    }
+}